@@ -0,0 +1,100 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    thread,
+};
+use storage::{Database, StorageError, StorageResult};
+
+const HOST: [u8; 4] = [127, 0, 0, 1];
+
+/// Serves administrative commands on the loopback interface, one command per line:
+///  * `BACKUP <file>` - writes a backup of the database into the file while clients keep working
+///  * `BACKUP PLAINTEXT <file>` - the same for an encrypted database, the backup is not encrypted
+///
+/// The server keeps working without administrative commands if the port can't be bound
+pub(crate) fn start(port: u16, database: Database) {
+    let listener = match TcpListener::bind(SocketAddr::from((HOST, port))) {
+        Ok(listener) => listener,
+        Err(error) => {
+            log::error!("admin commands are not served, port {} can't be bound: {}", port, error);
+            return;
+        }
+    };
+    thread::Builder::new()
+        .name("admin-thread".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                if let Err(error) = stream.and_then(|stream| serve(stream, &database)) {
+                    log::error!("admin connection failed: {}", error);
+                }
+            }
+        })
+        .expect("cannot spawn admin thread");
+}
+
+fn serve(stream: TcpStream, database: &Database) -> io::Result<()> {
+    let mut responses = stream.try_clone()?;
+    for command in BufReader::new(stream).lines() {
+        let command = command?;
+        log::info!("admin command {:?}", command);
+        let response = match command.trim().split_once(' ') {
+            Some((name, args)) if name.eq_ignore_ascii_case("BACKUP") => {
+                let (plaintext, file) = match args.trim().split_once(' ') {
+                    Some((option, file)) if option.eq_ignore_ascii_case("PLAINTEXT") => (true, file),
+                    _ => (false, args),
+                };
+                match backup(database, file.trim(), plaintext) {
+                    Ok(rows) => format!("OK {} rows", rows),
+                    Err(error) => format!("ERROR {}", error),
+                }
+            }
+            _ => format!("ERROR unknown command {:?}", command),
+        };
+        writeln!(responses, "{}", response)?;
+    }
+    Ok(())
+}
+
+/// backup is renamed into the target file only when it is complete and durable. The path has to be
+/// absolute, the server does not have to run in the directory of the admin. The backup is refused
+/// when a partial backup into the same file exists, it could be written by another connection
+fn backup(database: &Database, path: &str, plaintext: bool) -> StorageResult<u64> {
+    if Path::new(path).is_relative() {
+        return Err(StorageError::Io(format!("backup path {:?} is not absolute", path)));
+    }
+    let partial = format!("{}.partial", path);
+    let file = match OpenOptions::new().write(true).create_new(true).open(&partial) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+            return Err(StorageError::Io(format!("partial backup {:?} already exists", partial)))
+        }
+        Err(error) => return Err(error.into()),
+    };
+    let result = database.backup(&file, plaintext).and_then(|rows| {
+        file.sync_all()?;
+        fs::rename(&partial, path)?;
+        Ok(rows)
+    });
+    if result.is_err() {
+        if let Err(error) = fs::remove_file(&partial) {
+            log::error!("partial backup {:?} is not removed: {}", partial, error);
+        }
+    }
+    result
+}
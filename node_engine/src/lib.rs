@@ -28,8 +28,10 @@ use std::{
     sync::Arc,
     thread,
 };
-use storage::Database;
+use postgres::query_response::QueryError;
+use storage::{Database, DEFAULT_DATABASE};
 
+mod admin;
 mod query_engine;
 mod session;
 
@@ -59,6 +61,17 @@ pub fn start(database: Database) {
             .expect("cannot spawn executor thread");
     }
 
+    if let Ok(port) = env::var("ADMIN_PORT") {
+        match port.parse() {
+            Ok(port) => admin::start(port, database.clone()),
+            Err(error) => log::error!(
+                "admin commands are not served, ADMIN_PORT {:?} is not a port number: {}",
+                port,
+                error
+            ),
+        }
+    }
+
     async_io::block_on(async {
         let listener = Async::<TcpListener>::bind((HOST, PORT)).expect("OK");
 
@@ -96,6 +109,20 @@ pub fn start(database: Database) {
                         .map(|_| message_buffer)
                         .expect("to read message body");
 
+                    let database_name = database_name(&props);
+                    let database_exists = database
+                        .transaction(|db| Ok(db.database(&database_name).is_some()))
+                        .unwrap_or(false);
+                    if !database_exists {
+                        let error: BackendMessage = QueryError::fatal_database_does_not_exist(&database_name).into();
+                        channel
+                            .write_all(error.as_vec().as_slice())
+                            .await
+                            .expect("to send error");
+                        channel.flush().await.expect("to flush the buffer");
+                        continue;
+                    }
+
                     // we are ok with any password that user sent
                     channel
                         .write_all(BackendMessage::AuthenticationOk.as_vec().as_slice())
@@ -134,7 +161,7 @@ pub fn start(database: Database) {
                     let channel = Arc::new(AsyncMutex::new(channel));
                     let mut connection = Connection::new(conn_id, props, address, channel, conn_supervisor);
 
-                    let mut query_engine = QueryEngine::new(Arc::new(connection.sender()), database.clone());
+                    let mut query_engine = QueryEngine::new(Arc::new(connection.sender()), database.clone(), database_name);
                     log::debug!("ready to handle query");
                     WORKER
                         .spawn(async move {
@@ -168,6 +195,19 @@ pub fn start(database: Database) {
     });
 }
 
+/// database from the startup message, PostgreSQL clients default it to the user name
+fn database_name(props: &[(String, String)]) -> String {
+    let prop = |name: &str| {
+        props
+            .iter()
+            .find(|(key, _value)| key == name)
+            .map(|(_key, value)| value.clone())
+    };
+    prop("database")
+        .or_else(|| prop("user"))
+        .unwrap_or_else(|| DEFAULT_DATABASE.to_owned())
+}
+
 fn pfx_certificate_path() -> PathBuf {
     let file = env::var("PFX_CERTIFICATE_FILE").unwrap();
     let path = Path::new(&file);
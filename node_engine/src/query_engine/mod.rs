@@ -12,14 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::session::{
-    statement::{Portal, PreparedStatement},
-    Session,
+use crate::{
+    query_engine::outbox::Outbox,
+    session::{
+        statement::{Portal, PreparedStatement},
+        Session,
+    },
 };
 use catalog::CatalogHandler;
 use data_definition::ExecutionOutcome;
 use data_manipulation::{
-    DynamicTypedTree, QueryPlanResult, StaticTypedTree, TypedDeleteQuery, TypedInsertQuery, TypedQuery,
+    DynamicTypedTree, QueryPlanResult, SelectedRows, StaticTypedTree, TypedDeleteQuery, TypedInsertQuery, TypedQuery,
     TypedSelectQuery, TypedUpdateQuery, UntypedQuery,
 };
 use definition_planner::DefinitionPlanner;
@@ -38,10 +41,13 @@ use query_planner::QueryPlanner;
 use query_processing::{TypeChecker, TypeCoercion, TypeInference};
 use scalar::ScalarValue;
 use std::{
+    cell::Cell,
     rc::Rc,
     sync::{Arc, Mutex},
 };
-use storage::{ConflictableTransactionError, Database, TransactionResult};
+use storage::{
+    ConflictableTransactionError, Database, StorageError, TransactionError, TransactionResult, TransactionalDatabase,
+};
 
 pub(crate) struct QueryEngine {
     session: Arc<Mutex<Session>>,
@@ -50,10 +56,12 @@ pub(crate) struct QueryEngine {
     type_checker: TypeChecker,
     type_coercion: TypeCoercion,
     database: Database,
+    /// database that the connection is open to
+    database_name: String,
 }
 
 impl QueryEngine {
-    pub(crate) fn new(sender: Arc<dyn Sender>, database: Database) -> QueryEngine {
+    pub(crate) fn new(sender: Arc<dyn Sender>, database: Database, database_name: String) -> QueryEngine {
         QueryEngine {
             session: Arc::default(),
             sender,
@@ -61,18 +69,34 @@ impl QueryEngine {
             type_checker: TypeChecker,
             type_coercion: TypeCoercion,
             database,
+            database_name,
         }
     }
 
     pub(crate) fn execute(&mut self, command: CommandMessage) -> TransactionResult<()> {
         let inner = Rc::new(command);
         let mut session = self.session.lock().unwrap();
-        self.database.transaction(|db| {
+        let outbox = Outbox::new(self.sender.clone());
+        // changes of a failed statement are rolled back, but the connection stays open
+        let rolled_back = Cell::new(false);
+        let result = self.database.transaction(|db| {
+            outbox.clear();
+            rolled_back.set(false);
             log::trace!("TRANSACTION START");
+            let db = match db.use_database(&self.database_name) {
+                Some(db) => db,
+                None => {
+                    // the database was dropped by another connection
+                    outbox
+                        .send(QueryError::fatal_database_does_not_exist(&self.database_name).into())
+                        .expect("To Send Error to Client");
+                    return Err(ConflictableTransactionError::Abort);
+                }
+            };
             log::trace!("{:?}", db.table("DEFINITION_SCHEMA.TABLES"));
             let query_analyzer = QueryAnalyzer::from(db.clone());
             let definition_planner = DefinitionPlanner::from(db.clone());
-            let query_planner = QueryPlanner::from(db.clone());
+            let query_planner = QueryPlanner::from(db.clone()).with_current_values(session.current_values());
             let catalog = CatalogHandler::from(db.clone());
             let query_parser = QueryParser;
             let result = match &*inner {
@@ -98,7 +122,7 @@ impl QueryEngine {
                                                 .collect(),
                                         ),
                                     );
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementPrepared.into())
                                         .expect("To Send Result");
                                 }
@@ -156,15 +180,17 @@ impl QueryEngine {
                                                         full_table_name: insert.full_table_name,
                                                         values: type_coerced,
                                                     }))
-                                                    .execute(param_values)
+                                                    .and_then(|plan| plan.execute(param_values).map_err(QueryError::from))
                                                     .map(|r| { let r: QueryEvent = r.into(); r })
                                                     .map(|r| { let r: BackendMessage = r.into(); r })
-                                                    .map_err(|e| { let e: QueryError = e.into(); e })
                                                     .map_err(|e| { let e: BackendMessage = e.into(); e }) {
                                                     Ok(ok) => ok,
-                                                    Err(err) => err,
+                                                    Err(err) => {
+                                                        rolled_back.set(true);
+                                                        err
+                                                    }
                                                 };
-                                                self.sender.send(query_result).expect("To Send to client");
+                                                outbox.send(query_result).expect("To Send to client");
                                             }
                                             UntypedQuery::Update(update) => {
                                                 let typed_values = update
@@ -208,15 +234,17 @@ impl QueryEngine {
                                                         assignments: type_coerced,
                                                         filter: type_coerced_filter
                                                     }))
-                                                    .execute(param_values)
+                                                    .and_then(|plan| plan.execute(param_values).map_err(QueryError::from))
                                                     .map(|r| { let r: QueryEvent = r.into(); r })
                                                     .map(|r| { let r: BackendMessage = r.into(); r })
-                                                    .map_err(|e| { let e: QueryError = e.into(); e })
                                                     .map_err(|e| { let e: BackendMessage = e.into(); e }) {
                                                     Ok(ok) => ok,
-                                                    Err(err) => err,
+                                                    Err(err) => {
+                                                        rolled_back.set(true);
+                                                        err
+                                                    }
                                                 };
-                                                self.sender.send(query_result).expect("To Send to client");
+                                                outbox.send(query_result).expect("To Send to client");
                                             }
                                             UntypedQuery::Select(select) => {
                                                 log::debug!("SELECT UNTYPED VALUES - {:?}", select.projection_items);
@@ -255,38 +283,13 @@ impl QueryEngine {
                                                         full_table_name: select.full_table_name,
                                                         filter: type_coerced_filter
                                                     }))
-                                                    .execute(param_values)
-                                                    .map_err(|e| { let e: QueryError = e.into(); e })
+                                                    .and_then(|plan| plan.execute(param_values).map_err(QueryError::from))
                                                     .map_err(|e| { let e: BackendMessage = e.into(); e });
                                                 match query_result {
-                                                    Ok(QueryPlanResult::Selected((desc, data))) => {
-                                                        self.sender
-                                                            .send(QueryEvent::RowDescription(
-                                                                desc.into_iter()
-                                                                    .map(|col_def| {
-                                                                        let pg_type: PgType = (&col_def.sql_type()).into();
-                                                                        ColumnMetadata::new(col_def.name(), pg_type)
-                                                                    })
-                                                                    .collect(),
-                                                            ).into())
-                                                            .expect("To Send to client");
-                                                        let len = data.len();
-                                                        for row in data {
-                                                            self.sender
-                                                                .send(QueryEvent::DataRow(
-                                                                    row.into_iter()
-                                                                        .map(|scalar| scalar.as_text())
-                                                                        .collect(),
-                                                                ).into())
-                                                                .expect("To Send to client");
-                                                        }
-                                                        self.sender
-                                                            .send(QueryEvent::RecordsSelected(len).into())
-                                                            .expect("To Send to client");
-                                                    }
+                                                    Ok(QueryPlanResult::Selected((desc, rows))) => send_selected(&outbox, &db, desc, rows),
                                                     Ok(_) => unreachable!(),
                                                     Err(error) => {
-                                                        self.sender.send(error).expect("To Send to client");
+                                                        outbox.send(error).expect("To Send to client");
                                                     }
                                                 }
                                             }
@@ -309,19 +312,21 @@ impl QueryEngine {
                                                         full_table_name: delete.full_table_name,
                                                         filter: type_coerced_filter
                                                     }))
-                                                    .execute(param_values)
+                                                    .and_then(|plan| plan.execute(param_values).map_err(QueryError::from))
                                                     .map(|r| { let r: QueryEvent = r.into(); r })
                                                     .map(|r| { let r: BackendMessage = r.into(); r })
-                                                    .map_err(|e| { let e: QueryError = e.into(); e })
                                                     .map_err(|e| { let e: BackendMessage = e.into(); e }) {
                                                     Ok(ok) => ok,
-                                                    Err(err) => err,
+                                                    Err(err) => {
+                                                        rolled_back.set(true);
+                                                        err
+                                                    }
                                                 };
-                                                self.sender.send(query_result).expect("To Send to client");
+                                                outbox.send(query_result).expect("To Send to client");
                                             }
                                         },
                                         None => {
-                                            self.sender
+                                            outbox
                                                 .send(QueryError::prepared_statement_does_not_exist(name).into())
                                                 .expect("To Send Error to Client");
                                         }
@@ -329,7 +334,7 @@ impl QueryEngine {
                                 }
                                 Extended::Deallocate { name } => {
                                     session.remove_portal(&name);
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementDeallocated.into())
                                         .expect("To Send Statement Deallocated Event");
                                 }
@@ -343,16 +348,25 @@ impl QueryEngine {
                                         Ok(ExecutionOutcome::TableCreated) => QueryEvent::TableCreated.into(),
                                         Ok(ExecutionOutcome::TableDropped) => QueryEvent::TableDropped.into(),
                                         Ok(ExecutionOutcome::IndexCreated) => QueryEvent::IndexCreated.into(),
+                                        Ok(ExecutionOutcome::IndexDropped) => QueryEvent::IndexDropped.into(),
+                                        Ok(ExecutionOutcome::SequenceCreated) => QueryEvent::SequenceCreated.into(),
+                                        Ok(ExecutionOutcome::SequenceDropped) => QueryEvent::SequenceDropped.into(),
+                                        Ok(ExecutionOutcome::TablesAnalyzed) => QueryEvent::TablesAnalyzed.into(),
+                                        Ok(ExecutionOutcome::DatabaseCreated) => QueryEvent::DatabaseCreated.into(),
+                                        Ok(ExecutionOutcome::DatabaseDropped) => QueryEvent::DatabaseDropped.into(),
+                                        Ok(ExecutionOutcome::TableAltered) => QueryEvent::TableAltered.into(),
+                                        Ok(ExecutionOutcome::SchemaAltered) => QueryEvent::SchemaAltered.into(),
                                         Err(error) => {
+                                            rolled_back.set(true);
                                             let error: QueryError = error.into();
                                             error.into()
                                         },
                                     };
-                                    self.sender.send(query_result).expect("To Send Result to Client");
+                                    outbox.send(query_result).expect("To Send Result to Client");
                                 }
                                 Err(error) => {
                                     let error: QueryError = error.into();
-                                    self.sender.send(error.into()).expect("To Send Result to Client")
+                                    outbox.send(error.into()).expect("To Send Result to Client")
                                 },
                             },
                             Statement::Query(query) => match query_analyzer.analyze(query) {
@@ -375,15 +389,17 @@ impl QueryEngine {
                                             full_table_name: delete.full_table_name,
                                             filter: type_coerced_filter
                                         }))
-                                        .execute(vec![])
+                                        .and_then(|plan| plan.execute(vec![]).map_err(QueryError::from))
                                         .map(|r| { let r: QueryEvent = r.into(); r })
                                         .map(|r| { let r: BackendMessage = r.into(); r })
-                                        .map_err(|e| { let e: QueryError = e.into(); e })
                                         .map_err(|e| { let e: BackendMessage = e.into(); e }) {
                                         Ok(ok) => ok,
-                                        Err(err) => err,
+                                        Err(err) => {
+                                            rolled_back.set(true);
+                                            err
+                                        }
                                     };
-                                    self.sender.send(query_result).expect("To Send to client");
+                                    outbox.send(query_result).expect("To Send to client");
                                 }
                                 Ok(UntypedQuery::Update(update)) => {
                                     let typed_values = update
@@ -421,15 +437,17 @@ impl QueryEngine {
                                             assignments: type_coerced,
                                             filter: type_coerced_filter
                                         }))
-                                        .execute(vec![])
+                                        .and_then(|plan| plan.execute(vec![]).map_err(QueryError::from))
                                         .map(|r| { let r: QueryEvent = r.into(); r })
                                         .map(|r| { let r: BackendMessage = r.into(); r })
-                                        .map_err(|e| { let e: QueryError = e.into(); e })
                                         .map_err(|e| { let e: BackendMessage = e.into(); e }) {
                                         Ok(ok) => ok,
-                                        Err(err) => err,
+                                        Err(err) => {
+                                            rolled_back.set(true);
+                                            err
+                                        }
                                     };
-                                    self.sender.send(query_result).expect("To Send to client");
+                                    outbox.send(query_result).expect("To Send to client");
                                 }
                                 Ok(UntypedQuery::Insert(insert)) => {
                                     log::debug!("INSERT UNTYPED VALUES {:?}", insert.values);
@@ -475,15 +493,17 @@ impl QueryEngine {
                                             full_table_name: insert.full_table_name,
                                             values: type_coerced,
                                         }))
-                                        .execute(vec![])
+                                        .and_then(|plan| plan.execute(vec![]).map_err(QueryError::from))
                                         .map(|r| { let r: QueryEvent = r.into(); r })
                                         .map(|r| { let r: BackendMessage = r.into(); r })
-                                        .map_err(|e| { let e: QueryError = e.into(); e })
                                         .map_err(|e| { let e: BackendMessage = e.into(); e }) {
                                         Ok(ok) => ok,
-                                        Err(err) => err,
+                                        Err(err) => {
+                                            rolled_back.set(true);
+                                            err
+                                        }
                                     };
-                                    self.sender.send(query_result).expect("To Send to client");
+                                    outbox.send(query_result).expect("To Send to client");
                                 }
                                 Ok(UntypedQuery::Select(select)) => {
                                     log::debug!("SELECT UNTYPED VALUES - {:?}", select.projection_items);
@@ -522,58 +542,35 @@ impl QueryEngine {
                                             full_table_name: select.full_table_name,
                                             filter: type_coerced_filter
                                         }))
-                                        .execute(vec![])
-                                        .map_err(|e| { let e: QueryError = e.into(); e })
+                                        .and_then(|plan| plan.execute(vec![]).map_err(QueryError::from))
                                         .map_err(|e| { let e: BackendMessage = e.into(); e });
                                     match query_result {
-                                        Ok(QueryPlanResult::Selected((desc, data))) => {
-                                            self.sender
-                                                .send(QueryEvent::RowDescription(
-                                                    desc.into_iter()
-                                                        .map(|col_def| {
-                                                            let pg_type: PgType = (&col_def.sql_type()).into();
-                                                            ColumnMetadata::new(col_def.name(), pg_type)
-                                                        })
-                                                        .collect(),
-                                                ).into())
-                                                .expect("To Send to client");
-                                            let len = data.len();
-                                            for row in data {
-                                                self.sender
-                                                    .send(QueryEvent::DataRow(
-                                                        row.into_iter().map(|scalar| scalar.as_text()).collect(),
-                                                    ).into())
-                                                    .expect("To Send to client");
-                                            }
-                                            self.sender
-                                                .send(QueryEvent::RecordsSelected(len).into())
-                                                .expect("To Send to client");
-                                        }
+                                        Ok(QueryPlanResult::Selected((desc, rows))) => send_selected(&outbox, &db, desc, rows),
                                         Ok(_) => unreachable!(),
                                         Err(error) => {
-                                            self.sender.send(error).expect("To Send to client");
+                                            outbox.send(error).expect("To Send to client");
                                         }
                                     }
                                 }
                                 Err(error) => {
                                     let error: QueryError = error.into();
-                                    self.sender.send(error.into()).expect("To Send Error to Client");
+                                    outbox.send(error.into()).expect("To Send Error to Client");
                                 }
                             },
                             Statement::Config(_) => {
                                 // sending ok to the client to proceed with other requests
-                                self.sender
+                                outbox
                                     .send(QueryEvent::VariableSet.into())
                                     .expect("To Send Result to Client");
                             }
                         },
                         Err(parser_error) => {
-                            self.sender
-                                .send(QueryError::syntax_error(parser_error).into())
+                            outbox
+                                .send(QueryError::from(parser_error).into())
                                 .expect("To Send ParseComplete Event");
                         }
                     }
-                    self.sender
+                    outbox
                         .send(QueryEvent::QueryComplete.into())
                         .expect("To Send Query Complete to Client");
                     Ok(())
@@ -595,16 +592,15 @@ impl QueryEngine {
                                             .map(|o| o.unwrap())
                                             .collect(),
                                     );
-                                    self.sender.send(QueryEvent::ParseComplete.into()).expect("To Send Result");
+                                    outbox.send(QueryEvent::ParseComplete.into()).expect("To Send Result");
                                 }
-                                other => self
-                                    .sender
+                                other => outbox
                                     .send(QueryError::syntax_error(format!("{:?}", other)).into())
                                     .expect("To Send Result"),
                             },
                             Err(parser_error) => {
-                                self.sender
-                                    .send(QueryError::syntax_error(parser_error).into())
+                                outbox
+                                    .send(QueryError::from(parser_error).into())
                                     .expect("To Send Syntax Error Event");
                             }
                         },
@@ -624,16 +620,15 @@ impl QueryEngine {
                                             PreparedStatement::parsed(sql.clone(), query),
                                         );
                                     }
-                                    self.sender.send(QueryEvent::ParseComplete.into()).expect("To Send Result");
+                                    outbox.send(QueryEvent::ParseComplete.into()).expect("To Send Result");
                                 }
-                                other => self
-                                    .sender
+                                other => outbox
                                     .send(QueryError::syntax_error(format!("{:?}", other)).into())
                                     .expect("To Send Result"),
                             },
                             Err(parser_error) => {
-                                self.sender
-                                    .send(QueryError::syntax_error(parser_error).into())
+                                outbox
+                                    .send(QueryError::from(parser_error).into())
                                     .expect("To Send Syntax Error Event");
                             }
                         },
@@ -656,10 +651,10 @@ impl QueryEngine {
                                         .map(ColumnDef::sql_type)
                                         .map(|sql_type| (&sql_type).into())
                                         .collect::<Vec<PgType>>();
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementParameters(param_types.to_vec()).into())
                                         .expect("To Send Statement Parameters to Client");
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementDescription(vec![]).into())
                                         .expect("To Send Statement Description to Client");
                                     statement.described(UntypedQuery::Insert(insert), param_types);
@@ -675,10 +670,10 @@ impl QueryEngine {
                                         .map(ColumnDef::sql_type)
                                         .map(|sql_type| (&sql_type).into())
                                         .collect::<Vec<PgType>>();
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementParameters(param_types.to_vec()).into())
                                         .expect("To Send Statement Parameters to Client");
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementDescription(vec![]).into())
                                         .expect("To Send Statement Description to Client");
                                     statement.described(UntypedQuery::Update(update), param_types);
@@ -694,34 +689,34 @@ impl QueryEngine {
                                         .map(|col_def| (col_def.name().to_owned(), col_def.sql_type()))
                                         .map(|(name, sql_type)| (name, (&sql_type).into()))
                                         .collect::<Vec<(String, PgType)>>();
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementParameters(vec![]).into())
                                         .expect("To Send Statement Parameters to Client");
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementDescription(return_types).into())
                                         .expect("To Send Statement Description to Client");
                                     statement.described(UntypedQuery::Select(select), vec![]);
                                 }
                                 _ => {
-                                    self.sender
+                                    outbox
                                         .send(QueryError::prepared_statement_does_not_exist(name).into())
                                         .expect("To Send Error to Client");
                                 }
                             },
                             Some(param_types) => match query_analyzer.analyze(statement.query().unwrap()) {
                                 Ok(UntypedQuery::Insert(_insert)) => {
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementParameters(param_types.to_vec()).into())
                                         .expect("To Send Statement Parameters to Client");
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementDescription(vec![]).into())
                                         .expect("To Send Statement Description to Client");
                                 }
                                 Ok(UntypedQuery::Update(_update)) => {
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementParameters(param_types.to_vec()).into())
                                         .expect("To Send Statement Parameters to Client");
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementDescription(vec![]).into())
                                         .expect("To Send Statement Description to Client");
                                 }
@@ -734,15 +729,15 @@ impl QueryEngine {
                                         .map(|col_def| (col_def.name().to_owned(), col_def.sql_type()))
                                         .map(|(name, sql_type)| (name, (&sql_type).into()))
                                         .collect::<Vec<(String, PgType)>>();
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementParameters(param_types.to_vec()).into())
                                         .expect("To Send Statement Parameters to Client");
-                                    self.sender
+                                    outbox
                                         .send(QueryEvent::StatementDescription(return_types).into())
                                         .expect("To Send Statement Description to Client");
                                 }
                                 _ => {
-                                    self.sender
+                                    outbox
                                         .send(QueryError::prepared_statement_does_not_exist(name).into())
                                         .expect("To Send Error to Client");
                                 }
@@ -750,7 +745,7 @@ impl QueryEngine {
                         },
                         other => {
                             log::debug!("STMT {:?} associated with {:?} key", other, name);
-                            self.sender
+                            outbox
                                 .send(QueryError::prepared_statement_does_not_exist(name).into())
                                 .expect("To Send Error to Client");
                         }
@@ -774,7 +769,7 @@ impl QueryEngine {
                                         actual = raw_params.len(),
                                         expected = param_types.len()
                                     );
-                                    self.sender
+                                    outbox
                                         .send(QueryError::protocol_violation(message).into())
                                         .expect("To Send Error to Client");
                                 }
@@ -795,7 +790,7 @@ impl QueryEngine {
                                             match typ.decode(&format, &bytes) {
                                                 Ok(param) => param_values.push(From::from(param)),
                                                 Err(error) => {
-                                                    self.sender
+                                                    outbox
                                                         .send(QueryError::invalid_parameter_value(error).into())
                                                         .expect("To Send Error to Client");
                                                     return Err(ConflictableTransactionError::Abort);
@@ -820,12 +815,12 @@ impl QueryEngine {
                     match portal {
                         Some(portal) => {
                             session.set_portal(portal_name.clone(), portal);
-                            self.sender
+                            outbox
                                 .send(QueryEvent::BindComplete.into())
                                 .expect("To Send Bind Complete Event");
                         }
                         None => {
-                            self.sender
+                            outbox
                                 .send(QueryError::prepared_statement_does_not_exist(statement_name).into())
                                 .expect("To Send Error to Client");
                         }
@@ -835,13 +830,13 @@ impl QueryEngine {
                 CommandMessage::DescribePortal { name } => {
                     match session.get_portal(&name) {
                         None => {
-                            self.sender
+                            outbox
                                 .send(QueryError::portal_does_not_exist(name).into())
                                 .expect("To Send Error to Client");
                         }
                         Some(_portal) => {
                             log::debug!("DESCRIBING PORTAL START");
-                            self.sender
+                            outbox
                                 .send(QueryEvent::StatementDescription(vec![]).into())
                                 .expect("To Send Statement Description to Client");
                             log::debug!("DESCRIBING PORTAL END");
@@ -901,15 +896,17 @@ impl QueryEngine {
                                         full_table_name: insert.full_table_name,
                                         values: type_coerced,
                                     }))
-                                    .execute(portal.param_values())
+                                    .and_then(|plan| plan.execute(portal.param_values()).map_err(QueryError::from))
                                     .map(|r| { let r: QueryEvent = r.into(); r })
                                     .map(|r| { let r: BackendMessage = r.into(); r })
-                                    .map_err(|e| { let e: QueryError = e.into(); e })
                                     .map_err(|e| { let e: BackendMessage = e.into(); e }) {
                                     Ok(ok) => ok,
-                                    Err(err) => err,
+                                    Err(err) => {
+                                        rolled_back.set(true);
+                                        err
+                                    }
                                 };
-                                self.sender.send(query_result).expect("To Send to client");
+                                outbox.send(query_result).expect("To Send to client");
                             }
                             UntypedQuery::Update(update) => {
                                 let typed_values = update
@@ -949,15 +946,17 @@ impl QueryEngine {
                                         assignments: type_coerced,
                                         filter: type_coerced_filter
                                     }))
-                                    .execute(portal.param_values())
+                                    .and_then(|plan| plan.execute(portal.param_values()).map_err(QueryError::from))
                                     .map(|r| { let r: QueryEvent = r.into(); r })
                                     .map(|r| { let r: BackendMessage = r.into(); r })
-                                    .map_err(|e| { let e: QueryError = e.into(); e })
                                     .map_err(|e| { let e: BackendMessage = e.into(); e }) {
                                     Ok(ok) => ok,
-                                    Err(err) => err,
+                                    Err(err) => {
+                                        rolled_back.set(true);
+                                        err
+                                    }
                                 };
-                                self.sender.send(query_result).expect("To Send to client");
+                                outbox.send(query_result).expect("To Send to client");
                             }
                             UntypedQuery::Select(select) => {
                                 log::debug!("SELECT UNTYPED VALUES - {:?}", select.projection_items);
@@ -996,36 +995,13 @@ impl QueryEngine {
                                         full_table_name: select.full_table_name,
                                         filter: type_coerced_filter
                                     }))
-                                    .execute(vec![])
-                                    .map_err(|e| { let e: QueryError = e.into(); e })
+                                    .and_then(|plan| plan.execute(vec![]).map_err(QueryError::from))
                                     .map_err(|e| { let e: BackendMessage = e.into(); e });
                                 match query_result {
-                                    Ok(QueryPlanResult::Selected((desc, data))) => {
-                                        self.sender
-                                            .send(QueryEvent::RowDescription(
-                                                desc.into_iter()
-                                                    .map(|col_def| {
-                                                        let pg_type: PgType = (&col_def.sql_type()).into();
-                                                        ColumnMetadata::new(col_def.name(), pg_type)
-                                                    })
-                                                    .collect(),
-                                            ).into())
-                                            .expect("To Send to client");
-                                        let len = data.len();
-                                        for row in data {
-                                            self.sender
-                                                .send(QueryEvent::DataRow(
-                                                    row.into_iter().map(|scalar| scalar.as_text()).collect(),
-                                                ).into())
-                                                .expect("To Send to client");
-                                        }
-                                        self.sender
-                                            .send(QueryEvent::RecordsSelected(len).into())
-                                            .expect("To Send to client");
-                                    }
+                                    Ok(QueryPlanResult::Selected((desc, rows))) => send_selected(&outbox, &db, desc, rows),
                                     Ok(_) => unreachable!(),
                                     Err(error) => {
-                                        self.sender.send(error).expect("To Send to client");
+                                        outbox.send(error).expect("To Send to client");
                                     }
                                 }
                             }
@@ -1048,19 +1024,21 @@ impl QueryEngine {
                                         full_table_name: delete.full_table_name,
                                         filter: type_coerced_filter
                                     }))
-                                    .execute(vec![])
+                                    .and_then(|plan| plan.execute(vec![]).map_err(QueryError::from))
                                     .map(|r| { let r: QueryEvent = r.into(); r })
                                     .map(|r| { let r: BackendMessage = r.into(); r })
-                                    .map_err(|e| { let e: QueryError = e.into(); e })
                                     .map_err(|e| { let e: BackendMessage = e.into(); e }) {
                                     Ok(ok) => ok,
-                                    Err(err) => err,
+                                    Err(err) => {
+                                        rolled_back.set(true);
+                                        err
+                                    }
                                 };
-                                self.sender.send(query_result).expect("To Send to client");
+                                outbox.send(query_result).expect("To Send to client");
                             }
                         },
                         None => {
-                            self.sender
+                            outbox
                                 .send(QueryError::portal_does_not_exist(portal_name).into())
                                 .expect("To Send Error to Client");
                         }
@@ -1068,25 +1046,25 @@ impl QueryEngine {
                     Ok(())
                 }
                 CommandMessage::CloseStatement { .. } => {
-                    self.sender
+                    outbox
                         .send(QueryEvent::QueryComplete.into())
                         .expect("To Send Query Complete to Client");
                     Ok(())
                 }
                 CommandMessage::ClosePortal { .. } => {
-                    self.sender
+                    outbox
                         .send(QueryEvent::QueryComplete.into())
                         .expect("To Send Query Complete to Client");
                     Ok(())
                 }
                 CommandMessage::Sync => {
-                    self.sender
+                    outbox
                         .send(QueryEvent::QueryComplete.into())
                         .expect("To Send Query Complete to Client");
                     Ok(())
                 }
                 CommandMessage::Flush => {
-                    self.sender.flush().expect("Send All Buffered Messages to Client");
+                    outbox.flush().expect("Send All Buffered Messages to Client");
                     Ok(())
                 }
                 CommandMessage::Terminate => {
@@ -1095,10 +1073,84 @@ impl QueryEngine {
                 }
             };
             log::trace!("TRANSACTION END");
+            if rolled_back.get() {
+                return Err(ConflictableTransactionError::Abort);
+            }
             result
-        })
+        });
+        let result = match result {
+            Err(TransactionError::Storage(error)) => {
+                // nothing was committed and the client should not see results of the transaction
+                outbox.clear();
+                outbox
+                    .send(storage_error(error).into())
+                    .expect("To Send Error to Client");
+                if matches!(*inner, CommandMessage::Query { .. } | CommandMessage::Sync) {
+                    outbox
+                        .send(QueryEvent::QueryComplete.into())
+                        .expect("To Send Query Complete to Client");
+                }
+                Ok(())
+            }
+            Err(TransactionError::Abort) if rolled_back.get() => Ok(()),
+            result => result,
+        };
+        outbox.deliver();
+        result
+    }
+}
+
+/// `SELECT` only reads and its transaction is never retried, so rows go to the client as they are read.
+/// A scan that stopped on a storage error is not completed, the error is sent when the transaction fails
+fn send_selected(outbox: &Outbox, db: &TransactionalDatabase, columns: Vec<ColumnDef>, rows: SelectedRows) {
+    outbox.stream();
+    outbox
+        .send(
+            QueryEvent::RowDescription(
+                columns
+                    .into_iter()
+                    .map(|col_def| {
+                        let pg_type: PgType = (&col_def.sql_type()).into();
+                        ColumnMetadata::new(col_def.name(), pg_type)
+                    })
+                    .collect(),
+            )
+            .into(),
+        )
+        .expect("To Send to client");
+    let mut len = 0;
+    for row in rows {
+        match row {
+            Ok(row) => {
+                outbox
+                    .send(QueryEvent::DataRow(row.into_iter().map(|scalar| scalar.as_text()).collect()).into())
+                    .expect("To Send to client");
+                len += 1;
+            }
+            Err(error) => {
+                let error: QueryError = error.into();
+                outbox.send(error.into()).expect("To Send to client");
+                return;
+            }
+        }
+    }
+    if db.is_failed() {
+        return;
     }
+    outbox
+        .send(QueryEvent::RecordsSelected(len).into())
+        .expect("To Send to client");
 }
 
+fn storage_error(error: StorageError) -> QueryError {
+    match error {
+        StorageError::Io(message) => QueryError::io_error(message),
+        StorageError::TreeNotFound(table_name) => QueryError::table_does_not_exist(table_name),
+        StorageError::Corrupted(message) | StorageError::Encryption(message) => QueryError::data_corrupted(message),
+    }
+}
+
+mod outbox;
+
 #[cfg(test)]
 mod tests;
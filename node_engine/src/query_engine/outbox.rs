@@ -0,0 +1,83 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use postgres::wire_protocol::{payload::BackendMessage, Sender};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Holds messages to the client until transaction ends. Transaction could be retried
+/// on conflict and the client should not receive results of failed attempts.
+/// Statements that only read are never retried, their messages can go to the client right away
+pub(crate) struct Outbox {
+    sender: Arc<dyn Sender>,
+    messages: Mutex<Vec<BackendMessage>>,
+    flush: AtomicBool,
+    /// messages are not held until the attempt is over
+    streaming: AtomicBool,
+}
+
+impl Outbox {
+    pub(crate) fn new(sender: Arc<dyn Sender>) -> Outbox {
+        Outbox {
+            sender,
+            messages: Mutex::default(),
+            flush: AtomicBool::default(),
+            streaming: AtomicBool::default(),
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        self.messages.lock().unwrap().clear();
+        self.flush.store(false, Ordering::SeqCst);
+        self.streaming.store(false, Ordering::SeqCst);
+    }
+
+    /// held messages and the ones that are sent until the end of the attempt go to the client right away
+    pub(crate) fn stream(&self) {
+        self.deliver();
+        self.streaming.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn deliver(&self) {
+        for message in self.messages.lock().unwrap().drain(..) {
+            self.sender.send(message).expect("To Send to client");
+        }
+        if self.flush.swap(false, Ordering::SeqCst) {
+            self.sender.flush().expect("Send All Buffered Messages to Client");
+        }
+    }
+}
+
+impl Sender for Outbox {
+    fn flush(&self) -> io::Result<()> {
+        if self.streaming.load(Ordering::SeqCst) {
+            return self.sender.flush();
+        }
+        self.flush.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn send(&self, message: BackendMessage) -> io::Result<()> {
+        if self.streaming.load(Ordering::SeqCst) {
+            return self.sender.send(message);
+        }
+        self.messages.lock().unwrap().push(message);
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[rstest::rstest]
+fn add_and_rename_column(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (1, 2, 3);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter table schema_name.table_name add column col4 smallint, rename column col1 to id;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableAltered));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select id, col4 from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("id", PgType::SmallInt),
+            ColumnMetadata::new("col4", PgType::SmallInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec!["1".to_owned(), "NULL".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+}
+
+#[rstest::rstest]
+fn add_existing_column(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter table schema_name.table_name add column col1 integer;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::column_already_exists("col1", "table_name")));
+}
+
+#[rstest::rstest]
+fn drop_column(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (1, 2, 3);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter table schema_name.table_name drop column col2;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableAltered));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select * from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("col1", PgType::SmallInt),
+            ColumnMetadata::new("col3", PgType::SmallInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec!["1".to_owned(), "3".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+}
+
+#[rstest::rstest]
+fn rename_table(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter table schema_name.table_name rename to new_table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableAltered));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.new_table_name values (1, 2, 3);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+}
+
+#[rstest::rstest]
+fn rename_schema(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter schema schema_name rename to new_schema_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::SchemaAltered));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into new_schema_name.table_name values (1, 2, 3);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+}
@@ -0,0 +1,104 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[rstest::rstest]
+fn analyze_all_tables(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "analyze;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TablesAnalyzed));
+}
+
+#[rstest::rstest]
+fn analyze_non_existent_table(database_with_schema: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_schema;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "analyze schema_name.non_existent;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::table_does_not_exist("schema_name.non_existent")));
+}
+
+#[rstest::rstest]
+fn queries_on_analyzed_table_with_index(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create index index_name on schema_name.table_name (col1);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::IndexCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (1, 2, 3), (4, 5, 6), (7, 8, 9), (10, 11, 12), (13, 14, 15), (16, 17, 18);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(6)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "analyze schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TablesAnalyzed));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select col2 from schema_name.table_name where col1 = 4;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnMetadata::new(
+            "col2",
+            PgType::SmallInt,
+        )])),
+        Ok(QueryEvent::DataRow(vec!["5".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "update schema_name.table_name set col2 = 50 where col1 = 4;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsUpdated(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "delete from schema_name.table_name where col1 = 7;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsDeleted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select col1, col2 from schema_name.table_name where col1 = 4 or col1 = 7;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("col1", PgType::SmallInt),
+            ColumnMetadata::new("col2", PgType::SmallInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec!["4".to_owned(), "50".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+}
@@ -0,0 +1,234 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[rstest::fixture]
+fn table_with_defaults(database_with_schema: (InMemory, ResultCollector)) -> (InMemory, ResultCollector) {
+    let (mut engine, collector) = database_with_schema;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create table schema_name.table_name(id smallint not null, name varchar(10) default 'none');"
+                .to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_till_this_moment(vec![Ok(QueryEvent::TableCreated), Ok(QueryEvent::QueryComplete)]);
+
+    (engine, collector)
+}
+
+#[rstest::rstest]
+fn columns_without_values_get_defaults(table_with_defaults: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = table_with_defaults;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name (id) values (1);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (2, default), (3, null);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(2)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select * from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("id", PgType::SmallInt),
+            ColumnMetadata::new("name", PgType::VarChar),
+        ])),
+        Ok(QueryEvent::DataRow(vec!["1".to_owned(), "none".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["2".to_owned(), "none".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["3".to_owned(), "NULL".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(3)),
+    ]);
+}
+
+#[rstest::rstest]
+fn insert_null_into_not_null_column(table_with_defaults: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = table_with_defaults;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name (name) values ('name');".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::not_null_violation("id", "table_name")));
+}
+
+#[rstest::rstest]
+fn update_not_null_column_to_null(table_with_defaults: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = table_with_defaults;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (1, 'name');".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "update schema_name.table_name set id = null, name = default;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::not_null_violation("id", "table_name")));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "update schema_name.table_name set name = default;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsUpdated(1)));
+}
+
+#[rstest::rstest]
+fn add_not_null_column_to_table_with_rows(table_with_defaults: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = table_with_defaults;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (1, 'name');".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter table schema_name.table_name add column count integer not null;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::column_contains_nulls("count", "table_name")));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter table schema_name.table_name add column count integer not null default 0;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableAltered));
+}
+
+#[rstest::fixture]
+fn table_with_checks(database_with_schema: (InMemory, ResultCollector)) -> (InMemory, ResultCollector) {
+    let (mut engine, collector) = database_with_schema;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create table schema_name.table_name(\
+                price integer check (price > 0),\
+                discount integer,\
+                constraint valid_discount check (discount < price)\
+            );"
+            .to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_till_this_moment(vec![Ok(QueryEvent::TableCreated), Ok(QueryEvent::QueryComplete)]);
+
+    (engine, collector)
+}
+
+#[rstest::rstest]
+fn insert_rows_that_violate_checks(table_with_checks: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = table_with_checks;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (10, 5), (20, null);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(2)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (0, null);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::check_violation("table_name", "table_name_price_check")));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (10, 20);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::check_violation("table_name", "valid_discount")));
+}
+
+#[rstest::rstest]
+fn update_rows_to_violate_checks(table_with_checks: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = table_with_checks;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (10, 5);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "update schema_name.table_name set discount = 15;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::check_violation("table_name", "valid_discount")));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "update schema_name.table_name set price = 20, discount = 15;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsUpdated(1)));
+}
+
+#[rstest::rstest]
+fn add_and_drop_check_over_existing_rows(table_with_checks: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = table_with_checks;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (10, 5);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter table schema_name.table_name add constraint cheap check (price < 10);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::check_violated_by_row("cheap", "table_name")));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter table schema_name.table_name drop constraint valid_discount;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableAltered));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "alter table schema_name.table_name drop constraint valid_discount;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::constraint_does_not_exist(
+        "valid_discount",
+        "table_name",
+    )));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (10, 20);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+}
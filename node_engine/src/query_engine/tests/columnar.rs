@@ -0,0 +1,133 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+
+#[rstest::fixture]
+fn database_with_columnar_table(database_with_schema: (InMemory, ResultCollector)) -> (InMemory, ResultCollector) {
+    let (mut engine, collector) = database_with_schema;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create table schema_name.table_name (col1 smallint, col2 smallint, col3 smallint) with (storage = columnar);"
+                .to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (1, 2, 3), (4, 5, 6), (7, 8, 9);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(3)));
+
+    (engine, collector)
+}
+
+#[rstest::rstest]
+fn unknown_storage(database_with_schema: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_schema;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create table schema_name.table_name (col1 smallint) with (storage = heap);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::invalid_parameter_value(
+        "invalid value for option \"storage\": \"heap\"",
+    )));
+}
+
+#[rstest::rstest]
+fn select_all_columns(database_with_columnar_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_columnar_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select * from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("col1", PgType::SmallInt),
+            ColumnMetadata::new("col2", PgType::SmallInt),
+            ColumnMetadata::new("col3", PgType::SmallInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec![
+            "1".to_owned(),
+            "2".to_owned(),
+            "3".to_owned(),
+        ])),
+        Ok(QueryEvent::DataRow(vec![
+            "4".to_owned(),
+            "5".to_owned(),
+            "6".to_owned(),
+        ])),
+        Ok(QueryEvent::DataRow(vec![
+            "7".to_owned(),
+            "8".to_owned(),
+            "9".to_owned(),
+        ])),
+        Ok(QueryEvent::RecordsSelected(3)),
+    ]);
+}
+
+#[rstest::rstest]
+fn select_projected_columns_with_predicate(database_with_columnar_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_columnar_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select col3, col1 from schema_name.table_name where col2 > 2;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("col3", PgType::SmallInt),
+            ColumnMetadata::new("col1", PgType::SmallInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec!["6".to_owned(), "4".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["9".to_owned(), "7".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(2)),
+    ]);
+}
+
+#[rstest::rstest]
+fn update_and_delete(database_with_columnar_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_columnar_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "update schema_name.table_name set col2 = 50 where col1 = 4;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsUpdated(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "delete from schema_name.table_name where col1 = 7;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsDeleted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select col2 from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnMetadata::new(
+            "col2",
+            PgType::SmallInt,
+        )])),
+        Ok(QueryEvent::DataRow(vec!["2".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["50".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(2)),
+    ]);
+}
@@ -0,0 +1,112 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+fn connect(database: &Database, database_name: &str) -> (InMemory, ResultCollector) {
+    let collector = Collector::new();
+    (
+        InMemory::new(collector.clone(), database.clone(), database_name.to_owned()),
+        collector,
+    )
+}
+
+fn query(engine: &mut InMemory, sql: &str) {
+    engine
+        .execute(CommandMessage::Query { sql: sql.to_owned() })
+        .expect("query executed");
+}
+
+#[rstest::rstest]
+fn create_and_drop_database() {
+    setup_logger();
+    let database = Database::in_memory("");
+    let (mut engine, collector) = connect(&database, DEFAULT_DATABASE);
+
+    query(&mut engine, "create database database_name;");
+    collector.assert_receive_single(Ok(QueryEvent::DatabaseCreated));
+
+    query(&mut engine, "create database database_name;");
+    collector.assert_receive_single(Err(QueryError::database_already_exists("database_name")));
+
+    query(&mut engine, "drop database database_name;");
+    collector.assert_receive_single(Ok(QueryEvent::DatabaseDropped));
+
+    query(&mut engine, "drop database database_name;");
+    collector.assert_receive_single(Err(QueryError::database_does_not_exist("database_name")));
+
+    query(&mut engine, "drop database if exists database_name;");
+    collector.assert_receive_single(Ok(QueryEvent::DatabaseDropped));
+}
+
+#[rstest::rstest]
+fn objects_are_isolated_per_database() {
+    setup_logger();
+    let database = Database::in_memory("");
+    let (mut default, default_collector) = connect(&database, DEFAULT_DATABASE);
+    query(&mut default, "create database database_name;");
+    default_collector.assert_receive_single(Ok(QueryEvent::DatabaseCreated));
+
+    let (mut other, other_collector) = connect(&database, "database_name");
+    query(&mut other, "create schema schema_name;");
+    other_collector.assert_receive_single(Ok(QueryEvent::SchemaCreated));
+    query(&mut other, "create table schema_name.table_name (col smallint);");
+    other_collector.assert_receive_single(Ok(QueryEvent::TableCreated));
+    query(&mut other, "insert into schema_name.table_name values (1);");
+    other_collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    query(&mut default, "insert into schema_name.table_name values (1);");
+    default_collector.assert_receive_single(Err(QueryError::schema_does_not_exist("schema_name")));
+    query(&mut default, "create schema schema_name;");
+    default_collector.assert_receive_single(Ok(QueryEvent::SchemaCreated));
+}
+
+#[rstest::rstest]
+fn current_database_is_not_dropped() {
+    setup_logger();
+    let database = Database::in_memory("");
+    let (mut default, default_collector) = connect(&database, DEFAULT_DATABASE);
+    query(&mut default, "create database database_name;");
+    default_collector.assert_receive_single(Ok(QueryEvent::DatabaseCreated));
+
+    let (mut other, other_collector) = connect(&database, "database_name");
+    query(&mut other, "drop database database_name;");
+    other_collector.assert_receive_single(Err(QueryError::database_is_in_use("database_name")));
+
+    query(&mut other, "drop database postgres;");
+    other_collector.assert_receive_single(Err(QueryError::feature_not_supported(
+        "dropping of \"postgres\" database",
+    )));
+}
+
+#[rstest::rstest]
+fn connection_to_dropped_database_is_closed() {
+    setup_logger();
+    let database = Database::in_memory("");
+    let (mut default, default_collector) = connect(&database, DEFAULT_DATABASE);
+    query(&mut default, "create database database_name;");
+    default_collector.assert_receive_single(Ok(QueryEvent::DatabaseCreated));
+    let (mut other, other_collector) = connect(&database, "database_name");
+
+    query(&mut default, "drop database database_name;");
+    default_collector.assert_receive_single(Ok(QueryEvent::DatabaseDropped));
+
+    assert!(other
+        .execute(CommandMessage::Query {
+            sql: "create schema schema_name;".to_owned()
+        })
+        .is_err());
+    other_collector
+        .assert_receive_till_this_moment(vec![Err(QueryError::fatal_database_does_not_exist("database_name"))]);
+}
@@ -0,0 +1,172 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use std::time::{Duration, Instant};
+use storage::{Backends, Effect, Fault, Faults, Operation};
+
+const TABLE: &str = "schema_name.table_name";
+
+#[rstest::fixture]
+fn faults() -> Faults {
+    Faults::default()
+}
+
+#[rstest::fixture]
+fn faulty_database(faults: Faults) -> (InMemory, ResultCollector, Faults) {
+    setup_logger();
+    let mut backends = Backends::default();
+    backends
+        .register_faulty("faulty", "in_memory", faults.clone())
+        .expect("in memory backend is registered");
+    let collector = Collector::new();
+    let mut engine = InMemory::new(
+        collector.clone(),
+        backends.open("faulty", "").expect("faulty database is opened"),
+        DEFAULT_DATABASE.to_owned(),
+    );
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create schema schema_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::SchemaCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create table schema_name.table_name (col1 smallint, col2 smallint, col3 smallint);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (1, 2, 3);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    (engine, collector, faults)
+}
+
+fn on_table(operation: Operation, effect: Effect) -> Fault {
+    Fault {
+        tree: Some(TABLE.to_owned()),
+        ..Fault::new(operation, effect)
+    }
+}
+
+#[rstest::rstest]
+fn failed_write(faulty_database: (InMemory, ResultCollector, Faults)) {
+    let (mut engine, collector, faults) = faulty_database;
+    faults.inject(on_table(Operation::Write, Effect::Fail));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (4, 5, 6);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::io_error(
+        "injected failure of Write operation on \"schema_name.table_name\"",
+    )));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (7, 8, 9);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::io_error(
+        "previous commit was not applied completely, database has to be restarted",
+    )));
+}
+
+#[rstest::rstest]
+fn corrupted_read(faulty_database: (InMemory, ResultCollector, Faults)) {
+    let (mut engine, collector, faults) = faulty_database;
+    faults.inject(on_table(Operation::Read, Effect::Corrupt));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select * from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_till_this_moment(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("col1", PgType::SmallInt),
+            ColumnMetadata::new("col2", PgType::SmallInt),
+            ColumnMetadata::new("col3", PgType::SmallInt),
+        ])),
+        Err(QueryError::data_corrupted("unknown type tag 254")),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select * from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("col1", PgType::SmallInt),
+            ColumnMetadata::new("col2", PgType::SmallInt),
+            ColumnMetadata::new("col3", PgType::SmallInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec![
+            "1".to_owned(),
+            "2".to_owned(),
+            "3".to_owned(),
+        ])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+}
+
+#[rstest::rstest]
+fn failed_read(faulty_database: (InMemory, ResultCollector, Faults)) {
+    let (mut engine, collector, faults) = faulty_database;
+    faults.inject(on_table(Operation::Read, Effect::Fail));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select * from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_till_this_moment(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("col1", PgType::SmallInt),
+            ColumnMetadata::new("col2", PgType::SmallInt),
+            ColumnMetadata::new("col3", PgType::SmallInt),
+        ])),
+        Err(QueryError::io_error(
+            "injected failure of Read operation on \"schema_name.table_name\"",
+        )),
+        Ok(QueryEvent::QueryComplete),
+    ]);
+}
+
+#[rstest::rstest]
+fn delayed_write(faulty_database: (InMemory, ResultCollector, Faults)) {
+    let (mut engine, collector, faults) = faulty_database;
+    faults.inject(on_table(Operation::Write, Effect::Delay(Duration::from_millis(50))));
+
+    let start = Instant::now();
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (4, 5, 6);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert_eq!(faults.happened(), 1);
+}
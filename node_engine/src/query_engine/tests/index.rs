@@ -0,0 +1,97 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[rstest::rstest]
+fn drop_index(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create index index_name on schema_name.table_name (col1);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::IndexCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "drop index schema_name.index_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::IndexDropped));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "drop index schema_name.index_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::index_does_not_exist("schema_name.index_name")));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "drop index if exists schema_name.index_name cascade;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::IndexDropped));
+}
+
+#[rstest::rstest]
+fn create_index_with_existing_name(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create index index_name on schema_name.table_name (col1);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::IndexCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create index index_name on schema_name.table_name (col2);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::index_already_exists("schema_name.index_name")));
+}
+
+#[rstest::rstest]
+fn table_is_readable_after_index_is_created(database_with_table: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create index index_name on schema_name.table_name (col1);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::IndexCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (1, 2, 3);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select col1 from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnMetadata::new(
+            "col1",
+            PgType::SmallInt,
+        )])),
+        Ok(QueryEvent::DataRow(vec!["1".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+}
@@ -22,12 +22,27 @@ use std::{
     ops::DerefMut,
     sync::{Arc, Mutex},
 };
+use storage::DEFAULT_DATABASE;
 
+#[cfg(test)]
+mod alter_table;
+#[cfg(test)]
+mod analyze;
+#[cfg(test)]
+mod column_constraints;
+#[cfg(test)]
+mod columnar;
+#[cfg(test)]
+mod database;
 #[cfg(test)]
 mod delete;
 #[cfg(test)]
 mod extended_query_flow;
 #[cfg(test)]
+mod fault_injection;
+#[cfg(test)]
+mod index;
+#[cfg(test)]
 mod insert;
 #[cfg(test)]
 mod predicate;
@@ -36,8 +51,12 @@ mod schema;
 #[cfg(test)]
 mod select;
 #[cfg(test)]
+mod sequence;
+#[cfg(test)]
 mod simple_prepared_statement;
 #[cfg(test)]
+mod stat_views;
+#[cfg(test)]
 mod table;
 #[cfg(test)]
 mod type_constraints;
@@ -135,7 +154,10 @@ impl Collector {
 fn empty_database() -> (InMemory, ResultCollector) {
     setup_logger();
     let collector = Collector::new();
-    (InMemory::new(collector.clone(), Database::in_memory("")), collector)
+    (
+        InMemory::new(collector.clone(), Database::in_memory(""), DEFAULT_DATABASE.to_owned()),
+        collector,
+    )
 }
 
 #[rstest::fixture]
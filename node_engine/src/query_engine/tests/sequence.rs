@@ -0,0 +1,228 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[rstest::rstest]
+fn create_and_drop_sequence(database_with_schema: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_schema;
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create sequence schema_name.seq;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::SequenceCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create sequence schema_name.seq;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::sequence_already_exists("schema_name.seq")));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "drop sequence schema_name.seq;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::SequenceDropped));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "drop sequence schema_name.seq;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::sequence_does_not_exist("schema_name.seq")));
+}
+
+#[rstest::rstest]
+fn insert_sequence_values(database_with_schema: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_schema;
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create sequence schema_name.seq start 10 increment 5;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::SequenceCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create table schema_name.table_name (col bigint);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (currval('schema_name.seq'));".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::sequence_value_not_defined("seq")));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (nextval('schema_name.seq')), (nextval('schema_name.seq')), (currval('schema_name.seq'));".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(3)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (setval('schema_name.seq', 100, false)), (nextval('schema_name.seq'));".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(2)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select * from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnMetadata::new(
+            "col",
+            PgType::BigInt,
+        )])),
+        Ok(QueryEvent::DataRow(vec!["10".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["15".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["15".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["100".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["100".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(5)),
+    ]);
+}
+
+#[rstest::rstest]
+fn sequence_reaches_its_maximum(database_with_schema: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_schema;
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create sequence schema_name.seq maxvalue 1;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::SequenceCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create table schema_name.table_name (col bigint);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql:
+                "insert into schema_name.table_name values (nextval('schema_name.seq')), (nextval('schema_name.seq'));"
+                    .to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::sequence_limit_exceeded("seq", true, 1)));
+}
+
+#[rstest::rstest]
+fn serial_column(database_with_schema: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_schema;
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create table schema_name.table_name (id serial, col smallint);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name (col) values (1), (2);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(2)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (10, 3);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select * from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("id", PgType::Integer),
+            ColumnMetadata::new("col", PgType::SmallInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec!["1".to_owned(), "1".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["2".to_owned(), "2".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["10".to_owned(), "3".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(3)),
+    ]);
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "drop sequence schema_name.table_name_id_seq;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::sequence_is_required(
+        "table_name_id_seq",
+        "id",
+        "table_name",
+    )));
+}
+
+#[rstest::rstest]
+fn generated_always_identity_column(database_with_schema: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_schema;
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "create table schema_name.table_name (id bigint generated always as identity, col smallint);"
+                .to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::TableCreated));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (10, 1);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::generated_always("id")));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name (col) values (1);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select * from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("id", PgType::BigInt),
+            ColumnMetadata::new("col", PgType::SmallInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec!["1".to_owned(), "1".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+}
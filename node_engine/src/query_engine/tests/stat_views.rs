@@ -0,0 +1,152 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[rstest::fixture]
+fn database_with_activity(database_with_table: (InMemory, ResultCollector)) -> (InMemory, ResultCollector) {
+    let (mut engine, collector) = database_with_table;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "insert into schema_name.table_name values (1, 2, 3), (4, 5, 6);".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsInserted(2)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select col1 from schema_name.table_name;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![ColumnMetadata::new(
+            "col1",
+            PgType::SmallInt,
+        )])),
+        Ok(QueryEvent::DataRow(vec!["1".to_owned()])),
+        Ok(QueryEvent::DataRow(vec!["4".to_owned()])),
+        Ok(QueryEvent::RecordsSelected(2)),
+    ]);
+
+    (engine, collector)
+}
+
+#[rstest::rstest]
+fn stat_user_tables(database_with_activity: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_activity;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select schemaname, relname, seq_scan, seq_tup_read, n_tup_ins, n_tup_upd, n_tup_del, n_live_tup \
+                  from pg_catalog.pg_stat_user_tables;"
+                .to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("schemaname", PgType::VarChar),
+            ColumnMetadata::new("relname", PgType::VarChar),
+            ColumnMetadata::new("seq_scan", PgType::BigInt),
+            ColumnMetadata::new("seq_tup_read", PgType::BigInt),
+            ColumnMetadata::new("n_tup_ins", PgType::BigInt),
+            ColumnMetadata::new("n_tup_upd", PgType::BigInt),
+            ColumnMetadata::new("n_tup_del", PgType::BigInt),
+            ColumnMetadata::new("n_live_tup", PgType::BigInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec![
+            "schema_name".to_owned(),
+            "table_name".to_owned(),
+            "1".to_owned(),
+            "2".to_owned(),
+            "2".to_owned(),
+            "0".to_owned(),
+            "0".to_owned(),
+            "2".to_owned(),
+        ])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+}
+
+#[rstest::rstest]
+fn updates_and_deletes_are_counted(database_with_activity: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_activity;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "update schema_name.table_name set col2 = 10 where col1 = 1;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsUpdated(1)));
+    engine
+        .execute(CommandMessage::Query {
+            sql: "delete from schema_name.table_name where col1 = 4;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Ok(QueryEvent::RecordsDeleted(1)));
+
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select n_tup_upd, n_tup_del, n_live_tup from pg_stat_user_tables;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("n_tup_upd", PgType::BigInt),
+            ColumnMetadata::new("n_tup_del", PgType::BigInt),
+            ColumnMetadata::new("n_live_tup", PgType::BigInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec![
+            "1".to_owned(),
+            "1".to_owned(),
+            "1".to_owned(),
+        ])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+}
+
+#[rstest::rstest]
+fn stat_database(database_with_activity: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = database_with_activity;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "select datname, tup_returned, tup_inserted, conflicts from pg_stat_database;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_many(vec![
+        Ok(QueryEvent::RowDescription(vec![
+            ColumnMetadata::new("datname", PgType::VarChar),
+            ColumnMetadata::new("tup_returned", PgType::BigInt),
+            ColumnMetadata::new("tup_inserted", PgType::BigInt),
+            ColumnMetadata::new("conflicts", PgType::BigInt),
+        ])),
+        Ok(QueryEvent::DataRow(vec![
+            DEFAULT_DATABASE.to_owned(),
+            "2".to_owned(),
+            "2".to_owned(),
+            "0".to_owned(),
+        ])),
+        Ok(QueryEvent::RecordsSelected(1)),
+    ]);
+}
+
+#[rstest::rstest]
+fn views_are_read_only(empty_database: (InMemory, ResultCollector)) {
+    let (mut engine, collector) = empty_database;
+    engine
+        .execute(CommandMessage::Query {
+            sql: "delete from pg_stat_database;".to_owned(),
+        })
+        .expect("query executed");
+    collector.assert_receive_single(Err(QueryError::feature_not_supported(
+        "cannot delete from view \"pg_stat_database\"",
+    )));
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::session::statement::{Portal, PreparedStatement};
+use catalog::CurrentValues;
 use std::collections::HashMap;
 
 /// Module contains functionality to hold data about `PreparedStatement`
@@ -25,6 +26,8 @@ pub struct Session {
     prepared_statements: HashMap<String, PreparedStatement>,
     /// A map from statement names to bound statements
     portals: HashMap<String, Portal>,
+    /// Values that sequences returned to the session
+    current_values: CurrentValues,
 }
 
 impl Default for Session {
@@ -32,6 +35,7 @@ impl Default for Session {
         Session {
             prepared_statements: HashMap::default(),
             portals: HashMap::default(),
+            current_values: CurrentValues::default(),
         }
     }
 }
@@ -60,4 +64,9 @@ impl Session {
     pub fn remove_portal(&mut self, portal_name: &str) {
         self.portals.remove(portal_name);
     }
+
+    /// values of sequences that `currval` returns in the session
+    pub fn current_values(&self) -> CurrentValues {
+        self.current_values.clone()
+    }
 }
@@ -14,6 +14,8 @@
 
 use std::fmt::{self, Display, Formatter};
 
+mod text;
+
 #[derive(Debug, PartialEq)]
 pub enum Statement {
     Definition(Definition),
@@ -38,13 +40,18 @@ pub enum Definition {
         schema_name: String,
         table_name: String,
         columns: Vec<ColumnDef>,
+        /// constraints of columns are listed here together with constraints of the table
+        constraints: Vec<TableConstraint>,
         if_not_exists: bool,
+        /// storage parameters from `WITH (name = value, ...)` clause
+        options: Vec<(String, String)>,
     },
     CreateIndex {
         name: String,
         schema_name: String,
         table_name: String,
         column_names: Vec<String>,
+        unique: bool,
     },
     DropSchemas {
         names: Vec<String>,
@@ -56,12 +63,113 @@ pub enum Definition {
         if_exists: bool,
         cascade: bool,
     },
+    /// schema and name of indexes
+    DropIndexes {
+        names: Vec<(String, String)>,
+        if_exists: bool,
+        cascade: bool,
+    },
+    /// all tables are analyzed if `names` is empty
+    Analyze {
+        names: Vec<(String, String)>,
+    },
+    CreateSequence {
+        schema_name: String,
+        sequence_name: String,
+        if_not_exists: bool,
+        /// options from `INCREMENT BY`, `MINVALUE`, `START WITH`, etc. clauses
+        options: Vec<(String, String)>,
+    },
+    DropSequences {
+        names: Vec<(String, String)>,
+        if_exists: bool,
+        cascade: bool,
+    },
+    CreateDatabase {
+        database_name: String,
+    },
+    DropDatabase {
+        database_name: String,
+        if_exists: bool,
+    },
+    AlterTable {
+        schema_name: String,
+        table_name: String,
+        if_exists: bool,
+        actions: Vec<AlterTableAction>,
+    },
+    RenameSchema {
+        schema_name: String,
+        new_name: String,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AlterTableAction {
+    AddColumn {
+        column: ColumnDef,
+        if_not_exists: bool,
+    },
+    DropColumn {
+        name: String,
+        if_exists: bool,
+        cascade: bool,
+    },
+    RenameColumn {
+        old_name: String,
+        new_name: String,
+    },
+    /// values of the column are converted by `using` expression if it is given
+    AlterColumnType {
+        name: String,
+        data_type: DataType,
+        using: Option<Expr>,
+    },
+    RenameTable {
+        new_name: String,
+    },
+    AddConstraint(TableConstraint),
+    DropConstraint {
+        name: String,
+        if_exists: bool,
+        cascade: bool,
+    },
+}
+
+/// `PRIMARY KEY`, `UNIQUE` or `CHECK` constraint, the name is generated if it is not given.
+/// `CHECK` constraints do not list columns, they refer to them in the expression
+#[derive(Debug, PartialEq)]
+pub struct TableConstraint {
+    pub name: Option<String>,
+    pub kind: ConstraintKind,
+    pub column_names: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+    Check(Expr),
 }
 
 #[derive(Debug, PartialEq)]
 pub struct ColumnDef {
     pub name: String,
     pub data_type: DataType,
+    pub identity: Option<Identity>,
+    pub not_null: bool,
+    /// value of the column when it is not given
+    pub default: Option<Expr>,
+}
+
+/// values of the column are generated by a sequence when they are not given.
+/// `SERIAL` columns are the same as `GENERATED BY DEFAULT AS IDENTITY` ones
+#[derive(Debug, PartialEq)]
+pub struct Identity {
+    /// `GENERATED ALWAYS` columns do not accept values from `INSERT`
+    pub always: bool,
+    /// options of the sequence
+    pub options: Vec<(String, String)>,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -159,6 +267,60 @@ pub enum Expr {
         expr: Box<Expr>,
         data_type: DataType,
     },
+    Function {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// `DEFAULT` keyword in `VALUES` of `INSERT` and `SET` of `UPDATE`
+    Default,
+}
+
+impl Expr {
+    /// names of the columns that the expression refers to in order they first appear
+    pub fn column_names(&self) -> Vec<&str> {
+        fn collect<'e>(expr: &'e Expr, names: &mut Vec<&'e str>) {
+            match expr {
+                Expr::Column(name) if !names.contains(&name.as_str()) => names.push(name),
+                Expr::BinaryOp { left, right, .. } => {
+                    collect(left, names);
+                    collect(right, names);
+                }
+                Expr::UnaryOp { expr, .. } | Expr::Cast { expr, .. } => collect(expr, names),
+                Expr::Function { args, .. } => args.iter().for_each(|arg| collect(arg, names)),
+                _ => {}
+            }
+        }
+
+        let mut names = vec![];
+        collect(self, &mut names);
+        names
+    }
+
+    /// the same expression that refers to `new_name` column instead of `old_name` one
+    pub fn rename_column(self, old_name: &str, new_name: &str) -> Expr {
+        let rename = |expr: Box<Expr>| Box::new(expr.rename_column(old_name, new_name));
+        match self {
+            Expr::Column(name) if name == old_name => Expr::Column(new_name.to_owned()),
+            Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+                left: rename(left),
+                op,
+                right: rename(right),
+            },
+            Expr::UnaryOp { op, expr } => Expr::UnaryOp { op, expr: rename(expr) },
+            Expr::Cast { expr, data_type } => Expr::Cast {
+                expr: rename(expr),
+                data_type,
+            },
+            Expr::Function { name, args } => Expr::Function {
+                name,
+                args: args
+                    .into_iter()
+                    .map(|arg| arg.rename_column(old_name, new_name))
+                    .collect(),
+            },
+            other => other,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -230,3 +392,6 @@ pub enum Extended {
         name: String,
     },
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,114 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+
+fn number(value: i32) -> Expr {
+    Expr::Value(Value::Int(value))
+}
+
+fn read_back(expr: Expr, text: &str) {
+    assert_eq!(expr.to_string(), text);
+    assert_eq!(text.parse(), Ok(expr));
+}
+
+#[test]
+fn values() {
+    read_back(number(-10), "-10");
+    read_back(Expr::Value(Value::Number("1.5e-3".to_owned())), "1.5e-3");
+    read_back(Expr::Value(Value::String("it's".to_owned())), "'it''s'");
+    read_back(Expr::Value(Value::Boolean(true)), "true");
+    read_back(Expr::Value(Value::Null), "NULL");
+    read_back(Expr::Param(2), "$2");
+    read_back(Expr::Default, "DEFAULT");
+}
+
+#[test]
+fn operations() {
+    read_back(
+        Expr::BinaryOp {
+            left: Box::new(Expr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr: Box::new(Expr::Column("col".to_owned())),
+            }),
+            op: BinaryOperator::NotLike,
+            right: Box::new(Expr::UnaryOp {
+                op: UnaryOperator::PostfixFactorial,
+                expr: Box::new(number(3)),
+            }),
+        },
+        "((- \"col\") NOT LIKE (3 !))",
+    );
+    read_back(
+        Expr::BinaryOp {
+            left: Box::new(number(1)),
+            op: BinaryOperator::Minus,
+            right: Box::new(Expr::BinaryOp {
+                left: Box::new(number(-2)),
+                op: BinaryOperator::StringConcat,
+                right: Box::new(number(3)),
+            }),
+        },
+        "(1 - (-2 || 3))",
+    );
+}
+
+#[test]
+fn functions_and_casts() {
+    read_back(
+        Expr::Function {
+            name: "nextval".to_owned(),
+            args: vec![Expr::Value(Value::String("schema_name.seq".to_owned()))],
+        },
+        "nextval('schema_name.seq')",
+    );
+    read_back(
+        Expr::Function {
+            name: "now".to_owned(),
+            args: vec![],
+        },
+        "now()",
+    );
+    read_back(
+        Expr::Cast {
+            expr: Box::new(number(1)),
+            data_type: DataType::Double,
+        },
+        "CAST(1 AS double precision)",
+    );
+    read_back(
+        Expr::Cast {
+            expr: Box::new(Expr::Value(Value::String("abc".to_owned()))),
+            data_type: DataType::VarChar(Some(10)),
+        },
+        "CAST('abc' AS varchar(10))",
+    );
+}
+
+#[test]
+fn malformed_text() {
+    assert!("(1 +".parse::<Expr>().is_err());
+    assert!("'abc".parse::<Expr>().is_err());
+    assert!("1 2".parse::<Expr>().is_err());
+}
+
+#[test]
+fn referred_columns() {
+    let expr: Expr = "((\"col_1\" > \"col_2\") AND (abs(\"col_1\") < 10))".parse().unwrap();
+
+    assert_eq!(expr.column_names(), vec!["col_1", "col_2"]);
+    assert_eq!(
+        expr.rename_column("col_1", "col_3").to_string(),
+        "((\"col_3\" > \"col_2\") AND (abs(\"col_3\") < 10))"
+    );
+}
@@ -0,0 +1,319 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SQL text of expressions that are kept in the catalog, e.g. `DEFAULT` values of columns.
+//! Every operation is put in parentheses, so the text is read back without operator precedence
+
+use crate::{BinaryOperator, DataType, Expr, UnaryOperator, Value};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+const BINARY_OPERATORS: &[(&str, BinaryOperator)] = &[
+    ("NOT LIKE", BinaryOperator::NotLike),
+    ("LIKE", BinaryOperator::Like),
+    ("AND", BinaryOperator::And),
+    ("OR", BinaryOperator::Or),
+    ("<>", BinaryOperator::NotEq),
+    (">=", BinaryOperator::GtEq),
+    ("<=", BinaryOperator::LtEq),
+    ("<<", BinaryOperator::BitwiseShiftLeft),
+    (">>", BinaryOperator::BitwiseShiftRight),
+    ("||", BinaryOperator::StringConcat),
+    ("+", BinaryOperator::Plus),
+    ("-", BinaryOperator::Minus),
+    ("*", BinaryOperator::Multiply),
+    ("/", BinaryOperator::Divide),
+    ("%", BinaryOperator::Modulus),
+    ("^", BinaryOperator::Exp),
+    (">", BinaryOperator::Gt),
+    ("<", BinaryOperator::Lt),
+    ("=", BinaryOperator::Eq),
+    ("|", BinaryOperator::BitwiseOr),
+    ("&", BinaryOperator::BitwiseAnd),
+    ("#", BinaryOperator::BitwiseXor),
+];
+
+/// prefix operators, `!` of postfix factorial follows its operand
+const UNARY_OPERATORS: &[(&str, UnaryOperator)] = &[
+    ("NOT", UnaryOperator::Not),
+    ("||/", UnaryOperator::CubeRoot),
+    ("|/", UnaryOperator::SquareRoot),
+    ("!!", UnaryOperator::PrefixFactorial),
+    ("-", UnaryOperator::Minus),
+    ("+", UnaryOperator::Plus),
+    ("~", UnaryOperator::BitwiseNot),
+    ("@", UnaryOperator::Abs),
+];
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Value(value) => write!(f, "{}", value),
+            Expr::Param(index) => write!(f, "${}", index),
+            Expr::BinaryOp { left, op, right } => {
+                let (sign, _) = BINARY_OPERATORS.iter().find(|(_, known)| known == op).unwrap();
+                write!(f, "({} {} {})", left, sign, right)
+            }
+            Expr::UnaryOp {
+                op: UnaryOperator::PostfixFactorial,
+                expr,
+            } => write!(f, "({} !)", expr),
+            Expr::UnaryOp { op, expr } => {
+                let (sign, _) = UNARY_OPERATORS.iter().find(|(_, known)| known == op).unwrap();
+                write!(f, "({} {})", sign, expr)
+            }
+            Expr::Column(name) => write!(f, "\"{}\"", name.replace('"', "\"\"")),
+            Expr::Cast { expr, data_type } => write!(f, "CAST({} AS {})", expr, data_type),
+            Expr::Function { name, args } => {
+                write!(f, "{}(", name)?;
+                for (index, arg) in args.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Default => write!(f, "DEFAULT"),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Number(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "'{}'", value.replace('\'', "''")),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Null => write!(f, "NULL"),
+        }
+    }
+}
+
+impl Display for DataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DataType::SmallInt => write!(f, "smallint"),
+            DataType::Int => write!(f, "integer"),
+            DataType::BigInt => write!(f, "bigint"),
+            DataType::Char(len) => write!(f, "char({})", len),
+            DataType::VarChar(None) => write!(f, "varchar"),
+            DataType::VarChar(Some(len)) => write!(f, "varchar({})", len),
+            DataType::Real => write!(f, "real"),
+            DataType::Double => write!(f, "double precision"),
+            DataType::Bool => write!(f, "boolean"),
+        }
+    }
+}
+
+/// reads text that `Display` of the expression writes
+impl FromStr for Expr {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Expr, String> {
+        let mut reader = Reader { text, position: 0 };
+        let expr = reader.expr()?;
+        if reader.rest().is_empty() {
+            Ok(expr)
+        } else {
+            Err(reader.unexpected())
+        }
+    }
+}
+
+struct Reader<'t> {
+    text: &'t str,
+    position: usize,
+}
+
+impl<'t> Reader<'t> {
+    fn rest(&self) -> &'t str {
+        &self.text[self.position..]
+    }
+
+    fn unexpected(&self) -> String {
+        format!("unexpected \"{}\" in expression \"{}\"", self.rest(), self.text)
+    }
+
+    fn skip(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.skip(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// characters while the predicate holds
+    fn take_while<P: Fn(char) -> bool>(&mut self, predicate: P) -> &'t str {
+        let rest = self.rest();
+        let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.position += len;
+        &rest[..len]
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let rest = self.rest();
+        match rest.chars().next() {
+            Some('(') => self.operation(),
+            Some('\'') => Ok(Expr::Value(Value::String(self.quoted('\'')?))),
+            Some('"') => Ok(Expr::Column(self.quoted('"')?)),
+            Some('$') => {
+                self.position += 1;
+                match self.take_while(|c| c.is_ascii_digit()).parse() {
+                    Ok(index) => Ok(Expr::Param(index)),
+                    Err(_) => Err(self.unexpected()),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) => {
+                let start = self.position;
+                self.position += 1;
+                self.take_while(|c| c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || c == '+' || c == '-');
+                let number = &self.text[start..self.position];
+                match number.parse() {
+                    Ok(value) => Ok(Expr::Value(Value::Int(value))),
+                    Err(_) => Ok(Expr::Value(Value::Number(number.to_owned()))),
+                }
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => self.word(),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// `(left op right)`, `(op expr)` or `(expr !)`
+    fn operation(&mut self) -> Result<Expr, String> {
+        self.expect("(")?;
+        for (sign, op) in UNARY_OPERATORS {
+            if self.skip(&format!("{} ", sign)) {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                return Ok(Expr::UnaryOp {
+                    op: *op,
+                    expr: Box::new(expr),
+                });
+            }
+        }
+        let left = self.expr()?;
+        if self.skip(" !)") {
+            return Ok(Expr::UnaryOp {
+                op: UnaryOperator::PostfixFactorial,
+                expr: Box::new(left),
+            });
+        }
+        for (sign, op) in BINARY_OPERATORS {
+            if self.skip(&format!(" {} ", sign)) {
+                let right = self.expr()?;
+                self.expect(")")?;
+                return Ok(Expr::BinaryOp {
+                    left: Box::new(left),
+                    op: *op,
+                    right: Box::new(right),
+                });
+            }
+        }
+        Err(self.unexpected())
+    }
+
+    /// text between quotes, doubled quotes are the quote itself
+    fn quoted(&mut self, quote: char) -> Result<String, String> {
+        self.position += 1;
+        let mut value = String::new();
+        loop {
+            let part = self.take_while(|c| c != quote);
+            value.push_str(part);
+            if !self.skip(&quote.to_string()) {
+                return Err(self.unexpected());
+            }
+            if self.skip(&quote.to_string()) {
+                value.push(quote);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// keywords, casts and function calls
+    fn word(&mut self) -> Result<Expr, String> {
+        let word = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        match word {
+            "NULL" => Ok(Expr::Value(Value::Null)),
+            "true" => Ok(Expr::Value(Value::Boolean(true))),
+            "false" => Ok(Expr::Value(Value::Boolean(false))),
+            "DEFAULT" => Ok(Expr::Default),
+            "CAST" => {
+                self.expect("(")?;
+                let expr = self.expr()?;
+                self.expect(" AS ")?;
+                let data_type = self.data_type()?;
+                self.expect(")")?;
+                Ok(Expr::Cast {
+                    expr: Box::new(expr),
+                    data_type,
+                })
+            }
+            name => {
+                self.expect("(")?;
+                let mut args = vec![];
+                if !self.skip(")") {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.skip(")") {
+                            break;
+                        }
+                        self.expect(", ")?;
+                    }
+                }
+                Ok(Expr::Function {
+                    name: name.to_owned(),
+                    args,
+                })
+            }
+        }
+    }
+
+    fn data_type(&mut self) -> Result<DataType, String> {
+        if self.skip("double precision") {
+            return Ok(DataType::Double);
+        }
+        let name = self.take_while(|c| c.is_ascii_alphabetic());
+        let len = if self.skip("(") {
+            let len = self.take_while(|c| c.is_ascii_digit()).parse::<u32>();
+            self.expect(")")?;
+            Some(len.map_err(|_| self.unexpected())?)
+        } else {
+            None
+        };
+        match (name, len) {
+            ("smallint", None) => Ok(DataType::SmallInt),
+            ("integer", None) => Ok(DataType::Int),
+            ("bigint", None) => Ok(DataType::BigInt),
+            ("char", Some(len)) => Ok(DataType::Char(len)),
+            ("varchar", len) => Ok(DataType::VarChar(len)),
+            ("real", None) => Ok(DataType::Real),
+            ("boolean", None) => Ok(DataType::Bool),
+            _ => Err(self.unexpected()),
+        }
+    }
+}
@@ -14,8 +14,9 @@
 
 use postgres_parser::{nodes, sys, Node, PgParserError, SqlStatementScanner};
 use query_ast::{
-    Assignment, BinaryOperator, ColumnDef, DataType, Definition, DeleteStatement, Expr, Extended, InsertSource,
-    InsertStatement, Query, SelectItem, SelectStatement, Set, Statement, UnaryOperator, UpdateStatement, Value, Values,
+    AlterTableAction, Assignment, BinaryOperator, ColumnDef, ConstraintKind, DataType, Definition, DeleteStatement,
+    Expr, Extended, Identity, InsertSource, InsertStatement, Query, SelectItem, SelectStatement, Set, Statement,
+    TableConstraint, UnaryOperator, UpdateStatement, Value, Values,
 };
 use query_response::QueryError;
 use std::fmt::{self, Display, Formatter};
@@ -50,15 +51,25 @@ impl QueryParser {
                     partspec: _partition_spec,
                     ofTypename: _type_name,
                     constraints: _constraints,
-                    options: _options,
+                    options,
                     oncommit: _on_commit,
                     tablespacename: _table_space_name,
                     accessMethod: _access_method,
                     if_not_exists,
                 }))) => {
                     let mut columns = vec![];
+                    let mut constraints = vec![];
                     for table_element in table_elements.unwrap_or_else(Vec::new) {
-                        columns.push(self.process_column(table_element));
+                        match table_element {
+                            Node::Constraint(constraint) => {
+                                constraints.push(self.process_constraint(constraint, None)?)
+                            }
+                            column => {
+                                let (column, column_constraints) = self.process_column(column)?;
+                                columns.push(column);
+                                constraints.extend(column_constraints);
+                            }
+                        }
                     }
                     let table_name = table_name.unwrap();
                     statements.push(Statement::Definition(Definition::CreateTable {
@@ -66,6 +77,12 @@ impl QueryParser {
                         schema_name: table_name.schemaname.unwrap_or_else(|| "public".to_owned()),
                         table_name: table_name.relname.unwrap(),
                         columns,
+                        constraints,
+                        options: options
+                            .unwrap_or_else(Vec::new)
+                            .into_iter()
+                            .map(|option| self.process_option(option))
+                            .collect(),
                     }));
                 }
                 Ok(Some(Node::DropStmt(nodes::DropStmt {
@@ -91,7 +108,9 @@ impl QueryParser {
                                 cascade: behavior == sys::DropBehavior::DROP_CASCADE,
                             }));
                         }
-                        sys::ObjectType::OBJECT_TABLE => {
+                        sys::ObjectType::OBJECT_TABLE
+                        | sys::ObjectType::OBJECT_SEQUENCE
+                        | sys::ObjectType::OBJECT_INDEX => {
                             let mut names = vec![];
                             for object in objects.unwrap() {
                                 log::trace!("OBJECT - {:?}", object);
@@ -121,10 +140,24 @@ impl QueryParser {
                                     _ => unimplemented!(),
                                 }
                             }
-                            statements.push(Statement::Definition(Definition::DropTables {
-                                names,
-                                if_exists: missing_ok,
-                                cascade: behavior == sys::DropBehavior::DROP_CASCADE,
+                            let if_exists = missing_ok;
+                            let cascade = behavior == sys::DropBehavior::DROP_CASCADE;
+                            statements.push(Statement::Definition(match remove_type {
+                                sys::ObjectType::OBJECT_SEQUENCE => Definition::DropSequences {
+                                    names,
+                                    if_exists,
+                                    cascade,
+                                },
+                                sys::ObjectType::OBJECT_INDEX => Definition::DropIndexes {
+                                    names,
+                                    if_exists,
+                                    cascade,
+                                },
+                                _ => Definition::DropTables {
+                                    names,
+                                    if_exists,
+                                    cascade,
+                                },
                             }));
                         }
                         _ => unimplemented!(),
@@ -145,7 +178,7 @@ impl QueryParser {
                     oldNode: _old_node,
                     oldCreateSubid: _old_create_sub_id,
                     oldFirstRelfilenodeSubid: _old_first_rel_file_node_sub_id,
-                    unique: _unique,
+                    unique,
                     primary: _primary,
                     isconstraint: _is_constraint,
                     deferrable: _deferrable,
@@ -171,8 +204,116 @@ impl QueryParser {
                         schema_name: table_name.schemaname.unwrap_or_else(|| "public".to_owned()),
                         table_name: table_name.relname.unwrap(),
                         column_names,
+                        unique,
                     }));
                 }
+                Ok(Some(Node::VacuumStmt(nodes::VacuumStmt {
+                    options: _options,
+                    rels,
+                    is_vacuumcmd: false,
+                }))) => {
+                    let mut names = vec![];
+                    for relation in rels.unwrap_or_else(Vec::new) {
+                        log::trace!("RELATION - {:?}", relation);
+                        match relation {
+                            Node::VacuumRelation(nodes::VacuumRelation {
+                                relation: Some(table_name),
+                                va_cols: None,
+                                ..
+                            }) => names.push((
+                                table_name.schemaname.unwrap_or_else(|| "public".to_owned()),
+                                table_name.relname.unwrap(),
+                            )),
+                            _ => unimplemented!(),
+                        }
+                    }
+                    statements.push(Statement::Definition(Definition::Analyze { names }));
+                }
+                Ok(Some(Node::CreateSeqStmt(nodes::CreateSeqStmt {
+                    sequence,
+                    options,
+                    ownerId: _owner_id,
+                    for_identity: _for_identity,
+                    if_not_exists,
+                }))) => {
+                    let sequence = sequence.unwrap();
+                    statements.push(Statement::Definition(Definition::CreateSequence {
+                        schema_name: sequence.schemaname.unwrap_or_else(|| "public".to_owned()),
+                        sequence_name: sequence.relname.unwrap(),
+                        if_not_exists,
+                        options: self.process_sequence_options(options),
+                    }));
+                }
+                Ok(Some(Node::CreatedbStmt(nodes::CreatedbStmt {
+                    dbname,
+                    options: _options,
+                }))) => statements.push(Statement::Definition(Definition::CreateDatabase {
+                    database_name: dbname.unwrap(),
+                })),
+                Ok(Some(Node::DropdbStmt(nodes::DropdbStmt {
+                    dbname,
+                    missing_ok,
+                    options: _options,
+                }))) => statements.push(Statement::Definition(Definition::DropDatabase {
+                    database_name: dbname.unwrap(),
+                    if_exists: missing_ok,
+                })),
+                Ok(Some(Node::AlterTableStmt(nodes::AlterTableStmt {
+                    relation: table_name,
+                    cmds,
+                    relkind: sys::ObjectType::OBJECT_TABLE,
+                    missing_ok,
+                }))) => {
+                    let mut actions = vec![];
+                    for cmd in cmds.unwrap_or_else(Vec::new) {
+                        log::trace!("ALTER TABLE CMD - {:?}", cmd);
+                        match cmd {
+                            Node::AlterTableCmd(cmd) => actions.extend(self.process_alter_table_cmd(cmd)?),
+                            _ => unimplemented!(),
+                        }
+                    }
+                    let table_name = table_name.unwrap();
+                    statements.push(Statement::Definition(Definition::AlterTable {
+                        schema_name: table_name.schemaname.unwrap_or_else(|| "public".to_owned()),
+                        table_name: table_name.relname.unwrap(),
+                        if_exists: missing_ok,
+                        actions,
+                    }));
+                }
+                Ok(Some(Node::RenameStmt(nodes::RenameStmt {
+                    renameType: rename_type,
+                    relation,
+                    subname,
+                    newname,
+                    missing_ok,
+                    ..
+                }))) => match rename_type {
+                    sys::ObjectType::OBJECT_SCHEMA => {
+                        statements.push(Statement::Definition(Definition::RenameSchema {
+                            schema_name: subname.unwrap(),
+                            new_name: newname.unwrap(),
+                        }))
+                    }
+                    sys::ObjectType::OBJECT_TABLE | sys::ObjectType::OBJECT_COLUMN => {
+                        let table_name = relation.unwrap();
+                        let action = match rename_type {
+                            sys::ObjectType::OBJECT_TABLE => AlterTableAction::RenameTable {
+                                new_name: newname.unwrap().to_lowercase(),
+                            },
+                            _ => AlterTableAction::RenameColumn {
+                                old_name: subname.unwrap().to_lowercase(),
+                                new_name: newname.unwrap().to_lowercase(),
+                            },
+                        };
+                        statements.push(Statement::Definition(Definition::AlterTable {
+                            schema_name: table_name.schemaname.unwrap_or_else(|| "public".to_owned()),
+                            table_name: table_name.relname.unwrap(),
+                            if_exists: missing_ok,
+                            actions: vec![action],
+                        }));
+                    }
+                    _ => unimplemented!(),
+                },
                 Ok(Some(insert @ Node::InsertStmt(_))) => {
                     statements.push(Statement::Query(self.process_query(insert)));
                 }
@@ -334,7 +475,7 @@ impl QueryParser {
                         schemaname: schema_name,
                         relname: table_name,
                         ..
-                    })) => (schema_name.unwrap(), table_name.unwrap()),
+                    })) => (schema_name.unwrap_or_else(|| "public".to_owned()), table_name.unwrap()),
                     _ => unimplemented!(),
                 };
                 Query::Select(SelectStatement {
@@ -393,13 +534,259 @@ impl QueryParser {
         }
     }
 
-    fn process_column(&self, node: Node) -> ColumnDef {
+    /// `PRIMARY KEY`, `UNIQUE` and `CHECK` constraints of the column are returned as constraints of the table,
+    /// the ones that are not supported fail the statement
+    fn process_column(&self, node: Node) -> Result<(ColumnDef, Vec<TableConstraint>), ParserError> {
         if let Node::ColumnDef(column_def) = node {
-            let data_type = self.process_type(*column_def.typeName.unwrap());
-            ColumnDef {
-                name: column_def.colname.unwrap().to_lowercase(),
-                data_type,
+            let type_name = *column_def.typeName.unwrap();
+            let (data_type, mut identity) = match self.process_serial_type(&type_name) {
+                Some(data_type) => (
+                    data_type,
+                    Some(Identity {
+                        always: false,
+                        options: vec![],
+                    }),
+                ),
+                None => (self.process_type(type_name), None),
+            };
+            let name = column_def.colname.unwrap().to_lowercase();
+            let mut not_null = false;
+            let mut default = None;
+            let mut constraints = vec![];
+            for constraint in column_def.constraints.unwrap_or_else(Vec::new) {
+                log::trace!("CONSTRAINT {:?}", constraint);
+                match constraint {
+                    Node::Constraint(nodes::Constraint {
+                        contype: sys::ConstrType::CONSTR_IDENTITY,
+                        generated_when,
+                        options,
+                        ..
+                    }) => {
+                        identity = Some(Identity {
+                            always: generated_when == 'a',
+                            options: self.process_sequence_options(options),
+                        });
+                    }
+                    Node::Constraint(nodes::Constraint {
+                        contype: sys::ConstrType::CONSTR_NOTNULL,
+                        ..
+                    }) => not_null = true,
+                    Node::Constraint(nodes::Constraint {
+                        contype: sys::ConstrType::CONSTR_NULL,
+                        ..
+                    }) => not_null = false,
+                    Node::Constraint(nodes::Constraint {
+                        contype: sys::ConstrType::CONSTR_DEFAULT,
+                        raw_expr: Some(expr),
+                        ..
+                    }) => default = Some(self.parse_expr(*expr)),
+                    Node::Constraint(constraint) => constraints.push(self.process_constraint(constraint, Some(&name))?),
+                    _ => {}
+                }
             }
+            Ok((
+                ColumnDef {
+                    name,
+                    data_type,
+                    identity,
+                    not_null,
+                    default,
+                },
+                constraints,
+            ))
+        } else {
+            unimplemented!()
+        }
+    }
+
+    /// constraints of the column are added by separate actions after the column
+    fn process_alter_table_cmd(&self, cmd: nodes::AlterTableCmd) -> Result<Vec<AlterTableAction>, ParserError> {
+        let action = match cmd {
+            nodes::AlterTableCmd {
+                subtype: sys::AlterTableType::AT_AddColumn,
+                def: Some(column_def),
+                missing_ok,
+                ..
+            } => {
+                let (column, constraints) = self.process_column(*column_def)?;
+                let mut actions = vec![AlterTableAction::AddColumn {
+                    column,
+                    if_not_exists: missing_ok,
+                }];
+                actions.extend(constraints.into_iter().map(AlterTableAction::AddConstraint));
+                return Ok(actions);
+            }
+            nodes::AlterTableCmd {
+                subtype: sys::AlterTableType::AT_AddConstraint,
+                def: Some(constraint),
+                ..
+            } => match *constraint {
+                Node::Constraint(constraint) => {
+                    AlterTableAction::AddConstraint(self.process_constraint(constraint, None)?)
+                }
+                _ => unimplemented!(),
+            },
+            nodes::AlterTableCmd {
+                subtype: sys::AlterTableType::AT_DropConstraint,
+                name: Some(name),
+                behavior,
+                missing_ok,
+                ..
+            } => AlterTableAction::DropConstraint {
+                name: name.to_lowercase(),
+                if_exists: missing_ok,
+                cascade: behavior == sys::DropBehavior::DROP_CASCADE,
+            },
+            nodes::AlterTableCmd {
+                subtype: sys::AlterTableType::AT_DropColumn,
+                name: Some(name),
+                behavior,
+                missing_ok,
+                ..
+            } => AlterTableAction::DropColumn {
+                name: name.to_lowercase(),
+                if_exists: missing_ok,
+                cascade: behavior == sys::DropBehavior::DROP_CASCADE,
+            },
+            nodes::AlterTableCmd {
+                subtype: sys::AlterTableType::AT_AlterColumnType,
+                name: Some(name),
+                def: Some(column_def),
+                ..
+            } => match *column_def {
+                Node::ColumnDef(nodes::ColumnDef {
+                    typeName: Some(type_name),
+                    raw_default,
+                    ..
+                }) => AlterTableAction::AlterColumnType {
+                    name: name.to_lowercase(),
+                    data_type: self.process_type(*type_name),
+                    using: raw_default.map(|expr| self.parse_expr(*expr)),
+                },
+                _ => return Err(ParserError::not_supported("ALTER COLUMN TYPE without a type name")),
+            },
+            nodes::AlterTableCmd {
+                subtype: sys::AlterTableType::AT_SetNotNull,
+                ..
+            } => return Err(ParserError::not_supported("ALTER COLUMN SET NOT NULL")),
+            nodes::AlterTableCmd {
+                subtype: sys::AlterTableType::AT_DropNotNull,
+                ..
+            } => return Err(ParserError::not_supported("ALTER COLUMN DROP NOT NULL")),
+            nodes::AlterTableCmd {
+                subtype: sys::AlterTableType::AT_ColumnDefault,
+                def: Some(_),
+                ..
+            } => return Err(ParserError::not_supported("ALTER COLUMN SET DEFAULT")),
+            nodes::AlterTableCmd {
+                subtype: sys::AlterTableType::AT_ColumnDefault,
+                ..
+            } => return Err(ParserError::not_supported("ALTER COLUMN DROP DEFAULT")),
+            other => {
+                return Err(ParserError::not_supported(format!(
+                    "ALTER TABLE command {:?}",
+                    other.subtype
+                )))
+            }
+        };
+        Ok(vec![action])
+    }
+
+    /// constraint of the column has no keys, it is built on the column itself.
+    /// `CHECK` constraint refers to columns in its expression
+    fn process_constraint(
+        &self,
+        constraint: nodes::Constraint,
+        column_name: Option<&str>,
+    ) -> Result<TableConstraint, ParserError> {
+        let kind = match constraint.contype {
+            sys::ConstrType::CONSTR_PRIMARY => ConstraintKind::PrimaryKey,
+            sys::ConstrType::CONSTR_UNIQUE => ConstraintKind::Unique,
+            sys::ConstrType::CONSTR_CHECK => match constraint.raw_expr {
+                Some(expr) => ConstraintKind::Check(self.parse_expr(*expr)),
+                None => unimplemented!("CHECK constraint without expression"),
+            },
+            sys::ConstrType::CONSTR_FOREIGN => return Err(ParserError::not_supported("FOREIGN KEY constraint")),
+            other => return Err(ParserError::not_supported(format!("{:?} constraint", other))),
+        };
+        let column_names = match column_name {
+            _ if matches!(kind, ConstraintKind::Check(_)) => vec![],
+            Some(column_name) => vec![column_name.to_owned()],
+            None => constraint
+                .keys
+                .unwrap_or_else(Vec::new)
+                .into_iter()
+                .map(|key| match key {
+                    Node::Value(nodes::Value { string: Some(name), .. }) => name.to_lowercase(),
+                    _ => unimplemented!(),
+                })
+                .collect(),
+        };
+        Ok(TableConstraint {
+            name: constraint.conname.map(|name| name.to_lowercase()),
+            kind,
+            column_names,
+        })
+    }
+
+    /// `SMALLSERIAL`, `SERIAL` and `BIGSERIAL` are not real types but integer columns with a sequence
+    fn process_serial_type(&self, type_name: &nodes::TypeName) -> Option<DataType> {
+        match type_name.names.as_ref().and_then(|names| names.last()) {
+            Some(Node::Value(nodes::Value { string: Some(name), .. })) => match name.as_str() {
+                "smallserial" | "serial2" => Some(DataType::SmallInt),
+                "serial" | "serial4" => Some(DataType::Int),
+                "bigserial" | "serial8" => Some(DataType::BigInt),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// `NO MINVALUE` and `NO MAXVALUE` are the same as not giving the option
+    fn process_sequence_options(&self, options: Option<Vec<Node>>) -> Vec<(String, String)> {
+        let mut sequence_options = vec![];
+        for option in options.unwrap_or_else(Vec::new) {
+            if let Node::DefElem(nodes::DefElem {
+                defname: Some(name),
+                arg: None,
+                ..
+            }) = &option
+            {
+                if name == "minvalue" || name == "maxvalue" {
+                    continue;
+                }
+            }
+            sequence_options.push(self.process_option(option));
+        }
+        sequence_options
+    }
+
+    fn process_option(&self, node: Node) -> (String, String) {
+        log::trace!("OPTION {:?}", node);
+        if let Node::DefElem(nodes::DefElem {
+            defname: Some(name),
+            arg,
+            ..
+        }) = node
+        {
+            let value = match arg.map(|arg| *arg) {
+                Some(Node::TypeName(nodes::TypeName {
+                    names: Some(mut names), ..
+                })) => match names.pop() {
+                    Some(Node::Value(nodes::Value {
+                        string: Some(value), ..
+                    })) => value,
+                    _ => unimplemented!(),
+                },
+                Some(Node::Value(nodes::Value {
+                    string: Some(value), ..
+                })) => value,
+                Some(Node::Value(nodes::Value { int: Some(value), .. })) => value.to_string(),
+                Some(Node::Value(nodes::Value { float: Some(value), .. })) => value,
+                None => "true".to_owned(),
+                _ => unimplemented!(),
+            };
+            (name.to_lowercase(), value.to_lowercase())
         } else {
             unimplemented!()
         }
@@ -585,6 +972,10 @@ impl QueryParser {
                     string: Some(value), ..
                 },
             }) => Expr::Value(Value::String(value)),
+            Node::A_Const(nodes::A_Const {
+                val: nodes::Value { null: Some(()), .. },
+            }) => Expr::Value(Value::Null),
+            Node::SetToDefault(_) => Expr::Default,
             Node::ParamRef(nodes::ParamRef { number }) => Expr::Param(number as u32),
             Node::ColumnRef(nodes::ColumnRef {
                 fields: Some(mut values),
@@ -599,6 +990,21 @@ impl QueryParser {
                 expr: Box::new(self.parse_expr(*expr)),
                 data_type: self.process_type(*type_name),
             },
+            Node::FuncCall(nodes::FuncCall {
+                funcname: Some(mut names),
+                args,
+                ..
+            }) => match names.pop() {
+                Some(Node::Value(nodes::Value { string: Some(name), .. })) => Expr::Function {
+                    name: name.to_lowercase(),
+                    args: args
+                        .unwrap_or_else(Vec::new)
+                        .into_iter()
+                        .map(|arg| self.parse_expr(arg))
+                        .collect(),
+                },
+                _ => unimplemented!(),
+            },
             _ => unimplemented!(),
         }
     }
@@ -619,25 +1025,39 @@ impl QueryParser {
 }
 
 #[derive(Debug, PartialEq)]
-pub struct ParserError {
-    error: PgParserError,
+pub enum ParserError {
+    Syntax(PgParserError),
+    /// the statement is valid, but the database can't execute it
+    NotSupported(String),
+}
+
+impl ParserError {
+    pub fn not_supported<F: ToString>(feature: F) -> ParserError {
+        ParserError::NotSupported(feature.to_string())
+    }
 }
 
 impl From<PgParserError> for ParserError {
     fn from(error: PgParserError) -> ParserError {
-        ParserError { error }
+        ParserError::Syntax(error)
     }
 }
 
 impl From<ParserError> for QueryError {
     fn from(error: ParserError) -> QueryError {
-        QueryError::syntax_error(error)
+        match error {
+            ParserError::NotSupported(feature) => QueryError::feature_not_supported(feature),
+            syntax => QueryError::syntax_error(syntax),
+        }
     }
 }
 
 impl Display for ParserError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.error)
+        match self {
+            ParserError::Syntax(error) => write!(f, "{:?}", error),
+            ParserError::NotSupported(feature) => write!(f, "{} is not supported", feature),
+        }
     }
 }
 
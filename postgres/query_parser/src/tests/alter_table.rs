@@ -0,0 +1,212 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[test]
+fn add_column() {
+    let statements = QUERY_PARSER.parse("alter table table_name add column col_1 smallint;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![AlterTableAction::AddColumn {
+                column: ColumnDef {
+                    name: "col_1".to_owned(),
+                    data_type: DataType::SmallInt,
+                    identity: None,
+                    not_null: false,
+                    default: None,
+                },
+                if_not_exists: false,
+            }],
+        })])
+    );
+}
+
+#[test]
+fn add_column_if_not_exists_to_table_if_exists() {
+    let statements =
+        QUERY_PARSER.parse("alter table if exists schema_name.table_name add column if not exists col_1 bigserial;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "schema_name".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: true,
+            actions: vec![AlterTableAction::AddColumn {
+                column: ColumnDef {
+                    name: "col_1".to_owned(),
+                    data_type: DataType::BigInt,
+                    identity: Some(Identity {
+                        always: false,
+                        options: vec![],
+                    }),
+                    not_null: false,
+                    default: None,
+                },
+                if_not_exists: true,
+            }],
+        })])
+    );
+}
+
+#[test]
+fn drop_columns() {
+    let statements =
+        QUERY_PARSER.parse("alter table table_name drop column col_1, drop column if exists col_2 cascade;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![
+                AlterTableAction::DropColumn {
+                    name: "col_1".to_owned(),
+                    if_exists: false,
+                    cascade: false,
+                },
+                AlterTableAction::DropColumn {
+                    name: "col_2".to_owned(),
+                    if_exists: true,
+                    cascade: true,
+                }
+            ],
+        })])
+    );
+}
+
+#[test]
+fn rename_column() {
+    let statements = QUERY_PARSER.parse("alter table table_name rename column col_1 to col_2;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![AlterTableAction::RenameColumn {
+                old_name: "col_1".to_owned(),
+                new_name: "col_2".to_owned(),
+            }],
+        })])
+    );
+}
+
+#[test]
+fn alter_column_type() {
+    let statements = QUERY_PARSER.parse("alter table table_name alter column col_1 type bigint;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![AlterTableAction::AlterColumnType {
+                name: "col_1".to_owned(),
+                data_type: DataType::BigInt,
+                using: None,
+            }],
+        })])
+    );
+}
+
+#[test]
+fn alter_column_type_using_expression() {
+    let statements = QUERY_PARSER.parse("alter table table_name alter column col_1 type integer using col_1 + 1;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![AlterTableAction::AlterColumnType {
+                name: "col_1".to_owned(),
+                data_type: DataType::Int,
+                using: Some(Expr::BinaryOp {
+                    left: Box::new(Expr::Column("col_1".to_owned())),
+                    op: BinaryOperator::Plus,
+                    right: Box::new(Expr::Value(Value::Int(1)))
+                }),
+            }],
+        })])
+    );
+}
+
+#[test]
+fn rename_table() {
+    let statements = QUERY_PARSER.parse("alter table schema_name.table_name rename to new_table_name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "schema_name".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![AlterTableAction::RenameTable {
+                new_name: "new_table_name".to_owned(),
+            }],
+        })])
+    );
+}
+
+#[test]
+fn new_table_name_is_lowercased() {
+    let statements = QUERY_PARSER.parse("alter table table_name rename to New_Table_Name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![AlterTableAction::RenameTable {
+                new_name: "new_table_name".to_owned(),
+            }],
+        })])
+    );
+}
+
+#[test]
+fn set_and_drop_not_null_are_not_supported() {
+    assert_eq!(
+        QUERY_PARSER.parse("alter table table_name alter column col_1 set not null;"),
+        Err(ParserError::not_supported("ALTER COLUMN SET NOT NULL"))
+    );
+    assert_eq!(
+        QUERY_PARSER.parse("alter table table_name alter column col_1 drop not null;"),
+        Err(ParserError::not_supported("ALTER COLUMN DROP NOT NULL"))
+    );
+}
+
+#[test]
+fn set_and_drop_default_are_not_supported() {
+    assert_eq!(
+        QUERY_PARSER.parse("alter table table_name alter column col_1 set default 1;"),
+        Err(ParserError::not_supported("ALTER COLUMN SET DEFAULT"))
+    );
+    assert_eq!(
+        QUERY_PARSER.parse("alter table table_name alter column col_1 drop default;"),
+        Err(ParserError::not_supported("ALTER COLUMN DROP DEFAULT"))
+    );
+}
@@ -0,0 +1,40 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[test]
+fn analyze_all_tables() {
+    let statements = QUERY_PARSER.parse("analyze;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::Analyze { names: vec![] })])
+    );
+}
+
+#[test]
+fn analyze_tables() {
+    let statements = QUERY_PARSER.parse("analyze table_name, schema_name.other_table;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::Analyze {
+            names: vec![
+                ("public".to_owned(), "table_name".to_owned()),
+                ("schema_name".to_owned(), "other_table".to_owned())
+            ]
+        })])
+    );
+}
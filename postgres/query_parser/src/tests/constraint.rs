@@ -0,0 +1,236 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+fn int_column(name: &str) -> ColumnDef {
+    ColumnDef {
+        name: name.to_owned(),
+        data_type: DataType::Int,
+        identity: None,
+        not_null: false,
+        default: None,
+    }
+}
+
+#[test]
+fn column_constraints() {
+    let statements = QUERY_PARSER.parse("create table table_name (col_1 int primary key, col_2 int unique);");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![int_column("col_1"), int_column("col_2")],
+            constraints: vec![
+                TableConstraint {
+                    name: None,
+                    kind: ConstraintKind::PrimaryKey,
+                    column_names: vec!["col_1".to_owned()],
+                },
+                TableConstraint {
+                    name: None,
+                    kind: ConstraintKind::Unique,
+                    column_names: vec!["col_2".to_owned()],
+                }
+            ],
+            if_not_exists: false,
+            options: vec![],
+        })])
+    );
+}
+
+#[test]
+fn named_table_constraints() {
+    let statements = QUERY_PARSER.parse(
+        "create table table_name (\
+            col_1 int,\
+            col_2 int,\
+            constraint pk primary key (col_1, col_2),\
+            unique (col_2)\
+        );",
+    );
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![int_column("col_1"), int_column("col_2")],
+            constraints: vec![
+                TableConstraint {
+                    name: Some("pk".to_owned()),
+                    kind: ConstraintKind::PrimaryKey,
+                    column_names: vec!["col_1".to_owned(), "col_2".to_owned()],
+                },
+                TableConstraint {
+                    name: None,
+                    kind: ConstraintKind::Unique,
+                    column_names: vec!["col_2".to_owned()],
+                }
+            ],
+            if_not_exists: false,
+            options: vec![],
+        })])
+    );
+}
+
+#[test]
+fn add_column_with_constraint() {
+    let statements = QUERY_PARSER.parse("alter table table_name add column col_1 int constraint uq unique;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![
+                AlterTableAction::AddColumn {
+                    column: int_column("col_1"),
+                    if_not_exists: false,
+                },
+                AlterTableAction::AddConstraint(TableConstraint {
+                    name: Some("uq".to_owned()),
+                    kind: ConstraintKind::Unique,
+                    column_names: vec!["col_1".to_owned()],
+                })
+            ],
+        })])
+    );
+}
+
+#[test]
+fn add_table_constraint() {
+    let statements = QUERY_PARSER.parse("alter table table_name add primary key (col_1);");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![AlterTableAction::AddConstraint(TableConstraint {
+                name: None,
+                kind: ConstraintKind::PrimaryKey,
+                column_names: vec!["col_1".to_owned()],
+            })],
+        })])
+    );
+}
+
+fn greater_than_zero(column_name: &str) -> Expr {
+    Expr::BinaryOp {
+        left: Box::new(Expr::Column(column_name.to_owned())),
+        op: BinaryOperator::Gt,
+        right: Box::new(Expr::Value(Value::Int(0))),
+    }
+}
+
+#[test]
+fn check_constraints() {
+    let statements = QUERY_PARSER.parse(
+        "create table table_name (\
+            col_1 int check (col_1 > 0),\
+            col_2 int,\
+            constraint positive check (col_2 > 0)\
+        );",
+    );
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![int_column("col_1"), int_column("col_2")],
+            constraints: vec![
+                TableConstraint {
+                    name: None,
+                    kind: ConstraintKind::Check(greater_than_zero("col_1")),
+                    column_names: vec![],
+                },
+                TableConstraint {
+                    name: Some("positive".to_owned()),
+                    kind: ConstraintKind::Check(greater_than_zero("col_2")),
+                    column_names: vec![],
+                }
+            ],
+            if_not_exists: false,
+            options: vec![],
+        })])
+    );
+}
+
+#[test]
+fn add_check_constraint() {
+    let statements = QUERY_PARSER.parse("alter table table_name add check (col_1 > 0);");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![AlterTableAction::AddConstraint(TableConstraint {
+                name: None,
+                kind: ConstraintKind::Check(greater_than_zero("col_1")),
+                column_names: vec![],
+            })],
+        })])
+    );
+}
+
+#[test]
+fn drop_constraint() {
+    let statements = QUERY_PARSER
+        .parse("alter table table_name drop constraint Positive, drop constraint if exists table_name_pkey cascade;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::AlterTable {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            if_exists: false,
+            actions: vec![
+                AlterTableAction::DropConstraint {
+                    name: "positive".to_owned(),
+                    if_exists: false,
+                    cascade: false,
+                },
+                AlterTableAction::DropConstraint {
+                    name: "table_name_pkey".to_owned(),
+                    if_exists: true,
+                    cascade: true,
+                }
+            ],
+        })])
+    );
+}
+
+#[test]
+fn foreign_keys_are_not_supported() {
+    assert_eq!(
+        QUERY_PARSER.parse("create table table_name (col_1 integer, foreign key (col_1) references other (col_1));"),
+        Err(ParserError::not_supported("FOREIGN KEY constraint"))
+    );
+    assert_eq!(
+        QUERY_PARSER.parse("create table table_name (col_1 integer references other (col_1));"),
+        Err(ParserError::not_supported("FOREIGN KEY constraint"))
+    );
+    assert_eq!(
+        QUERY_PARSER.parse("alter table table_name add foreign key (col_1) references other (col_1);"),
+        Err(ParserError::not_supported("FOREIGN KEY constraint"))
+    );
+}
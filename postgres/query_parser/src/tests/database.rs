@@ -0,0 +1,53 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[test]
+fn create_database() {
+    let statements = QUERY_PARSER.parse("create database database_name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateDatabase {
+            database_name: "database_name".to_owned(),
+        })])
+    );
+}
+
+#[test]
+fn drop_database() {
+    let statements = QUERY_PARSER.parse("drop database database_name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::DropDatabase {
+            database_name: "database_name".to_owned(),
+            if_exists: false,
+        })])
+    );
+}
+
+#[test]
+fn drop_database_if_exists() {
+    let statements = QUERY_PARSER.parse("drop database if exists database_name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::DropDatabase {
+            database_name: "database_name".to_owned(),
+            if_exists: true,
+        })])
+    );
+}
@@ -24,7 +24,55 @@ fn create_index() {
             name: "index_name".to_owned(),
             schema_name: "public".to_owned(),
             table_name: "table_name".to_owned(),
-            column_names: vec!["col_1".to_owned(), "col_2".to_owned()]
+            column_names: vec!["col_1".to_owned(), "col_2".to_owned()],
+            unique: false,
+        })])
+    );
+}
+
+#[test]
+fn create_unique_index() {
+    let statements = QUERY_PARSER.parse("create unique index index_name on table_name (col_1);");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateIndex {
+            name: "index_name".to_owned(),
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            column_names: vec!["col_1".to_owned()],
+            unique: true,
+        })])
+    );
+}
+
+#[test]
+fn drop_index() {
+    let statements = QUERY_PARSER.parse("drop index index_name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::DropIndexes {
+            names: vec![("public".to_owned(), "index_name".to_owned())],
+            if_exists: false,
+            cascade: false,
+        })])
+    );
+}
+
+#[test]
+fn drop_indexes_if_exists_cascade() {
+    let statements = QUERY_PARSER.parse("drop index if exists index_name_1, schema_name.index_name_2 cascade;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::DropIndexes {
+            names: vec![
+                ("public".to_owned(), "index_name_1".to_owned()),
+                ("schema_name".to_owned(), "index_name_2".to_owned())
+            ],
+            if_exists: true,
+            cascade: true,
         })])
     );
 }
@@ -44,6 +44,21 @@ fn insert_string() {
     );
 }
 
+#[test]
+fn insert_null_and_default() {
+    let statements = QUERY_PARSER.parse("insert into schema_name.table_name values (null, default);");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Query(Query::Insert(InsertStatement {
+            schema_name: "schema_name".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![],
+            source: InsertSource::Values(Values(vec![vec![Expr::Value(Value::Null), Expr::Default]]))
+        }))])
+    );
+}
+
 #[cfg(test)]
 mod operators {
     use super::*;
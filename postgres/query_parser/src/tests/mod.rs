@@ -14,6 +14,14 @@
 
 use super::*;
 
+#[cfg(test)]
+mod alter_table;
+#[cfg(test)]
+mod analyze;
+#[cfg(test)]
+mod constraint;
+#[cfg(test)]
+mod database;
 #[cfg(test)]
 mod delete;
 #[cfg(test)]
@@ -29,6 +37,8 @@ mod schema;
 #[cfg(test)]
 mod select;
 #[cfg(test)]
+mod sequence;
+#[cfg(test)]
 mod table;
 #[cfg(test)]
 mod update;
@@ -95,3 +95,16 @@ fn drop_schema_if_exists() {
         })])
     );
 }
+
+#[test]
+fn rename_schema() {
+    let statements = QUERY_PARSER.parse("alter schema schema_name rename to new_schema_name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::RenameSchema {
+            schema_name: "schema_name".to_owned(),
+            new_name: "new_schema_name".to_owned()
+        })])
+    );
+}
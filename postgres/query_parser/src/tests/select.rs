@@ -28,3 +28,18 @@ fn select_all_from_table() {
         }))])
     );
 }
+
+#[test]
+fn select_from_table_without_schema() {
+    let statements = QUERY_PARSER.parse("select * from table_name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Query(Query::Select(SelectStatement {
+            select_items: vec![SelectItem::Wildcard],
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            where_clause: None,
+        }))])
+    );
+}
@@ -0,0 +1,214 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[test]
+fn create_sequence() {
+    let statements = QUERY_PARSER.parse("create sequence sequence_name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateSequence {
+            schema_name: "public".to_owned(),
+            sequence_name: "sequence_name".to_owned(),
+            if_not_exists: false,
+            options: vec![],
+        })])
+    );
+}
+
+#[test]
+fn create_sequence_if_not_exists() {
+    let statements = QUERY_PARSER.parse("create sequence if not exists schema_name.sequence_name;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateSequence {
+            schema_name: "schema_name".to_owned(),
+            sequence_name: "sequence_name".to_owned(),
+            if_not_exists: true,
+            options: vec![],
+        })])
+    );
+}
+
+#[test]
+fn create_sequence_with_options() {
+    let statements = QUERY_PARSER.parse(
+        "create sequence sequence_name as integer increment by -2 minvalue -100 no maxvalue start with -10 cycle;",
+    );
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateSequence {
+            schema_name: "public".to_owned(),
+            sequence_name: "sequence_name".to_owned(),
+            if_not_exists: false,
+            options: vec![
+                ("as".to_owned(), "int4".to_owned()),
+                ("increment".to_owned(), "-2".to_owned()),
+                ("minvalue".to_owned(), "-100".to_owned()),
+                ("start".to_owned(), "-10".to_owned()),
+                ("cycle".to_owned(), "1".to_owned()),
+            ],
+        })])
+    );
+}
+
+#[test]
+fn drop_sequences() {
+    let statements =
+        QUERY_PARSER.parse("drop sequence if exists sequence_name_1, schema_name.sequence_name_2 cascade;");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::DropSequences {
+            names: vec![
+                ("public".to_owned(), "sequence_name_1".to_owned()),
+                ("schema_name".to_owned(), "sequence_name_2".to_owned())
+            ],
+            if_exists: true,
+            cascade: true
+        })])
+    );
+}
+
+#[test]
+fn serial_columns() {
+    let statements =
+        QUERY_PARSER.parse("create table table_name (col_ss smallserial, col_s serial, col_bs bigserial);");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateTable {
+            if_not_exists: false,
+            constraints: vec![],
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![
+                ColumnDef {
+                    name: "col_ss".to_owned(),
+                    data_type: DataType::SmallInt,
+                    identity: Some(Identity {
+                        always: false,
+                        options: vec![]
+                    }),
+                    not_null: false,
+                    default: None,
+                },
+                ColumnDef {
+                    name: "col_s".to_owned(),
+                    data_type: DataType::Int,
+                    identity: Some(Identity {
+                        always: false,
+                        options: vec![]
+                    }),
+                    not_null: false,
+                    default: None,
+                },
+                ColumnDef {
+                    name: "col_bs".to_owned(),
+                    data_type: DataType::BigInt,
+                    identity: Some(Identity {
+                        always: false,
+                        options: vec![]
+                    }),
+                    not_null: false,
+                    default: None,
+                }
+            ],
+            options: vec![],
+        })])
+    );
+}
+
+#[test]
+fn identity_columns() {
+    let statements = QUERY_PARSER.parse(
+        "create table table_name (\
+            col_a integer generated always as identity (start with 10 increment by 5),\
+            col_d bigint generated by default as identity\
+        );",
+    );
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateTable {
+            if_not_exists: false,
+            constraints: vec![],
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![
+                ColumnDef {
+                    name: "col_a".to_owned(),
+                    data_type: DataType::Int,
+                    identity: Some(Identity {
+                        always: true,
+                        options: vec![
+                            ("start".to_owned(), "10".to_owned()),
+                            ("increment".to_owned(), "5".to_owned())
+                        ]
+                    }),
+                    not_null: false,
+                    default: None,
+                },
+                ColumnDef {
+                    name: "col_d".to_owned(),
+                    data_type: DataType::BigInt,
+                    identity: Some(Identity {
+                        always: false,
+                        options: vec![]
+                    }),
+                    not_null: false,
+                    default: None,
+                }
+            ],
+            options: vec![],
+        })])
+    );
+}
+
+#[test]
+fn sequence_functions() {
+    let statements = QUERY_PARSER.parse(
+        "insert into table_name values (nextval('sequence_name'), currval('sequence_name'), setval('sequence_name', 10));",
+    );
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Query(Query::Insert(InsertStatement {
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![],
+            source: InsertSource::Values(Values(vec![vec![
+                Expr::Function {
+                    name: "nextval".to_owned(),
+                    args: vec![Expr::Value(Value::String("sequence_name".to_owned()))]
+                },
+                Expr::Function {
+                    name: "currval".to_owned(),
+                    args: vec![Expr::Value(Value::String("sequence_name".to_owned()))]
+                },
+                Expr::Function {
+                    name: "setval".to_owned(),
+                    args: vec![
+                        Expr::Value(Value::String("sequence_name".to_owned())),
+                        Expr::Value(Value::Int(10))
+                    ]
+                }
+            ]]))
+        }))])
+    );
+}
@@ -22,22 +22,33 @@ fn create_ints_table() {
         statements,
         Ok(vec![Statement::Definition(Definition::CreateTable {
             if_not_exists: false,
+            constraints: vec![],
             schema_name: "public".to_owned(),
             table_name: "table_name".to_owned(),
             columns: vec![
                 ColumnDef {
                     name: "col_si".to_owned(),
                     data_type: DataType::SmallInt,
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_i".to_owned(),
                     data_type: DataType::Int,
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_bi".to_owned(),
                     data_type: DataType::BigInt,
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 }
             ],
+            options: vec![],
         })])
     );
 }
@@ -63,42 +74,68 @@ fn create_strings_table() {
         statements,
         Ok(vec![Statement::Definition(Definition::CreateTable {
             if_not_exists: false,
+            constraints: vec![],
             schema_name: "schema_name".to_owned(),
             table_name: "table_name".to_owned(),
             columns: vec![
                 ColumnDef {
                     name: "col_c".to_owned(),
                     data_type: DataType::Char(1),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_cs".to_owned(),
                     data_type: DataType::Char(255),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_cl".to_owned(),
                     data_type: DataType::Char(1),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_cls".to_owned(),
                     data_type: DataType::Char(255),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_v".to_owned(),
                     data_type: DataType::VarChar(None),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_vs".to_owned(),
                     data_type: DataType::VarChar(Some(255)),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_vl".to_owned(),
                     data_type: DataType::VarChar(None),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_vls".to_owned(),
                     data_type: DataType::VarChar(Some(255)),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 }
             ],
+            options: vec![],
         })])
     );
 }
@@ -111,18 +148,26 @@ fn create_float_table() {
         statements,
         Ok(vec![Statement::Definition(Definition::CreateTable {
             if_not_exists: false,
+            constraints: vec![],
             schema_name: "public".to_owned(),
             table_name: "table_name".to_owned(),
             columns: vec![
                 ColumnDef {
                     name: "col_r".to_owned(),
                     data_type: DataType::Real,
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 },
                 ColumnDef {
                     name: "col_d".to_owned(),
                     data_type: DataType::Double,
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 }
             ],
+            options: vec![],
         })])
     );
 }
@@ -135,12 +180,107 @@ fn create_boolean_table() {
         statements,
         Ok(vec![Statement::Definition(Definition::CreateTable {
             if_not_exists: false,
+            constraints: vec![],
             schema_name: "public".to_owned(),
             table_name: "table_name".to_owned(),
             columns: vec![ColumnDef {
                 name: "col_b".to_owned(),
                 data_type: DataType::Bool,
+                identity: None,
+                not_null: false,
+                default: None,
             }],
+            options: vec![],
+        })])
+    );
+}
+
+#[test]
+fn create_table_with_storage_option() {
+    let statements = QUERY_PARSER.parse("create table table_name (col_i int) with (storage = columnar);");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateTable {
+            if_not_exists: false,
+            constraints: vec![],
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![ColumnDef {
+                name: "col_i".to_owned(),
+                data_type: DataType::Int,
+                identity: None,
+                not_null: false,
+                default: None,
+            }],
+            options: vec![("storage".to_owned(), "columnar".to_owned())],
+        })])
+    );
+}
+
+#[test]
+fn create_table_with_quoted_option() {
+    let statements = QUERY_PARSER.parse("create table table_name (col_i int) with (STORAGE = 'Row', fillfactor = 70);");
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateTable {
+            if_not_exists: false,
+            constraints: vec![],
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![ColumnDef {
+                name: "col_i".to_owned(),
+                data_type: DataType::Int,
+                identity: None,
+                not_null: false,
+                default: None,
+            }],
+            options: vec![
+                ("storage".to_owned(), "row".to_owned()),
+                ("fillfactor".to_owned(), "70".to_owned())
+            ],
+        })])
+    );
+}
+
+#[test]
+fn create_table_with_not_null_and_default_columns() {
+    let statements = QUERY_PARSER.parse(
+        "create table table_name (col_i int not null, col_v varchar(10) default 'none', col_bi bigint null default 0);",
+    );
+
+    assert_eq!(
+        statements,
+        Ok(vec![Statement::Definition(Definition::CreateTable {
+            if_not_exists: false,
+            constraints: vec![],
+            schema_name: "public".to_owned(),
+            table_name: "table_name".to_owned(),
+            columns: vec![
+                ColumnDef {
+                    name: "col_i".to_owned(),
+                    data_type: DataType::Int,
+                    identity: None,
+                    not_null: true,
+                    default: None,
+                },
+                ColumnDef {
+                    name: "col_v".to_owned(),
+                    data_type: DataType::VarChar(Some(10)),
+                    identity: None,
+                    not_null: false,
+                    default: Some(Expr::Value(Value::String("none".to_owned()))),
+                },
+                ColumnDef {
+                    name: "col_bi".to_owned(),
+                    data_type: DataType::BigInt,
+                    identity: None,
+                    not_null: false,
+                    default: Some(Expr::Value(Value::Int(0))),
+                }
+            ],
+            options: vec![],
         })])
     );
 }
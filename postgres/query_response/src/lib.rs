@@ -31,6 +31,22 @@ pub enum QueryEvent {
     TableDropped,
     /// Index successfully created
     IndexCreated,
+    /// Index successfully dropped
+    IndexDropped,
+    /// Table statistics successfully collected
+    TablesAnalyzed,
+    /// Sequence successfully created
+    SequenceCreated,
+    /// Sequence successfully dropped
+    SequenceDropped,
+    /// Database successfully created
+    DatabaseCreated,
+    /// Database successfully dropped
+    DatabaseDropped,
+    /// Table successfully altered
+    TableAltered,
+    /// Schema successfully altered
+    SchemaAltered,
     /// Variable successfully set
     VariableSet,
     /// Transaction is started
@@ -71,6 +87,14 @@ impl From<QueryEvent> for BackendMessage {
             QueryEvent::TableCreated => BackendMessage::CommandComplete("CREATE TABLE".to_owned()),
             QueryEvent::TableDropped => BackendMessage::CommandComplete("DROP TABLE".to_owned()),
             QueryEvent::IndexCreated => BackendMessage::CommandComplete("CREATE INDEX".to_owned()),
+            QueryEvent::IndexDropped => BackendMessage::CommandComplete("DROP INDEX".to_owned()),
+            QueryEvent::TablesAnalyzed => BackendMessage::CommandComplete("ANALYZE".to_owned()),
+            QueryEvent::SequenceCreated => BackendMessage::CommandComplete("CREATE SEQUENCE".to_owned()),
+            QueryEvent::SequenceDropped => BackendMessage::CommandComplete("DROP SEQUENCE".to_owned()),
+            QueryEvent::DatabaseCreated => BackendMessage::CommandComplete("CREATE DATABASE".to_owned()),
+            QueryEvent::DatabaseDropped => BackendMessage::CommandComplete("DROP DATABASE".to_owned()),
+            QueryEvent::TableAltered => BackendMessage::CommandComplete("ALTER TABLE".to_owned()),
+            QueryEvent::SchemaAltered => BackendMessage::CommandComplete("ALTER SCHEMA".to_owned()),
             QueryEvent::VariableSet => BackendMessage::CommandComplete("SET".to_owned()),
             QueryEvent::TransactionStarted => BackendMessage::CommandComplete("BEGIN".to_owned()),
             QueryEvent::RecordsInserted(records) => BackendMessage::CommandComplete(format!("INSERT 0 {}", records)),
@@ -204,6 +228,56 @@ pub(crate) enum QueryErrorKind {
     InvalidArgumentForPowerFunction,
     InvalidTextRepresentation2(String, String),
     CannotCoerce(String, String),
+    UniqueViolation(String),
+    IoError(String),
+    DataCorrupted(String),
+    SequenceAlreadyExists(String),
+    SequenceDoesNotExist(String),
+    SequenceIsRequired {
+        sequence: String,
+        column: String,
+        table: String,
+    },
+    SequenceLimitExceeded {
+        sequence: String,
+        limit: &'static str,
+        value: i64,
+    },
+    SequenceValueOutOfBounds {
+        sequence: String,
+        value: i64,
+        min_value: i64,
+        max_value: i64,
+    },
+    SequenceValueNotDefined(String),
+    GeneratedAlways(String),
+    FunctionDoesNotExist(String),
+    DatabaseAlreadyExists(String),
+    DatabaseDoesNotExist(String),
+    DatabaseIsInUse(String),
+    InvalidDatabaseName(String),
+    IndexAlreadyExists(String),
+    IndexDoesNotExist(String),
+    /// column and table
+    ColumnAlreadyExists(String, String),
+    /// column and type
+    ColumnCannotBeCast(String, String),
+    /// index and table of the constraint
+    IndexIsRequired(String, String),
+    MultiplePrimaryKeys(String),
+    /// column and table
+    NotNullViolation(String, String),
+    /// column and table
+    ColumnContainsNulls(String, String),
+    DefaultNotAllowed,
+    /// table and constraint
+    CheckViolation(String, String),
+    /// constraint and table
+    CheckViolatedByRow(String, String),
+    /// constraint and table
+    ConstraintDoesNotExist(String, String),
+    /// constraint and table
+    ConstraintAlreadyExists(String, String),
 }
 
 impl QueryErrorKind {
@@ -238,6 +312,34 @@ impl QueryErrorKind {
             Self::DatatypeMismatch { .. } => "42804",
             Self::InvalidArgumentForPowerFunction => "2201F",
             Self::CannotCoerce(_, _) => "42846",
+            Self::UniqueViolation(_) => "23505",
+            Self::IoError(_) => "58030",
+            Self::DataCorrupted(_) => "XX001",
+            Self::SequenceAlreadyExists(_) => "42P07",
+            Self::SequenceDoesNotExist(_) => "42P01",
+            Self::SequenceIsRequired { .. } => "2BP01",
+            Self::SequenceLimitExceeded { .. } => "2200H",
+            Self::SequenceValueOutOfBounds { .. } => "22003",
+            Self::SequenceValueNotDefined(_) => "55000",
+            Self::GeneratedAlways(_) => "428C9",
+            Self::FunctionDoesNotExist(_) => "42883",
+            Self::DatabaseAlreadyExists(_) => "42P04",
+            Self::DatabaseDoesNotExist(_) => "3D000",
+            Self::DatabaseIsInUse(_) => "55006",
+            Self::InvalidDatabaseName(_) => "42602",
+            Self::IndexAlreadyExists(_) => "42P07",
+            Self::IndexDoesNotExist(_) => "42704",
+            Self::ColumnAlreadyExists(_, _) => "42701",
+            Self::ColumnCannotBeCast(_, _) => "42804",
+            Self::IndexIsRequired(_, _) => "2BP01",
+            Self::MultiplePrimaryKeys(_) => "42P16",
+            Self::NotNullViolation(_, _) => "23502",
+            Self::ColumnContainsNulls(_, _) => "23502",
+            Self::DefaultNotAllowed => "42601",
+            Self::CheckViolation(_, _) => "23514",
+            Self::CheckViolatedByRow(_, _) => "23514",
+            Self::ConstraintDoesNotExist(_, _) => "42704",
+            Self::ConstraintAlreadyExists(_, _) => "42710",
         }
     }
 }
@@ -345,6 +447,99 @@ impl Display for QueryErrorKind {
             ),
             Self::InvalidArgumentForPowerFunction => write!(f, "cannot take square root of a negative number"),
             Self::CannotCoerce(from_type, to_type) => write!(f, "cannot cast type {} to {}", from_type, to_type),
+            Self::UniqueViolation(constraint) => {
+                write!(f, "duplicate key value violates unique constraint \"{}\"", constraint)
+            }
+            Self::IoError(message) => write!(f, "could not access storage: {}", message),
+            Self::DataCorrupted(message) => write!(f, "{}", message),
+            Self::SequenceAlreadyExists(sequence) => write!(f, "relation \"{}\" already exists", sequence),
+            Self::SequenceDoesNotExist(sequence) => write!(f, "relation \"{}\" does not exist", sequence),
+            Self::SequenceIsRequired {
+                sequence,
+                column,
+                table,
+            } => write!(
+                f,
+                "cannot drop sequence {} because column {} of table {} requires it",
+                sequence, column, table
+            ),
+            Self::SequenceLimitExceeded { sequence, limit, value } => write!(
+                f,
+                "nextval: reached {} value of sequence \"{}\" ({})",
+                limit, sequence, value
+            ),
+            Self::SequenceValueOutOfBounds {
+                sequence,
+                value,
+                min_value,
+                max_value,
+            } => write!(
+                f,
+                "setval: value {} is out of bounds for sequence \"{}\" ({}..{})",
+                value, sequence, min_value, max_value
+            ),
+            Self::SequenceValueNotDefined(sequence) => write!(
+                f,
+                "currval of sequence \"{}\" is not yet defined in this session",
+                sequence
+            ),
+            Self::GeneratedAlways(column) => write!(f, "cannot insert into column \"{}\"", column),
+            Self::FunctionDoesNotExist(function) => write!(f, "function {} does not exist", function),
+            Self::DatabaseAlreadyExists(database) => write!(f, "database \"{}\" already exists", database),
+            Self::DatabaseDoesNotExist(database) => write!(f, "database \"{}\" does not exist", database),
+            Self::DatabaseIsInUse(_) => write!(f, "cannot drop the currently open database"),
+            Self::InvalidDatabaseName(database) => write!(f, "database name \"{}\" contains \"/\"", database),
+            Self::IndexAlreadyExists(index) => write!(f, "relation \"{}\" already exists", index),
+            Self::IndexDoesNotExist(index) => write!(f, "index \"{}\" does not exist", index),
+            Self::ColumnAlreadyExists(column, table) => {
+                write!(f, "column \"{}\" of relation \"{}\" already exists", column, table)
+            }
+            Self::ColumnCannotBeCast(column, sql_type) => write!(
+                f,
+                "column \"{}\" cannot be cast automatically to type {}",
+                column, sql_type
+            ),
+            Self::IndexIsRequired(index, table) => write!(
+                f,
+                "cannot drop index {} because constraint {} on table {} requires it",
+                index, index, table
+            ),
+            Self::MultiplePrimaryKeys(table) => {
+                write!(f, "multiple primary keys for table \"{}\" are not allowed", table)
+            }
+            Self::NotNullViolation(column, table) => write!(
+                f,
+                "null value in column \"{}\" of relation \"{}\" violates not-null constraint",
+                column, table
+            ),
+            Self::ColumnContainsNulls(column, table) => {
+                write!(
+                    f,
+                    "column \"{}\" of relation \"{}\" contains null values",
+                    column, table
+                )
+            }
+            Self::DefaultNotAllowed => write!(f, "DEFAULT is not allowed in this context"),
+            Self::CheckViolation(table, constraint) => write!(
+                f,
+                "new row for relation \"{}\" violates check constraint \"{}\"",
+                table, constraint
+            ),
+            Self::CheckViolatedByRow(constraint, table) => write!(
+                f,
+                "check constraint \"{}\" of relation \"{}\" is violated by some row",
+                constraint, table
+            ),
+            Self::ConstraintDoesNotExist(constraint, table) => write!(
+                f,
+                "constraint \"{}\" of relation \"{}\" does not exist",
+                constraint, table
+            ),
+            Self::ConstraintAlreadyExists(constraint, table) => write!(
+                f,
+                "constraint \"{}\" for relation \"{}\" already exists",
+                constraint, table
+            ),
         }
     }
 }
@@ -659,6 +854,236 @@ impl QueryError {
             kind: QueryErrorKind::CannotCoerce(from_type.to_string(), to_type.to_string()),
         }
     }
+
+    pub fn unique_violation<S: ToString>(constraint: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::UniqueViolation(constraint.to_string()),
+        }
+    }
+
+    pub fn io_error<S: ToString>(message: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::IoError(message.to_string()),
+        }
+    }
+
+    pub fn data_corrupted<S: ToString>(message: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::DataCorrupted(message.to_string()),
+        }
+    }
+
+    pub fn sequence_already_exists<S: ToString>(sequence_name: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SequenceAlreadyExists(sequence_name.to_string()),
+        }
+    }
+
+    pub fn sequence_does_not_exist<S: ToString>(sequence_name: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SequenceDoesNotExist(sequence_name.to_string()),
+        }
+    }
+
+    /// sequence generates values of an identity column and is dropped only together with the column
+    pub fn sequence_is_required<S: ToString, C: ToString, T: ToString>(sequence: S, column: C, table: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SequenceIsRequired {
+                sequence: sequence.to_string(),
+                column: column.to_string(),
+                table: table.to_string(),
+            },
+        }
+    }
+
+    /// `maximum` is `false` for descending sequences that reached their minimum value
+    pub fn sequence_limit_exceeded<S: ToString>(sequence: S, maximum: bool, value: i64) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SequenceLimitExceeded {
+                sequence: sequence.to_string(),
+                limit: if maximum { "maximum" } else { "minimum" },
+                value,
+            },
+        }
+    }
+
+    pub fn sequence_value_out_of_bounds<S: ToString>(
+        sequence: S,
+        value: i64,
+        min_value: i64,
+        max_value: i64,
+    ) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SequenceValueOutOfBounds {
+                sequence: sequence.to_string(),
+                value,
+                min_value,
+                max_value,
+            },
+        }
+    }
+
+    pub fn sequence_value_not_defined<S: ToString>(sequence: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::SequenceValueNotDefined(sequence.to_string()),
+        }
+    }
+
+    /// explicit value is given to `GENERATED ALWAYS AS IDENTITY` column
+    pub fn generated_always<S: ToString>(column: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::GeneratedAlways(column.to_string()),
+        }
+    }
+
+    pub fn function_does_not_exist<S: ToString>(function: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::FunctionDoesNotExist(function.to_string()),
+        }
+    }
+
+    pub fn database_already_exists<S: ToString>(database_name: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::DatabaseAlreadyExists(database_name.to_string()),
+        }
+    }
+
+    pub fn database_does_not_exist<S: ToString>(database_name: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::DatabaseDoesNotExist(database_name.to_string()),
+        }
+    }
+
+    /// connection is made to a database that does not exist or was dropped while the connection was open
+    pub fn fatal_database_does_not_exist<S: ToString>(database_name: S) -> QueryError {
+        QueryError {
+            severity: Severity::Fatal,
+            kind: QueryErrorKind::DatabaseDoesNotExist(database_name.to_string()),
+        }
+    }
+
+    pub fn database_is_in_use<S: ToString>(database_name: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::DatabaseIsInUse(database_name.to_string()),
+        }
+    }
+
+    pub fn invalid_database_name<S: ToString>(database_name: S) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::InvalidDatabaseName(database_name.to_string()),
+        }
+    }
+
+    pub fn index_already_exists<I: ToString>(index_name: I) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::IndexAlreadyExists(index_name.to_string()),
+        }
+    }
+
+    pub fn index_does_not_exist<I: ToString>(index_name: I) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::IndexDoesNotExist(index_name.to_string()),
+        }
+    }
+
+    pub fn column_already_exists<C: ToString, T: ToString>(column_name: C, table_name: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ColumnAlreadyExists(column_name.to_string(), table_name.to_string()),
+        }
+    }
+
+    /// values of the column can't be converted to the type without `USING` expression
+    pub fn column_cannot_be_cast<C: ToString, T: ToString>(column_name: C, sql_type: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ColumnCannotBeCast(column_name.to_string(), sql_type.to_string()),
+        }
+    }
+
+    /// index enforces the constraint with the same name and can be dropped only together with it
+    pub fn index_is_required<I: ToString, T: ToString>(index_name: I, table_name: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::IndexIsRequired(index_name.to_string(), table_name.to_string()),
+        }
+    }
+
+    pub fn multiple_primary_keys<T: ToString>(table_name: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::MultiplePrimaryKeys(table_name.to_string()),
+        }
+    }
+
+    pub fn not_null_violation<C: ToString, T: ToString>(column_name: C, table_name: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::NotNullViolation(column_name.to_string(), table_name.to_string()),
+        }
+    }
+
+    /// `NOT NULL` can't be set on the column because of existing rows
+    pub fn column_contains_nulls<C: ToString, T: ToString>(column_name: C, table_name: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ColumnContainsNulls(column_name.to_string(), table_name.to_string()),
+        }
+    }
+
+    /// `DEFAULT` keyword is used outside of `VALUES` of `INSERT` or `SET` of `UPDATE`
+    pub fn default_not_allowed() -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::DefaultNotAllowed,
+        }
+    }
+
+    pub fn check_violation<T: ToString, C: ToString>(table_name: T, constraint_name: C) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::CheckViolation(table_name.to_string(), constraint_name.to_string()),
+        }
+    }
+
+    /// `CHECK` constraint can't be added to the table because of existing rows
+    pub fn check_violated_by_row<C: ToString, T: ToString>(constraint_name: C, table_name: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::CheckViolatedByRow(constraint_name.to_string(), table_name.to_string()),
+        }
+    }
+
+    pub fn constraint_does_not_exist<C: ToString, T: ToString>(constraint_name: C, table_name: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ConstraintDoesNotExist(constraint_name.to_string(), table_name.to_string()),
+        }
+    }
+
+    pub fn constraint_already_exists<C: ToString, T: ToString>(constraint_name: C, table_name: T) -> QueryError {
+        QueryError {
+            severity: Severity::Error,
+            kind: QueryErrorKind::ConstraintAlreadyExists(constraint_name.to_string(), table_name.to_string()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -693,6 +1118,24 @@ mod tests {
             assert_eq!(message, BackendMessage::CommandComplete("DROP TABLE".to_owned()));
         }
 
+        #[test]
+        fn drop_index() {
+            let message: BackendMessage = QueryEvent::IndexDropped.into();
+            assert_eq!(message, BackendMessage::CommandComplete("DROP INDEX".to_owned()));
+        }
+
+        #[test]
+        fn alter_table() {
+            let message: BackendMessage = QueryEvent::TableAltered.into();
+            assert_eq!(message, BackendMessage::CommandComplete("ALTER TABLE".to_owned()));
+        }
+
+        #[test]
+        fn alter_schema() {
+            let message: BackendMessage = QueryEvent::SchemaAltered.into();
+            assert_eq!(message, BackendMessage::CommandComplete("ALTER SCHEMA".to_owned()));
+        }
+
         #[test]
         fn insert_record() {
             let records_number = 3;
@@ -1049,6 +1492,327 @@ mod tests {
                 )
             )
         }
+
+        #[test]
+        fn unique_violation() {
+            let message: BackendMessage = QueryError::unique_violation("idx").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("23505"),
+                    Some("duplicate key value violates unique constraint \"idx\"".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn io_error() {
+            let message: BackendMessage = QueryError::io_error("No space left on device").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("58030"),
+                    Some("could not access storage: No space left on device".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn data_corrupted() {
+            let message: BackendMessage = QueryError::data_corrupted("checksum mismatch").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(Some("ERROR"), Some("XX001"), Some("checksum mismatch".to_owned()))
+            )
+        }
+
+        #[test]
+        fn sequence_is_required() {
+            let message: BackendMessage = QueryError::sequence_is_required("t_id_seq", "id", "t").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("2BP01"),
+                    Some("cannot drop sequence t_id_seq because column id of table t requires it".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn sequence_limit_exceeded() {
+            let message: BackendMessage = QueryError::sequence_limit_exceeded("seq", true, 10).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("2200H"),
+                    Some("nextval: reached maximum value of sequence \"seq\" (10)".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn sequence_value_out_of_bounds() {
+            let message: BackendMessage = QueryError::sequence_value_out_of_bounds("seq", 0, 1, 10).into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("22003"),
+                    Some("setval: value 0 is out of bounds for sequence \"seq\" (1..10)".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn sequence_value_not_defined() {
+            let message: BackendMessage = QueryError::sequence_value_not_defined("seq").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("55000"),
+                    Some("currval of sequence \"seq\" is not yet defined in this session".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn generated_always() {
+            let message: BackendMessage = QueryError::generated_always("id").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("428C9"),
+                    Some("cannot insert into column \"id\"".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn database_already_exists() {
+            let message: BackendMessage = QueryError::database_already_exists("db").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42P04"),
+                    Some("database \"db\" already exists".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn database_does_not_exist() {
+            let message: BackendMessage = QueryError::fatal_database_does_not_exist("db").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("FATAL"),
+                    Some("3D000"),
+                    Some("database \"db\" does not exist".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn database_is_in_use() {
+            let message: BackendMessage = QueryError::database_is_in_use("db").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("55006"),
+                    Some("cannot drop the currently open database".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn invalid_database_name() {
+            let message: BackendMessage = QueryError::invalid_database_name("db/name").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42602"),
+                    Some("database name \"db/name\" contains \"/\"".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn index_already_exists() {
+            let message: BackendMessage = QueryError::index_already_exists("schema_name.index_name").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42P07"),
+                    Some("relation \"schema_name.index_name\" already exists".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn index_does_not_exist() {
+            let message: BackendMessage = QueryError::index_does_not_exist("schema_name.index_name").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("index \"schema_name.index_name\" does not exist".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn column_already_exists() {
+            let message: BackendMessage = QueryError::column_already_exists("column_name", "table_name").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42701"),
+                    Some("column \"column_name\" of relation \"table_name\" already exists".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn column_cannot_be_cast() {
+            let message: BackendMessage = QueryError::column_cannot_be_cast("column_name", "integer").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42804"),
+                    Some("column \"column_name\" cannot be cast automatically to type integer".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn index_is_required() {
+            let message: BackendMessage = QueryError::index_is_required("t_pkey", "t").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("2BP01"),
+                    Some("cannot drop index t_pkey because constraint t_pkey on table t requires it".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn multiple_primary_keys() {
+            let message: BackendMessage = QueryError::multiple_primary_keys("t").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42P16"),
+                    Some("multiple primary keys for table \"t\" are not allowed".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn not_null_violation() {
+            let message: BackendMessage = QueryError::not_null_violation("c", "t").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("23502"),
+                    Some("null value in column \"c\" of relation \"t\" violates not-null constraint".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn column_contains_nulls() {
+            let message: BackendMessage = QueryError::column_contains_nulls("c", "t").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("23502"),
+                    Some("column \"c\" of relation \"t\" contains null values".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn default_not_allowed() {
+            let message: BackendMessage = QueryError::default_not_allowed().into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42601"),
+                    Some("DEFAULT is not allowed in this context".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn check_violation() {
+            let message: BackendMessage = QueryError::check_violation("t", "c").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("23514"),
+                    Some("new row for relation \"t\" violates check constraint \"c\"".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn check_violated_by_row() {
+            let message: BackendMessage = QueryError::check_violated_by_row("c", "t").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("23514"),
+                    Some("check constraint \"c\" of relation \"t\" is violated by some row".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn constraint_does_not_exist() {
+            let message: BackendMessage = QueryError::constraint_does_not_exist("c", "t").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42704"),
+                    Some("constraint \"c\" of relation \"t\" does not exist".to_owned()),
+                )
+            )
+        }
+
+        #[test]
+        fn constraint_already_exists() {
+            let message: BackendMessage = QueryError::constraint_already_exists("c", "t").into();
+            assert_eq!(
+                message,
+                BackendMessage::ErrorResponse(
+                    Some("ERROR"),
+                    Some("42710"),
+                    Some("constraint \"c\" for relation \"t\" already exists".to_owned()),
+                )
+            )
+        }
     }
 
     #[cfg(test)]
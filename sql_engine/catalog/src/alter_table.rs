@@ -0,0 +1,648 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    constraints::{columns_start, is_check},
+    identity_sequence_name, index_def,
+    sequences::Sequence,
+    write_error, CatalogHandler, CurrentValues, SequenceError, COLUMNS_TABLE, DEFINITION_SCHEMA, INDEXES_TABLE,
+    SCHEMATA_TABLE, SEQUENCES_TABLE, STATISTICS_TABLE, TABLES_TABLE, TABLE_CONSTRAINTS_TABLE,
+};
+use bigdecimal::{BigDecimal, FromPrimitive};
+use data_definition_execution_plan::{AlterTableAction, AlterTableQuery, ColumnInfo, ExecutionError, ExecutionOutcome};
+use data_manipulation_operators::UnOperator;
+use data_manipulation_query_result::QueryExecutionError;
+use data_manipulation_typed_tree::DynamicTypedTree;
+use definition::{FullTableName, SchemaName};
+use query_ast::Expr;
+use scalar::ScalarValue;
+use storage::{
+    repr::{Datum, ToDatum},
+    Binary, Key,
+};
+use types::{SqlType, SqlTypeFamily};
+
+impl<'c> CatalogHandler<'c> {
+    pub(crate) fn alter_table(&self, query: AlterTableQuery) -> Result<ExecutionOutcome, ExecutionError> {
+        let AlterTableQuery {
+            mut full_table_name,
+            if_exists,
+            actions,
+        } = query;
+        if !self.schema_exists(&SchemaName::from(&full_table_name.schema())) {
+            return Err(ExecutionError::SchemaDoesNotExist(full_table_name.schema().to_owned()));
+        }
+        // key of the table record does not change when the table is renamed
+        let full_table_id = match self.full_table_id(&full_table_name) {
+            Some(full_table_id) => full_table_id,
+            None if if_exists => return Ok(ExecutionOutcome::TableAltered),
+            None => {
+                return Err(ExecutionError::TableDoesNotExist(
+                    full_table_name.schema().to_owned(),
+                    full_table_name.table().to_owned(),
+                ))
+            }
+        };
+        for action in actions {
+            match action {
+                AlterTableAction::AddColumn { column, if_not_exists } => {
+                    self.add_column(&full_table_name, &full_table_id, column, if_not_exists)?
+                }
+                // constraints and indexes over the column are dropped even without cascade as PostgreSQL does
+                AlterTableAction::DropColumn {
+                    name,
+                    if_exists,
+                    cascade: _cascade,
+                } => self.drop_column(&full_table_name, &full_table_id, &name, if_exists)?,
+                AlterTableAction::RenameColumn { old_name, new_name } => {
+                    self.rename_column(&full_table_name, &full_table_id, &old_name, &new_name)?
+                }
+                AlterTableAction::AlterColumnType {
+                    name,
+                    sql_type,
+                    using,
+                    checks,
+                } => self.alter_column_type(&full_table_name, &full_table_id, &name, sql_type, using, checks)?,
+                AlterTableAction::RenameTable { new_name } => {
+                    full_table_name = self.rename_table(&full_table_name, &new_name)?;
+                }
+                AlterTableAction::AddConstraint(constraint) => {
+                    self.add_constraint(&full_table_name, &full_table_id, constraint)?
+                }
+                // nothing else depends on constraints, so cascade has no effect
+                AlterTableAction::DropConstraint {
+                    name,
+                    if_exists,
+                    cascade: _cascade,
+                } => self.drop_constraint(&full_table_name, &full_table_id, &name, if_exists)?,
+            }
+        }
+        log::debug!("{} TABLE ALTERED", full_table_name);
+        Ok(ExecutionOutcome::TableAltered)
+    }
+
+    /// tables, indexes and sequences of the schema are moved under the new name
+    pub(crate) fn rename_schema(&self, schema_name: &str, new_name: &str) -> Result<ExecutionOutcome, ExecutionError> {
+        if self.schema_id(schema_name).is_none() {
+            return Err(ExecutionError::SchemaDoesNotExist(schema_name.to_owned()));
+        }
+        if self.schema_id(new_name).is_some() {
+            return Err(ExecutionError::SchemaAlreadyExists(new_name.to_owned()));
+        }
+        for full_table_name in self
+            .table_names()
+            .into_iter()
+            .filter(|full_table_name| full_table_name.schema() == schema_name)
+        {
+            let new_full_table_name = FullTableName::from((&new_name, &full_table_name.table()));
+            self.database
+                .rename_tree(&full_table_name.to_string(), &new_full_table_name.to_string())
+                .map_err(write_error)?;
+        }
+        for catalog_table in &[
+            SCHEMATA_TABLE,
+            TABLES_TABLE,
+            COLUMNS_TABLE,
+            STATISTICS_TABLE,
+            INDEXES_TABLE,
+            SEQUENCES_TABLE,
+            TABLE_CONSTRAINTS_TABLE,
+        ] {
+            self.update_records(catalog_table, None, |record| {
+                if record[1] == schema_name {
+                    record[1] = Datum::from_string(new_name.to_owned());
+                    true
+                } else {
+                    false
+                }
+            })?;
+        }
+        log::debug!("{} SCHEMA RENAMED TO {}", schema_name, new_name);
+        Ok(ExecutionOutcome::SchemaAltered)
+    }
+
+    fn add_column(
+        &self,
+        full_table_name: &FullTableName,
+        full_table_id: &Key,
+        column: ColumnInfo,
+        if_not_exists: bool,
+    ) -> Result<(), ExecutionError> {
+        let columns = self.column_records(full_table_id);
+        if columns.iter().any(|(_key, record)| record[3] == column.name.as_str()) {
+            return if if_not_exists {
+                Ok(())
+            } else {
+                Err(ExecutionError::ColumnAlreadyExists(
+                    column.name,
+                    full_table_name.table().to_owned(),
+                ))
+            };
+        }
+        let ordinal = columns.len();
+        let mut sequence = match &column.identity {
+            None => None,
+            Some(identity) => {
+                let full_sequence_name = FullTableName::from((
+                    &full_table_name.schema(),
+                    &identity_sequence_name(full_table_name.table(), &column.name),
+                ));
+                if self.full_sequence_id(&full_sequence_name).is_some() {
+                    return Err(ExecutionError::SequenceAlreadyExists(
+                        full_sequence_name.schema().to_owned(),
+                        full_sequence_name.table().to_owned(),
+                    ));
+                }
+                let owner = (full_table_name.table().to_owned(), column.name.clone());
+                Some((full_sequence_name, Sequence::new(identity.options, Some(owner))))
+            }
+        };
+
+        // existing rows get values of the identity sequence, of the default or NULL
+        let sequences = self.sequences(CurrentValues::default());
+        self.rewrite_rows(full_table_name, |row_number, mut row| {
+            let value = match (&mut sequence, &column.default) {
+                (None, None) => Datum::from_null(),
+                (None, Some(default)) => {
+                    let value = default
+                        .value
+                        .clone()
+                        .eval_with(&[], &sequences)
+                        .map_err(ExecutionError::Evaluation)?;
+                    cast(value, &column.sql_type, &column.name, row_number).map_err(ExecutionError::Evaluation)?
+                }
+                (Some((full_sequence_name, sequence)), _) => {
+                    let next = sequence.advance(full_sequence_name.table()).map_err(sequence_error)?;
+                    let value = ScalarValue::Num {
+                        value: BigDecimal::from(next),
+                        type_family: SqlTypeFamily::BigInt,
+                    };
+                    cast(value, &column.sql_type, &column.name, row_number).map_err(ExecutionError::Evaluation)?
+                }
+            };
+            if column.not_null && value == Datum::Null {
+                return Err(ExecutionError::Evaluation(QueryExecutionError::column_contains_nulls(
+                    &column.name,
+                    full_table_name.table(),
+                )));
+            }
+            row.resize(ordinal, Datum::from_null());
+            row.push(value);
+            Ok(row)
+        })?;
+
+        let mut key = full_table_id.unpack();
+        key.push(Datum::from_u64(ordinal as u64));
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE))
+            .write_key(
+                Binary::pack(&key),
+                Some(self.column_record(full_table_name, ordinal, &column)),
+            )
+            .map_err(write_error)?;
+        if let Some((full_sequence_name, sequence)) = sequence {
+            self.create_sequence(&full_sequence_name, sequence)?;
+        }
+        Ok(())
+    }
+
+    fn drop_column(
+        &self,
+        full_table_name: &FullTableName,
+        full_table_id: &Key,
+        name: &str,
+        if_exists: bool,
+    ) -> Result<(), ExecutionError> {
+        let columns = self.column_records(full_table_id);
+        let position = match columns.iter().position(|(_key, record)| record[3] == name) {
+            Some(position) => position,
+            None if if_exists => return Ok(()),
+            None => return Err(ExecutionError::ColumnNotFound(name.to_owned())),
+        };
+
+        let constraints_table = self
+            .database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, TABLE_CONSTRAINTS_TABLE));
+        for (key, value) in constraints_table.scan_prefix(full_table_id).collect::<Vec<_>>() {
+            let record = value.unpack();
+            if record[columns_start(&record)..].iter().any(|column| *column == name) {
+                constraints_table.write_key(key, None).map_err(write_error)?;
+            }
+        }
+
+        // indexes over the column are dropped, the other ones are built again over shifted columns
+        let table_name = full_table_name.to_string();
+        let indexes_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE));
+        let mut indexes = vec![];
+        for (key, value) in indexes_table.scan_prefix(full_table_id).collect::<Vec<_>>() {
+            let index = index_def(&value.unpack());
+            self.database.drop_index(&table_name, index.name());
+            if index.column_names().iter().any(|column| column == name) {
+                indexes_table.write_key(key, None).map_err(write_error)?;
+            } else {
+                indexes.push(index);
+            }
+        }
+
+        if let Datum::String(sequence) = &columns[position].1[7] {
+            let full_sequence_name = FullTableName::from((&full_table_name.schema(), sequence));
+            if let Some(sequence_key) = self.full_sequence_id(&full_sequence_name) {
+                self.database
+                    .table(format!("{}.{}", DEFINITION_SCHEMA, SEQUENCES_TABLE))
+                    .write_key(sequence_key, None)
+                    .map_err(write_error)?;
+            }
+        }
+
+        self.rewrite_rows(full_table_name, |_row_number, mut row| {
+            if position < row.len() {
+                row.remove(position);
+            }
+            Ok(row)
+        })?;
+        self.remove_column_record(COLUMNS_TABLE, full_table_id, position)?;
+        self.remove_column_record(STATISTICS_TABLE, full_table_id, position)?;
+
+        let column_names = self
+            .column_records(full_table_id)
+            .into_iter()
+            .map(|(_key, record)| record[3].as_string())
+            .collect::<Vec<_>>();
+        for index in indexes {
+            let columns = index
+                .column_names()
+                .iter()
+                .filter_map(|column| column_names.iter().position(|name| name == column))
+                .collect::<Vec<_>>();
+            self.database
+                .create_index(&table_name, index.name(), &columns, index.unique())
+                .map_err(write_error)?;
+        }
+        Ok(())
+    }
+
+    fn rename_column(
+        &self,
+        full_table_name: &FullTableName,
+        full_table_id: &Key,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), ExecutionError> {
+        let columns = self.column_records(full_table_id);
+        if columns.iter().any(|(_key, record)| record[3] == new_name) {
+            return Err(ExecutionError::ColumnAlreadyExists(
+                new_name.to_owned(),
+                full_table_name.table().to_owned(),
+            ));
+        }
+        if columns.iter().all(|(_key, record)| record[3] != old_name) {
+            return Err(ExecutionError::ColumnNotFound(old_name.to_owned()));
+        }
+
+        let new_name = Datum::from_string(new_name.to_owned());
+        for catalog_table in &[COLUMNS_TABLE, STATISTICS_TABLE] {
+            self.update_records(catalog_table, Some(full_table_id), |record| {
+                if record[3] == old_name {
+                    record[3] = new_name.clone();
+                    true
+                } else {
+                    false
+                }
+            })?;
+        }
+        // names of indexed and constrained columns are at the end of the records
+        for catalog_table in &[INDEXES_TABLE, TABLE_CONSTRAINTS_TABLE] {
+            self.update_records(catalog_table, Some(full_table_id), |record| {
+                let start = if *catalog_table == INDEXES_TABLE {
+                    6
+                } else {
+                    columns_start(record)
+                };
+                let mut changed = false;
+                for column in record[start..].iter_mut().filter(|column| **column == old_name) {
+                    *column = new_name.clone();
+                    changed = true;
+                }
+                // predicate of `CHECK` constraint refers to the column by its name
+                if changed && *catalog_table == TABLE_CONSTRAINTS_TABLE && is_check(record) {
+                    let predicate = record[5]
+                        .as_string()
+                        .parse::<Expr>()
+                        .expect("CHECK predicate is kept as valid text");
+                    record[5] =
+                        Datum::from_string(predicate.rename_column(old_name, &new_name.as_string()).to_string());
+                }
+                changed
+            })?;
+        }
+        // identity sequence keeps its name as PostgreSQL does
+        self.update_records(SEQUENCES_TABLE, None, |record| {
+            if record[1] == full_table_name.schema() && record[10] == full_table_name.table() && record[11] == old_name
+            {
+                record[11] = new_name.clone();
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    fn alter_column_type(
+        &self,
+        full_table_name: &FullTableName,
+        full_table_id: &Key,
+        name: &str,
+        sql_type: SqlType,
+        using: Option<DynamicTypedTree>,
+        checks: Vec<(String, DynamicTypedTree)>,
+    ) -> Result<(), ExecutionError> {
+        let columns = self.column_records(full_table_id);
+        let width = columns.len();
+        let (position, (key, mut record)) = match columns
+            .into_iter()
+            .enumerate()
+            .find(|(_position, (_key, record))| record[3] == name)
+        {
+            Some(column) => column,
+            None => return Err(ExecutionError::ColumnNotFound(name.to_owned())),
+        };
+        let current_type = SqlType::from_type_id(record[4].as_u64(), record[5].as_u64());
+        if using.is_none() && !assignable(current_type.family(), sql_type.family()) {
+            return Err(ExecutionError::ColumnCannotBeCast(
+                name.to_owned(),
+                sql_type.to_string(),
+            ));
+        }
+
+        // constraints dropped by previous actions of the statement are not checked
+        let checks = checks
+            .into_iter()
+            .filter(|(constraint, _predicate)| self.constraint_exists(full_table_id, constraint))
+            .collect::<Vec<_>>();
+        // indexes over the column follow changed rows
+        self.rewrite_rows(full_table_name, |row_number, mut row| {
+            row.resize(width, Datum::from_null());
+            let value = match &using {
+                None => scalar(&row[position]),
+                Some(using) => using
+                    .clone()
+                    .eval(&[], &row.iter().map(scalar).collect::<Vec<_>>())
+                    .map_err(ExecutionError::Evaluation)?,
+            };
+            row[position] = cast(value, &sql_type, name, row_number).map_err(ExecutionError::Evaluation)?;
+            let values = row.iter().map(scalar).collect::<Vec<_>>();
+            for (constraint, predicate) in &checks {
+                match predicate.clone().eval(&[], &values) {
+                    Ok(ScalarValue::Bool(false)) => {
+                        return Err(ExecutionError::Evaluation(QueryExecutionError::check_violated_by_row(
+                            constraint,
+                            full_table_name.table(),
+                        )))
+                    }
+                    Ok(_) => {}
+                    // predicate can't compare values of the new type
+                    Err(_) => {
+                        return Err(ExecutionError::ColumnCannotBeCast(
+                            name.to_owned(),
+                            sql_type.to_string(),
+                        ))
+                    }
+                }
+            }
+            Ok(row)
+        })?;
+
+        record[4] = Datum::from_u64(sql_type.type_id());
+        record[5] = Datum::from_optional_u64(sql_type.chars_len());
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE))
+            .write_key(key, Some(Binary::pack(&record)))
+            .map_err(write_error)?;
+        // statistics of the column are collected again by the next `ANALYZE`
+        let statistics_table = self
+            .database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, STATISTICS_TABLE));
+        for statistics_key in statistics_table
+            .scan_prefix(full_table_id)
+            .filter(|(_key, value)| value.unpack()[3] == name)
+            .map(|(key, _value)| key)
+        {
+            statistics_table.write_key(statistics_key, None).map_err(write_error)?;
+        }
+        Ok(())
+    }
+
+    fn rename_table(&self, full_table_name: &FullTableName, new_name: &str) -> Result<FullTableName, ExecutionError> {
+        let new_full_table_name = FullTableName::from((&full_table_name.schema(), &new_name));
+        if self.full_table_id(&new_full_table_name).is_some() {
+            return Err(ExecutionError::TableAlreadyExists(
+                new_full_table_name.schema().to_owned(),
+                new_full_table_name.table().to_owned(),
+            ));
+        }
+        let schema_name = full_table_name.schema();
+        let table_name = full_table_name.table();
+        for catalog_table in &[
+            TABLES_TABLE,
+            COLUMNS_TABLE,
+            STATISTICS_TABLE,
+            INDEXES_TABLE,
+            TABLE_CONSTRAINTS_TABLE,
+        ] {
+            self.update_records(catalog_table, None, |record| {
+                if record[1] == schema_name && record[2] == table_name {
+                    record[2] = Datum::from_string(new_name.to_owned());
+                    true
+                } else {
+                    false
+                }
+            })?;
+        }
+        // identity sequences keep their names as PostgreSQL does
+        self.update_records(SEQUENCES_TABLE, None, |record| {
+            if record[1] == schema_name && record[10] == table_name {
+                record[10] = Datum::from_string(new_name.to_owned());
+                true
+            } else {
+                false
+            }
+        })?;
+        self.database
+            .rename_tree(&full_table_name.to_string(), &new_full_table_name.to_string())
+            .map_err(write_error)?;
+        Ok(new_full_table_name)
+    }
+
+    /// catalog records of the table columns in order of their ordinal numbers
+    pub(crate) fn column_records(&self, full_table_id: &Key) -> Vec<(Key, Vec<Datum>)> {
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE))
+            .scan_prefix(full_table_id)
+            .map(|(key, value)| (key, value.unpack()))
+            .collect()
+    }
+
+    /// replaces every row of the table with the one that `change` makes of it, rows are numbered from 1
+    fn rewrite_rows<F>(&self, full_table_name: &FullTableName, mut change: F) -> Result<(), ExecutionError>
+    where
+        F: FnMut(usize, Vec<Datum>) -> Result<Vec<Datum>, ExecutionError>,
+    {
+        let table = self.database.table(full_table_name.to_string());
+        let rows = table.scan().collect::<Vec<_>>();
+        for (index, (key, row)) in rows.into_iter().enumerate() {
+            let row = change(index + 1, row.unpack())?;
+            table.write_key(key, Some(Binary::pack(&row))).map_err(write_error)?;
+        }
+        Ok(())
+    }
+
+    /// writes back records of the catalog table that `change` changed, only records of the table are
+    /// scanned if its key is given
+    pub(crate) fn update_records<F>(
+        &self,
+        catalog_table: &str,
+        full_table_id: Option<&Key>,
+        mut change: F,
+    ) -> Result<(), ExecutionError>
+    where
+        F: FnMut(&mut Vec<Datum>) -> bool,
+    {
+        let table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, catalog_table));
+        let records = match full_table_id {
+            None => table.scan().collect::<Vec<_>>(),
+            Some(full_table_id) => table.scan_prefix(full_table_id).collect(),
+        };
+        for (key, value) in records {
+            let mut record = value.unpack();
+            if change(&mut record) {
+                table.write_key(key, Some(Binary::pack(&record))).map_err(write_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// removes record of the column from the catalog table which records are keyed by ordinal numbers of
+    /// columns, records of the following columns are moved one position back
+    fn remove_column_record(
+        &self,
+        catalog_table: &str,
+        full_table_id: &Key,
+        position: usize,
+    ) -> Result<(), ExecutionError> {
+        let table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, catalog_table));
+        let mut moved = vec![];
+        for (key, value) in table.scan_prefix(full_table_id).collect::<Vec<_>>() {
+            let mut key = key.unpack();
+            let ordinal = key.last().map(|ordinal| ordinal.as_u64()).unwrap_or_default() as usize;
+            if ordinal < position {
+                continue;
+            }
+            table.write_key(Binary::pack(&key), None).map_err(write_error)?;
+            if ordinal > position {
+                let ordinal = Datum::from_u64(ordinal as u64 - 1);
+                let mut record = value.unpack();
+                // columns keep their ordinal numbers in records too
+                if catalog_table == COLUMNS_TABLE {
+                    record[6] = ordinal.clone();
+                }
+                key.pop();
+                key.push(ordinal);
+                moved.push((key, record));
+            }
+        }
+        for (key, record) in moved {
+            table
+                .write_key(Binary::pack(&key), Some(Binary::pack(&record)))
+                .map_err(write_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// values are converted without `USING` expression only between numbers and into strings
+fn assignable(from: SqlTypeFamily, to: SqlTypeFamily) -> bool {
+    fn number(type_family: SqlTypeFamily) -> bool {
+        !matches!(type_family, SqlTypeFamily::Bool | SqlTypeFamily::String)
+    }
+
+    from == to || to == SqlTypeFamily::String || (number(from) && number(to))
+}
+
+/// converts the value to the type of the column, fractions are rounded if the column is integer
+fn cast(
+    value: ScalarValue,
+    sql_type: &SqlType,
+    column_name: &str,
+    row_number: usize,
+) -> Result<Datum, QueryExecutionError> {
+    let type_family = sql_type.family();
+    let range = match type_family {
+        SqlTypeFamily::SmallInt => Some((i16::MIN as i64, i16::MAX as i64)),
+        SqlTypeFamily::Integer => Some((i32::MIN as i64, i32::MAX as i64)),
+        SqlTypeFamily::BigInt => Some((i64::MIN, i64::MAX)),
+        _ => None,
+    };
+    match (UnOperator::Cast(type_family).eval(value)?, range) {
+        (ScalarValue::Num { value, type_family }, Some((min, max))) => {
+            let value = value.round(0);
+            if value < BigDecimal::from(min) || value > BigDecimal::from(max) {
+                Err(QueryExecutionError::out_of_range(type_family, column_name, row_number))
+            } else {
+                Ok(ScalarValue::Num { value, type_family }.convert())
+            }
+        }
+        (value, _) => Ok(value.convert()),
+    }
+}
+
+pub(crate) fn scalar(datum: &Datum) -> ScalarValue {
+    match datum {
+        Datum::Null => ScalarValue::Null,
+        Datum::True => ScalarValue::Bool(true),
+        Datum::False => ScalarValue::Bool(false),
+        Datum::Int16(value) => ScalarValue::Num {
+            value: BigDecimal::from(*value),
+            type_family: SqlTypeFamily::SmallInt,
+        },
+        Datum::Int32(value) => ScalarValue::Num {
+            value: BigDecimal::from(*value),
+            type_family: SqlTypeFamily::Integer,
+        },
+        Datum::Int64(value) => ScalarValue::Num {
+            value: BigDecimal::from(*value),
+            type_family: SqlTypeFamily::BigInt,
+        },
+        Datum::Float32(value) => ScalarValue::Num {
+            value: BigDecimal::from_f32(**value).unwrap(),
+            type_family: SqlTypeFamily::Real,
+        },
+        Datum::Float64(value) => ScalarValue::Num {
+            value: BigDecimal::from_f64(**value).unwrap(),
+            type_family: SqlTypeFamily::Double,
+        },
+        Datum::String(value) => ScalarValue::String(value.clone()),
+    }
+}
+
+/// only identity sequences are advanced while the table is altered
+fn sequence_error(error: SequenceError) -> ExecutionError {
+    match error {
+        SequenceError::LimitExceeded {
+            sequence,
+            maximum,
+            value,
+        } => ExecutionError::Evaluation(QueryExecutionError::sequence_limit_exceeded(sequence, maximum, value)),
+        other => unreachable!("{:?} while identity column is added", other),
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    alter_table::scalar, write_error, CatalogHandler, COLUMNS_TABLE, DEFINITION_SCHEMA, INDEXES_TABLE,
+    TABLE_CONSTRAINTS_TABLE,
+};
+use data_definition_execution_plan::{CheckInfo, ConstraintInfo, ExecutionError};
+use data_manipulation_query_result::QueryExecutionError;
+use definition::{ConstraintDef, ConstraintKind, FullTableName};
+use scalar::ScalarValue;
+use storage::{repr::Datum, Binary, Key};
+
+const PRIMARY_KEY: &str = "PRIMARY KEY";
+const UNIQUE: &str = "UNIQUE";
+const CHECK: &str = "CHECK";
+
+impl<'c> CatalogHandler<'c> {
+    /// `PRIMARY KEY` and `UNIQUE` constraints are enforced by the unique index with the same name,
+    /// existing rows of the table that violate them fail creation of the index. Existing rows have
+    /// to satisfy `CHECK` constraint as well
+    pub(crate) fn add_constraint(
+        &self,
+        full_table_name: &FullTableName,
+        full_table_id: &Key,
+        constraint: ConstraintInfo,
+    ) -> Result<(), ExecutionError> {
+        let ConstraintInfo {
+            name,
+            kind,
+            column_names,
+            check,
+        } = constraint;
+        if kind == ConstraintKind::PrimaryKey
+            && self
+                .constraint_records(full_table_id)
+                .iter()
+                .any(|record| constraint_def(record).kind() == ConstraintKind::PrimaryKey)
+        {
+            return Err(ExecutionError::MultiplePrimaryKeys(full_table_name.table().to_owned()));
+        }
+        let name = match name {
+            Some(name) if kind != ConstraintKind::Check && self.index_exists(full_table_name, &name) => {
+                return Err(ExecutionError::IndexAlreadyExists(
+                    full_table_name.schema().to_owned(),
+                    name,
+                ))
+            }
+            Some(name) if self.constraint_exists(full_table_id, &name) => {
+                return Err(ExecutionError::ConstraintAlreadyExists(
+                    name,
+                    full_table_name.table().to_owned(),
+                ))
+            }
+            Some(name) => name,
+            None => self.constraint_name(full_table_name, full_table_id, kind, &column_names),
+        };
+
+        let mut record = vec![
+            Datum::from_string(self.database.name().to_owned()),
+            Datum::from_string(full_table_name.schema().to_owned()),
+            Datum::from_string(full_table_name.table().to_owned()),
+            Datum::from_string(name.clone()),
+        ];
+        match check {
+            Some(CheckInfo { text, predicate }) => {
+                let width = self.column_records(full_table_id).len();
+                for (_key, row) in self.database.table(full_table_name.to_string()).scan() {
+                    let mut row = row.unpack();
+                    row.resize(width, Datum::from_null());
+                    let values = row.iter().map(scalar).collect::<Vec<_>>();
+                    let satisfied = predicate
+                        .clone()
+                        .eval(&[], &values)
+                        .map_err(ExecutionError::Evaluation)?;
+                    if satisfied == ScalarValue::Bool(false) {
+                        return Err(ExecutionError::Evaluation(QueryExecutionError::check_violated_by_row(
+                            name,
+                            full_table_name.table(),
+                        )));
+                    }
+                }
+                record.push(Datum::from_string(CHECK.to_owned()));
+                record.push(Datum::from_string(text));
+            }
+            None => {
+                if kind == ConstraintKind::PrimaryKey {
+                    self.set_not_null(full_table_name, full_table_id, &column_names)?;
+                }
+                self.create_index(full_table_name, full_table_id, name.clone(), column_names.clone(), true)?;
+                let kind = match kind {
+                    ConstraintKind::PrimaryKey => PRIMARY_KEY,
+                    _ => UNIQUE,
+                };
+                record.push(Datum::from_string(kind.to_owned()));
+            }
+        }
+        record.extend(column_names.into_iter().map(Datum::from_string));
+        let mut key = full_table_id.unpack();
+        key.push(Datum::from_string(name));
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, TABLE_CONSTRAINTS_TABLE))
+            .write_key(Binary::pack(&key), Some(Binary::pack(&record)))
+            .map_err(write_error)?;
+        Ok(())
+    }
+
+    /// index of `PRIMARY KEY` or `UNIQUE` constraint is dropped together with it
+    pub(crate) fn drop_constraint(
+        &self,
+        full_table_name: &FullTableName,
+        full_table_id: &Key,
+        name: &str,
+        if_exists: bool,
+    ) -> Result<(), ExecutionError> {
+        let constraints_table = self
+            .database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, TABLE_CONSTRAINTS_TABLE));
+        let (key, record) = match constraints_table
+            .scan_prefix(full_table_id)
+            .map(|(key, value)| (key, value.unpack()))
+            .find(|(_key, record)| record[3] == name)
+        {
+            Some(constraint) => constraint,
+            None if if_exists => return Ok(()),
+            None => {
+                return Err(ExecutionError::ConstraintDoesNotExist(
+                    name.to_owned(),
+                    full_table_name.table().to_owned(),
+                ))
+            }
+        };
+        constraints_table.write_key(key, None).map_err(write_error)?;
+        if !is_check(&record) {
+            let indexes_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE));
+            if let Some(index_key) = indexes_table
+                .scan_prefix(full_table_id)
+                .find(|(_key, value)| value.unpack()[3] == name)
+                .map(|(key, _value)| key)
+            {
+                indexes_table.write_key(index_key, None).map_err(write_error)?;
+            }
+            self.database.drop_index(&full_table_name.to_string(), name);
+        }
+        Ok(())
+    }
+
+    /// catalog records of the table constraints in order of their names
+    pub(crate) fn constraint_records(&self, full_table_id: &Key) -> Vec<Vec<Datum>> {
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, TABLE_CONSTRAINTS_TABLE))
+            .scan_prefix(full_table_id)
+            .map(|(_key, value)| value.unpack())
+            .collect()
+    }
+
+    /// columns of primary key become `NOT NULL`, existing rows of the table must have values in them
+    fn set_not_null(
+        &self,
+        full_table_name: &FullTableName,
+        full_table_id: &Key,
+        column_names: &[String],
+    ) -> Result<(), ExecutionError> {
+        let rows = self
+            .database
+            .table(full_table_name.to_string())
+            .scan()
+            .map(|(_key, row)| row.unpack())
+            .collect::<Vec<_>>();
+        for (_key, record) in self.column_records(full_table_id) {
+            if !column_names.iter().any(|name| record[3] == name.as_str()) {
+                continue;
+            }
+            let ordinal = record[6].as_u64() as usize;
+            if rows
+                .iter()
+                .any(|row| matches!(row.get(ordinal), None | Some(Datum::Null)))
+            {
+                return Err(ExecutionError::Evaluation(QueryExecutionError::column_contains_nulls(
+                    record[3].as_string(),
+                    full_table_name.table(),
+                )));
+            }
+        }
+        self.update_records(COLUMNS_TABLE, Some(full_table_id), |record| {
+            if column_names.iter().any(|name| record[3] == name.as_str()) {
+                // records of columns that were created before have neither `NOT NULL` nor default
+                record.resize(record.len().max(11), Datum::from_null());
+                record[9] = Datum::from_bool(true);
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    fn index_exists(&self, full_table_name: &FullTableName, name: &str) -> bool {
+        self.full_index_id(&FullTableName::from((&full_table_name.schema(), &name)))
+            .is_some()
+    }
+
+    pub(crate) fn constraint_exists(&self, full_table_id: &Key, name: &str) -> bool {
+        self.constraint_records(full_table_id)
+            .iter()
+            .any(|record| record[3] == name)
+    }
+
+    /// names are made as PostgreSQL does, a number is added to the name if an index or another
+    /// constraint of the table already has it
+    fn constraint_name(
+        &self,
+        full_table_name: &FullTableName,
+        full_table_id: &Key,
+        kind: ConstraintKind,
+        column_names: &[String],
+    ) -> String {
+        let name = match kind {
+            ConstraintKind::PrimaryKey => format!("{}_pkey", full_table_name.table()),
+            ConstraintKind::Unique => format!("{}_{}_key", full_table_name.table(), column_names.join("_")),
+            ConstraintKind::Check => match column_names.first() {
+                Some(column_name) => format!("{}_{}_check", full_table_name.table(), column_name),
+                None => format!("{}_check", full_table_name.table()),
+            },
+        };
+        let taken =
+            |name: &str| self.index_exists(full_table_name, name) || self.constraint_exists(full_table_id, name);
+        if !taken(&name) {
+            return name;
+        }
+        (1..)
+            .map(|suffix| format!("{}{}", name, suffix))
+            .find(|name| !taken(name))
+            .unwrap()
+    }
+}
+
+/// constraint definition from its catalog record, constrained columns are at the end of the record.
+/// Records of `CHECK` constraints have SQL text of the predicate before the columns
+pub(crate) fn constraint_def(row: &[Datum]) -> ConstraintDef {
+    let start = columns_start(row);
+    let (kind, check) = if row[4] == PRIMARY_KEY {
+        (ConstraintKind::PrimaryKey, None)
+    } else if row[4] == UNIQUE {
+        (ConstraintKind::Unique, None)
+    } else {
+        (ConstraintKind::Check, Some(row[5].as_string()))
+    };
+    ConstraintDef::new(
+        row[3].as_string(),
+        kind,
+        row[start..].iter().map(Datum::as_string).collect(),
+    )
+    .with_check(check)
+}
+
+/// position of the first constrained column in the catalog record of the constraint
+pub(crate) fn columns_start(row: &[Datum]) -> usize {
+    if is_check(row) {
+        6
+    } else {
+        5
+    }
+}
+
+pub(crate) fn is_check(row: &[Datum]) -> bool {
+    row[4] == CHECK
+}
@@ -12,19 +12,41 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::{constraints::constraint_def, sequences::Sequence, statistics::Analyzer, views::PG_CATALOG};
 use data_definition_execution_plan::{
-    CreateIndexQuery, CreateSchemaQuery, CreateTableQuery, DropSchemasQuery, DropTablesQuery, ExecutionError,
-    ExecutionOutcome, SchemaChange,
+    AnalyzeQuery, ColumnInfo, CreateDatabaseQuery, CreateIndexQuery, CreateSchemaQuery, CreateSequenceQuery,
+    CreateTableQuery, DropDatabaseQuery, DropIndexesQuery, DropSchemasQuery, DropSequencesQuery, DropTablesQuery,
+    ExecutionError, ExecutionOutcome, RenameSchemaQuery, SchemaChange, TableStorage,
+};
+use definition::{
+    ColumnDef, ColumnIdentity, ConstraintDef, ConstraintKind, FullTableName, IndexDef, SchemaName, TableDef,
+};
+pub use sequences::{CurrentValues, SequenceError, Sequences};
+pub use statistics::{ColumnStatistics, TableStatistics};
+use std::collections::BTreeSet;
+use storage::{
+    repr::Datum, Binary, Compression, Key, TransactionalDatabase, TreeLayout, WriteError, DATABASE_SEPARATOR,
+    DEFAULT_DATABASE,
 };
-use definition::{ColumnDef, FullTableName, SchemaName, TableDef};
-use storage::{repr::Datum, Binary, TransactionalDatabase};
 use types::{SqlType, SqlTypeFamily};
+pub use views::SystemView;
+
+mod alter_table;
+mod constraints;
+mod sequences;
+mod statistics;
+mod views;
 
 const DEFINITION_SCHEMA: &str = "DEFINITION_SCHEMA";
 const SCHEMATA_TABLE: &str = "SCHEMATA";
 const TABLES_TABLE: &str = "TABLES";
-const INDEXES_TABLE: &str = "TABLES";
+const INDEXES_TABLE: &str = "INDEXES";
 const COLUMNS_TABLE: &str = "COLUMNS";
+const STATISTICS_TABLE: &str = "STATISTICS";
+const SEQUENCES_TABLE: &str = "SEQUENCES";
+const TABLE_CONSTRAINTS_TABLE: &str = "TABLE_CONSTRAINTS";
+/// indexes are ordered trees of the storage
+const INDEX_METHOD: &str = "btree";
 
 pub struct CatalogHandler<'c> {
     database: TransactionalDatabase<'c>,
@@ -47,7 +69,19 @@ impl<'c> CatalogHandler<'c> {
             })
     }
 
+    fn schema_id(&self, schema_name: &str) -> Option<Binary> {
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE))
+            .scan()
+            .find(|(_key, value)| value.unpack()[1] == schema_name)
+            .map(|(key, _value)| key)
+    }
+
     pub fn table_definition(&self, full_table_name: FullTableName) -> Option<Option<TableDef>> {
+        if let Some(view) = self.system_view(&full_table_name) {
+            let columns = view.columns();
+            return Some(Some(TableDef::new(full_table_name, columns)));
+        }
         if !self.schema_exists(&SchemaName::from(&full_table_name.schema())) {
             None
         } else {
@@ -65,15 +99,8 @@ impl<'c> CatalogHandler<'c> {
                     let columns = self
                         .database
                         .table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE))
-                        .scan()
-                        .filter(|(key, _value)| key.starts_with(&full_table_id))
-                        .map(|(_key, value)| {
-                            let row = value.unpack();
-                            let name = row[3].as_string();
-                            let sql_type = SqlType::from_type_id(row[4].as_u64(), row[5].as_u64());
-                            let ord_num = row[6].as_u64() as usize;
-                            ColumnDef::new(name, sql_type, ord_num)
-                        })
+                        .scan_prefix(&full_table_id)
+                        .map(|(_key, value)| column_def(full_table_name.schema(), &value.unpack()))
                         .collect();
 
                     Some(Some(TableDef::new(full_table_name, columns)))
@@ -91,6 +118,13 @@ impl<'c> CatalogHandler<'c> {
     }
 
     pub fn columns_short(&self, full_table_name: &FullTableName) -> Vec<(String, SqlType)> {
+        if let Some(view) = self.system_view(full_table_name) {
+            return view
+                .columns()
+                .into_iter()
+                .map(|column| (column.name().to_owned(), column.sql_type()))
+                .collect();
+        }
         let full_table_id = self
             .database
             .table(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE))
@@ -104,8 +138,7 @@ impl<'c> CatalogHandler<'c> {
 
         self.database
             .table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE))
-            .scan()
-            .filter(|(key, _value)| key.starts_with(&full_table_id))
+            .scan_prefix(&full_table_id)
             .map(|(_key, value)| {
                 let row = value.unpack();
                 let name = row[3].as_string();
@@ -115,6 +148,248 @@ impl<'c> CatalogHandler<'c> {
             .collect()
     }
 
+    /// size of the table and statistics of its columns that were collected by the last `ANALYZE`.
+    /// Returns `None` if the table does not exist
+    pub fn table_statistics(&self, full_table_name: &FullTableName) -> Option<TableStatistics> {
+        let full_table_id = self.full_table_id(full_table_name)?;
+        let tree_stats = self.database.tree_stats(&full_table_name.to_string());
+        let columns = self
+            .database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, STATISTICS_TABLE))
+            .scan_prefix(&full_table_id)
+            .map(|(_key, value)| ColumnStatistics::decode(&value.unpack()[3..]))
+            .collect();
+        Some(TableStatistics {
+            rows: tree_stats.rows,
+            bytes: tree_stats.bytes,
+            columns,
+        })
+    }
+
+    /// secondary indexes of the table in order of their names. Returns `None` if the table does not exist
+    pub fn table_indexes(&self, full_table_name: &FullTableName) -> Option<Vec<IndexDef>> {
+        let full_table_id = self.full_table_id(full_table_name)?;
+        Some(
+            self.database
+                .table(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE))
+                .scan_prefix(&full_table_id)
+                .map(|(_key, value)| index_def(&value.unpack()))
+                .collect(),
+        )
+    }
+
+    /// constraints of the table in order of their names. Returns `None` if the table does not exist
+    pub fn table_constraints(&self, full_table_name: &FullTableName) -> Option<Vec<ConstraintDef>> {
+        let full_table_id = self.full_table_id(full_table_name)?;
+        let mut constraints = self
+            .constraint_records(&full_table_id)
+            .iter()
+            .map(|record| constraint_def(record))
+            .collect::<Vec<_>>();
+        constraints.sort_by(|left, right| left.name().cmp(right.name()));
+        Some(constraints)
+    }
+
+    /// key of the index record and the table of the index
+    fn full_index_id(&self, full_index_name: &FullTableName) -> Option<(Key, FullTableName)> {
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE))
+            .scan()
+            .map(|(key, value)| (key, value.unpack()))
+            .find(|(_key, value)| value[1] == full_index_name.schema() && value[3] == full_index_name.table())
+            .map(|(key, value)| (key, FullTableName::from((&value[1].as_string(), &value[2].as_string()))))
+    }
+
+    /// view of the database activity that the name refers to. Views are in `pg_catalog` schema and
+    /// can be referred without it unless there is a user table with the same name in `public` schema
+    pub fn system_view(&self, full_table_name: &FullTableName) -> Option<SystemView> {
+        let view = SystemView::from_name(full_table_name.table())?;
+        match full_table_name.schema() {
+            PG_CATALOG => Some(view),
+            "public" if self.full_table_id(full_table_name).is_none() => Some(view),
+            _ => None,
+        }
+    }
+
+    /// rows of the view as they are seen by the transaction
+    pub fn system_view_rows(&self, view: SystemView) -> Vec<Vec<Datum>> {
+        view.rows(&self.database, self.table_names())
+    }
+
+    /// sequences that the transaction changes and reads, `current_values` are values that the session
+    /// got from them before
+    pub fn sequences(&self, current_values: CurrentValues) -> Sequences {
+        Sequences::new(
+            self.database
+                .table(format!("{}.{}", DEFINITION_SCHEMA, SEQUENCES_TABLE)),
+            self.database.name(),
+            current_values,
+            self.database.autonomous(),
+        )
+    }
+
+    fn full_sequence_id(&self, full_sequence_name: &FullTableName) -> Option<Key> {
+        sequences::find_record(
+            &self
+                .database
+                .table(format!("{}.{}", DEFINITION_SCHEMA, SEQUENCES_TABLE)),
+            self.database.name(),
+            full_sequence_name,
+        )
+        .map(|(key, _value)| key)
+    }
+
+    fn create_sequence(&self, full_sequence_name: &FullTableName, sequence: Sequence) -> Result<(), ExecutionError> {
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, SEQUENCES_TABLE))
+            .write(sequence.encode(self.database.name(), full_sequence_name))
+            .map_err(write_error)?;
+        Ok(())
+    }
+
+    /// names of all user tables in order
+    fn table_names(&self) -> Vec<FullTableName> {
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE))
+            .scan()
+            .map(|(_key, value)| {
+                let value = value.unpack();
+                (value[1].as_string(), value[2].as_string())
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|(schema_name, table_name)| FullTableName::from((&schema_name, &table_name)))
+            .collect()
+    }
+
+    fn full_table_id(&self, full_table_name: &FullTableName) -> Option<Key> {
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE))
+            .scan()
+            .find(|(_key, value)| {
+                let value = value.unpack();
+                value[1] == full_table_name.schema() && value[2] == full_table_name.table()
+            })
+            .map(|(key, _value)| key)
+    }
+
+    /// `[database, schema, table, column name, type id, chars length, ordinal number,
+    /// identity sequence name, whether identity is always generated]`
+    fn column_record(&self, full_table_name: &FullTableName, index: usize, def: &ColumnInfo) -> Binary {
+        let (sequence, always) = match &def.identity {
+            None => (Datum::from_null(), Datum::from_bool(false)),
+            Some(identity) => (
+                Datum::from_string(identity_sequence_name(full_table_name.table(), &def.name)),
+                Datum::from_bool(identity.always),
+            ),
+        };
+        Binary::pack(&[
+            Datum::from_string(self.database.name().to_owned()),
+            Datum::from_string(full_table_name.schema().to_owned()),
+            Datum::from_string(full_table_name.table().to_owned()),
+            Datum::from_string(def.name.clone()),
+            Datum::from_u64(def.sql_type.type_id()),
+            Datum::from_optional_u64(def.sql_type.chars_len()),
+            Datum::from_u64(index as u64),
+            sequence,
+            always,
+            Datum::from_bool(def.not_null),
+            match &def.default {
+                None => Datum::from_null(),
+                Some(default) => Datum::from_string(default.text.clone()),
+            },
+        ])
+    }
+
+    /// builds index over existing rows of the table and keeps its record in the catalog
+    fn create_index(
+        &self,
+        full_table_name: &FullTableName,
+        full_table_id: &Key,
+        name: String,
+        column_names: Vec<String>,
+        unique: bool,
+    ) -> Result<(), ExecutionError> {
+        let table_columns = self
+            .database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE))
+            .scan_prefix(full_table_id)
+            .map(|(_key, value)| column_def(full_table_name.schema(), &value.unpack()))
+            .collect::<Vec<_>>();
+        let mut column_indexes = vec![];
+        for column_name in column_names.iter() {
+            if let Some(col_def) = table_columns.iter().find(|col| col.has_name(column_name)) {
+                column_indexes.push(col_def.index());
+            } else {
+                return Err(ExecutionError::ColumnNotFound(column_name.to_owned()));
+            }
+        }
+        self.database
+            .create_index(&full_table_name.to_string(), &name, &column_indexes, unique)
+            .map_err(write_error)?;
+        let mut record = vec![
+            Datum::from_string(self.database.name().to_owned()),
+            Datum::from_string(full_table_name.schema().to_owned()),
+            Datum::from_string(full_table_name.table().to_owned()),
+            Datum::from_string(name.clone()),
+            Datum::from_bool(unique),
+            Datum::from_string(INDEX_METHOD.to_owned()),
+        ];
+        record.extend(column_names.into_iter().map(Datum::from_string));
+        let mut key = full_table_id.unpack();
+        key.push(Datum::from_string(name));
+        self.database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE))
+            .write_key(Binary::pack(&key), Some(Binary::pack(&record)))
+            .map_err(write_error)?;
+        Ok(())
+    }
+
+    /// replaces statistics of the table columns with the ones computed from the rows that the transaction sees
+    fn analyze(&self, full_table_name: &FullTableName) -> Result<(), ExecutionError> {
+        let full_table_id = match self.full_table_id(full_table_name) {
+            None => {
+                return Err(ExecutionError::TableDoesNotExist(
+                    full_table_name.schema().to_owned(),
+                    full_table_name.table().to_owned(),
+                ))
+            }
+            Some(full_table_id) => full_table_id,
+        };
+        let column_names = self
+            .database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE))
+            .scan_prefix(&full_table_id)
+            .map(|(_key, value)| value.unpack()[3].as_string())
+            .collect();
+        let mut analyzer = Analyzer::new(column_names);
+        for (_key, row) in self.database.table(full_table_name.to_string()).scan() {
+            analyzer.add(&row);
+        }
+
+        let statistics_table = self
+            .database
+            .table(format!("{}.{}", DEFINITION_SCHEMA, STATISTICS_TABLE));
+        for key in statistics_table.scan_prefix(&full_table_id).map(|(key, _value)| key) {
+            statistics_table.write_key(key, None).map_err(write_error)?;
+        }
+        for (index, column) in analyzer.finish().into_iter().enumerate() {
+            let mut record = vec![
+                Datum::from_string(self.database.name().to_owned()),
+                Datum::from_string(full_table_name.schema().to_owned()),
+                Datum::from_string(full_table_name.table().to_owned()),
+            ];
+            record.extend(column.encode());
+            let mut key = full_table_id.unpack();
+            key.push(Datum::from_u64(index as u64));
+            statistics_table
+                .write_key(Binary::pack(&key), Some(Binary::pack(&record)))
+                .map_err(write_error)?;
+        }
+        log::debug!("{} TABLE ANALYZED", full_table_name);
+        Ok(())
+    }
+
     pub fn apply(&self, schema_change: SchemaChange) -> Result<ExecutionOutcome, ExecutionError> {
         match schema_change {
             SchemaChange::CreateSchema(CreateSchemaQuery {
@@ -131,9 +406,10 @@ impl<'c> CatalogHandler<'c> {
                     self.database
                         .table(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE))
                         .write(Binary::pack(&[
-                            Datum::from_string("IN_MEMORY".to_owned()),
+                            Datum::from_string(self.database.name().to_owned()),
                             Datum::from_string(schema_name.as_ref().to_owned()),
-                        ]));
+                        ]))
+                        .map_err(write_error)?;
                     Ok(ExecutionOutcome::SchemaCreated)
                 }
             }
@@ -145,15 +421,7 @@ impl<'c> CatalogHandler<'c> {
                 let schemas_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE));
                 let tables_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE));
                 for schema_name in schema_names {
-                    let full_schema_name = Binary::pack(&[
-                        Datum::from_string("IN_MEMORY".to_owned()),
-                        Datum::from_string(schema_name.as_ref().to_owned()),
-                    ]);
-
-                    let schema_id = schemas_table
-                        .scan()
-                        .find(|(_key, value)| value.starts_with(&full_schema_name))
-                        .map(|(key, _value)| key);
+                    let schema_id = self.schema_id(schema_name.as_ref());
 
                     match schema_id {
                         None => {
@@ -162,11 +430,15 @@ impl<'c> CatalogHandler<'c> {
                             }
                         }
                         Some(schema_id) => {
+                            let sequences_table = self
+                                .database
+                                .table(format!("{}.{}", DEFINITION_SCHEMA, SEQUENCES_TABLE));
                             let is_empty = tables_table
                                 .scan()
+                                .chain(sequences_table.scan())
                                 .find(|(_key, value)| {
                                     let value = value.unpack();
-                                    value[0] == "IN_MEMORY" && value[1] == schema_name.as_ref()
+                                    value[1] == schema_name.as_ref()
                                 })
                                 .is_none();
                             if !is_empty && !cascade {
@@ -184,7 +456,48 @@ impl<'c> CatalogHandler<'c> {
                                     })
                                     .map(|(key, _value)| key)
                                 {
-                                    columns_table.write_key(column_key, None);
+                                    columns_table.write_key(column_key, None).map_err(write_error)?;
+                                }
+
+                                let statistics_table = self
+                                    .database
+                                    .table(format!("{}.{}", DEFINITION_SCHEMA, STATISTICS_TABLE));
+                                for statistics_key in statistics_table
+                                    .scan()
+                                    .filter(|(_key, value)| {
+                                        let value = value.unpack();
+                                        value[1] == schema_name.as_ref()
+                                    })
+                                    .map(|(key, _value)| key)
+                                {
+                                    statistics_table.write_key(statistics_key, None).map_err(write_error)?;
+                                }
+
+                                for sequence_key in sequences_table
+                                    .scan()
+                                    .filter(|(_key, value)| {
+                                        let value = value.unpack();
+                                        value[1] == schema_name.as_ref()
+                                    })
+                                    .map(|(key, _value)| key)
+                                {
+                                    sequences_table.write_key(sequence_key, None).map_err(write_error)?;
+                                }
+
+                                // storage drops index trees together with their tables
+                                for catalog_table in &[INDEXES_TABLE, TABLE_CONSTRAINTS_TABLE] {
+                                    let catalog_table =
+                                        self.database.table(format!("{}.{}", DEFINITION_SCHEMA, catalog_table));
+                                    for key in catalog_table
+                                        .scan()
+                                        .filter(|(_key, value)| {
+                                            let value = value.unpack();
+                                            value[1] == schema_name.as_ref()
+                                        })
+                                        .map(|(key, _value)| key)
+                                    {
+                                        catalog_table.write_key(key, None).map_err(write_error)?;
+                                    }
                                 }
 
                                 for (table_key, table_name) in tables_table
@@ -198,11 +511,11 @@ impl<'c> CatalogHandler<'c> {
                                         (key, format!("{}.{}", value[1], value[2]))
                                     })
                                 {
-                                    tables_table.write_key(table_key, None);
+                                    tables_table.write_key(table_key, None).map_err(write_error)?;
                                     self.database.drop_tree(table_name);
                                 }
 
-                                schemas_table.write_key(schema_id, None);
+                                schemas_table.write_key(schema_id, None).map_err(write_error)?;
                             }
                         }
                     }
@@ -213,17 +526,11 @@ impl<'c> CatalogHandler<'c> {
                 full_table_name,
                 column_defs,
                 if_not_exists,
+                storage,
+                compression,
+                constraints,
             }) => {
-                let schemas_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE));
-                let full_schema_name = Binary::pack(&[
-                    Datum::from_string("IN_MEMORY".to_owned()),
-                    Datum::from_string(full_table_name.schema().to_owned()),
-                ]);
-
-                let schema_id = schemas_table
-                    .scan()
-                    .find(|(_key, value)| value.starts_with(&full_schema_name))
-                    .map(|(key, _value)| key);
+                let schema_id = self.schema_id(full_table_name.schema());
 
                 match schema_id {
                     None => Err(ExecutionError::SchemaDoesNotExist(full_table_name.schema().to_owned())),
@@ -231,9 +538,7 @@ impl<'c> CatalogHandler<'c> {
                         let tables_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE));
                         let table_id = tables_table.scan().find(|(_key, value)| {
                             let value = value.unpack();
-                            value[0] == "IN_MEMORY"
-                                && value[1] == full_table_name.schema()
-                                && value[2] == full_table_name.table()
+                            value[1] == full_table_name.schema() && value[2] == full_table_name.table()
                         });
                         log::trace!("DEBUG {:?}", table_id);
                         match table_id {
@@ -248,33 +553,62 @@ impl<'c> CatalogHandler<'c> {
                                 }
                             }
                             None => {
+                                let mut sequences = vec![];
+                                for def in column_defs.iter() {
+                                    if let Some(identity) = &def.identity {
+                                        let full_sequence_name = FullTableName::from((
+                                            &full_table_name.schema(),
+                                            &identity_sequence_name(full_table_name.table(), &def.name),
+                                        ));
+                                        if self.full_sequence_id(&full_sequence_name).is_some() {
+                                            return Err(ExecutionError::SequenceAlreadyExists(
+                                                full_sequence_name.schema().to_owned(),
+                                                full_sequence_name.table().to_owned(),
+                                            ));
+                                        }
+                                        let owner = (full_table_name.table().to_owned(), def.name.clone());
+                                        sequences
+                                            .push((full_sequence_name, Sequence::new(identity.options, Some(owner))));
+                                    }
+                                }
+
                                 let full_table_name_record = Binary::pack(&[
-                                    Datum::from_string("IN_MEMORY".to_owned()),
+                                    Datum::from_string(self.database.name().to_owned()),
                                     Datum::from_string(full_table_name.schema().to_owned()),
                                     Datum::from_string(full_table_name.table().to_owned()),
                                 ]);
-                                let full_table_id = tables_table.write(full_table_name_record).unpack();
+                                let full_table_id = tables_table
+                                    .write(full_table_name_record)
+                                    .map_err(write_error)?
+                                    .unpack();
+                                let full_table_key = Binary::pack(&full_table_id);
 
                                 let columns_table =
                                     self.database.table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE));
 
                                 for (index, def) in column_defs.iter().enumerate() {
-                                    let record = Binary::pack(&[
-                                        Datum::from_string("IN_MEMORY".to_owned()),
-                                        Datum::from_string(full_table_name.schema().to_owned()),
-                                        Datum::from_string(full_table_name.table().to_owned()),
-                                        Datum::from_string(def.name.clone()),
-                                        Datum::from_u64(def.sql_type.type_id()),
-                                        Datum::from_optional_u64(def.sql_type.chars_len()),
-                                        Datum::from_u64(index as u64),
-                                    ]);
+                                    let record = self.column_record(&full_table_name, index, def);
                                     let mut key = full_table_id.clone();
                                     key.push(Datum::from_u64(index as u64));
                                     let key = Binary::pack(&key);
-                                    columns_table.write_key(key, Some(record));
+                                    columns_table.write_key(key, Some(record)).map_err(write_error)?;
                                 }
 
-                                self.database.create_tree(&full_table_name);
+                                for (full_sequence_name, sequence) in sequences {
+                                    self.create_sequence(&full_sequence_name, sequence)?;
+                                }
+
+                                let layout = match storage {
+                                    TableStorage::Row => TreeLayout::Rows,
+                                    TableStorage::Columnar => TreeLayout::Columns,
+                                };
+                                self.database.create_tree_with(&full_table_name, layout);
+                                if let Some(threshold) = compression {
+                                    self.database.compress_tree(&full_table_name, Compression { threshold });
+                                }
+                                for constraint in constraints {
+                                    self.add_constraint(&full_table_name, &full_table_key, constraint)?;
+                                }
 
                                 Ok(ExecutionOutcome::TableCreated)
                             }
@@ -288,20 +622,21 @@ impl<'c> CatalogHandler<'c> {
                 cascade: _cascade,
                 if_exists,
             }) => {
-                let schemas_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE));
                 let tables_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE));
                 let columns_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE));
+                let statistics_table = self
+                    .database
+                    .table(format!("{}.{}", DEFINITION_SCHEMA, STATISTICS_TABLE));
+                let sequences_table = self
+                    .database
+                    .table(format!("{}.{}", DEFINITION_SCHEMA, SEQUENCES_TABLE));
+                let indexes_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE));
+                let constraints_table = self
+                    .database
+                    .table(format!("{}.{}", DEFINITION_SCHEMA, TABLE_CONSTRAINTS_TABLE));
 
                 for full_table_name in full_table_names {
-                    let full_schema_name = Binary::pack(&[
-                        Datum::from_string("IN_MEMORY".to_owned()),
-                        Datum::from_string(full_table_name.schema().to_owned()),
-                    ]);
-
-                    let schema_id = schemas_table
-                        .scan()
-                        .find(|(_key, value)| value.starts_with(&full_schema_name))
-                        .map(|(key, _value)| key);
+                    let schema_id = self.schema_id(full_table_name.schema());
 
                     match schema_id {
                         None => return Err(ExecutionError::SchemaDoesNotExist(full_table_name.schema().to_owned())),
@@ -323,14 +658,36 @@ impl<'c> CatalogHandler<'c> {
                                     }
                                 }
                                 Some(full_table_id) => {
-                                    for column_key in columns_table
+                                    for column_key in columns_table.scan_prefix(&full_table_id).map(|(key, _value)| key)
+                                    {
+                                        columns_table.write_key(column_key, None).map_err(write_error)?;
+                                    }
+                                    for statistics_key in
+                                        statistics_table.scan_prefix(&full_table_id).map(|(key, _value)| key)
+                                    {
+                                        statistics_table.write_key(statistics_key, None).map_err(write_error)?;
+                                    }
+                                    for index_key in indexes_table.scan_prefix(&full_table_id).map(|(key, _value)| key)
+                                    {
+                                        indexes_table.write_key(index_key, None).map_err(write_error)?;
+                                    }
+                                    for constraint_key in
+                                        constraints_table.scan_prefix(&full_table_id).map(|(key, _value)| key)
+                                    {
+                                        constraints_table.write_key(constraint_key, None).map_err(write_error)?;
+                                    }
+                                    // sequences of identity columns are dropped together with the table
+                                    for sequence_key in sequences_table
                                         .scan()
-                                        .filter(|(key, _value)| key.starts_with(&full_table_id))
+                                        .filter(|(_key, value)| {
+                                            let value = value.unpack();
+                                            value[1] == full_table_name.schema() && value[10] == full_table_name.table()
+                                        })
                                         .map(|(key, _value)| key)
                                     {
-                                        columns_table.write_key(column_key, None);
+                                        sequences_table.write_key(sequence_key, None).map_err(write_error)?;
                                     }
-                                    tables_table.write_key(full_table_id, None);
+                                    tables_table.write_key(full_table_id, None).map_err(write_error)?;
                                     self.database.drop_tree(&full_table_name);
                                 }
                             }
@@ -343,30 +700,30 @@ impl<'c> CatalogHandler<'c> {
                 name,
                 full_table_name,
                 column_names,
+                unique,
             }) => {
-                let schemas_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE));
                 let tables_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE));
-                let columns_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE));
-                let indexes_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE));
 
-                let full_schema_name = Binary::pack(&[
-                    Datum::from_string("IN_MEMORY".to_owned()),
-                    Datum::from_string(full_table_name.schema().to_owned()),
-                ]);
-
-                let schema_id = schemas_table
-                    .scan()
-                    .find(|(_key, value)| value.starts_with(&full_schema_name))
-                    .map(|(key, _value)| key);
+                let schema_id = self.schema_id(full_table_name.schema());
 
                 match schema_id {
                     None => Err(ExecutionError::SchemaDoesNotExist(full_table_name.schema().to_owned())),
-                    Some(full_schema_id) => {
+                    Some(_full_schema_id)
+                        if self
+                            .full_index_id(&FullTableName::from((&full_table_name.schema(), &name)))
+                            .is_some() =>
+                    {
+                        Err(ExecutionError::IndexAlreadyExists(
+                            full_table_name.schema().to_owned(),
+                            name,
+                        ))
+                    }
+                    Some(_full_schema_id) => {
                         let table_id = tables_table
                             .scan()
-                            .find(|(key, value)| {
+                            .find(|(_key, value)| {
                                 let value = value.unpack();
-                                key.starts_with(&full_schema_id) && value[2] == full_table_name.table()
+                                value[1] == full_table_name.schema() && value[2] == full_table_name.table()
                             })
                             .map(|(key, _value)| key);
                         match table_id {
@@ -375,45 +732,215 @@ impl<'c> CatalogHandler<'c> {
                                 full_table_name.table().to_owned(),
                             )),
                             Some(full_table_id) => {
-                                let table_columns = columns_table
-                                    .scan()
-                                    .filter(|(key, _value)| key.starts_with(&full_table_id))
-                                    .map(|(_key, value)| {
-                                        let row = value.unpack();
-                                        let name = row[3].as_string();
-                                        let sql_type = SqlType::from_type_id(row[4].as_u64(), row[5].as_u64());
-                                        let ord_num = row[6].as_u64() as usize;
-                                        ColumnDef::new(name, sql_type, ord_num)
-                                    })
-                                    .collect::<Vec<_>>();
-                                let mut column_indexes = vec![];
-                                for column_name in column_names.iter() {
-                                    if let Some(col_def) = table_columns.iter().find(|col| col.has_name(column_name)) {
-                                        column_indexes.push(col_def.index());
-                                    } else {
-                                        return Err(ExecutionError::ColumnNotFound(column_names[0].to_owned()));
-                                    }
-                                }
-                                indexes_table.write(Binary::pack(&[
-                                    Datum::from_string("IN_MEMORY".to_owned()),
-                                    Datum::from_string(full_table_name.schema().to_owned()),
-                                    Datum::from_string(full_table_name.table().to_owned()),
-                                    Datum::from_string(name.clone()),
-                                    Datum::from_string(column_names.join(", ")),
-                                ]));
-
-                                self.database.create_tree(format!(
-                                    "{}.{}.{}",
-                                    full_table_name.schema(),
-                                    full_table_name.table(),
-                                    name
-                                ));
+                                self.create_index(&full_table_name, &full_table_id, name, column_names, unique)?;
                                 Ok(ExecutionOutcome::IndexCreated)
                             }
                         }
                     }
                 }
             }
+            // indexes of constraints are dropped only together with their constraints, even with cascade
+            SchemaChange::DropIndexes(DropIndexesQuery {
+                full_index_names,
+                cascade: _cascade,
+                if_exists,
+            }) => {
+                let indexes_table = self.database.table(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE));
+                for full_index_name in full_index_names {
+                    if !self.schema_exists(&SchemaName::from(&full_index_name.schema())) {
+                        return Err(ExecutionError::SchemaDoesNotExist(full_index_name.schema().to_owned()));
+                    }
+                    match self.full_index_id(&full_index_name) {
+                        None => {
+                            if !if_exists {
+                                return Err(ExecutionError::IndexDoesNotExist(
+                                    full_index_name.schema().to_owned(),
+                                    full_index_name.table().to_owned(),
+                                ));
+                            }
+                        }
+                        Some((index_key, full_table_name)) => {
+                            let is_required = self
+                                .full_table_id(&full_table_name)
+                                .map(|full_table_id| self.constraint_records(&full_table_id))
+                                .unwrap_or_default()
+                                .iter()
+                                .map(|record| constraint_def(record))
+                                .any(|constraint| {
+                                    constraint.kind() != ConstraintKind::Check
+                                        && constraint.name() == full_index_name.table()
+                                });
+                            if is_required {
+                                return Err(ExecutionError::IndexIsRequired(
+                                    full_index_name.table().to_owned(),
+                                    full_table_name.table().to_owned(),
+                                ));
+                            }
+                            indexes_table.write_key(index_key, None).map_err(write_error)?;
+                            self.database
+                                .drop_index(&full_table_name.to_string(), full_index_name.table());
+                        }
+                    }
+                }
+                Ok(ExecutionOutcome::IndexDropped)
+            }
+            SchemaChange::Analyze(AnalyzeQuery { full_table_names }) => {
+                let full_table_names = if full_table_names.is_empty() {
+                    self.table_names()
+                } else {
+                    full_table_names
+                };
+                for full_table_name in full_table_names {
+                    self.analyze(&full_table_name)?;
+                }
+                Ok(ExecutionOutcome::TablesAnalyzed)
+            }
+            SchemaChange::CreateSequence(CreateSequenceQuery {
+                full_sequence_name,
+                if_not_exists,
+                options,
+            }) => {
+                if !self.schema_exists(&SchemaName::from(&full_sequence_name.schema())) {
+                    Err(ExecutionError::SchemaDoesNotExist(
+                        full_sequence_name.schema().to_owned(),
+                    ))
+                } else if self.full_sequence_id(&full_sequence_name).is_some() {
+                    if if_not_exists {
+                        Ok(ExecutionOutcome::SequenceCreated)
+                    } else {
+                        Err(ExecutionError::SequenceAlreadyExists(
+                            full_sequence_name.schema().to_owned(),
+                            full_sequence_name.table().to_owned(),
+                        ))
+                    }
+                } else {
+                    self.create_sequence(&full_sequence_name, Sequence::new(options, None))?;
+                    Ok(ExecutionOutcome::SequenceCreated)
+                }
+            }
+            // sequences of identity columns are dropped only together with their tables, even with cascade
+            SchemaChange::DropSequences(DropSequencesQuery {
+                full_sequence_names,
+                cascade: _cascade,
+                if_exists,
+            }) => {
+                let sequences_table = self
+                    .database
+                    .table(format!("{}.{}", DEFINITION_SCHEMA, SEQUENCES_TABLE));
+                for full_sequence_name in full_sequence_names {
+                    if !self.schema_exists(&SchemaName::from(&full_sequence_name.schema())) {
+                        return Err(ExecutionError::SchemaDoesNotExist(
+                            full_sequence_name.schema().to_owned(),
+                        ));
+                    }
+                    let sequence = sequences_table.scan().find(|(_key, value)| {
+                        let value = value.unpack();
+                        value[1] == full_sequence_name.schema() && value[2] == full_sequence_name.table()
+                    });
+                    match sequence {
+                        None => {
+                            if !if_exists {
+                                return Err(ExecutionError::SequenceDoesNotExist(
+                                    full_sequence_name.schema().to_owned(),
+                                    full_sequence_name.table().to_owned(),
+                                ));
+                            }
+                        }
+                        Some((key, value)) => {
+                            if let Some((table, column)) = Sequence::decode(&value.unpack()).owner {
+                                return Err(ExecutionError::SequenceIsRequired(
+                                    full_sequence_name.table().to_owned(),
+                                    column,
+                                    table,
+                                ));
+                            }
+                            sequences_table.write_key(key, None).map_err(write_error)?;
+                        }
+                    }
+                }
+                Ok(ExecutionOutcome::SequenceDropped)
+            }
+            SchemaChange::AlterTable(query) => self.alter_table(query),
+            SchemaChange::RenameSchema(RenameSchemaQuery { schema_name, new_name }) => {
+                self.rename_schema(schema_name.as_ref(), new_name.as_ref())
+            }
+            SchemaChange::CreateDatabase(CreateDatabaseQuery { database_name }) => {
+                if database_name.contains(DATABASE_SEPARATOR) {
+                    Err(ExecutionError::InvalidDatabaseName(database_name))
+                } else if self.database.database(&database_name).is_some() {
+                    Err(ExecutionError::DatabaseAlreadyExists(database_name))
+                } else {
+                    self.database.create_database(&database_name);
+                    Ok(ExecutionOutcome::DatabaseCreated)
+                }
+            }
+            SchemaChange::DropDatabase(DropDatabaseQuery {
+                database_name,
+                if_exists,
+            }) => {
+                if database_name == self.database.name() {
+                    Err(ExecutionError::DatabaseIsInUse(database_name))
+                } else if database_name == DEFAULT_DATABASE {
+                    Err(ExecutionError::DefaultDatabase(database_name))
+                } else if self.database.database(&database_name).is_none() {
+                    if if_exists {
+                        Ok(ExecutionOutcome::DatabaseDropped)
+                    } else {
+                        Err(ExecutionError::DatabaseDoesNotExist(database_name))
+                    }
+                } else {
+                    self.database.drop_database(&database_name);
+                    Ok(ExecutionOutcome::DatabaseDropped)
+                }
+            }
         }
     }
 }
+
+/// sequence that is created for the identity column
+fn identity_sequence_name(table_name: &str, column_name: &str) -> String {
+    format!("{}_{}_seq", table_name, column_name)
+}
+
+/// column definition from its catalog record, sequences of identity columns are in the table schema
+fn column_def(schema_name: &str, row: &[Datum]) -> ColumnDef {
+    let name = row[3].as_string();
+    let sql_type = SqlType::from_type_id(row[4].as_u64(), row[5].as_u64());
+    let ord_num = row[6].as_u64() as usize;
+    let identity = match row.get(7) {
+        Some(Datum::String(sequence)) => Some(ColumnIdentity::new(
+            FullTableName::from((&schema_name, sequence)),
+            row[8] == Datum::True,
+        )),
+        _ => None,
+    };
+    // columns of catalogs that were created before are nullable and do not have defaults
+    let not_null = row.get(9) == Some(&Datum::True);
+    let default = match row.get(10) {
+        Some(Datum::String(default)) => Some(default.clone()),
+        _ => None,
+    };
+    ColumnDef::new(name, sql_type, ord_num)
+        .with_identity(identity)
+        .with_not_null(not_null)
+        .with_default(default)
+}
+
+/// index definition from its catalog record, indexed columns are at the end of the record
+fn index_def(row: &[Datum]) -> IndexDef {
+    IndexDef::new(
+        row[3].as_string(),
+        row[6..].iter().map(Datum::as_string).collect(),
+        row[4] == Datum::True,
+        row[5].as_string(),
+    )
+}
+
+fn write_error(error: WriteError) -> ExecutionError {
+    match error {
+        WriteError::UniqueViolation(index) => ExecutionError::UniqueViolation(index),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,332 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{DEFINITION_SCHEMA, SEQUENCES_TABLE};
+use data_definition_execution_plan::SequenceOptions;
+use data_manipulation_query_result::QueryExecutionError;
+use data_manipulation_typed_tree::SequenceCalls;
+use definition::FullTableName;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use storage::{repr::Datum, Autonomous, Binary, Key, Table, NAMES_INDEX};
+
+/// Sequence as it is kept in the catalog together with its last value
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Sequence {
+    pub(crate) options: SequenceOptions,
+    pub(crate) last_value: i64,
+    /// `false` until `nextval` returned `last_value`
+    pub(crate) is_called: bool,
+    /// table and column which values the sequence generates
+    pub(crate) owner: Option<(String, String)>,
+}
+
+impl Sequence {
+    pub(crate) fn new(options: SequenceOptions, owner: Option<(String, String)>) -> Sequence {
+        Sequence {
+            options,
+            last_value: options.start,
+            is_called: false,
+            owner,
+        }
+    }
+
+    /// `[database, schema, sequence name, start, increment, min value, max value, cycle,
+    /// last value, is called, owner table, owner column]`
+    pub(crate) fn encode(&self, database_name: &str, full_sequence_name: &FullTableName) -> Binary {
+        let (table, column) = match &self.owner {
+            None => (Datum::from_null(), Datum::from_null()),
+            Some((table, column)) => (Datum::from_string(table.clone()), Datum::from_string(column.clone())),
+        };
+        Binary::pack(&[
+            Datum::from_string(database_name.to_owned()),
+            Datum::from_string(full_sequence_name.schema().to_owned()),
+            Datum::from_string(full_sequence_name.table().to_owned()),
+            Datum::from_i64(self.options.start),
+            Datum::from_i64(self.options.increment),
+            Datum::from_i64(self.options.min_value),
+            Datum::from_i64(self.options.max_value),
+            Datum::from_bool(self.options.cycle),
+            Datum::from_i64(self.last_value),
+            Datum::from_bool(self.is_called),
+            table,
+            column,
+        ])
+    }
+
+    pub(crate) fn decode(record: &[Datum]) -> Sequence {
+        let owner = match (&record[10], &record[11]) {
+            (Datum::String(table), Datum::String(column)) => Some((table.clone(), column.clone())),
+            _ => None,
+        };
+        Sequence {
+            options: SequenceOptions {
+                start: record[3].as_u64() as i64,
+                increment: record[4].as_u64() as i64,
+                min_value: record[5].as_u64() as i64,
+                max_value: record[6].as_u64() as i64,
+                cycle: record[7] == Datum::True,
+            },
+            last_value: record[8].as_u64() as i64,
+            is_called: record[9] == Datum::True,
+            owner,
+        }
+    }
+
+    /// takes the value that `nextval` returns
+    pub(crate) fn advance(&mut self, sequence_name: &str) -> Result<i64, SequenceError> {
+        let next = self.next().ok_or_else(|| SequenceError::LimitExceeded {
+            sequence: sequence_name.to_owned(),
+            maximum: self.options.increment > 0,
+            value: if self.options.increment > 0 {
+                self.options.max_value
+            } else {
+                self.options.min_value
+            },
+        })?;
+        self.last_value = next;
+        self.is_called = true;
+        Ok(next)
+    }
+
+    /// value that `nextval` returns
+    fn next(&self) -> Option<i64> {
+        if !self.is_called {
+            return Some(self.last_value);
+        }
+        let SequenceOptions {
+            increment,
+            min_value,
+            max_value,
+            cycle,
+            ..
+        } = self.options;
+        match self.last_value.checked_add(increment) {
+            Some(next) if (min_value..=max_value).contains(&next) => Some(next),
+            _ if !cycle => None,
+            _ if increment > 0 => Some(min_value),
+            _ => Some(max_value),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SequenceError {
+    DoesNotExist(String),
+    /// `maximum` is `false` when a descending sequence reached its minimum value
+    LimitExceeded {
+        sequence: String,
+        maximum: bool,
+        value: i64,
+    },
+    OutOfBounds {
+        sequence: String,
+        value: i64,
+        min_value: i64,
+        max_value: i64,
+    },
+    /// `currval` is called before `nextval` in the session
+    NotDefined(String),
+}
+
+impl From<SequenceError> for QueryExecutionError {
+    fn from(error: SequenceError) -> QueryExecutionError {
+        match error {
+            SequenceError::DoesNotExist(sequence) => QueryExecutionError::sequence_does_not_exist(sequence),
+            SequenceError::LimitExceeded {
+                sequence,
+                maximum,
+                value,
+            } => QueryExecutionError::sequence_limit_exceeded(sequence, maximum, value),
+            SequenceError::OutOfBounds {
+                sequence,
+                value,
+                min_value,
+                max_value,
+            } => QueryExecutionError::sequence_value_out_of_bounds(sequence, value, min_value, max_value),
+            SequenceError::NotDefined(sequence) => QueryExecutionError::sequence_value_not_defined(sequence),
+        }
+    }
+}
+
+/// Values that `nextval` and `setval` returned to a session, `currval` reads them
+#[derive(Debug, Default, Clone)]
+pub struct CurrentValues(Arc<Mutex<HashMap<String, i64>>>);
+
+impl CurrentValues {
+    fn get(&self, full_sequence_name: &FullTableName) -> Option<i64> {
+        self.0.lock().unwrap().get(&full_sequence_name.to_string()).copied()
+    }
+
+    fn set(&self, full_sequence_name: &FullTableName, value: i64) {
+        self.0.lock().unwrap().insert(full_sequence_name.to_string(), value);
+    }
+}
+
+/// Sequences as they are seen by a transaction.
+///
+/// `nextval` and `setval` change sequences in short transactions of their own that are committed
+/// right away, concurrent transactions that use the same sequence do not conflict and values taken
+/// by a rolled back transaction are not given out again. Sequences that the transaction created or
+/// altered are changed by the transaction itself
+pub struct Sequences {
+    table: Table,
+    database_name: String,
+    current_values: CurrentValues,
+    autonomous: Autonomous,
+}
+
+impl Sequences {
+    pub(crate) fn new(
+        table: Table,
+        database_name: &str,
+        current_values: CurrentValues,
+        autonomous: Autonomous,
+    ) -> Sequences {
+        Sequences {
+            table,
+            database_name: database_name.to_owned(),
+            current_values,
+            autonomous,
+        }
+    }
+
+    pub fn next_value(&self, full_sequence_name: &FullTableName) -> Result<i64, SequenceError> {
+        let next = self.update(full_sequence_name, |sequence| {
+            sequence.advance(full_sequence_name.table())
+        })?;
+        self.current_values.set(full_sequence_name, next);
+        Ok(next)
+    }
+
+    pub fn current_value(&self, full_sequence_name: &FullTableName) -> Result<i64, SequenceError> {
+        self.find(full_sequence_name)?;
+        self.current_values
+            .get(full_sequence_name)
+            .ok_or_else(|| SequenceError::NotDefined(full_sequence_name.table().to_owned()))
+    }
+
+    pub fn set_value(
+        &self,
+        full_sequence_name: &FullTableName,
+        value: i64,
+        is_called: bool,
+    ) -> Result<i64, SequenceError> {
+        self.update(full_sequence_name, |sequence| {
+            let SequenceOptions {
+                min_value, max_value, ..
+            } = sequence.options;
+            if !(min_value..=max_value).contains(&value) {
+                return Err(SequenceError::OutOfBounds {
+                    sequence: full_sequence_name.table().to_owned(),
+                    value,
+                    min_value,
+                    max_value,
+                });
+            }
+            sequence.last_value = value;
+            sequence.is_called = is_called;
+            Ok(value)
+        })?;
+        if is_called {
+            self.current_values.set(full_sequence_name, value);
+        }
+        Ok(value)
+    }
+
+    /// changes the sequence in a transaction of its own unless the sequence was created or altered by
+    /// the caller's transaction or the sequence was dropped by a committed one
+    fn update<F>(&self, full_sequence_name: &FullTableName, change: F) -> Result<i64, SequenceError>
+    where
+        F: Fn(&mut Sequence) -> Result<i64, SequenceError>,
+    {
+        let (key, mut sequence) = self.find(full_sequence_name)?;
+        if !self.table.is_changed(&key) {
+            let committed = self.autonomous.transaction(|db| {
+                let table = db.table(format!("{}.{}", DEFINITION_SCHEMA, SEQUENCES_TABLE));
+                Ok(table.get(&key).map(|value| {
+                    let mut sequence = Sequence::decode(&value.unpack());
+                    let value = change(&mut sequence)?;
+                    table
+                        .write_key(
+                            key.clone(),
+                            Some(sequence.encode(&self.database_name, full_sequence_name)),
+                        )
+                        .expect("sequence keeps its name");
+                    Ok(value)
+                }))
+            });
+            // a storage error fails the caller's transaction as well
+            if let Ok(Some(result)) = committed {
+                return result;
+            }
+        }
+        let value = change(&mut sequence)?;
+        self.write(key, full_sequence_name, &sequence);
+        Ok(value)
+    }
+
+    fn find(&self, full_sequence_name: &FullTableName) -> Result<(Key, Sequence), SequenceError> {
+        find_record(&self.table, &self.database_name, full_sequence_name)
+            .map(|(key, value)| (key, Sequence::decode(&value)))
+            .ok_or_else(|| SequenceError::DoesNotExist(full_sequence_name.to_string()))
+    }
+
+    fn write(&self, key: Key, full_sequence_name: &FullTableName, sequence: &Sequence) {
+        self.table
+            .write_key(key, Some(sequence.encode(&self.database_name, full_sequence_name)))
+            .expect("sequence keeps its name")
+    }
+}
+
+/// key and values of the sequence record. Records are looked up by the index of their names,
+/// catalogs that could not be indexed are scanned
+pub(crate) fn find_record(
+    table: &Table,
+    database_name: &str,
+    full_sequence_name: &FullTableName,
+) -> Option<(Key, Vec<Datum>)> {
+    let name = [
+        Datum::from_string(database_name.to_owned()),
+        Datum::from_string(full_sequence_name.schema().to_owned()),
+        Datum::from_string(full_sequence_name.table().to_owned()),
+    ];
+    table
+        .scan_index(NAMES_INDEX, &name)
+        .unwrap_or_else(|| table.scan())
+        .map(|(key, value)| (key, value.unpack()))
+        .find(|(_key, value)| value[..3] == name)
+}
+
+/// expressions that call `nextval`, `currval` and `setval` are evaluated with catalog sequences
+impl SequenceCalls for Sequences {
+    fn next_value(&self, full_sequence_name: &FullTableName) -> Result<i64, QueryExecutionError> {
+        Ok(Sequences::next_value(self, full_sequence_name)?)
+    }
+
+    fn current_value(&self, full_sequence_name: &FullTableName) -> Result<i64, QueryExecutionError> {
+        Ok(Sequences::current_value(self, full_sequence_name)?)
+    }
+
+    fn set_value(
+        &self,
+        full_sequence_name: &FullTableName,
+        value: i64,
+        is_called: bool,
+    ) -> Result<i64, QueryExecutionError> {
+        Ok(Sequences::set_value(self, full_sequence_name, value, is_called)?)
+    }
+}
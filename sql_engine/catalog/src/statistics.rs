@@ -0,0 +1,283 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use storage::{repr::Datum, Binary};
+
+/// rows that are used to estimate distribution of values, the rest of a table is only scanned for min and max
+const SAMPLE_SIZE: usize = 30_000;
+/// how many most common values and histogram buckets are kept per column
+const STATISTICS_TARGET: usize = 100;
+
+/// Size of a table and distribution of values of its columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableStatistics {
+    /// number of rows as of the last commit
+    pub rows: u64,
+    /// approximate size of the table in bytes
+    pub bytes: u64,
+    /// empty if the table has never been analyzed
+    pub columns: Vec<ColumnStatistics>,
+}
+
+impl TableStatistics {
+    pub fn column(&self, name: &str) -> Option<&ColumnStatistics> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+}
+
+/// Distribution of values of a column as it was when the table was analyzed.
+/// Numbers are compared by their value regardless of their width, e.g. all integers are `Datum::Int64`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    pub name: String,
+    /// fraction of rows where the column is NULL
+    pub null_fraction: f64,
+    /// estimated number of distinct values besides NULL
+    pub distinct: f64,
+    /// `Datum::Null` if the column has only NULLs
+    pub min: Datum,
+    pub max: Datum,
+    /// the most common values together with fractions of rows that have them, the most common first
+    pub most_common: Vec<(Datum, f64)>,
+    /// bounds of buckets that have the same number of values, the most common values are not counted
+    pub histogram: Vec<Datum>,
+}
+
+impl ColumnStatistics {
+    /// estimated fraction of rows where the column is equal to the value
+    pub fn eq_selectivity(&self, value: &Datum) -> f64 {
+        let value = normalize(value.clone());
+        if value == Datum::Null || self.min == Datum::Null || value < self.min || value > self.max {
+            return 0.0;
+        }
+        if let Some((_value, fraction)) = self.most_common.iter().find(|(common, _fraction)| *common == value) {
+            return *fraction;
+        }
+        let other_fraction = 1.0 - self.null_fraction - self.most_common.iter().map(|(_value, f)| f).sum::<f64>();
+        let other_distinct = self.distinct - self.most_common.len() as f64;
+        if other_distinct < 1.0 {
+            0.0
+        } else {
+            (other_fraction / other_distinct).max(0.0)
+        }
+    }
+
+    /// `[column name, null fraction, distinct, min, max, number of the most common values,
+    /// (value, fraction) for each of them, histogram bounds]`
+    pub(crate) fn encode(&self) -> Vec<Datum> {
+        let mut row = vec![
+            Datum::from_string(self.name.clone()),
+            Datum::from_f64(self.null_fraction),
+            Datum::from_f64(self.distinct),
+            self.min.clone(),
+            self.max.clone(),
+            Datum::from_u64(self.most_common.len() as u64),
+        ];
+        for (value, fraction) in self.most_common.iter() {
+            row.push(value.clone());
+            row.push(Datum::from_f64(*fraction));
+        }
+        row.extend(self.histogram.iter().cloned());
+        row
+    }
+
+    pub(crate) fn decode(row: &[Datum]) -> ColumnStatistics {
+        fn float(datum: &Datum) -> f64 {
+            match datum {
+                Datum::Float64(value) => value.into_inner(),
+                _ => panic!("invalid use of ColumnStatistics::decode"),
+            }
+        }
+
+        let most_common_len = row[5].as_u64() as usize;
+        let histogram_start = 6 + most_common_len * 2;
+        ColumnStatistics {
+            name: row[0].as_string(),
+            null_fraction: float(&row[1]),
+            distinct: float(&row[2]),
+            min: row[3].clone(),
+            max: row[4].clone(),
+            most_common: row[6..histogram_start]
+                .chunks(2)
+                .map(|pair| (pair[0].clone(), float(&pair[1])))
+                .collect(),
+            histogram: row[histogram_start..].to_vec(),
+        }
+    }
+}
+
+/// Computes statistics of columns from table rows. Distribution of values is estimated
+/// from a uniform random sample of `SAMPLE_SIZE` rows
+pub(crate) struct Analyzer {
+    names: Vec<String>,
+    rows: u64,
+    sample: Vec<Vec<Datum>>,
+    bounds: Vec<Option<(Datum, Datum)>>,
+    random: StdRng,
+}
+
+impl Analyzer {
+    pub(crate) fn new(names: Vec<String>) -> Analyzer {
+        let columns = names.len();
+        Analyzer {
+            names,
+            rows: 0,
+            sample: vec![],
+            bounds: vec![None; columns],
+            // the same table is analyzed into the same statistics
+            random: StdRng::seed_from_u64(0),
+        }
+    }
+
+    pub(crate) fn add(&mut self, row: &Binary) {
+        let mut row = row.unpack();
+        row.resize(self.names.len(), Datum::from_null());
+        let row = row.into_iter().map(normalize).collect::<Vec<_>>();
+        for (value, bounds) in row.iter().zip(self.bounds.iter_mut()) {
+            if *value != Datum::Null {
+                *bounds = match bounds.take() {
+                    None => Some((value.clone(), value.clone())),
+                    Some((min, max)) => Some((min.min(value.clone()), max.max(value.clone()))),
+                };
+            }
+        }
+        self.rows += 1;
+        // reservoir sampling, every row ends up in the sample with the same probability
+        if self.sample.len() < SAMPLE_SIZE {
+            self.sample.push(row);
+        } else {
+            let index = self.random.gen_range(0, self.rows) as usize;
+            if index < SAMPLE_SIZE {
+                self.sample[index] = row;
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<ColumnStatistics> {
+        let Analyzer {
+            names,
+            rows,
+            sample,
+            bounds,
+            ..
+        } = self;
+        let sampled_rows = sample.len() as u64;
+        names
+            .into_iter()
+            .zip(bounds)
+            .enumerate()
+            .map(|(index, (name, bounds))| {
+                let values = sample.iter().map(|row| row[index].clone()).collect();
+                column_statistics(name, values, bounds, rows, sampled_rows)
+            })
+            .collect()
+    }
+}
+
+fn column_statistics(
+    name: String,
+    mut sample: Vec<Datum>,
+    bounds: Option<(Datum, Datum)>,
+    rows: u64,
+    sampled_rows: u64,
+) -> ColumnStatistics {
+    sample.retain(|value| *value != Datum::Null);
+    sample.sort();
+    let (min, max) = bounds.unwrap_or((Datum::Null, Datum::Null));
+    if sampled_rows == 0 {
+        return ColumnStatistics {
+            name,
+            null_fraction: 0.0,
+            distinct: 0.0,
+            min,
+            max,
+            most_common: vec![],
+            histogram: vec![],
+        };
+    }
+
+    let null_fraction = 1.0 - sample.len() as f64 / sampled_rows as f64;
+    let mut counts: Vec<(Datum, usize)> = vec![];
+    for value in sample.iter() {
+        match counts.last_mut() {
+            Some((last, count)) if last == value => *count += 1,
+            _ => counts.push((value.clone(), 1)),
+        }
+    }
+
+    let sampled = sample.len() as f64;
+    let sampled_distinct = counts.len() as f64;
+    let distinct = if sampled_rows == rows {
+        sampled_distinct
+    } else {
+        // Haas and Stokes estimator, the same as PostgreSQL uses
+        let total = rows as f64 * (1.0 - null_fraction);
+        let once = counts.iter().filter(|(_value, count)| *count == 1).count() as f64;
+        let estimate = sampled * sampled_distinct / (sampled - once + once * sampled / total);
+        estimate.max(sampled_distinct).min(total).round()
+    };
+
+    // all values are the most common ones if the sample seems to have every one of them
+    let mut most_common = if counts.len() <= STATISTICS_TARGET && distinct == sampled_distinct {
+        counts.clone()
+    } else {
+        let average = sampled / sampled_distinct;
+        counts
+            .iter()
+            .filter(|(_value, count)| *count > 1 && *count as f64 > average)
+            .cloned()
+            .collect()
+    };
+    most_common.sort_by(|(_, left), (_, right)| right.cmp(left));
+    most_common.truncate(STATISTICS_TARGET);
+
+    let others = sample
+        .into_iter()
+        .filter(|value| most_common.iter().all(|(common, _count)| common != value))
+        .collect::<Vec<_>>();
+    let mut histogram = vec![];
+    if others.len() > 1 {
+        let buckets = STATISTICS_TARGET.min(others.len() - 1);
+        for bucket in 0..=buckets {
+            let bound = &others[bucket * (others.len() - 1) / buckets];
+            if histogram.last() != Some(bound) {
+                histogram.push(bound.clone());
+            }
+        }
+    }
+
+    ColumnStatistics {
+        name,
+        null_fraction,
+        distinct,
+        min,
+        max,
+        most_common: most_common
+            .into_iter()
+            .map(|(value, count)| (value, count as f64 / sampled_rows as f64))
+            .collect(),
+        histogram,
+    }
+}
+
+/// rows can have the same number stored in datums of different width
+fn normalize(value: Datum) -> Datum {
+    match value {
+        Datum::Int16(value) => Datum::from_i64(value as i64),
+        Datum::Int32(value) => Datum::from_i64(value as i64),
+        Datum::Float32(value) => Datum::from_f64(value.into_inner() as f64),
+        other => other,
+    }
+}
@@ -0,0 +1,726 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use bigdecimal::BigDecimal;
+use data_definition_execution_plan::{
+    AlterTableAction, AlterTableQuery, CreateIndexQuery, DefaultInfo, IdentityInfo, RenameSchemaQuery, SequenceOptions,
+};
+use data_manipulation_operators::{BiArithmetic, BiOperator};
+use data_manipulation_query_result::QueryExecutionError;
+use data_manipulation_typed_tree::{DynamicTypedItem, DynamicTypedTree, StaticTypedItem, StaticTypedTree, TypedValue};
+
+const SCHEMA: &str = "schema_name";
+const TABLE: &str = "table_name";
+
+fn table_name() -> FullTableName {
+    FullTableName::from((&SCHEMA, &TABLE))
+}
+
+fn apply(database: &Database, schema_change: SchemaChange) -> Result<ExecutionOutcome, ExecutionError> {
+    let mut schema_change = Some(schema_change);
+    database
+        .transaction(|db| Ok(CatalogHandler::from(db).apply(schema_change.take().expect("single transaction"))))
+        .unwrap()
+}
+
+fn column(name: &str, sql_type: SqlType) -> ColumnInfo {
+    ColumnInfo {
+        name: name.to_owned(),
+        sql_type,
+        identity: None,
+        not_null: false,
+        default: None,
+    }
+}
+
+/// table with `col_1 integer, col_2 integer` columns and rows `(1, 10), (2, 20)`
+fn with_table(database: &Database) {
+    apply(
+        database,
+        SchemaChange::CreateSchema(CreateSchemaQuery {
+            schema_name: SchemaName::from(&SCHEMA),
+            if_not_exists: false,
+        }),
+    )
+    .unwrap();
+    apply(
+        database,
+        SchemaChange::CreateTable(CreateTableQuery {
+            full_table_name: table_name(),
+            column_defs: vec![column("col_1", SqlType::integer()), column("col_2", SqlType::integer())],
+            if_not_exists: false,
+            storage: TableStorage::Row,
+            compression: None,
+            constraints: vec![],
+        }),
+    )
+    .unwrap();
+    database
+        .transaction(|db| {
+            let table = db.table(table_name().to_string());
+            table
+                .write(Binary::pack(&[Datum::from_i32(1), Datum::from_i32(10)]))
+                .unwrap();
+            table
+                .write(Binary::pack(&[Datum::from_i32(2), Datum::from_i32(20)]))
+                .unwrap();
+            Ok(())
+        })
+        .unwrap();
+}
+
+fn alter_table(database: &Database, actions: Vec<AlterTableAction>) -> Result<ExecutionOutcome, ExecutionError> {
+    apply(
+        database,
+        SchemaChange::AlterTable(AlterTableQuery {
+            full_table_name: table_name(),
+            if_exists: false,
+            actions,
+        }),
+    )
+}
+
+fn create_index(database: &Database, name: &str, column_names: Vec<&str>) {
+    apply(
+        database,
+        SchemaChange::CreateIndex(CreateIndexQuery {
+            name: name.to_owned(),
+            full_table_name: table_name(),
+            column_names: column_names.into_iter().map(ToOwned::to_owned).collect(),
+            unique: true,
+        }),
+    )
+    .unwrap();
+}
+
+fn columns(database: &Database, full_table_name: FullTableName) -> Vec<ColumnDef> {
+    database
+        .transaction(|db| {
+            Ok(CatalogHandler::from(db)
+                .table_definition(full_table_name.clone())
+                .unwrap()
+                .unwrap()
+                .columns()
+                .to_vec())
+        })
+        .unwrap()
+}
+
+fn rows(database: &Database, full_table_name: FullTableName) -> Vec<Vec<Datum>> {
+    database
+        .transaction(|db| {
+            Ok(db
+                .table(full_table_name.to_string())
+                .scan()
+                .map(|(_key, row)| row.unpack())
+                .collect())
+        })
+        .unwrap()
+}
+
+fn write(database: &Database, full_table_name: FullTableName, row: Vec<Datum>) -> Result<Key, WriteError> {
+    database
+        .transaction(|db| Ok(db.table(full_table_name.to_string()).write(Binary::pack(&row))))
+        .unwrap()
+}
+
+#[test]
+fn add_column() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddColumn {
+                column: column("col_3", SqlType::var_char(10)),
+                if_not_exists: false,
+            }]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+
+    assert_eq!(
+        columns(&database, table_name()),
+        vec![
+            ColumnDef::new("col_1".to_owned(), SqlType::integer(), 0),
+            ColumnDef::new("col_2".to_owned(), SqlType::integer(), 1),
+            ColumnDef::new("col_3".to_owned(), SqlType::var_char(10), 2),
+        ]
+    );
+    assert_eq!(
+        rows(&database, table_name()),
+        vec![
+            vec![Datum::from_i32(1), Datum::from_i32(10), Datum::from_null()],
+            vec![Datum::from_i32(2), Datum::from_i32(20), Datum::from_null()],
+        ]
+    );
+}
+
+#[test]
+fn add_existing_column() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    let add_column = |if_not_exists| {
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddColumn {
+                column: column("col_2", SqlType::bool()),
+                if_not_exists,
+            }],
+        )
+    };
+
+    assert_eq!(
+        add_column(false),
+        Err(ExecutionError::ColumnAlreadyExists(
+            "col_2".to_owned(),
+            TABLE.to_owned()
+        ))
+    );
+    assert_eq!(add_column(true), Ok(ExecutionOutcome::TableAltered));
+    assert_eq!(columns(&database, table_name()).len(), 2);
+}
+
+#[test]
+fn add_identity_column() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    alter_table(
+        &database,
+        vec![AlterTableAction::AddColumn {
+            column: ColumnInfo {
+                name: "id".to_owned(),
+                sql_type: SqlType::small_int(),
+                identity: Some(IdentityInfo {
+                    always: false,
+                    options: SequenceOptions::default(),
+                }),
+                not_null: false,
+                default: None,
+            },
+            if_not_exists: false,
+        }],
+    )
+    .unwrap();
+
+    assert_eq!(
+        rows(&database, table_name()),
+        vec![
+            vec![Datum::from_i32(1), Datum::from_i32(10), Datum::from_i16(1)],
+            vec![Datum::from_i32(2), Datum::from_i32(20), Datum::from_i16(2)],
+        ]
+    );
+    let full_sequence_name = FullTableName::from((&SCHEMA, &"table_name_id_seq"));
+    assert_eq!(
+        database
+            .transaction(|db| {
+                Ok(CatalogHandler::from(db)
+                    .sequences(CurrentValues::default())
+                    .next_value(&full_sequence_name))
+            })
+            .unwrap(),
+        Ok(3)
+    );
+}
+
+#[test]
+fn add_column_with_default() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    alter_table(
+        &database,
+        vec![AlterTableAction::AddColumn {
+            column: ColumnInfo {
+                not_null: true,
+                default: Some(DefaultInfo {
+                    text: "'none'".to_owned(),
+                    value: StaticTypedTree::Item(StaticTypedItem::Const(TypedValue::String("none".to_owned()))),
+                }),
+                ..column("col_3", SqlType::var_char(10))
+            },
+            if_not_exists: false,
+        }],
+    )
+    .unwrap();
+
+    assert_eq!(
+        columns(&database, table_name())[2],
+        ColumnDef::new("col_3".to_owned(), SqlType::var_char(10), 2)
+            .with_not_null(true)
+            .with_default(Some("'none'".to_owned()))
+    );
+    assert_eq!(
+        rows(&database, table_name()),
+        vec![
+            vec![
+                Datum::from_i32(1),
+                Datum::from_i32(10),
+                Datum::from_string("none".to_owned())
+            ],
+            vec![
+                Datum::from_i32(2),
+                Datum::from_i32(20),
+                Datum::from_string("none".to_owned())
+            ],
+        ]
+    );
+}
+
+#[test]
+fn add_not_null_column_without_default() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddColumn {
+                column: ColumnInfo {
+                    not_null: true,
+                    ..column("col_3", SqlType::integer())
+                },
+                if_not_exists: false,
+            }]
+        ),
+        Err(ExecutionError::Evaluation(QueryExecutionError::column_contains_nulls(
+            "col_3", TABLE
+        )))
+    );
+}
+
+#[test]
+fn drop_column() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::DropColumn {
+                name: "col_1".to_owned(),
+                if_exists: false,
+                cascade: false,
+            }]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+
+    assert_eq!(
+        columns(&database, table_name()),
+        vec![ColumnDef::new("col_2".to_owned(), SqlType::integer(), 0)]
+    );
+    assert_eq!(
+        rows(&database, table_name()),
+        vec![vec![Datum::from_i32(10)], vec![Datum::from_i32(20)]]
+    );
+}
+
+#[test]
+fn drop_nonexistent_column() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    let drop_column = |if_exists| {
+        alter_table(
+            &database,
+            vec![AlterTableAction::DropColumn {
+                name: "col_3".to_owned(),
+                if_exists,
+                cascade: false,
+            }],
+        )
+    };
+
+    assert_eq!(
+        drop_column(false),
+        Err(ExecutionError::ColumnNotFound("col_3".to_owned()))
+    );
+    assert_eq!(drop_column(true), Ok(ExecutionOutcome::TableAltered));
+}
+
+#[test]
+fn indexes_of_dropped_column_are_dropped() {
+    let database = Database::in_memory("");
+    with_table(&database);
+    create_index(&database, "index_1", vec!["col_1"]);
+    create_index(&database, "index_2", vec!["col_2"]);
+
+    alter_table(
+        &database,
+        vec![AlterTableAction::DropColumn {
+            name: "col_1".to_owned(),
+            if_exists: false,
+            cascade: false,
+        }],
+    )
+    .unwrap();
+
+    assert_eq!(
+        database
+            .transaction(|db| Ok(CatalogHandler::from(db).table_indexes(&table_name())))
+            .unwrap(),
+        Some(vec![IndexDef::new(
+            "index_2".to_owned(),
+            vec!["col_2".to_owned()],
+            true,
+            "btree".to_owned()
+        )])
+    );
+    assert_eq!(
+        write(&database, table_name(), vec![Datum::from_i32(10)]),
+        Err(WriteError::UniqueViolation("index_2".to_owned()))
+    );
+}
+
+#[test]
+fn rename_column() {
+    let database = Database::in_memory("");
+    with_table(&database);
+    create_index(&database, "index_name", vec!["col_1", "col_2"]);
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::RenameColumn {
+                old_name: "col_1".to_owned(),
+                new_name: "id".to_owned(),
+            }]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+
+    assert_eq!(
+        columns(&database, table_name()),
+        vec![
+            ColumnDef::new("id".to_owned(), SqlType::integer(), 0),
+            ColumnDef::new("col_2".to_owned(), SqlType::integer(), 1),
+        ]
+    );
+    assert_eq!(
+        database
+            .transaction(|db| Ok(CatalogHandler::from(db).table_indexes(&table_name())))
+            .unwrap(),
+        Some(vec![IndexDef::new(
+            "index_name".to_owned(),
+            vec!["id".to_owned(), "col_2".to_owned()],
+            true,
+            "btree".to_owned()
+        )])
+    );
+}
+
+#[test]
+fn rename_column_to_existing_one() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::RenameColumn {
+                old_name: "col_1".to_owned(),
+                new_name: "col_2".to_owned(),
+            }]
+        ),
+        Err(ExecutionError::ColumnAlreadyExists(
+            "col_2".to_owned(),
+            TABLE.to_owned()
+        ))
+    );
+}
+
+#[test]
+fn alter_column_type() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AlterColumnType {
+                name: "col_2".to_owned(),
+                sql_type: SqlType::var_char(5),
+                using: None,
+                checks: vec![],
+            }]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+
+    assert_eq!(
+        columns(&database, table_name())[1],
+        ColumnDef::new("col_2".to_owned(), SqlType::var_char(5), 1)
+    );
+    assert_eq!(
+        rows(&database, table_name()),
+        vec![
+            vec![Datum::from_i32(1), Datum::from_string("10".to_owned())],
+            vec![Datum::from_i32(2), Datum::from_string("20".to_owned())],
+        ]
+    );
+}
+
+#[test]
+fn alter_column_type_out_of_range() {
+    let database = Database::in_memory("");
+    with_table(&database);
+    write(
+        &database,
+        table_name(),
+        vec![Datum::from_i32(3), Datum::from_i32(100_000)],
+    )
+    .unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AlterColumnType {
+                name: "col_2".to_owned(),
+                sql_type: SqlType::small_int(),
+                using: None,
+                checks: vec![],
+            }]
+        ),
+        Err(ExecutionError::Evaluation(QueryExecutionError::out_of_range(
+            SqlTypeFamily::SmallInt,
+            "col_2",
+            3
+        )))
+    );
+    assert_eq!(
+        columns(&database, table_name())[1],
+        ColumnDef::new("col_2".to_owned(), SqlType::integer(), 1)
+    );
+}
+
+#[test]
+fn alter_column_type_that_can_not_be_cast() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AlterColumnType {
+                name: "col_2".to_owned(),
+                sql_type: SqlType::bool(),
+                using: None,
+                checks: vec![],
+            }]
+        ),
+        Err(ExecutionError::ColumnCannotBeCast(
+            "col_2".to_owned(),
+            "bool".to_owned()
+        ))
+    );
+}
+
+#[test]
+fn alter_column_type_using_expression() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    alter_table(
+        &database,
+        vec![AlterTableAction::AlterColumnType {
+            name: "col_2".to_owned(),
+            sql_type: SqlType::big_int(),
+            using: Some(DynamicTypedTree::BiOp {
+                type_family: SqlTypeFamily::Integer,
+                left: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Column {
+                    name: "col_1".to_owned(),
+                    sql_type: SqlTypeFamily::Integer,
+                    index: 0,
+                })),
+                op: BiOperator::Arithmetic(BiArithmetic::Mul),
+                right: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Const(TypedValue::Num {
+                    value: BigDecimal::from(100),
+                    type_family: SqlTypeFamily::SmallInt,
+                }))),
+            }),
+            checks: vec![],
+        }],
+    )
+    .unwrap();
+
+    assert_eq!(
+        rows(&database, table_name()),
+        vec![
+            vec![Datum::from_i32(1), Datum::from_i64(100)],
+            vec![Datum::from_i32(2), Datum::from_i64(200)],
+        ]
+    );
+}
+
+#[test]
+fn rename_table() {
+    let database = Database::in_memory("");
+    with_table(&database);
+    create_index(&database, "index_name", vec!["col_1"]);
+    let new_table_name = FullTableName::from((&SCHEMA, &"new_table_name"));
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::RenameTable {
+                new_name: "new_table_name".to_owned(),
+            }]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+
+    database
+        .transaction(|db| {
+            let catalog = CatalogHandler::from(db);
+            assert!(matches!(catalog.table_definition(table_name()), Some(None)));
+            assert_eq!(
+                catalog.table_indexes(&new_table_name).map(|indexes| indexes.len()),
+                Some(1)
+            );
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(columns(&database, new_table_name.clone()).len(), 2);
+    assert_eq!(rows(&database, new_table_name.clone()).len(), 2);
+    assert_eq!(
+        write(&database, new_table_name, vec![Datum::from_i32(1), Datum::from_i32(30)]),
+        Err(WriteError::UniqueViolation("index_name".to_owned()))
+    );
+}
+
+#[test]
+fn rename_table_to_existing_one() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::RenameTable {
+                new_name: TABLE.to_owned(),
+            }]
+        ),
+        Err(ExecutionError::TableAlreadyExists(SCHEMA.to_owned(), TABLE.to_owned()))
+    );
+}
+
+#[test]
+fn actions_see_changes_of_previous_ones() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    alter_table(
+        &database,
+        vec![
+            AlterTableAction::RenameTable {
+                new_name: "new_table_name".to_owned(),
+            },
+            AlterTableAction::AddColumn {
+                column: column("col_3", SqlType::bool()),
+                if_not_exists: false,
+            },
+            AlterTableAction::DropColumn {
+                name: "col_1".to_owned(),
+                if_exists: false,
+                cascade: false,
+            },
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        columns(&database, FullTableName::from((&SCHEMA, &"new_table_name"))),
+        vec![
+            ColumnDef::new("col_2".to_owned(), SqlType::integer(), 0),
+            ColumnDef::new("col_3".to_owned(), SqlType::bool(), 1),
+        ]
+    );
+}
+
+#[test]
+fn alter_nonexistent_table() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    let alter_table = |if_exists| {
+        apply(
+            &database,
+            SchemaChange::AlterTable(AlterTableQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &"other_table")),
+                if_exists,
+                actions: vec![],
+            }),
+        )
+    };
+
+    assert_eq!(
+        alter_table(false),
+        Err(ExecutionError::TableDoesNotExist(
+            SCHEMA.to_owned(),
+            "other_table".to_owned()
+        ))
+    );
+    assert_eq!(alter_table(true), Ok(ExecutionOutcome::TableAltered));
+}
+
+#[test]
+fn rename_schema() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        apply(
+            &database,
+            SchemaChange::RenameSchema(RenameSchemaQuery {
+                schema_name: SchemaName::from(&SCHEMA),
+                new_name: SchemaName::from(&"new_schema_name"),
+            })
+        ),
+        Ok(ExecutionOutcome::SchemaAltered)
+    );
+
+    let new_table_name = FullTableName::from((&"new_schema_name", &TABLE));
+    database
+        .transaction(|db| {
+            let catalog = CatalogHandler::from(db);
+            assert!(!catalog.schema_exists(&SchemaName::from(&SCHEMA)));
+            assert!(catalog.schema_exists(&SchemaName::from(&"new_schema_name")));
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(columns(&database, new_table_name.clone()).len(), 2);
+    assert_eq!(rows(&database, new_table_name).len(), 2);
+}
+
+#[test]
+fn rename_schema_to_existing_one() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        apply(
+            &database,
+            SchemaChange::RenameSchema(RenameSchemaQuery {
+                schema_name: SchemaName::from(&SCHEMA),
+                new_name: SchemaName::from(&"public"),
+            })
+        ),
+        Err(ExecutionError::SchemaAlreadyExists("public".to_owned()))
+    );
+}
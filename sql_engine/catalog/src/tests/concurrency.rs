@@ -0,0 +1,101 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+const SCHEMA: &str = "schema_name";
+const TABLE: &str = "table_name";
+
+fn create_schema() -> SchemaChange {
+    SchemaChange::CreateSchema(CreateSchemaQuery {
+        schema_name: SchemaName::from(&SCHEMA),
+        if_not_exists: false,
+    })
+}
+
+fn create_table(column_name: &str) -> SchemaChange {
+    SchemaChange::CreateTable(CreateTableQuery {
+        full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+        column_defs: vec![ColumnInfo {
+            name: column_name.to_owned(),
+            sql_type: SqlType::integer(),
+            identity: None,
+            not_null: false,
+            default: None,
+        }],
+        if_not_exists: false,
+        storage: TableStorage::Row,
+        compression: None,
+        constraints: vec![],
+    })
+}
+
+#[test]
+fn concurrent_schemas_with_the_same_name() -> TransactionResult<()> {
+    let database = Database::in_memory("");
+
+    let mut attempts = 0;
+    let result = database.transaction(|db| {
+        attempts += 1;
+        if attempts == 1 {
+            database
+                .transaction(|other| {
+                    CatalogHandler::from(other).apply(create_schema()).unwrap();
+                    Ok(())
+                })
+                .unwrap();
+        }
+        Ok(CatalogHandler::from(db).apply(create_schema()))
+    })?;
+
+    assert_eq!(attempts, 2);
+    assert_eq!(result, Err(ExecutionError::SchemaAlreadyExists(SCHEMA.to_owned())));
+    Ok(())
+}
+
+#[test]
+fn concurrent_tables_with_the_same_name() -> TransactionResult<()> {
+    let database = Database::in_memory("");
+    database.transaction(|db| {
+        CatalogHandler::from(db).apply(create_schema()).unwrap();
+        Ok(())
+    })?;
+
+    let mut attempts = 0;
+    let result = database.transaction(|db| {
+        attempts += 1;
+        if attempts == 1 {
+            database
+                .transaction(|other| {
+                    CatalogHandler::from(other).apply(create_table("first")).unwrap();
+                    Ok(())
+                })
+                .unwrap();
+        }
+        Ok(CatalogHandler::from(db).apply(create_table("second")))
+    })?;
+
+    assert_eq!(attempts, 2);
+    assert_eq!(
+        result,
+        Err(ExecutionError::TableAlreadyExists(SCHEMA.to_owned(), TABLE.to_owned()))
+    );
+    database.transaction(|db| {
+        assert_eq!(
+            CatalogHandler::from(db).columns_short(&FullTableName::from((&SCHEMA, &TABLE))),
+            vec![("first".to_owned(), SqlType::integer())]
+        );
+        Ok(())
+    })
+}
@@ -0,0 +1,751 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use bigdecimal::BigDecimal;
+use data_definition_execution_plan::{
+    AlterTableAction, AlterTableQuery, CheckInfo, ConstraintInfo, CreateIndexQuery, DropIndexesQuery,
+};
+use data_manipulation_operators::{BiArithmetic, BiOperator, Comparison};
+use data_manipulation_query_result::QueryExecutionError;
+use data_manipulation_typed_tree::{DynamicTypedItem, DynamicTypedTree, TypedValue};
+use definition::ConstraintKind;
+use storage::{ConflictableTransactionError, TransactionError};
+
+const SCHEMA: &str = "schema_name";
+const TABLE: &str = "table_name";
+
+fn table_name() -> FullTableName {
+    FullTableName::from((&SCHEMA, &TABLE))
+}
+
+/// changes of a failed schema change are rolled back as the query engine does
+fn apply(database: &Database, schema_change: SchemaChange) -> Result<ExecutionOutcome, ExecutionError> {
+    let mut schema_change = Some(schema_change);
+    let mut result = None;
+    let transaction = database.transaction(|db| {
+        match CatalogHandler::from(db).apply(schema_change.take().expect("single transaction")) {
+            Ok(outcome) => {
+                result = Some(Ok(outcome));
+                Ok(())
+            }
+            Err(error) => {
+                result = Some(Err(error));
+                Err(ConflictableTransactionError::Abort)
+            }
+        }
+    });
+    assert!(matches!(transaction, Ok(()) | Err(TransactionError::Abort)));
+    result.expect("transaction is run")
+}
+
+fn constraint(name: Option<&str>, kind: ConstraintKind, column_names: Vec<&str>) -> ConstraintInfo {
+    ConstraintInfo {
+        name: name.map(ToOwned::to_owned),
+        kind,
+        column_names: column_names.into_iter().map(ToOwned::to_owned).collect(),
+        check: None,
+    }
+}
+
+/// `CHECK (column > 0)` over one of `col_1 integer, col_2 integer` columns
+fn positive(name: Option<&str>, column_name: &str) -> ConstraintInfo {
+    ConstraintInfo {
+        name: name.map(ToOwned::to_owned),
+        kind: ConstraintKind::Check,
+        column_names: vec![column_name.to_owned()],
+        check: Some(CheckInfo {
+            text: format!("(\"{}\" > 0)", column_name),
+            predicate: DynamicTypedTree::BiOp {
+                type_family: SqlTypeFamily::Integer,
+                left: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Column {
+                    name: column_name.to_owned(),
+                    sql_type: SqlTypeFamily::Integer,
+                    index: if column_name == "col_1" { 0 } else { 1 },
+                })),
+                op: BiOperator::Comparison(Comparison::Gt),
+                right: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Const(TypedValue::Num {
+                    value: BigDecimal::from(0),
+                    type_family: SqlTypeFamily::SmallInt,
+                }))),
+            },
+        }),
+    }
+}
+
+/// table with `col_1 integer, col_2 integer` columns
+fn with_table(database: &Database, constraints: Vec<ConstraintInfo>) -> Result<ExecutionOutcome, ExecutionError> {
+    apply(
+        database,
+        SchemaChange::CreateSchema(CreateSchemaQuery {
+            schema_name: SchemaName::from(&SCHEMA),
+            if_not_exists: true,
+        }),
+    )
+    .unwrap();
+    apply(
+        database,
+        SchemaChange::CreateTable(CreateTableQuery {
+            full_table_name: table_name(),
+            column_defs: vec![
+                ColumnInfo {
+                    name: "col_1".to_owned(),
+                    sql_type: SqlType::integer(),
+                    identity: None,
+                    not_null: false,
+                    default: None,
+                },
+                ColumnInfo {
+                    name: "col_2".to_owned(),
+                    sql_type: SqlType::integer(),
+                    identity: None,
+                    not_null: false,
+                    default: None,
+                },
+            ],
+            if_not_exists: false,
+            storage: TableStorage::Row,
+            compression: None,
+            constraints,
+        }),
+    )
+}
+
+fn alter_table(database: &Database, actions: Vec<AlterTableAction>) -> Result<ExecutionOutcome, ExecutionError> {
+    apply(
+        database,
+        SchemaChange::AlterTable(AlterTableQuery {
+            full_table_name: table_name(),
+            if_exists: false,
+            actions,
+        }),
+    )
+}
+
+fn write_rows(database: &Database, rows: Vec<(i32, i32)>) -> Result<(), WriteError> {
+    database
+        .transaction(|db| {
+            let table = db.table(table_name().to_string());
+            for (col_1, col_2) in rows.iter() {
+                if let Err(error) = table.write(Binary::pack(&[Datum::from_i32(*col_1), Datum::from_i32(*col_2)])) {
+                    return Ok(Err(error));
+                }
+            }
+            Ok(Ok(()))
+        })
+        .unwrap()
+}
+
+fn constraints(database: &Database) -> Option<Vec<ConstraintDef>> {
+    database
+        .transaction(|db| Ok(CatalogHandler::from(db).table_constraints(&table_name())))
+        .unwrap()
+}
+
+fn constraint_def(name: &str, kind: ConstraintKind, column_names: Vec<&str>) -> ConstraintDef {
+    ConstraintDef::new(
+        name.to_owned(),
+        kind,
+        column_names.into_iter().map(ToOwned::to_owned).collect(),
+    )
+}
+
+fn positive_def(name: &str, column_name: &str) -> ConstraintDef {
+    constraint_def(name, ConstraintKind::Check, vec![column_name])
+        .with_check(Some(format!("(\"{}\" > 0)", column_name)))
+}
+
+#[test]
+fn constraints_are_listed_with_generated_names() {
+    let database = Database::in_memory("");
+    assert_eq!(
+        with_table(
+            &database,
+            vec![
+                constraint(None, ConstraintKind::PrimaryKey, vec!["col_1"]),
+                constraint(None, ConstraintKind::Unique, vec!["col_1", "col_2"]),
+                constraint(Some("named"), ConstraintKind::Unique, vec!["col_2"]),
+            ]
+        ),
+        Ok(ExecutionOutcome::TableCreated)
+    );
+
+    assert_eq!(
+        constraints(&database),
+        Some(vec![
+            constraint_def("named", ConstraintKind::Unique, vec!["col_2"]),
+            constraint_def(
+                "table_name_col_1_col_2_key",
+                ConstraintKind::Unique,
+                vec!["col_1", "col_2"]
+            ),
+            constraint_def("table_name_pkey", ConstraintKind::PrimaryKey, vec!["col_1"]),
+        ])
+    );
+    assert_eq!(
+        database
+            .transaction(|db| Ok(CatalogHandler::from(db).table_indexes(&table_name())))
+            .unwrap()
+            .map(|indexes| {
+                let mut indexes = indexes
+                    .into_iter()
+                    .map(|index| (index.name().to_owned(), index.unique()))
+                    .collect::<Vec<_>>();
+                indexes.sort();
+                indexes
+            }),
+        Some(vec![
+            ("named".to_owned(), true),
+            ("table_name_col_1_col_2_key".to_owned(), true),
+            ("table_name_pkey".to_owned(), true),
+        ])
+    );
+}
+
+#[test]
+fn rows_with_duplicate_keys_are_rejected() {
+    let database = Database::in_memory("");
+    with_table(
+        &database,
+        vec![
+            constraint(None, ConstraintKind::PrimaryKey, vec!["col_1"]),
+            constraint(None, ConstraintKind::Unique, vec!["col_2"]),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(write_rows(&database, vec![(1, 10), (2, 20)]), Ok(()));
+    assert_eq!(
+        write_rows(&database, vec![(1, 30)]),
+        Err(WriteError::UniqueViolation("table_name_pkey".to_owned()))
+    );
+    assert_eq!(
+        write_rows(&database, vec![(3, 20)]),
+        Err(WriteError::UniqueViolation("table_name_col_2_key".to_owned()))
+    );
+}
+
+#[test]
+fn table_has_single_primary_key() {
+    let database = Database::in_memory("");
+    assert_eq!(
+        with_table(
+            &database,
+            vec![
+                constraint(None, ConstraintKind::PrimaryKey, vec!["col_1"]),
+                constraint(None, ConstraintKind::PrimaryKey, vec!["col_2"]),
+            ]
+        ),
+        Err(ExecutionError::MultiplePrimaryKeys(TABLE.to_owned()))
+    );
+    assert_eq!(constraints(&database), None);
+
+    with_table(
+        &database,
+        vec![constraint(None, ConstraintKind::PrimaryKey, vec!["col_1"])],
+    )
+    .unwrap();
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddConstraint(constraint(
+                None,
+                ConstraintKind::PrimaryKey,
+                vec!["col_2"]
+            ))]
+        ),
+        Err(ExecutionError::MultiplePrimaryKeys(TABLE.to_owned()))
+    );
+}
+
+#[test]
+fn generated_name_is_numbered_when_it_is_taken() {
+    let database = Database::in_memory("");
+    with_table(&database, vec![]).unwrap();
+    apply(
+        &database,
+        SchemaChange::CreateIndex(CreateIndexQuery {
+            name: "table_name_col_1_key".to_owned(),
+            full_table_name: table_name(),
+            column_names: vec!["col_2".to_owned()],
+            unique: false,
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddConstraint(constraint(
+                None,
+                ConstraintKind::Unique,
+                vec!["col_1"]
+            ))]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+    assert_eq!(
+        constraints(&database),
+        Some(vec![constraint_def(
+            "table_name_col_1_key1",
+            ConstraintKind::Unique,
+            vec!["col_1"]
+        )])
+    );
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddConstraint(constraint(
+                Some("table_name_col_1_key"),
+                ConstraintKind::Unique,
+                vec!["col_1"]
+            ))]
+        ),
+        Err(ExecutionError::IndexAlreadyExists(
+            SCHEMA.to_owned(),
+            "table_name_col_1_key".to_owned()
+        ))
+    );
+}
+
+#[test]
+fn constraint_is_not_added_over_duplicate_rows() {
+    let database = Database::in_memory("");
+    with_table(&database, vec![]).unwrap();
+    write_rows(&database, vec![(1, 10), (1, 20)]).unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddConstraint(constraint(
+                None,
+                ConstraintKind::PrimaryKey,
+                vec!["col_1"]
+            ))]
+        ),
+        Err(ExecutionError::UniqueViolation("table_name_pkey".to_owned()))
+    );
+    assert_eq!(constraints(&database), Some(vec![]));
+}
+
+#[test]
+fn index_of_constraint_is_not_dropped() {
+    let database = Database::in_memory("");
+    with_table(
+        &database,
+        vec![constraint(None, ConstraintKind::PrimaryKey, vec!["col_1"])],
+    )
+    .unwrap();
+
+    assert_eq!(
+        apply(
+            &database,
+            SchemaChange::DropIndexes(DropIndexesQuery {
+                full_index_names: vec![FullTableName::from((&SCHEMA, &"table_name_pkey"))],
+                cascade: true,
+                if_exists: false,
+            })
+        ),
+        Err(ExecutionError::IndexIsRequired(
+            "table_name_pkey".to_owned(),
+            TABLE.to_owned()
+        ))
+    );
+}
+
+#[test]
+fn constraints_follow_columns() {
+    let database = Database::in_memory("");
+    with_table(
+        &database,
+        vec![
+            constraint(None, ConstraintKind::PrimaryKey, vec!["col_1"]),
+            constraint(None, ConstraintKind::Unique, vec!["col_2"]),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![
+                AlterTableAction::RenameColumn {
+                    old_name: "col_1".to_owned(),
+                    new_name: "id".to_owned(),
+                },
+                AlterTableAction::DropColumn {
+                    name: "col_2".to_owned(),
+                    if_exists: false,
+                    cascade: false,
+                },
+            ]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+    assert_eq!(
+        constraints(&database),
+        Some(vec![constraint_def(
+            "table_name_pkey",
+            ConstraintKind::PrimaryKey,
+            vec!["id"]
+        )])
+    );
+}
+
+#[test]
+fn constraints_are_dropped_with_table() {
+    let database = Database::in_memory("");
+    with_table(
+        &database,
+        vec![constraint(None, ConstraintKind::PrimaryKey, vec!["col_1"])],
+    )
+    .unwrap();
+    apply(
+        &database,
+        SchemaChange::DropTables(DropTablesQuery {
+            full_table_names: vec![table_name()],
+            cascade: false,
+            if_exists: false,
+        }),
+    )
+    .unwrap();
+
+    with_table(&database, vec![]).unwrap();
+    assert_eq!(constraints(&database), Some(vec![]));
+}
+
+#[test]
+fn columns_of_primary_key_are_not_null() {
+    let database = Database::in_memory("");
+    with_table(&database, vec![]).unwrap();
+    write_rows(&database, vec![(1, 10)]).unwrap();
+
+    alter_table(
+        &database,
+        vec![AlterTableAction::AddConstraint(constraint(
+            None,
+            ConstraintKind::PrimaryKey,
+            vec!["col_2"],
+        ))],
+    )
+    .unwrap();
+
+    assert_eq!(
+        database
+            .transaction(|db| {
+                Ok(CatalogHandler::from(db)
+                    .table_definition(table_name())
+                    .unwrap()
+                    .unwrap()
+                    .columns()
+                    .iter()
+                    .map(ColumnDef::not_null)
+                    .collect::<Vec<_>>())
+            })
+            .unwrap(),
+        vec![false, true]
+    );
+}
+
+#[test]
+fn primary_key_is_not_added_over_nulls() {
+    let database = Database::in_memory("");
+    with_table(&database, vec![]).unwrap();
+    database
+        .transaction(|db| {
+            db.table(table_name().to_string())
+                .write(Binary::pack(&[Datum::from_i32(1), Datum::from_null()]))
+                .unwrap();
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddConstraint(constraint(
+                None,
+                ConstraintKind::PrimaryKey,
+                vec!["col_2"]
+            ))]
+        ),
+        Err(ExecutionError::Evaluation(QueryExecutionError::column_contains_nulls(
+            "col_2", TABLE
+        )))
+    );
+    assert_eq!(constraints(&database), Some(vec![]));
+}
+
+#[test]
+fn check_constraints_are_listed() {
+    let database = Database::in_memory("");
+
+    assert_eq!(
+        with_table(
+            &database,
+            vec![positive(None, "col_1"), positive(Some("col_2_is_positive"), "col_2")]
+        ),
+        Ok(ExecutionOutcome::TableCreated)
+    );
+    assert_eq!(
+        constraints(&database),
+        Some(vec![
+            positive_def("col_2_is_positive", "col_2"),
+            positive_def("table_name_col_1_check", "col_1"),
+        ])
+    );
+}
+
+#[test]
+fn check_name_is_unique_within_table() {
+    let database = Database::in_memory("");
+    with_table(&database, vec![positive(Some("positive"), "col_1")]).unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddConstraint(positive(Some("positive"), "col_2"))]
+        ),
+        Err(ExecutionError::ConstraintAlreadyExists(
+            "positive".to_owned(),
+            TABLE.to_owned()
+        ))
+    );
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![
+                AlterTableAction::AddConstraint(positive(None, "col_2")),
+                AlterTableAction::AddConstraint(positive(None, "col_2")),
+            ]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+    assert_eq!(
+        constraints(&database),
+        Some(vec![
+            positive_def("positive", "col_1"),
+            positive_def("table_name_col_2_check", "col_2"),
+            positive_def("table_name_col_2_check1", "col_2"),
+        ])
+    );
+}
+
+#[test]
+fn check_is_not_added_over_violating_rows() {
+    let database = Database::in_memory("");
+    with_table(&database, vec![]).unwrap();
+    write_rows(&database, vec![(1, 1), (2, -2)]).unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddConstraint(positive(None, "col_1"))]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AddConstraint(positive(None, "col_2"))]
+        ),
+        Err(ExecutionError::Evaluation(QueryExecutionError::check_violated_by_row(
+            "table_name_col_2_check",
+            TABLE
+        )))
+    );
+    assert_eq!(
+        constraints(&database),
+        Some(vec![positive_def("table_name_col_1_check", "col_1")])
+    );
+}
+
+#[test]
+fn drop_constraints() {
+    let database = Database::in_memory("");
+    with_table(
+        &database,
+        vec![
+            constraint(None, ConstraintKind::PrimaryKey, vec!["col_1"]),
+            positive(None, "col_2"),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![
+                AlterTableAction::DropConstraint {
+                    name: "table_name_pkey".to_owned(),
+                    if_exists: false,
+                    cascade: false,
+                },
+                AlterTableAction::DropConstraint {
+                    name: "table_name_col_2_check".to_owned(),
+                    if_exists: false,
+                    cascade: false,
+                },
+                AlterTableAction::DropConstraint {
+                    name: "table_name_pkey".to_owned(),
+                    if_exists: true,
+                    cascade: false,
+                },
+            ]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+    assert_eq!(constraints(&database), Some(vec![]));
+    assert_eq!(write_rows(&database, vec![(1, 1), (1, 1)]), Ok(()));
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::DropConstraint {
+                name: "table_name_pkey".to_owned(),
+                if_exists: false,
+                cascade: false,
+            }]
+        ),
+        Err(ExecutionError::ConstraintDoesNotExist(
+            "table_name_pkey".to_owned(),
+            TABLE.to_owned()
+        ))
+    );
+}
+
+#[test]
+fn checks_follow_columns() {
+    let database = Database::in_memory("");
+    with_table(&database, vec![positive(None, "col_1"), positive(None, "col_2")]).unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![
+                AlterTableAction::RenameColumn {
+                    old_name: "col_1".to_owned(),
+                    new_name: "id".to_owned(),
+                },
+                AlterTableAction::DropColumn {
+                    name: "col_2".to_owned(),
+                    if_exists: false,
+                    cascade: false,
+                },
+            ]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+    assert_eq!(
+        constraints(&database),
+        Some(vec![constraint_def(
+            "table_name_col_1_check",
+            ConstraintKind::Check,
+            vec!["id"]
+        )
+        .with_check(Some("(\"id\" > 0)".to_owned()))])
+    );
+}
+
+/// predicate of `positive` check over `col_2` after its type is changed to `type_family`
+fn col_2_is_positive(type_family: SqlTypeFamily) -> (String, DynamicTypedTree) {
+    let predicate = DynamicTypedTree::BiOp {
+        type_family,
+        left: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Column {
+            name: "col_2".to_owned(),
+            sql_type: type_family,
+            index: 1,
+        })),
+        op: BiOperator::Comparison(Comparison::Gt),
+        right: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Const(TypedValue::Num {
+            value: BigDecimal::from(0),
+            type_family: SqlTypeFamily::SmallInt,
+        }))),
+    };
+    ("table_name_col_2_check".to_owned(), predicate)
+}
+
+#[test]
+fn column_type_is_not_changed_when_check_can_not_compare_its_values() {
+    let database = Database::in_memory("");
+    with_table(&database, vec![positive(None, "col_2")]).unwrap();
+    write_rows(&database, vec![(1, 10)]).unwrap();
+
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AlterColumnType {
+                name: "col_2".to_owned(),
+                sql_type: SqlType::var_char(5),
+                using: None,
+                checks: vec![col_2_is_positive(SqlTypeFamily::String)],
+            }]
+        ),
+        Err(ExecutionError::ColumnCannotBeCast(
+            "col_2".to_owned(),
+            SqlType::var_char(5).to_string()
+        ))
+    );
+    assert_eq!(
+        constraints(&database),
+        Some(vec![positive_def("table_name_col_2_check", "col_2")])
+    );
+}
+
+#[test]
+fn rows_of_changed_column_type_have_to_satisfy_checks() {
+    let database = Database::in_memory("");
+    with_table(&database, vec![positive(None, "col_2")]).unwrap();
+    write_rows(&database, vec![(1, 10), (2, 200)]).unwrap();
+
+    let subtract_hundred = DynamicTypedTree::BiOp {
+        type_family: SqlTypeFamily::Integer,
+        left: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Column {
+            name: "col_2".to_owned(),
+            sql_type: SqlTypeFamily::Integer,
+            index: 1,
+        })),
+        op: BiOperator::Arithmetic(BiArithmetic::Sub),
+        right: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Const(TypedValue::Num {
+            value: BigDecimal::from(100),
+            type_family: SqlTypeFamily::SmallInt,
+        }))),
+    };
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AlterColumnType {
+                name: "col_2".to_owned(),
+                sql_type: SqlType::big_int(),
+                using: Some(subtract_hundred),
+                checks: vec![col_2_is_positive(SqlTypeFamily::BigInt)],
+            }]
+        ),
+        Err(ExecutionError::Evaluation(QueryExecutionError::check_violated_by_row(
+            "table_name_col_2_check",
+            TABLE
+        )))
+    );
+    assert_eq!(
+        alter_table(
+            &database,
+            vec![AlterTableAction::AlterColumnType {
+                name: "col_2".to_owned(),
+                sql_type: SqlType::big_int(),
+                using: None,
+                checks: vec![col_2_is_positive(SqlTypeFamily::BigInt)],
+            }]
+        ),
+        Ok(ExecutionOutcome::TableAltered)
+    );
+}
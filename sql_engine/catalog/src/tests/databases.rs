@@ -0,0 +1,160 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+const DATABASE: &str = "database_name";
+const SCHEMA: &str = "schema_name";
+
+fn apply(
+    database: &Database,
+    database_name: &str,
+    schema_change: SchemaChange,
+) -> Result<ExecutionOutcome, ExecutionError> {
+    let mut schema_change = Some(schema_change);
+    database
+        .transaction(|db| {
+            let db = db.database(database_name).expect("database exists");
+            Ok(CatalogHandler::from(db).apply(schema_change.take().expect("single transaction")))
+        })
+        .unwrap()
+}
+
+fn create_database(database_name: &str) -> SchemaChange {
+    SchemaChange::CreateDatabase(CreateDatabaseQuery {
+        database_name: database_name.to_owned(),
+    })
+}
+
+fn drop_database(database_name: &str, if_exists: bool) -> SchemaChange {
+    SchemaChange::DropDatabase(DropDatabaseQuery {
+        database_name: database_name.to_owned(),
+        if_exists,
+    })
+}
+
+fn create_schema(schema_name: &str) -> SchemaChange {
+    SchemaChange::CreateSchema(CreateSchemaQuery {
+        schema_name: SchemaName::from(&schema_name),
+        if_not_exists: false,
+    })
+}
+
+fn schema_exists(database: &Database, database_name: &str, schema_name: &str) -> bool {
+    database
+        .transaction(|db| {
+            let db = db.database(database_name).expect("database exists");
+            Ok(CatalogHandler::from(db).schema_exists(&SchemaName::from(&schema_name)))
+        })
+        .unwrap()
+}
+
+#[test]
+fn create_database_with_public_schema() {
+    let database = Database::in_memory("");
+
+    assert_eq!(
+        apply(&database, DEFAULT_DATABASE, create_database(DATABASE)),
+        Ok(ExecutionOutcome::DatabaseCreated)
+    );
+    assert!(schema_exists(&database, DATABASE, "public"));
+}
+
+#[test]
+fn create_existing_database() {
+    let database = Database::in_memory("");
+    apply(&database, DEFAULT_DATABASE, create_database(DATABASE)).unwrap();
+
+    assert_eq!(
+        apply(&database, DEFAULT_DATABASE, create_database(DATABASE)),
+        Err(ExecutionError::DatabaseAlreadyExists(DATABASE.to_owned()))
+    );
+    assert_eq!(
+        apply(&database, DEFAULT_DATABASE, create_database(DEFAULT_DATABASE)),
+        Err(ExecutionError::DatabaseAlreadyExists(DEFAULT_DATABASE.to_owned()))
+    );
+}
+
+#[test]
+fn create_database_with_separator_in_name() {
+    let database = Database::in_memory("");
+
+    assert_eq!(
+        apply(&database, DEFAULT_DATABASE, create_database("database_name/nested")),
+        Err(ExecutionError::InvalidDatabaseName("database_name/nested".to_owned()))
+    );
+}
+
+#[test]
+fn schemas_are_isolated() {
+    let database = Database::in_memory("");
+    apply(&database, DEFAULT_DATABASE, create_database(DATABASE)).unwrap();
+
+    assert_eq!(
+        apply(&database, DATABASE, create_schema(SCHEMA)),
+        Ok(ExecutionOutcome::SchemaCreated)
+    );
+    assert!(schema_exists(&database, DATABASE, SCHEMA));
+    assert!(!schema_exists(&database, DEFAULT_DATABASE, SCHEMA));
+    assert_eq!(
+        apply(&database, DEFAULT_DATABASE, create_schema(SCHEMA)),
+        Ok(ExecutionOutcome::SchemaCreated)
+    );
+}
+
+#[test]
+fn dropped_database_is_created_empty() {
+    let database = Database::in_memory("");
+    apply(&database, DEFAULT_DATABASE, create_database(DATABASE)).unwrap();
+    apply(&database, DATABASE, create_schema(SCHEMA)).unwrap();
+
+    assert_eq!(
+        apply(&database, DEFAULT_DATABASE, drop_database(DATABASE, false)),
+        Ok(ExecutionOutcome::DatabaseDropped)
+    );
+    assert_eq!(
+        apply(&database, DEFAULT_DATABASE, create_database(DATABASE)),
+        Ok(ExecutionOutcome::DatabaseCreated)
+    );
+    assert!(!schema_exists(&database, DATABASE, SCHEMA));
+}
+
+#[test]
+fn drop_non_existent_database() {
+    let database = Database::in_memory("");
+
+    assert_eq!(
+        apply(&database, DEFAULT_DATABASE, drop_database(DATABASE, false)),
+        Err(ExecutionError::DatabaseDoesNotExist(DATABASE.to_owned()))
+    );
+    assert_eq!(
+        apply(&database, DEFAULT_DATABASE, drop_database(DATABASE, true)),
+        Ok(ExecutionOutcome::DatabaseDropped)
+    );
+}
+
+#[test]
+fn drop_current_database() {
+    let database = Database::in_memory("");
+    apply(&database, DEFAULT_DATABASE, create_database(DATABASE)).unwrap();
+
+    assert_eq!(
+        apply(&database, DATABASE, drop_database(DATABASE, false)),
+        Err(ExecutionError::DatabaseIsInUse(DATABASE.to_owned()))
+    );
+    assert_eq!(
+        apply(&database, DATABASE, drop_database(DEFAULT_DATABASE, false)),
+        Err(ExecutionError::DefaultDatabase(DEFAULT_DATABASE.to_owned()))
+    );
+}
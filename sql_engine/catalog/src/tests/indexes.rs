@@ -0,0 +1,253 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+use data_definition_execution_plan::{CreateIndexQuery, DropIndexesQuery, DropSchemasQuery};
+
+const SCHEMA: &str = "schema_name";
+const TABLE: &str = "table_name";
+const INDEX: &str = "index_name";
+
+fn table_name() -> FullTableName {
+    FullTableName::from((&SCHEMA, &TABLE))
+}
+
+fn index_name() -> FullTableName {
+    FullTableName::from((&SCHEMA, &INDEX))
+}
+
+fn apply(database: &Database, schema_change: SchemaChange) -> Result<ExecutionOutcome, ExecutionError> {
+    let mut schema_change = Some(schema_change);
+    database
+        .transaction(|db| Ok(CatalogHandler::from(db).apply(schema_change.take().expect("single transaction"))))
+        .unwrap()
+}
+
+fn with_table(database: &Database) {
+    apply(
+        database,
+        SchemaChange::CreateSchema(CreateSchemaQuery {
+            schema_name: SchemaName::from(&SCHEMA),
+            if_not_exists: true,
+        }),
+    )
+    .unwrap();
+    apply(
+        database,
+        SchemaChange::CreateTable(CreateTableQuery {
+            full_table_name: table_name(),
+            column_defs: vec![
+                ColumnInfo {
+                    name: "col_1".to_owned(),
+                    sql_type: SqlType::integer(),
+                    identity: None,
+                    not_null: false,
+                    default: None,
+                },
+                ColumnInfo {
+                    name: "col_2".to_owned(),
+                    sql_type: SqlType::integer(),
+                    identity: None,
+                    not_null: false,
+                    default: None,
+                },
+            ],
+            if_not_exists: false,
+            storage: TableStorage::Row,
+            compression: None,
+            constraints: vec![],
+        }),
+    )
+    .unwrap();
+}
+
+fn create_index(
+    database: &Database,
+    name: &str,
+    column_names: Vec<&str>,
+    unique: bool,
+) -> Result<ExecutionOutcome, ExecutionError> {
+    apply(
+        database,
+        SchemaChange::CreateIndex(CreateIndexQuery {
+            name: name.to_owned(),
+            full_table_name: table_name(),
+            column_names: column_names.into_iter().map(ToOwned::to_owned).collect(),
+            unique,
+        }),
+    )
+}
+
+fn drop_index(database: &Database, if_exists: bool) -> Result<ExecutionOutcome, ExecutionError> {
+    apply(
+        database,
+        SchemaChange::DropIndexes(DropIndexesQuery {
+            full_index_names: vec![index_name()],
+            cascade: false,
+            if_exists,
+        }),
+    )
+}
+
+fn indexes(database: &Database) -> Option<Vec<IndexDef>> {
+    database
+        .transaction(|db| Ok(CatalogHandler::from(db).table_indexes(&table_name())))
+        .unwrap()
+}
+
+fn storage_indexes(database: &Database) -> Vec<String> {
+    database
+        .transaction(|db| {
+            Ok(db
+                .table(table_name().to_string())
+                .indexes()
+                .into_iter()
+                .map(|index| index.name)
+                .collect())
+        })
+        .unwrap()
+}
+
+#[test]
+fn created_indexes_are_listed() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        create_index(&database, INDEX, vec!["col_2", "col_1"], true),
+        Ok(ExecutionOutcome::IndexCreated)
+    );
+    assert_eq!(
+        create_index(&database, "index_1", vec!["col_1"], false),
+        Ok(ExecutionOutcome::IndexCreated)
+    );
+
+    assert_eq!(
+        indexes(&database),
+        Some(vec![
+            IndexDef::new(
+                "index_1".to_owned(),
+                vec!["col_1".to_owned()],
+                false,
+                "btree".to_owned()
+            ),
+            IndexDef::new(
+                INDEX.to_owned(),
+                vec!["col_2".to_owned(), "col_1".to_owned()],
+                true,
+                "btree".to_owned()
+            ),
+        ])
+    );
+}
+
+#[test]
+fn indexes_of_nonexistent_table() {
+    let database = Database::in_memory("");
+
+    assert_eq!(indexes(&database), None);
+}
+
+#[test]
+fn index_does_not_change_table_definition() {
+    let database = Database::in_memory("");
+    with_table(&database);
+    create_index(&database, INDEX, vec!["col_1"], false).unwrap();
+
+    let columns = database
+        .transaction(|db| Ok(CatalogHandler::from(db).table_definition(table_name())))
+        .unwrap()
+        .and_then(|definition| definition)
+        .map(|definition| definition.column_names());
+    assert_eq!(columns, Some(vec!["col_1".to_owned(), "col_2".to_owned()]));
+}
+
+#[test]
+fn create_index_with_existing_name() {
+    let database = Database::in_memory("");
+    with_table(&database);
+    create_index(&database, INDEX, vec!["col_1"], false).unwrap();
+
+    assert_eq!(
+        create_index(&database, INDEX, vec!["col_2"], false),
+        Err(ExecutionError::IndexAlreadyExists(SCHEMA.to_owned(), INDEX.to_owned()))
+    );
+}
+
+#[test]
+fn dropped_index_is_removed() {
+    let database = Database::in_memory("");
+    with_table(&database);
+    create_index(&database, INDEX, vec!["col_1"], false).unwrap();
+
+    assert_eq!(drop_index(&database, false), Ok(ExecutionOutcome::IndexDropped));
+    assert_eq!(indexes(&database), Some(vec![]));
+    assert_eq!(storage_indexes(&database), Vec::<String>::new());
+}
+
+#[test]
+fn drop_nonexistent_index() {
+    let database = Database::in_memory("");
+    with_table(&database);
+
+    assert_eq!(
+        drop_index(&database, false),
+        Err(ExecutionError::IndexDoesNotExist(SCHEMA.to_owned(), INDEX.to_owned()))
+    );
+    assert_eq!(drop_index(&database, true), Ok(ExecutionOutcome::IndexDropped));
+}
+
+#[test]
+fn indexes_are_dropped_with_table() {
+    let database = Database::in_memory("");
+    with_table(&database);
+    create_index(&database, INDEX, vec!["col_1"], false).unwrap();
+
+    apply(
+        &database,
+        SchemaChange::DropTables(DropTablesQuery {
+            full_table_names: vec![table_name()],
+            cascade: false,
+            if_exists: false,
+        }),
+    )
+    .unwrap();
+    with_table(&database);
+
+    assert_eq!(indexes(&database), Some(vec![]));
+    assert_eq!(
+        create_index(&database, INDEX, vec!["col_1"], false),
+        Ok(ExecutionOutcome::IndexCreated)
+    );
+}
+
+#[test]
+fn indexes_are_dropped_with_schema() {
+    let database = Database::in_memory("");
+    with_table(&database);
+    create_index(&database, INDEX, vec!["col_1"], false).unwrap();
+
+    apply(
+        &database,
+        SchemaChange::DropSchemas(DropSchemasQuery {
+            schema_names: vec![SchemaName::from(&SCHEMA)],
+            cascade: true,
+            if_exists: false,
+        }),
+    )
+    .unwrap();
+    with_table(&database);
+
+    assert_eq!(indexes(&database), Some(vec![]));
+    assert_eq!(storage_indexes(&database), Vec::<String>::new());
+}
@@ -0,0 +1,465 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use data_definition_execution_plan::{IdentityInfo, SequenceOptions};
+use std::fs;
+use storage::{ConflictableTransactionError, TransactionError, TransactionalDatabase};
+
+const SCHEMA: &str = "schema_name";
+const SEQUENCE: &str = "sequence_name";
+const TABLE: &str = "table_name";
+
+fn sequence_name() -> FullTableName {
+    FullTableName::from((&SCHEMA, &SEQUENCE))
+}
+
+fn with_schema(database: &Database) {
+    database
+        .transaction(|db| {
+            CatalogHandler::from(db)
+                .apply(SchemaChange::CreateSchema(CreateSchemaQuery {
+                    schema_name: SchemaName::from(&SCHEMA),
+                    if_not_exists: false,
+                }))
+                .unwrap();
+            Ok(())
+        })
+        .unwrap();
+}
+
+fn apply(database: &Database, schema_change: SchemaChange) -> Result<ExecutionOutcome, ExecutionError> {
+    let mut schema_change = Some(schema_change);
+    database
+        .transaction(|db| Ok(CatalogHandler::from(db).apply(schema_change.take().expect("single transaction"))))
+        .unwrap()
+}
+
+fn create_sequence(database: &Database, options: SequenceOptions) {
+    assert_eq!(
+        apply(
+            database,
+            SchemaChange::CreateSequence(CreateSequenceQuery {
+                full_sequence_name: sequence_name(),
+                if_not_exists: false,
+                options,
+            })
+        ),
+        Ok(ExecutionOutcome::SequenceCreated)
+    );
+}
+
+fn create_identity_table(database: &Database) {
+    assert_eq!(
+        apply(
+            database,
+            SchemaChange::CreateTable(CreateTableQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                column_defs: vec![ColumnInfo {
+                    name: "id".to_owned(),
+                    sql_type: SqlType::integer(),
+                    identity: Some(IdentityInfo {
+                        always: true,
+                        options: SequenceOptions::default(),
+                    }),
+                    not_null: false,
+                    default: None,
+                }],
+                if_not_exists: false,
+                storage: TableStorage::Row,
+                compression: None,
+                constraints: vec![],
+            })
+        ),
+        Ok(ExecutionOutcome::TableCreated)
+    );
+}
+
+fn with_sequences<R, F: Fn(&Sequences) -> R>(database: &Database, current_values: &CurrentValues, f: F) -> R {
+    database
+        .transaction(|db| Ok(f(&CatalogHandler::from(db).sequences(current_values.clone()))))
+        .unwrap()
+}
+
+#[test]
+fn next_values() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_sequence(&database, SequenceOptions::default());
+
+    let current_values = CurrentValues::default();
+    assert_eq!(
+        with_sequences(&database, &current_values, |sequences| sequences
+            .next_value(&sequence_name())),
+        Ok(1)
+    );
+    assert_eq!(
+        with_sequences(&database, &current_values, |sequences| sequences
+            .next_value(&sequence_name())),
+        Ok(2)
+    );
+    assert_eq!(
+        with_sequences(&database, &current_values, |sequences| sequences
+            .current_value(&sequence_name())),
+        Ok(2)
+    );
+}
+
+#[test]
+fn current_value_before_next_value() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_sequence(&database, SequenceOptions::default());
+
+    assert_eq!(
+        with_sequences(&database, &CurrentValues::default(), |sequences| sequences
+            .current_value(&sequence_name())),
+        Err(SequenceError::NotDefined(SEQUENCE.to_owned()))
+    );
+}
+
+#[test]
+fn set_value() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_sequence(&database, SequenceOptions::default());
+
+    let current_values = CurrentValues::default();
+    assert_eq!(
+        with_sequences(&database, &current_values, |sequences| sequences.set_value(
+            &sequence_name(),
+            10,
+            false
+        )),
+        Ok(10)
+    );
+    assert_eq!(
+        with_sequences(&database, &current_values, |sequences| sequences
+            .next_value(&sequence_name())),
+        Ok(10)
+    );
+    assert_eq!(
+        with_sequences(&database, &current_values, |sequences| sequences.set_value(
+            &sequence_name(),
+            20,
+            true
+        )),
+        Ok(20)
+    );
+    assert_eq!(
+        with_sequences(&database, &current_values, |sequences| sequences
+            .next_value(&sequence_name())),
+        Ok(21)
+    );
+}
+
+#[test]
+fn set_value_out_of_bounds() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_sequence(&database, SequenceOptions::default());
+
+    assert_eq!(
+        with_sequences(&database, &CurrentValues::default(), |sequences| sequences.set_value(
+            &sequence_name(),
+            0,
+            true
+        )),
+        Err(SequenceError::OutOfBounds {
+            sequence: SEQUENCE.to_owned(),
+            value: 0,
+            min_value: 1,
+            max_value: i64::MAX,
+        })
+    );
+}
+
+#[test]
+fn limit_exceeded() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_sequence(
+        &database,
+        SequenceOptions {
+            max_value: 2,
+            ..SequenceOptions::default()
+        },
+    );
+
+    let current_values = CurrentValues::default();
+    for _ in 0..2 {
+        assert!(with_sequences(&database, &current_values, |sequences| sequences
+            .next_value(&sequence_name()))
+        .is_ok());
+    }
+    assert_eq!(
+        with_sequences(&database, &current_values, |sequences| sequences
+            .next_value(&sequence_name())),
+        Err(SequenceError::LimitExceeded {
+            sequence: SEQUENCE.to_owned(),
+            maximum: true,
+            value: 2,
+        })
+    );
+}
+
+#[test]
+fn cycle() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_sequence(
+        &database,
+        SequenceOptions {
+            start: -1,
+            increment: -1,
+            min_value: -2,
+            max_value: -1,
+            cycle: true,
+        },
+    );
+
+    let current_values = CurrentValues::default();
+    let values = (0..3)
+        .map(|_| {
+            with_sequences(&database, &current_values, |sequences| {
+                sequences.next_value(&sequence_name())
+            })
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![Ok(-1), Ok(-2), Ok(-1)]);
+}
+
+#[test]
+fn sequence_already_exists() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_sequence(&database, SequenceOptions::default());
+
+    assert_eq!(
+        apply(
+            &database,
+            SchemaChange::CreateSequence(CreateSequenceQuery {
+                full_sequence_name: sequence_name(),
+                if_not_exists: false,
+                options: SequenceOptions::default(),
+            })
+        ),
+        Err(ExecutionError::SequenceAlreadyExists(
+            SCHEMA.to_owned(),
+            SEQUENCE.to_owned()
+        ))
+    );
+}
+
+#[test]
+fn drop_sequence() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_sequence(&database, SequenceOptions::default());
+
+    assert_eq!(
+        apply(
+            &database,
+            SchemaChange::DropSequences(DropSequencesQuery {
+                full_sequence_names: vec![sequence_name()],
+                cascade: false,
+                if_exists: false,
+            })
+        ),
+        Ok(ExecutionOutcome::SequenceDropped)
+    );
+    assert_eq!(
+        with_sequences(&database, &CurrentValues::default(), |sequences| sequences
+            .next_value(&sequence_name())),
+        Err(SequenceError::DoesNotExist(sequence_name().to_string()))
+    );
+}
+
+#[test]
+fn identity_column_sequence() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_identity_table(&database);
+
+    let identity_sequence = FullTableName::from((&SCHEMA, &"table_name_id_seq"));
+    assert_eq!(
+        with_sequences(&database, &CurrentValues::default(), |sequences| sequences
+            .next_value(&identity_sequence)),
+        Ok(1)
+    );
+    assert_eq!(
+        apply(
+            &database,
+            SchemaChange::DropSequences(DropSequencesQuery {
+                full_sequence_names: vec![identity_sequence.clone()],
+                cascade: true,
+                if_exists: false,
+            })
+        ),
+        Err(ExecutionError::SequenceIsRequired(
+            "table_name_id_seq".to_owned(),
+            "id".to_owned(),
+            TABLE.to_owned()
+        ))
+    );
+
+    assert_eq!(
+        apply(
+            &database,
+            SchemaChange::DropTables(DropTablesQuery {
+                full_table_names: vec![FullTableName::from((&SCHEMA, &TABLE))],
+                cascade: false,
+                if_exists: false,
+            })
+        ),
+        Ok(ExecutionOutcome::TableDropped)
+    );
+    assert_eq!(
+        with_sequences(&database, &CurrentValues::default(), |sequences| sequences
+            .next_value(&identity_sequence)),
+        Err(SequenceError::DoesNotExist(identity_sequence.to_string()))
+    );
+}
+
+fn insert_next_id(db: TransactionalDatabase, identity_sequence: &FullTableName, current_values: &CurrentValues) -> i64 {
+    let id = CatalogHandler::from(db.clone())
+        .sequences(current_values.clone())
+        .next_value(identity_sequence)
+        .unwrap();
+    db.table(FullTableName::from((&SCHEMA, &TABLE)).to_string())
+        .write(Binary::pack(&[Datum::from_i32(id as i32)]))
+        .unwrap();
+    id
+}
+
+#[test]
+fn concurrent_inserts_with_identity() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_identity_table(&database);
+    let identity_sequence = FullTableName::from((&SCHEMA, &"table_name_id_seq"));
+
+    let mut attempts = 0;
+    let current_values = CurrentValues::default();
+    let id = database
+        .transaction(|db| {
+            attempts += 1;
+            let first = insert_next_id(db.clone(), &identity_sequence, &current_values);
+            if attempts == 1 {
+                database
+                    .transaction(|other| {
+                        insert_next_id(other, &identity_sequence, &CurrentValues::default());
+                        Ok(())
+                    })
+                    .unwrap();
+            }
+            Ok(first)
+        })
+        .unwrap();
+
+    assert_eq!(attempts, 1);
+    assert_eq!(id, 1);
+    assert_eq!(
+        with_sequences(&database, &current_values, |sequences| sequences
+            .current_value(&identity_sequence)),
+        Ok(1)
+    );
+    let rows = database
+        .transaction(|db| {
+            Ok(db
+                .table(FullTableName::from((&SCHEMA, &TABLE)).to_string())
+                .scan()
+                .map(|(_key, value)| value.unpack()[0].clone())
+                .collect::<Vec<_>>())
+        })
+        .unwrap();
+    assert_eq!(rows, vec![Datum::from_i32(1), Datum::from_i32(2)]);
+}
+
+#[test]
+fn values_of_rolled_back_transaction_are_not_given_out_again() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+    create_sequence(&database, SequenceOptions::default());
+
+    assert_eq!(
+        database.transaction(|db| {
+            CatalogHandler::from(db)
+                .sequences(CurrentValues::default())
+                .next_value(&sequence_name())
+                .unwrap();
+            Err::<(), _>(ConflictableTransactionError::Abort)
+        }),
+        Err(TransactionError::Abort)
+    );
+
+    assert_eq!(
+        with_sequences(&database, &CurrentValues::default(), |sequences| sequences
+            .next_value(&sequence_name())),
+        Ok(2)
+    );
+}
+
+#[test]
+fn sequence_created_by_the_transaction() {
+    let database = Database::in_memory("");
+    with_schema(&database);
+
+    let next = database
+        .transaction(|db| {
+            let catalog = CatalogHandler::from(db);
+            catalog
+                .apply(SchemaChange::CreateSequence(CreateSequenceQuery {
+                    full_sequence_name: sequence_name(),
+                    if_not_exists: false,
+                    options: SequenceOptions::default(),
+                }))
+                .unwrap();
+            Ok(catalog.sequences(CurrentValues::default()).next_value(&sequence_name()))
+        })
+        .unwrap();
+
+    assert_eq!(next, Ok(1));
+    assert_eq!(
+        with_sequences(&database, &CurrentValues::default(), |sequences| sequences
+            .next_value(&sequence_name())),
+        Ok(2)
+    );
+}
+
+#[test]
+fn values_survive_restart() {
+    let path = std::env::temp_dir().join(format!("isomorphicdb-sequences-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    let path = path.to_str().unwrap().to_owned();
+    {
+        let database = Database::in_memory(&path);
+        with_schema(&database);
+        create_sequence(&database, SequenceOptions::default());
+        for _ in 0..3 {
+            with_sequences(&database, &CurrentValues::default(), |sequences| {
+                sequences.next_value(&sequence_name())
+            })
+            .unwrap();
+        }
+    }
+
+    let database = Database::in_memory(&path);
+    assert_eq!(
+        with_sequences(&database, &CurrentValues::default(), |sequences| sequences
+            .next_value(&sequence_name())),
+        Ok(4)
+    );
+    let _ = fs::remove_dir_all(&path);
+}
@@ -0,0 +1,255 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+const SCHEMA: &str = "schema_name";
+const TABLE: &str = "table_name";
+
+fn full_table_name() -> FullTableName {
+    FullTableName::from((&SCHEMA, &TABLE))
+}
+
+fn int(value: i64) -> Datum {
+    Datum::from_i64(value)
+}
+
+fn string(value: &str) -> Datum {
+    Datum::from_string(value.to_owned())
+}
+
+/// table with `id integer, name varchar` columns
+fn database(rows: Vec<(Datum, Datum)>) -> Database {
+    let database = Database::in_memory("");
+    database
+        .transaction(|db| {
+            let catalog = CatalogHandler::from(db.clone());
+            catalog
+                .apply(SchemaChange::CreateSchema(CreateSchemaQuery {
+                    schema_name: SchemaName::from(&SCHEMA),
+                    if_not_exists: false,
+                }))
+                .unwrap();
+            catalog
+                .apply(SchemaChange::CreateTable(CreateTableQuery {
+                    full_table_name: full_table_name(),
+                    column_defs: vec![
+                        ColumnInfo {
+                            name: "id".to_owned(),
+                            sql_type: SqlType::integer(),
+                            identity: None,
+                            not_null: false,
+                            default: None,
+                        },
+                        ColumnInfo {
+                            name: "name".to_owned(),
+                            sql_type: SqlType::var_char(10),
+                            identity: None,
+                            not_null: false,
+                            default: None,
+                        },
+                    ],
+                    if_not_exists: false,
+                    storage: TableStorage::Row,
+                    compression: None,
+                    constraints: vec![],
+                }))
+                .unwrap();
+            Ok(())
+        })
+        .unwrap();
+    database
+        .transaction(|db| {
+            db.table(full_table_name().to_string())
+                .write_batch(
+                    rows.iter()
+                        .map(|(id, name)| Binary::pack(&[id.clone(), name.clone()]))
+                        .collect(),
+                )
+                .unwrap();
+            Ok(())
+        })
+        .unwrap();
+    database
+}
+
+fn analyze(database: &Database, full_table_names: Vec<FullTableName>) -> TableStatistics {
+    database
+        .transaction(|db| {
+            let catalog = CatalogHandler::from(db);
+            assert_eq!(
+                catalog.apply(SchemaChange::Analyze(AnalyzeQuery {
+                    full_table_names: full_table_names.clone()
+                })),
+                Ok(ExecutionOutcome::TablesAnalyzed)
+            );
+            Ok(())
+        })
+        .unwrap();
+    database
+        .transaction(|db| Ok(CatalogHandler::from(db).table_statistics(&full_table_name())))
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn table_is_not_analyzed() -> TransactionResult<()> {
+    let database = database(vec![
+        (Datum::from_i16(1), string("a")),
+        (Datum::from_i16(2), string("b")),
+    ]);
+    database.transaction(|db| {
+        let statistics = CatalogHandler::from(db).table_statistics(&full_table_name()).unwrap();
+        assert_eq!(statistics.rows, 2);
+        assert!(statistics.bytes > 0);
+        assert_eq!(statistics.columns, vec![]);
+        Ok(())
+    })
+}
+
+#[test]
+fn statistics_of_nonexistent_table() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        assert_eq!(CatalogHandler::from(db).table_statistics(&full_table_name()), None);
+        Ok(())
+    })
+}
+
+#[test]
+fn analyze_table() {
+    let database = database(vec![
+        (Datum::from_i16(3), string("a")),
+        (Datum::from_i32(1), string("b")),
+        (Datum::from_i16(1), Datum::from_null()),
+        (Datum::from_i64(2), Datum::from_null()),
+    ]);
+
+    let statistics = analyze(&database, vec![full_table_name()]);
+
+    assert_eq!(statistics.rows, 4);
+    assert_eq!(
+        statistics.column("id"),
+        Some(&ColumnStatistics {
+            name: "id".to_owned(),
+            null_fraction: 0.0,
+            distinct: 3.0,
+            min: int(1),
+            max: int(3),
+            most_common: vec![(int(1), 0.5), (int(2), 0.25), (int(3), 0.25)],
+            histogram: vec![],
+        })
+    );
+    assert_eq!(
+        statistics.column("name"),
+        Some(&ColumnStatistics {
+            name: "name".to_owned(),
+            null_fraction: 0.5,
+            distinct: 2.0,
+            min: string("a"),
+            max: string("b"),
+            most_common: vec![(string("a"), 0.25), (string("b"), 0.25)],
+            histogram: vec![],
+        })
+    );
+}
+
+#[test]
+fn analyze_all_tables() {
+    let database = database(vec![(Datum::from_i16(1), string("a"))]);
+
+    let statistics = analyze(&database, vec![]);
+
+    assert_eq!(statistics.columns.len(), 2);
+}
+
+#[test]
+fn analyze_nonexistent_table() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        assert_eq!(
+            CatalogHandler::from(db).apply(SchemaChange::Analyze(AnalyzeQuery {
+                full_table_names: vec![full_table_name()]
+            })),
+            Err(ExecutionError::TableDoesNotExist(SCHEMA.to_owned(), TABLE.to_owned()))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn large_table_is_sampled() {
+    let database = database(
+        (0..40_000)
+            .map(|id| (Datum::from_i32(id), string(if id % 2 == 0 { "even" } else { "odd" })))
+            .collect(),
+    );
+
+    let statistics = analyze(&database, vec![full_table_name()]);
+
+    let id = statistics.column("id").unwrap();
+    assert_eq!((id.min.clone(), id.max.clone()), (int(0), int(39_999)));
+    assert_eq!(id.distinct, 40_000.0);
+    assert_eq!(id.most_common, vec![]);
+    assert_eq!(id.histogram.len(), 101);
+    // bounds are taken from the sample
+    assert!(id.histogram.windows(2).all(|bounds| bounds[0] < bounds[1]));
+
+    let name = statistics.column("name").unwrap();
+    assert_eq!(name.distinct, 2.0);
+    assert_eq!(
+        name.most_common.iter().map(|(_value, fraction)| fraction).sum::<f64>(),
+        1.0
+    );
+}
+
+#[test]
+fn eq_selectivity() {
+    let database = database(vec![
+        (Datum::from_i16(1), string("a")),
+        (Datum::from_i16(1), string("b")),
+        (Datum::from_i16(2), string("c")),
+        (Datum::from_i16(4), Datum::from_null()),
+    ]);
+
+    let statistics = analyze(&database, vec![full_table_name()]);
+
+    let id = statistics.column("id").unwrap();
+    assert_eq!(id.eq_selectivity(&Datum::from_i16(1)), 0.5);
+    assert_eq!(id.eq_selectivity(&Datum::from_i32(3)), 0.0);
+    assert_eq!(id.eq_selectivity(&Datum::from_i64(10)), 0.0);
+    assert_eq!(id.eq_selectivity(&Datum::from_null()), 0.0);
+}
+
+#[test]
+fn statistics_are_dropped_with_table() -> TransactionResult<()> {
+    let database = database(vec![(Datum::from_i16(1), string("a"))]);
+    analyze(&database, vec![full_table_name()]);
+
+    database.transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog
+            .apply(SchemaChange::DropTables(DropTablesQuery {
+                full_table_names: vec![full_table_name()],
+                cascade: false,
+                if_exists: false,
+            }))
+            .unwrap();
+        assert_eq!(
+            db.table(format!("{}.{}", DEFINITION_SCHEMA, STATISTICS_TABLE))
+                .scan()
+                .count(),
+            0
+        );
+        Ok(())
+    })
+}
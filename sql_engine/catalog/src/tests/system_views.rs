@@ -0,0 +1,179 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+const SCHEMA: &str = "schema_name";
+const TABLE: &str = "table_name";
+
+fn full_table_name() -> FullTableName {
+    FullTableName::from((&SCHEMA, &TABLE))
+}
+
+fn create_table(database: &Database, full_table_name: FullTableName) {
+    database
+        .transaction(|db| {
+            let catalog = CatalogHandler::from(db.clone());
+            catalog
+                .apply(SchemaChange::CreateSchema(CreateSchemaQuery {
+                    schema_name: SchemaName::from(&full_table_name.schema()),
+                    if_not_exists: true,
+                }))
+                .unwrap();
+            catalog
+                .apply(SchemaChange::CreateTable(CreateTableQuery {
+                    full_table_name: full_table_name.clone(),
+                    column_defs: vec![ColumnInfo {
+                        name: "id".to_owned(),
+                        sql_type: SqlType::integer(),
+                        identity: None,
+                        not_null: false,
+                        default: None,
+                    }],
+                    if_not_exists: false,
+                    storage: TableStorage::Row,
+                    compression: None,
+                    constraints: vec![],
+                }))
+                .unwrap();
+            Ok(())
+        })
+        .unwrap();
+}
+
+fn view(database: &Database, full_table_name: FullTableName) -> Option<SystemView> {
+    database
+        .transaction(|db| Ok(CatalogHandler::from(db).system_view(&full_table_name)))
+        .unwrap()
+}
+
+fn rows(database: &Database, view: SystemView) -> Vec<Vec<Datum>> {
+    database
+        .transaction(|db| Ok(CatalogHandler::from(db).system_view_rows(view)))
+        .unwrap()
+}
+
+fn int(value: i64) -> Datum {
+    Datum::from_i64(value)
+}
+
+fn string(value: &str) -> Datum {
+    Datum::from_string(value.to_owned())
+}
+
+#[test]
+fn views_are_found_in_pg_catalog_and_public_schemas() {
+    let database = Database::in_memory("");
+
+    assert_eq!(
+        view(&database, FullTableName::from((&"pg_catalog", &"pg_stat_user_tables"))),
+        Some(SystemView::StatUserTables)
+    );
+    assert_eq!(
+        view(&database, FullTableName::from((&"public", &"pg_stat_database"))),
+        Some(SystemView::StatDatabase)
+    );
+    assert_eq!(
+        view(&database, FullTableName::from((&"schema_name", &"pg_stat_database"))),
+        None
+    );
+    assert_eq!(view(&database, FullTableName::from((&"pg_catalog", &"pg_class"))), None);
+}
+
+#[test]
+fn user_table_hides_view_in_public_schema() {
+    let database = Database::in_memory("");
+    create_table(&database, FullTableName::from((&"public", &"pg_stat_database")));
+
+    assert_eq!(
+        view(&database, FullTableName::from((&"public", &"pg_stat_database"))),
+        None
+    );
+    assert_eq!(
+        view(&database, FullTableName::from((&"pg_catalog", &"pg_stat_database"))),
+        Some(SystemView::StatDatabase)
+    );
+}
+
+#[test]
+fn view_columns_are_table_definition() {
+    let database = Database::in_memory("");
+    let definition = database
+        .transaction(|db| {
+            Ok(CatalogHandler::from(db).table_definition(FullTableName::from((&"pg_catalog", &"pg_stat_database"))))
+        })
+        .unwrap();
+
+    assert_eq!(
+        definition
+            .and_then(|definition| definition)
+            .map(|definition| definition.columns().to_vec()),
+        Some(SystemView::StatDatabase.columns())
+    );
+}
+
+#[test]
+fn activity_of_user_tables() -> TransactionResult<()> {
+    let database = Database::in_memory("");
+    create_table(&database, full_table_name());
+    database.transaction(|db| {
+        let table = db.table(full_table_name().to_string());
+        let key = table.write(Binary::pack(&[int(1)])).unwrap();
+        table.write(Binary::pack(&[int(2)])).unwrap();
+        table.write_key(key, None).unwrap();
+        Ok(())
+    })?;
+    database.transaction(|db| {
+        assert_eq!(db.table(full_table_name().to_string()).scan().count(), 1);
+        Ok(())
+    })?;
+
+    let row = rows(&database, SystemView::StatUserTables).remove(0);
+    assert_eq!(
+        row[..10].to_vec(),
+        vec![
+            string(SCHEMA),
+            string(TABLE),
+            int(1),
+            int(1),
+            int(0),
+            int(0),
+            int(1),
+            int(0),
+            int(0),
+            int(1),
+        ]
+    );
+    assert_ne!(row[10], int(0));
+    assert_eq!(row[11..].to_vec(), vec![int(0), int(0)]);
+    Ok(())
+}
+
+#[test]
+fn activity_of_database() -> TransactionResult<()> {
+    let database = Database::in_memory("");
+    create_table(&database, full_table_name());
+    database.transaction(|db| {
+        db.table(full_table_name().to_string())
+            .write(Binary::pack(&[int(1)]))
+            .unwrap();
+        Ok(())
+    })?;
+
+    let row = rows(&database, SystemView::StatDatabase).remove(0);
+    assert_eq!(row[0], string(DEFAULT_DATABASE));
+    assert_eq!(row[5], int(1));
+    assert_eq!(row[8], int(0));
+    Ok(())
+}
@@ -0,0 +1,102 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+
+const SCHEMA: &str = "schema_name";
+const TABLE: &str = "table_name";
+
+fn create_table(database: &Database, storage: TableStorage, compression: Option<usize>) {
+    database
+        .transaction(|db| {
+            let catalog = CatalogHandler::from(db);
+            catalog
+                .apply(SchemaChange::CreateSchema(CreateSchemaQuery {
+                    schema_name: SchemaName::from(&SCHEMA),
+                    if_not_exists: false,
+                }))
+                .unwrap();
+            catalog
+                .apply(SchemaChange::CreateTable(CreateTableQuery {
+                    full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                    column_defs: vec![ColumnInfo {
+                        name: "id".to_owned(),
+                        sql_type: SqlType::integer(),
+                        identity: None,
+                        not_null: false,
+                        default: None,
+                    }],
+                    if_not_exists: false,
+                    storage,
+                    compression,
+                    constraints: vec![],
+                }))
+                .unwrap();
+            Ok(())
+        })
+        .unwrap();
+}
+
+fn layout(database: &Database) -> TreeLayout {
+    database
+        .transaction(|db| Ok(db.table(FullTableName::from((&SCHEMA, &TABLE)).to_string()).layout()))
+        .unwrap()
+}
+
+fn write_long_string(database: &Database) -> TreeMetrics {
+    let full_table_name = FullTableName::from((&SCHEMA, &TABLE)).to_string();
+    database
+        .transaction(|db| {
+            db.table(full_table_name.as_str())
+                .write(Binary::pack(&[Datum::from_string("long".repeat(100))]))
+                .unwrap();
+            Ok(())
+        })
+        .unwrap();
+    database
+        .transaction(|db| Ok(db.tree_metrics(&full_table_name)))
+        .unwrap()
+}
+
+#[test]
+fn row_table() {
+    let database = Database::in_memory("");
+    create_table(&database, TableStorage::Row, None);
+    assert_eq!(layout(&database), TreeLayout::Rows);
+}
+
+#[test]
+fn columnar_table() {
+    let database = Database::in_memory("");
+    create_table(&database, TableStorage::Columnar, None);
+    assert_eq!(layout(&database), TreeLayout::Columns);
+}
+
+#[test]
+fn compressed_table() {
+    let database = Database::in_memory("");
+    create_table(&database, TableStorage::Row, Some(64));
+
+    let metrics = write_long_string(&database);
+    assert_eq!(metrics.values_compressed, 1);
+    assert!(metrics.bytes_saved > 0);
+}
+
+#[test]
+fn table_is_not_compressed_by_default() {
+    let database = Database::in_memory("");
+    create_table(&database, TableStorage::Row, None);
+
+    let metrics = write_long_string(&database);
+    assert_eq!((metrics.values_compressed, metrics.bytes_saved), (0, 0));
+}
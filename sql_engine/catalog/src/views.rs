@@ -0,0 +1,135 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use definition::{ColumnDef, FullTableName};
+use storage::{repr::Datum, TransactionalDatabase, TreeMetrics};
+use types::SqlType;
+
+pub(crate) const PG_CATALOG: &str = "pg_catalog";
+const NAME_LEN: u64 = 63;
+
+/// Read only views of database activity with the columns that Postgres monitoring tools query.
+/// Counters start from zero when the database is started
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SystemView {
+    StatUserTables,
+    StatDatabase,
+}
+
+impl SystemView {
+    pub(crate) fn from_name(name: &str) -> Option<SystemView> {
+        match name {
+            "pg_stat_user_tables" => Some(SystemView::StatUserTables),
+            "pg_stat_database" => Some(SystemView::StatDatabase),
+            _ => None,
+        }
+    }
+
+    pub fn columns(&self) -> Vec<ColumnDef> {
+        let (names, counters): (&[&str], &[&str]) = match self {
+            SystemView::StatUserTables => (
+                &["schemaname", "relname"],
+                &[
+                    "seq_scan",
+                    "seq_tup_read",
+                    "idx_scan",
+                    "idx_tup_fetch",
+                    "n_tup_ins",
+                    "n_tup_upd",
+                    "n_tup_del",
+                    "n_live_tup",
+                    "n_bytes_written",
+                    "n_values_compressed",
+                    "n_bytes_saved",
+                ],
+            ),
+            SystemView::StatDatabase => (
+                &["datname"],
+                &[
+                    "xact_commit",
+                    "xact_rollback",
+                    "tup_returned",
+                    "tup_fetched",
+                    "tup_inserted",
+                    "tup_updated",
+                    "tup_deleted",
+                    "conflicts",
+                ],
+            ),
+        };
+        names
+            .iter()
+            .map(|name| (name, SqlType::var_char(NAME_LEN)))
+            .chain(counters.iter().map(|name| (name, SqlType::big_int())))
+            .enumerate()
+            .map(|(index, (name, sql_type))| ColumnDef::new((*name).to_owned(), sql_type, index))
+            .collect()
+    }
+
+    /// rows of the view as they are seen by the transaction.
+    /// `tables` are user tables which activity is reported
+    pub(crate) fn rows(&self, database: &TransactionalDatabase, tables: Vec<FullTableName>) -> Vec<Vec<Datum>> {
+        match self {
+            SystemView::StatUserTables => tables
+                .into_iter()
+                .map(|full_table_name| {
+                    let metrics = database.tree_metrics(&full_table_name.to_string());
+                    let stats = database.tree_stats(&full_table_name.to_string());
+                    vec![
+                        Datum::from_string(full_table_name.schema().to_owned()),
+                        Datum::from_string(full_table_name.table().to_owned()),
+                        counter(metrics.scans),
+                        counter(metrics.rows_read),
+                        counter(metrics.index_scans),
+                        counter(metrics.index_rows_read),
+                        counter(metrics.rows_inserted),
+                        counter(metrics.rows_updated),
+                        counter(metrics.rows_deleted),
+                        counter(stats.rows),
+                        counter(metrics.bytes_written),
+                        counter(metrics.values_compressed),
+                        counter(metrics.bytes_saved),
+                    ]
+                })
+                .collect(),
+            SystemView::StatDatabase => {
+                let transactions = database.database_metrics();
+                let mut tuples = TreeMetrics::default();
+                for full_table_name in tables {
+                    let metrics = database.tree_metrics(&full_table_name.to_string());
+                    tuples.rows_read += metrics.rows_read;
+                    tuples.index_rows_read += metrics.index_rows_read;
+                    tuples.rows_inserted += metrics.rows_inserted;
+                    tuples.rows_updated += metrics.rows_updated;
+                    tuples.rows_deleted += metrics.rows_deleted;
+                }
+                vec![vec![
+                    Datum::from_string(database.name().to_owned()),
+                    counter(transactions.committed),
+                    counter(transactions.rolled_back),
+                    counter(tuples.rows_read),
+                    counter(tuples.index_rows_read),
+                    counter(tuples.rows_inserted),
+                    counter(tuples.rows_updated),
+                    counter(tuples.rows_deleted),
+                    counter(transactions.conflicts),
+                ]]
+            }
+        }
+    }
+}
+
+fn counter(value: u64) -> Datum {
+    Datum::from_i64(value as i64)
+}
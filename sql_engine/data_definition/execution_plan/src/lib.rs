@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use definition::{FullTableName, SchemaName};
+use data_manipulation_query_result::QueryExecutionError;
+use data_manipulation_typed_tree::{DynamicTypedTree, StaticTypedTree};
+use definition::{ConstraintKind, FullTableName, SchemaName};
 use query_response::QueryError;
 use types::SqlType;
 
@@ -33,6 +35,56 @@ pub struct DropSchemasQuery {
 pub struct ColumnInfo {
     pub name: String,
     pub sql_type: SqlType,
+    pub identity: Option<IdentityInfo>,
+    pub not_null: bool,
+    pub default: Option<DefaultInfo>,
+}
+
+/// expression which value the column gets when it is not given
+#[derive(Debug, PartialEq)]
+pub struct DefaultInfo {
+    /// SQL text of the expression that is kept in the catalog
+    pub text: String,
+    /// evaluated for rows that exist when the column is added
+    pub value: StaticTypedTree,
+}
+
+/// column which values are generated by a sequence that is created together with the table
+#[derive(Debug, PartialEq)]
+pub struct IdentityInfo {
+    pub always: bool,
+    pub options: SequenceOptions,
+}
+
+/// Values that a sequence generates. Sequences are ascending from 1 by default
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SequenceOptions {
+    pub start: i64,
+    pub increment: i64,
+    pub min_value: i64,
+    pub max_value: i64,
+    /// sequence starts over from `min_value` (or `max_value` if it is descending) when it reached the limit
+    pub cycle: bool,
+}
+
+impl Default for SequenceOptions {
+    fn default() -> SequenceOptions {
+        SequenceOptions {
+            start: 1,
+            increment: 1,
+            min_value: 1,
+            max_value: i64::MAX,
+            cycle: false,
+        }
+    }
+}
+
+/// how records of a table are laid out in storage
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum TableStorage {
+    #[default]
+    Row,
+    Columnar,
 }
 
 #[derive(Debug, PartialEq)]
@@ -40,6 +92,29 @@ pub struct CreateTableQuery {
     pub full_table_name: FullTableName,
     pub column_defs: Vec<ColumnInfo>,
     pub if_not_exists: bool,
+    pub storage: TableStorage,
+    /// string values longer than this number of bytes are compressed, `None` if they are stored as is
+    pub compression: Option<usize>,
+    pub constraints: Vec<ConstraintInfo>,
+}
+
+/// `PRIMARY KEY` and `UNIQUE` constraints are enforced by the unique index with the same name
+#[derive(Debug, PartialEq)]
+pub struct ConstraintInfo {
+    /// generated from names of the table and the columns if it is not given
+    pub name: Option<String>,
+    pub kind: ConstraintKind,
+    pub column_names: Vec<String>,
+    pub check: Option<CheckInfo>,
+}
+
+/// predicate that every row of the table has to satisfy, rows that it evaluates to `NULL` for satisfy it too
+#[derive(Debug, PartialEq)]
+pub struct CheckInfo {
+    /// SQL text of the predicate that is kept in the catalog
+    pub text: String,
+    /// evaluated over rows that exist when the constraint is added
+    pub predicate: DynamicTypedTree,
 }
 
 #[derive(Debug, PartialEq)]
@@ -49,6 +124,56 @@ pub struct DropTablesQuery {
     pub if_exists: bool,
 }
 
+/// actions are applied one after another, existing rows of the table are rewritten if their layout changes
+#[derive(Debug, PartialEq)]
+pub struct AlterTableQuery {
+    pub full_table_name: FullTableName,
+    pub if_exists: bool,
+    pub actions: Vec<AlterTableAction>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AlterTableAction {
+    AddColumn {
+        column: ColumnInfo,
+        if_not_exists: bool,
+    },
+    DropColumn {
+        name: String,
+        if_exists: bool,
+        cascade: bool,
+    },
+    RenameColumn {
+        old_name: String,
+        new_name: String,
+    },
+    /// `using` is evaluated over rows of the table, otherwise values of the column are converted as they are.
+    /// Converted rows have to satisfy `checks`, names and predicates of `CHECK` constraints over the column
+    AlterColumnType {
+        name: String,
+        sql_type: SqlType,
+        using: Option<DynamicTypedTree>,
+        checks: Vec<(String, DynamicTypedTree)>,
+    },
+    RenameTable {
+        new_name: String,
+    },
+    /// existing rows have to satisfy the constraint
+    AddConstraint(ConstraintInfo),
+    /// index of `PRIMARY KEY` or `UNIQUE` constraint is dropped together with the constraint
+    DropConstraint {
+        name: String,
+        if_exists: bool,
+        cascade: bool,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RenameSchemaQuery {
+    pub schema_name: SchemaName,
+    pub new_name: SchemaName,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct ColumnDesc {
     pub name: String,
@@ -72,6 +197,46 @@ pub struct CreateIndexQuery {
     pub name: String,
     pub full_table_name: FullTableName,
     pub column_names: Vec<String>,
+    pub unique: bool,
+}
+
+/// indexes are named within schemas of their tables
+#[derive(Debug, PartialEq)]
+pub struct DropIndexesQuery {
+    pub full_index_names: Vec<FullTableName>,
+    pub cascade: bool,
+    pub if_exists: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CreateSequenceQuery {
+    pub full_sequence_name: FullTableName,
+    pub if_not_exists: bool,
+    pub options: SequenceOptions,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DropSequencesQuery {
+    pub full_sequence_names: Vec<FullTableName>,
+    pub cascade: bool,
+    pub if_exists: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CreateDatabaseQuery {
+    pub database_name: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DropDatabaseQuery {
+    pub database_name: String,
+    pub if_exists: bool,
+}
+
+/// collects statistics of all tables if `full_table_names` is empty
+#[derive(Debug, PartialEq)]
+pub struct AnalyzeQuery {
+    pub full_table_names: Vec<FullTableName>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -81,6 +246,14 @@ pub enum SchemaChange {
     CreateTable(CreateTableQuery),
     DropTables(DropTablesQuery),
     CreateIndex(CreateIndexQuery),
+    DropIndexes(DropIndexesQuery),
+    Analyze(AnalyzeQuery),
+    CreateSequence(CreateSequenceQuery),
+    DropSequences(DropSequencesQuery),
+    CreateDatabase(CreateDatabaseQuery),
+    DropDatabase(DropDatabaseQuery),
+    AlterTable(AlterTableQuery),
+    RenameSchema(RenameSchemaQuery),
 }
 
 #[derive(Debug, PartialEq)]
@@ -90,6 +263,14 @@ pub enum ExecutionOutcome {
     TableCreated,
     TableDropped,
     IndexCreated,
+    IndexDropped,
+    TablesAnalyzed,
+    SequenceCreated,
+    SequenceDropped,
+    DatabaseCreated,
+    DatabaseDropped,
+    TableAltered,
+    SchemaAltered,
 }
 
 #[derive(Debug, PartialEq)]
@@ -100,6 +281,35 @@ pub enum ExecutionError {
     TableDoesNotExist(String, String),
     SchemaHasDependentObjects(String),
     ColumnNotFound(String),
+    UniqueViolation(String),
+    SequenceAlreadyExists(String, String),
+    SequenceDoesNotExist(String, String),
+    /// sequence, column and table which values it generates
+    SequenceIsRequired(String, String, String),
+    DatabaseAlreadyExists(String),
+    DatabaseDoesNotExist(String),
+    /// database is open by the connection that drops it
+    DatabaseIsInUse(String),
+    /// the default database can't be dropped
+    DefaultDatabase(String),
+    /// name of a database contains `DATABASE_SEPARATOR` of the storage
+    InvalidDatabaseName(String),
+    IndexAlreadyExists(String, String),
+    IndexDoesNotExist(String, String),
+    /// column and table
+    ColumnAlreadyExists(String, String),
+    /// column and type it can't be converted to without `USING` expression
+    ColumnCannotBeCast(String, String),
+    /// values of existing rows could not be computed
+    Evaluation(QueryExecutionError),
+    /// index and table of the constraint that the index enforces
+    IndexIsRequired(String, String),
+    /// table that already has a primary key
+    MultiplePrimaryKeys(String),
+    /// constraint and table
+    ConstraintDoesNotExist(String, String),
+    /// constraint and table
+    ConstraintAlreadyExists(String, String),
 }
 
 impl From<ExecutionError> for QueryError {
@@ -115,6 +325,40 @@ impl From<ExecutionError> for QueryError {
             }
             ExecutionError::SchemaHasDependentObjects(schema) => QueryError::schema_has_dependent_objects(schema),
             ExecutionError::ColumnNotFound(column) => QueryError::column_does_not_exist(column),
+            ExecutionError::UniqueViolation(index) => QueryError::unique_violation(index),
+            ExecutionError::SequenceAlreadyExists(schema, sequence) => {
+                QueryError::sequence_already_exists(schema + "." + sequence.as_str())
+            }
+            ExecutionError::SequenceDoesNotExist(schema, sequence) => {
+                QueryError::sequence_does_not_exist(schema + "." + sequence.as_str())
+            }
+            ExecutionError::SequenceIsRequired(sequence, column, table) => {
+                QueryError::sequence_is_required(sequence, column, table)
+            }
+            ExecutionError::DatabaseAlreadyExists(database) => QueryError::database_already_exists(database),
+            ExecutionError::DatabaseDoesNotExist(database) => QueryError::database_does_not_exist(database),
+            ExecutionError::DatabaseIsInUse(database) => QueryError::database_is_in_use(database),
+            ExecutionError::InvalidDatabaseName(database) => QueryError::invalid_database_name(database),
+            ExecutionError::DefaultDatabase(database) => {
+                QueryError::feature_not_supported(format!("dropping of \"{}\" database", database))
+            }
+            ExecutionError::IndexAlreadyExists(schema, index) => {
+                QueryError::index_already_exists(schema + "." + index.as_str())
+            }
+            ExecutionError::IndexDoesNotExist(schema, index) => {
+                QueryError::index_does_not_exist(schema + "." + index.as_str())
+            }
+            ExecutionError::ColumnAlreadyExists(column, table) => QueryError::column_already_exists(column, table),
+            ExecutionError::ColumnCannotBeCast(column, sql_type) => QueryError::column_cannot_be_cast(column, sql_type),
+            ExecutionError::Evaluation(error) => error.into(),
+            ExecutionError::IndexIsRequired(index, table) => QueryError::index_is_required(index, table),
+            ExecutionError::MultiplePrimaryKeys(table) => QueryError::multiple_primary_keys(table),
+            ExecutionError::ConstraintDoesNotExist(constraint, table) => {
+                QueryError::constraint_does_not_exist(constraint, table)
+            }
+            ExecutionError::ConstraintAlreadyExists(constraint, table) => {
+                QueryError::constraint_already_exists(constraint, table)
+            }
         }
     }
 }
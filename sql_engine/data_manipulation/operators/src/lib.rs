@@ -222,7 +222,20 @@ pub enum BiOperator {
 }
 
 impl BiOperator {
+    /// `NULL` operand makes the result `NULL` unless `AND` or `OR` is known from the other operand
     pub fn eval(self, left: ScalarValue, right: ScalarValue) -> Result<ScalarValue, QueryExecutionError> {
+        match (self, &left, &right) {
+            (BiOperator::Logical(BiLogical::And), ScalarValue::Bool(false), ScalarValue::Null)
+            | (BiOperator::Logical(BiLogical::And), ScalarValue::Null, ScalarValue::Bool(false)) => {
+                return Ok(ScalarValue::Bool(false))
+            }
+            (BiOperator::Logical(BiLogical::Or), ScalarValue::Bool(true), ScalarValue::Null)
+            | (BiOperator::Logical(BiLogical::Or), ScalarValue::Null, ScalarValue::Bool(true)) => {
+                return Ok(ScalarValue::Bool(true))
+            }
+            (_, ScalarValue::Null, _) | (_, _, ScalarValue::Null) => return Ok(ScalarValue::Null),
+            _ => {}
+        }
         match self {
             BiOperator::Arithmetic(op) => match (left, right) {
                 (ScalarValue::Num { value: left_value, .. }, ScalarValue::Num { value: right_value, .. }) => {
@@ -700,5 +713,26 @@ impl Display for UnArithmetic {
     }
 }
 
+/// functions that read and change sequences
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SequenceFunction {
+    NextVal,
+    CurrVal,
+    /// `is_called` is `false` when the next `nextval` has to return the set value
+    SetVal {
+        is_called: bool,
+    },
+}
+
+impl Display for SequenceFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SequenceFunction::NextVal => write!(f, "nextval"),
+            SequenceFunction::CurrVal => write!(f, "currval"),
+            SequenceFunction::SetVal { .. } => write!(f, "setval"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests;
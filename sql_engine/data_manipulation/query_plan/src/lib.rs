@@ -19,20 +19,58 @@ use binary::{
     repr::{Datum, ToDatum},
     Binary,
 };
+use catalog::Sequences;
 use data_manipulation_query_result::QueryExecutionError;
 use data_manipulation_typed_tree::{DynamicTypedTree, StaticTypedTree};
 use definition::ColumnDef;
 use query_response::QueryEvent;
 use scalar::ScalarValue;
-use std::collections::HashMap;
-use storage::{Cursor, Table};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use storage::{Cursor, Table, WriteError};
 use types::{SqlType, SqlTypeFamily};
 
 pub enum QueryPlanResult {
     Inserted(usize),
     Deleted(usize),
     Updated(usize),
-    Selected((Vec<ColumnDef>, Vec<Vec<ScalarValue>>)),
+    /// rows are read from the table as they are taken
+    Selected((Vec<ColumnDef>, SelectedRows)),
+}
+
+fn write_error(error: WriteError) -> QueryExecutionError {
+    match error {
+        WriteError::UniqueViolation(index) => QueryExecutionError::unique_violation(index),
+    }
+}
+
+/// value of a stored row that expressions are evaluated over
+fn mapper(datum: &Datum) -> ScalarValue {
+    match datum {
+        Datum::Null => ScalarValue::Null,
+        Datum::True => ScalarValue::Bool(true),
+        Datum::False => ScalarValue::Bool(false),
+        Datum::Int16(value) => ScalarValue::Num {
+            value: BigDecimal::from(*value),
+            type_family: SqlTypeFamily::SmallInt,
+        },
+        Datum::Int32(value) => ScalarValue::Num {
+            value: BigDecimal::from(*value),
+            type_family: SqlTypeFamily::Integer,
+        },
+        Datum::Int64(value) => ScalarValue::Num {
+            value: BigDecimal::from(*value),
+            type_family: SqlTypeFamily::BigInt,
+        },
+        Datum::Float32(value) => ScalarValue::Num {
+            value: BigDecimal::from_f32(**value).unwrap(),
+            type_family: SqlTypeFamily::Real,
+        },
+        Datum::Float64(value) => ScalarValue::Num {
+            value: BigDecimal::from_f64(**value).unwrap(),
+            type_family: SqlTypeFamily::Double,
+        },
+        Datum::String(value) => ScalarValue::String(value.clone()),
+    }
 }
 
 impl From<QueryPlanResult> for QueryEvent {
@@ -97,11 +135,15 @@ impl Flow for StaticValues {
 
 pub struct StaticExpressionEval {
     source: Box<dyn Flow<Output = Vec<Option<StaticTypedTree>>>>,
+    sequences: Sequences,
 }
 
 impl StaticExpressionEval {
-    pub fn new(source: Box<dyn Flow<Output = Vec<Option<StaticTypedTree>>>>) -> Box<StaticExpressionEval> {
-        Box::new(StaticExpressionEval { source })
+    pub fn new(
+        source: Box<dyn Flow<Output = Vec<Option<StaticTypedTree>>>>,
+        sequences: Sequences,
+    ) -> Box<StaticExpressionEval> {
+        Box::new(StaticExpressionEval { source, sequences })
     }
 }
 
@@ -112,9 +154,10 @@ impl Flow for StaticExpressionEval {
         if let Ok(Some(tuple)) = self.source.next_tuple(param_values) {
             let mut next_tuple = vec![];
             for value in tuple {
+                // columns without values and defaults are NULL
                 let typed_value = match value {
-                    None => None,
-                    Some(value) => match value.eval(param_values) {
+                    None => Some(ScalarValue::Null),
+                    Some(value) => match value.eval_with(param_values, &self.sequences) {
                         Err(error) => return Err(error),
                         Ok(value) => Some(value),
                     },
@@ -128,17 +171,24 @@ impl Flow for StaticExpressionEval {
     }
 }
 
+/// checks values of the table columns, `None` values are left unchanged by update
 pub struct ConstraintValidator {
     source: Box<dyn Flow<Output = (Vec<ScalarValue>, Vec<Option<ScalarValue>>)>>,
-    column_types: Vec<(String, SqlTypeFamily)>,
+    table_name: String,
+    columns: Vec<ColumnDef>,
 }
 
 impl ConstraintValidator {
     pub fn new(
         source: Box<dyn Flow<Output = (Vec<ScalarValue>, Vec<Option<ScalarValue>>)>>,
-        column_types: Vec<(String, SqlTypeFamily)>,
+        table_name: String,
+        columns: Vec<ColumnDef>,
     ) -> Box<ConstraintValidator> {
-        Box::new(ConstraintValidator { source, column_types })
+        Box::new(ConstraintValidator {
+            source,
+            table_name,
+            columns,
+        })
     }
 }
 
@@ -150,8 +200,13 @@ impl Flow for ConstraintValidator {
             log::debug!("ConstraintValidator key - {:?}", key);
             let mut data = vec![];
             for (index, value) in tuple.into_iter().enumerate() {
-                let value = match (value, self.column_types[index].1) {
+                let column = &self.columns[index];
+                let value = match (value, column.sql_type().family()) {
                     (None, _) => None,
+                    (Some(ScalarValue::Null), _) if column.not_null() => {
+                        return Err(QueryExecutionError::not_null_violation(column.name(), &self.table_name));
+                    }
+                    (Some(ScalarValue::Null), _) => Some(ScalarValue::Null.as_to_datum()),
                     (Some(value), type_family) => match value.type_family() {
                         None => unimplemented!(),
                         Some(value_type) => match value_type.compare(&type_family) {
@@ -167,7 +222,7 @@ impl Flow for ConstraintValidator {
                                         if !(BigDecimal::from(i16::MIN)..=BigDecimal::from(i16::MAX)).contains(&value) {
                                             return Err(QueryExecutionError::out_of_range(
                                                 type_family,
-                                                self.column_types[index].0.as_str(),
+                                                column.name(),
                                                 index + 1,
                                             ));
                                         }
@@ -176,7 +231,7 @@ impl Flow for ConstraintValidator {
                                         if !(BigDecimal::from(i32::MIN)..=BigDecimal::from(i32::MAX)).contains(&value) {
                                             return Err(QueryExecutionError::out_of_range(
                                                 type_family,
-                                                self.column_types[index].0.as_str(),
+                                                column.name(),
                                                 index + 1,
                                             ));
                                         }
@@ -185,7 +240,7 @@ impl Flow for ConstraintValidator {
                                         if !(BigDecimal::from(i64::MIN)..=BigDecimal::from(i64::MAX)).contains(&value) {
                                             return Err(QueryExecutionError::out_of_range(
                                                 type_family,
-                                                self.column_types[index].0.as_str(),
+                                                column.name(),
                                                 index + 1,
                                             ));
                                         }
@@ -211,31 +266,59 @@ impl Flow for ConstraintValidator {
     }
 }
 
+/// `CHECK` constraints of the table, a row satisfies a constraint unless its predicate is `false`
+pub struct CheckConstraints {
+    table_name: String,
+    predicates: Vec<(String, DynamicTypedTree)>,
+}
+
+impl CheckConstraints {
+    /// predicates are paired with names of their constraints
+    pub fn new(table_name: String, predicates: Vec<(String, DynamicTypedTree)>) -> CheckConstraints {
+        CheckConstraints { table_name, predicates }
+    }
+
+    fn validate(&self, row: &[Datum]) -> Result<(), QueryExecutionError> {
+        if self.predicates.is_empty() {
+            return Ok(());
+        }
+        let values = row.iter().map(mapper).collect::<Vec<_>>();
+        for (name, predicate) in self.predicates.iter() {
+            if predicate.clone().eval(&[], &values)? == ScalarValue::Bool(false) {
+                return Err(QueryExecutionError::check_violation(&self.table_name, name));
+            }
+        }
+        Ok(())
+    }
+}
+
 pub struct InsertQueryPlan {
     source: Box<dyn Flow<Output = (Vec<ScalarValue>, Vec<Option<Box<dyn ToDatum>>>)>>,
+    checks: CheckConstraints,
     table: Table,
 }
 
 impl InsertQueryPlan {
     pub fn new(
         source: Box<dyn Flow<Output = (Vec<ScalarValue>, Vec<Option<Box<dyn ToDatum>>>)>>,
+        checks: CheckConstraints,
         table: Table,
     ) -> InsertQueryPlan {
-        InsertQueryPlan { source, table }
+        InsertQueryPlan { source, checks, table }
     }
 
     pub fn execute(mut self, param_values: Vec<ScalarValue>) -> Result<usize, QueryExecutionError> {
-        let mut len = 0;
+        let mut rows = vec![];
         while let Some((_, data)) = self.source.next_tuple(&param_values)? {
-            self.table.write(Binary::pack(
-                &data
-                    .into_iter()
-                    .map(|v| v.map(|v| v.convert()).unwrap_or_else(Datum::from_null))
-                    .collect::<Vec<Datum>>()
-                    .as_slice(),
-            ));
-            len += 1;
+            let row = data
+                .into_iter()
+                .map(|v| v.map(|v| v.convert()).unwrap_or_else(Datum::from_null))
+                .collect::<Vec<Datum>>();
+            self.checks.validate(&row)?;
+            rows.push(Binary::pack(&row));
         }
+        let len = rows.len();
+        self.table.write_batch(rows).map_err(write_error)?;
         Ok(len)
     }
 }
@@ -290,35 +373,6 @@ impl Flow for Projection {
     type Output = (Vec<ScalarValue>, Vec<ScalarValue>);
 
     fn next_tuple(&mut self, param_values: &[ScalarValue]) -> Result<Option<Self::Output>, QueryExecutionError> {
-        fn mapper(datum: &Datum) -> ScalarValue {
-            match datum {
-                Datum::Null => ScalarValue::Null,
-                Datum::True => ScalarValue::Bool(true),
-                Datum::False => ScalarValue::Bool(false),
-                Datum::Int16(value) => ScalarValue::Num {
-                    value: BigDecimal::from(*value),
-                    type_family: SqlTypeFamily::SmallInt,
-                },
-                Datum::Int32(value) => ScalarValue::Num {
-                    value: BigDecimal::from(*value),
-                    type_family: SqlTypeFamily::Integer,
-                },
-                Datum::Int64(value) => ScalarValue::Num {
-                    value: BigDecimal::from(*value),
-                    type_family: SqlTypeFamily::BigInt,
-                },
-                Datum::Float32(value) => ScalarValue::Num {
-                    value: BigDecimal::from_f32(**value).unwrap(),
-                    type_family: SqlTypeFamily::Real,
-                },
-                Datum::Float64(value) => ScalarValue::Num {
-                    value: BigDecimal::from_f64(**value).unwrap(),
-                    type_family: SqlTypeFamily::Double,
-                },
-                Datum::String(value) => ScalarValue::String(value.clone()),
-            }
-        }
-
         if let Some(row) = self.source.next_tuple(&param_values)? {
             let key = row.0.unpack();
             let value = row.1.unpack();
@@ -352,6 +406,135 @@ impl Flow for FullTableScan {
     }
 }
 
+/// Rows that are computed before the query is executed, e.g. rows of system views
+pub struct ViewScan {
+    source: Cursor,
+}
+
+impl ViewScan {
+    pub fn new(rows: Vec<Vec<Datum>>) -> Box<ViewScan> {
+        Box::new(ViewScan {
+            source: Cursor::new(
+                rows.into_iter()
+                    .enumerate()
+                    .map(|(index, row)| (Binary::pack(&[Datum::from_u64(index as u64)]), Binary::pack(&row))),
+            ),
+        })
+    }
+}
+
+impl Flow for ViewScan {
+    type Output = (Binary, Binary);
+
+    fn next_tuple(&mut self, _param_values: &[ScalarValue]) -> Result<Option<Self::Output>, QueryExecutionError> {
+        Ok(self.source.next())
+    }
+}
+
+/// Reads only values of `columns` from the table, other values of a row are NULLs.
+/// Rows are as long as the last of `columns`
+pub struct ColumnScan {
+    source: Cursor,
+    columns: Vec<usize>,
+}
+
+impl ColumnScan {
+    pub fn new(source: &Table, mut columns: Vec<usize>) -> Box<ColumnScan> {
+        columns.sort_unstable();
+        columns.dedup();
+        Box::new(ColumnScan {
+            source: source.scan_columns(&columns),
+            columns,
+        })
+    }
+}
+
+impl Flow for ColumnScan {
+    type Output = (Binary, Binary);
+
+    fn next_tuple(&mut self, _param_values: &[ScalarValue]) -> Result<Option<Self::Output>, QueryExecutionError> {
+        let record = self.source.next().map(|(key, values)| {
+            let width = self.columns.last().map(|column| column + 1).unwrap_or_default();
+            let mut row = vec![Datum::from_null(); width];
+            for (column, value) in self.columns.iter().zip(values.unpack()) {
+                row[*column] = value;
+            }
+            (key, Binary::pack(&row))
+        });
+        log::debug!("TABLE COLUMNS {:?}", record);
+        Ok(record)
+    }
+}
+
+/// Rows that have one of `values` in the first column of the index
+pub struct IndexScan {
+    source: Cursor,
+}
+
+impl IndexScan {
+    /// `None` if the table does not have such index
+    pub fn new(source: &Table, index: &str, values: Vec<Datum>) -> Option<Box<IndexScan>> {
+        let cursors = values
+            .into_iter()
+            .map(|value| source.scan_index(index, &[value]))
+            .collect::<Option<Vec<Cursor>>>()?;
+        Some(Box::new(IndexScan {
+            source: Cursor::new(cursors.into_iter().flatten()),
+        }))
+    }
+}
+
+impl Flow for IndexScan {
+    type Output = (Binary, Binary);
+
+    fn next_tuple(&mut self, _param_values: &[ScalarValue]) -> Result<Option<Self::Output>, QueryExecutionError> {
+        let record = self.source.next();
+        log::debug!("INDEX RECORD {:?}", record);
+        Ok(record)
+    }
+}
+
+/// Rows of the source that are also kept one at a time for the plan which changes them.
+/// Flows over the scan pull one row after another, so the last read row is the one
+/// that values they return are computed from
+pub struct SharedScan {
+    source: Box<dyn Flow<Output = (Binary, Binary)>>,
+    last: LastRow,
+}
+
+impl SharedScan {
+    pub fn new(source: Box<dyn Flow<Output = (Binary, Binary)>>) -> (Box<SharedScan>, LastRow) {
+        let last = LastRow::default();
+        (
+            Box::new(SharedScan {
+                source,
+                last: last.clone(),
+            }),
+            last,
+        )
+    }
+}
+
+impl Flow for SharedScan {
+    type Output = (Binary, Binary);
+
+    fn next_tuple(&mut self, param_values: &[ScalarValue]) -> Result<Option<Self::Output>, QueryExecutionError> {
+        let record = self.source.next_tuple(param_values)?;
+        *self.last.0.borrow_mut() = record.clone();
+        Ok(record)
+    }
+}
+
+/// the row that `SharedScan` read last
+#[derive(Default, Clone)]
+pub struct LastRow(Rc<RefCell<Option<(Binary, Binary)>>>);
+
+impl LastRow {
+    fn take(&self) -> Option<(Binary, Binary)> {
+        self.0.borrow_mut().take()
+    }
+}
+
 pub struct TableRecordKeys {
     source: Box<dyn Flow<Output = (Vec<ScalarValue>, Vec<ScalarValue>)>>,
 }
@@ -392,7 +575,7 @@ impl DeleteQueryPlan {
     pub fn execute(mut self, param_values: Vec<ScalarValue>) -> Result<usize, QueryExecutionError> {
         let mut len = 0;
         while let Some(key) = self.source.next_tuple(&param_values)? {
-            self.table.write_key(key, None);
+            self.table.write_key(key, None).map_err(write_error)?;
             len += 1;
         }
         Ok(len)
@@ -462,71 +645,45 @@ impl Flow for DynamicValues {
 
 pub struct UpdateQueryPlan {
     values: Box<dyn Flow<Output = (Vec<ScalarValue>, Vec<Option<Box<dyn ToDatum>>>)>>,
-    records: Box<dyn Flow<Output = (Binary, Binary)>>,
+    records: LastRow,
+    checks: CheckConstraints,
     table: Table,
 }
 
 impl UpdateQueryPlan {
+    /// `records` are rows of `SharedScan` that `values` are computed from
     pub fn new(
         values: Box<dyn Flow<Output = (Vec<ScalarValue>, Vec<Option<Box<dyn ToDatum>>>)>>,
-        records: Box<dyn Flow<Output = (Binary, Binary)>>,
+        records: LastRow,
+        checks: CheckConstraints,
         table: Table,
     ) -> UpdateQueryPlan {
-        UpdateQueryPlan { values, records, table }
+        UpdateQueryPlan {
+            values,
+            records,
+            checks,
+            table,
+        }
     }
 
     pub fn execute(mut self, param_values: Vec<ScalarValue>) -> Result<usize, QueryExecutionError> {
-        fn mapper(datum: &Datum) -> ScalarValue {
-            match datum {
-                Datum::Null => ScalarValue::Null,
-                Datum::True => ScalarValue::Bool(true),
-                Datum::False => ScalarValue::Bool(false),
-                Datum::Int16(value) => ScalarValue::Num {
-                    value: BigDecimal::from(*value),
-                    type_family: SqlTypeFamily::SmallInt,
-                },
-                Datum::Int32(value) => ScalarValue::Num {
-                    value: BigDecimal::from(*value),
-                    type_family: SqlTypeFamily::Integer,
-                },
-                Datum::Int64(value) => ScalarValue::Num {
-                    value: BigDecimal::from(*value),
-                    type_family: SqlTypeFamily::BigInt,
-                },
-                Datum::Float32(value) => ScalarValue::Num {
-                    value: BigDecimal::from_f32(**value).unwrap(),
-                    type_family: SqlTypeFamily::Real,
-                },
-                Datum::Float64(value) => ScalarValue::Num {
-                    value: BigDecimal::from_f64(**value).unwrap(),
-                    type_family: SqlTypeFamily::Double,
-                },
-                Datum::String(value) => ScalarValue::String(value.clone()),
-            }
-        }
-
-        let mut len = 0;
-        let mut values = HashMap::new();
-        while let Some((updated_key, value)) = self.values.next_tuple(&param_values)? {
-            values.insert(updated_key, value);
-        }
-        while let Some((key, row)) = self.records.next_tuple(&param_values)? {
+        let mut updated = vec![];
+        while let Some((_updated_key, value)) = self.values.next_tuple(&param_values)? {
+            let (key, row) = self.records.take().expect("values are computed from the last read row");
             let mut unpacked = row.unpack();
-            let unpacked_key = key.unpack();
-            let unpacked_key = unpacked_key.iter().map(mapper).collect::<Vec<ScalarValue>>();
-            if let Some(value) = values.remove(&unpacked_key) {
-                for (index, value) in value.iter().enumerate() {
-                    let new_value = match value {
-                        None => unpacked[index].clone(),
-                        Some(value) => value.convert(),
-                    };
-                    unpacked[index] = new_value;
-                }
-                let new_row = Binary::pack(&unpacked);
-                self.table.write_key(key, Some(new_row));
-                len += 1;
+            for (index, value) in value.iter().enumerate() {
+                let new_value = match value {
+                    None => unpacked[index].clone(),
+                    Some(value) => value.convert(),
+                };
+                unpacked[index] = new_value;
             }
+            self.checks.validate(&unpacked)?;
+            updated.push((key, Binary::pack(&unpacked)));
         }
+        // rows are written together, so unique values can move from one row to another
+        let len = updated.len();
+        self.table.update_batch(updated).map_err(write_error)?;
         Ok(len)
     }
 }
@@ -551,31 +708,50 @@ impl SelectQueryPlan {
     }
 
     pub fn execute(
-        mut self,
+        self,
         param_values: Vec<ScalarValue>,
-    ) -> Result<(Vec<ColumnDef>, Vec<Vec<ScalarValue>>), QueryExecutionError> {
+    ) -> Result<(Vec<ColumnDef>, SelectedRows), QueryExecutionError> {
         log::debug!("COLUMNS TO SELECT {:?}", self.columns);
-        let mut column_defs = vec![];
         let columns = self
             .column_types
             .iter()
             .enumerate()
             .map(|(index, (name, sql_type))| (name.clone(), ColumnDef::new(name.clone(), *sql_type, index)))
             .collect::<HashMap<String, ColumnDef>>();
-        for name in self.columns.iter() {
-            column_defs.push(columns.get(name).unwrap().clone());
-        }
+        let column_defs = self
+            .columns
+            .iter()
+            .map(|name| columns.get(name).unwrap().clone())
+            .collect::<Vec<ColumnDef>>();
         log::debug!("COLUMNS METADATA {:?}", column_defs);
-        let mut set = vec![];
-        while let Some((_key, value)) = self.source.next_tuple(&param_values)? {
-            let mut data = vec![];
-            for name in self.columns.iter() {
-                let index = columns.get(name).unwrap().index();
-                let value = value[index].clone();
-                data.push(value);
-            }
-            set.push(data);
+        let indexes = column_defs.iter().map(|column| column.index()).collect();
+        Ok((
+            column_defs,
+            SelectedRows {
+                source: self.source,
+                indexes,
+                param_values,
+            },
+        ))
+    }
+}
+
+/// Rows of `SELECT` that are read one at a time, each of them has values of selected columns
+pub struct SelectedRows {
+    source: Box<dyn Flow<Output = (Vec<ScalarValue>, Vec<ScalarValue>)>>,
+    /// positions of selected columns in rows of the table
+    indexes: Vec<usize>,
+    param_values: Vec<ScalarValue>,
+}
+
+impl Iterator for SelectedRows {
+    type Item = Result<Vec<ScalarValue>, QueryExecutionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.source.next_tuple(&self.param_values) {
+            Ok(None) => None,
+            Ok(Some((_key, value))) => Some(Ok(self.indexes.iter().map(|index| value[*index].clone()).collect())),
+            Err(error) => Some(Err(error)),
         }
-        Ok((column_defs, set))
     }
 }
@@ -36,6 +36,19 @@ pub enum QueryExecutionError {
     MostSpecificTypeMismatch(String, String, String, usize),
     CannotCoerce(String, String),
     NumberOutOfRange(String, String, usize),
+    UniqueViolation(String),
+    SequenceDoesNotExist(String),
+    SequenceLimitExceeded(String, bool, i64),
+    SequenceValueOutOfBounds(String, i64, i64, i64),
+    SequenceValueNotDefined(String),
+    /// column and table
+    NotNullViolation(String, String),
+    /// column and table
+    ColumnContainsNulls(String, String),
+    /// table and constraint
+    CheckViolation(String, String),
+    /// constraint and table
+    CheckViolatedByRow(String, String),
 }
 
 impl QueryExecutionError {
@@ -88,6 +101,50 @@ impl QueryExecutionError {
     pub fn out_of_range<T: ToString, S: ToString>(pg_type: T, column_name: S, row_index: usize) -> QueryExecutionError {
         QueryExecutionError::NumberOutOfRange(pg_type.to_string(), column_name.to_string(), row_index)
     }
+
+    pub fn unique_violation<C: ToString>(constraint: C) -> QueryExecutionError {
+        QueryExecutionError::UniqueViolation(constraint.to_string())
+    }
+
+    pub fn sequence_does_not_exist<S: ToString>(sequence: S) -> QueryExecutionError {
+        QueryExecutionError::SequenceDoesNotExist(sequence.to_string())
+    }
+
+    /// `maximum` is `false` when a descending sequence reached its minimum value
+    pub fn sequence_limit_exceeded<S: ToString>(sequence: S, maximum: bool, value: i64) -> QueryExecutionError {
+        QueryExecutionError::SequenceLimitExceeded(sequence.to_string(), maximum, value)
+    }
+
+    pub fn sequence_value_out_of_bounds<S: ToString>(
+        sequence: S,
+        value: i64,
+        min_value: i64,
+        max_value: i64,
+    ) -> QueryExecutionError {
+        QueryExecutionError::SequenceValueOutOfBounds(sequence.to_string(), value, min_value, max_value)
+    }
+
+    pub fn sequence_value_not_defined<S: ToString>(sequence: S) -> QueryExecutionError {
+        QueryExecutionError::SequenceValueNotDefined(sequence.to_string())
+    }
+
+    pub fn not_null_violation<C: ToString, T: ToString>(column_name: C, table_name: T) -> QueryExecutionError {
+        QueryExecutionError::NotNullViolation(column_name.to_string(), table_name.to_string())
+    }
+
+    /// existing rows have `NULL`s in the column that becomes `NOT NULL`
+    pub fn column_contains_nulls<C: ToString, T: ToString>(column_name: C, table_name: T) -> QueryExecutionError {
+        QueryExecutionError::ColumnContainsNulls(column_name.to_string(), table_name.to_string())
+    }
+
+    pub fn check_violation<T: ToString, C: ToString>(table_name: T, constraint_name: C) -> QueryExecutionError {
+        QueryExecutionError::CheckViolation(table_name.to_string(), constraint_name.to_string())
+    }
+
+    /// existing rows do not satisfy `CHECK` constraint that is added to the table
+    pub fn check_violated_by_row<C: ToString, T: ToString>(constraint_name: C, table_name: T) -> QueryExecutionError {
+        QueryExecutionError::CheckViolatedByRow(constraint_name.to_string(), table_name.to_string())
+    }
 }
 
 impl From<QueryExecutionError> for query_response::QueryError {
@@ -115,6 +172,21 @@ impl From<QueryExecutionError> for query_response::QueryError {
             QueryExecutionError::NumberOutOfRange(sql_type, column, index) => {
                 QueryError::out_of_range_2(sql_type, column, index)
             }
+            QueryExecutionError::UniqueViolation(constraint) => QueryError::unique_violation(constraint),
+            QueryExecutionError::SequenceDoesNotExist(sequence) => QueryError::sequence_does_not_exist(sequence),
+            QueryExecutionError::SequenceLimitExceeded(sequence, maximum, value) => {
+                QueryError::sequence_limit_exceeded(sequence, maximum, value)
+            }
+            QueryExecutionError::SequenceValueOutOfBounds(sequence, value, min_value, max_value) => {
+                QueryError::sequence_value_out_of_bounds(sequence, value, min_value, max_value)
+            }
+            QueryExecutionError::SequenceValueNotDefined(sequence) => QueryError::sequence_value_not_defined(sequence),
+            QueryExecutionError::NotNullViolation(column, table) => QueryError::not_null_violation(column, table),
+            QueryExecutionError::ColumnContainsNulls(column, table) => QueryError::column_contains_nulls(column, table),
+            QueryExecutionError::CheckViolation(table, constraint) => QueryError::check_violation(table, constraint),
+            QueryExecutionError::CheckViolatedByRow(constraint, table) => {
+                QueryError::check_violated_by_row(constraint, table)
+            }
         }
     }
 }
@@ -12,13 +12,40 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use bigdecimal::BigDecimal;
-use data_manipulation_operators::{BiOperator, UnOperator};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use data_manipulation_operators::{BiOperator, SequenceFunction, UnOperator};
 use data_manipulation_query_result::QueryExecutionError;
+use definition::FullTableName;
 use scalar::ScalarValue;
 use std::fmt::{self, Display, Formatter};
 use types::SqlTypeFamily;
 
+/// Sequences that `nextval`, `currval` and `setval` calls read and change when they are evaluated
+pub trait SequenceCalls {
+    fn next_value(&self, sequence: &FullTableName) -> Result<i64, QueryExecutionError>;
+
+    fn current_value(&self, sequence: &FullTableName) -> Result<i64, QueryExecutionError>;
+
+    fn set_value(&self, sequence: &FullTableName, value: i64, is_called: bool) -> Result<i64, QueryExecutionError>;
+}
+
+/// trees that are evaluated without access to sequences can't call them
+struct NoSequences;
+
+impl SequenceCalls for NoSequences {
+    fn next_value(&self, sequence: &FullTableName) -> Result<i64, QueryExecutionError> {
+        Err(QueryExecutionError::sequence_does_not_exist(sequence))
+    }
+
+    fn current_value(&self, sequence: &FullTableName) -> Result<i64, QueryExecutionError> {
+        Err(QueryExecutionError::sequence_does_not_exist(sequence))
+    }
+
+    fn set_value(&self, sequence: &FullTableName, _value: i64, _is_called: bool) -> Result<i64, QueryExecutionError> {
+        Err(QueryExecutionError::sequence_does_not_exist(sequence))
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum StaticTypedTree {
     Item(StaticTypedItem),
@@ -32,6 +59,12 @@ pub enum StaticTypedTree {
         op: UnOperator,
         item: Box<StaticTypedTree>,
     },
+    /// call of a sequence function, `value` is the argument of `setval`
+    Sequence {
+        function: SequenceFunction,
+        sequence: FullTableName,
+        value: Option<Box<StaticTypedTree>>,
+    },
 }
 
 impl StaticTypedTree {
@@ -40,17 +73,60 @@ impl StaticTypedTree {
             StaticTypedTree::Item(item) => item.type_family(),
             StaticTypedTree::BiOp { type_family, .. } => Some(*type_family),
             StaticTypedTree::UnOp { item, .. } => item.type_family(),
+            StaticTypedTree::Sequence { .. } => Some(SqlTypeFamily::BigInt),
         }
     }
 
     pub fn eval(self, param_values: &[ScalarValue]) -> Result<ScalarValue, QueryExecutionError> {
+        self.eval_with(param_values, &NoSequences)
+    }
+
+    pub fn eval_with(
+        self,
+        param_values: &[ScalarValue],
+        sequences: &dyn SequenceCalls,
+    ) -> Result<ScalarValue, QueryExecutionError> {
         match self {
             StaticTypedTree::Item(StaticTypedItem::Const(value)) => Ok(value.eval()),
             StaticTypedTree::Item(StaticTypedItem::Null(_)) => Ok(ScalarValue::Null),
             StaticTypedTree::Item(StaticTypedItem::Param { index, .. }) => Ok(param_values[index].clone()),
-            StaticTypedTree::UnOp { op, item } => op.eval(item.eval(param_values)?),
-            StaticTypedTree::BiOp { left, op, right, .. } => {
-                op.eval(left.eval(param_values)?, right.eval(param_values)?)
+            StaticTypedTree::UnOp { op, item } => op.eval(item.eval_with(param_values, sequences)?),
+            StaticTypedTree::BiOp { left, op, right, .. } => op.eval(
+                left.eval_with(param_values, sequences)?,
+                right.eval_with(param_values, sequences)?,
+            ),
+            StaticTypedTree::Sequence {
+                function,
+                sequence,
+                value,
+            } => {
+                let result = match function {
+                    SequenceFunction::NextVal => sequences.next_value(&sequence)?,
+                    SequenceFunction::CurrVal => sequences.current_value(&sequence)?,
+                    SequenceFunction::SetVal { is_called } => {
+                        match value
+                            .map(|value| value.eval_with(param_values, sequences))
+                            .transpose()?
+                        {
+                            // `setval` is strict, NULL argument makes it NULL
+                            None | Some(ScalarValue::Null) => return Ok(ScalarValue::Null),
+                            Some(ScalarValue::Num { value, type_family }) => match value.to_i64() {
+                                Some(value) => sequences.set_value(&sequence, value, is_called)?,
+                                None => return Err(QueryExecutionError::undefined_function(function, type_family)),
+                            },
+                            Some(other) => {
+                                return Err(QueryExecutionError::undefined_function(
+                                    function,
+                                    other.type_family().unwrap(),
+                                ))
+                            }
+                        }
+                    }
+                };
+                Ok(ScalarValue::Num {
+                    value: BigDecimal::from(result),
+                    type_family: SqlTypeFamily::BigInt,
+                })
             }
         }
     }
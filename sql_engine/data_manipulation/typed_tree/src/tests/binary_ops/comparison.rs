@@ -218,3 +218,20 @@ fn others(operator: BiOperator) {
         ))
     );
 }
+
+#[test]
+fn number_and_null() {
+    assert_eq!(
+        StaticTypedTree::BiOp {
+            type_family: SqlTypeFamily::SmallInt,
+            left: Box::new(StaticTypedTree::Item(StaticTypedItem::Const(TypedValue::Num {
+                value: BigDecimal::from(10),
+                type_family: SqlTypeFamily::SmallInt
+            }))),
+            op: BiOperator::Comparison(Comparison::Gt),
+            right: Box::new(StaticTypedTree::Item(StaticTypedItem::Null(None))),
+        }
+        .eval(&[]),
+        Ok(ScalarValue::Null)
+    );
+}
@@ -166,3 +166,25 @@ fn others(operator: BiOperator) {
         ))
     );
 }
+
+#[rstest::rstest(
+    operator,
+    left,
+    result,
+    case::false_and_null(BiOperator::Logical(BiLogical::And), false, ScalarValue::Bool(false)),
+    case::true_and_null(BiOperator::Logical(BiLogical::And), true, ScalarValue::Null),
+    case::true_or_null(BiOperator::Logical(BiLogical::Or), true, ScalarValue::Bool(true)),
+    case::false_or_null(BiOperator::Logical(BiLogical::Or), false, ScalarValue::Null)
+)]
+fn boolean_and_null(operator: BiOperator, left: bool, result: ScalarValue) {
+    assert_eq!(
+        StaticTypedTree::BiOp {
+            type_family: SqlTypeFamily::Bool,
+            left: Box::new(StaticTypedTree::Item(StaticTypedItem::Const(TypedValue::Bool(left)))),
+            op: operator,
+            right: Box::new(StaticTypedTree::Item(StaticTypedItem::Null(None))),
+        }
+        .eval(&[]),
+        Ok(result)
+    );
+}
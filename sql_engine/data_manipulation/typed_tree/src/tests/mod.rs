@@ -20,4 +20,6 @@ mod binary_ops;
 #[cfg(test)]
 mod constants;
 #[cfg(test)]
+mod sequences;
+#[cfg(test)]
 mod unary_op;
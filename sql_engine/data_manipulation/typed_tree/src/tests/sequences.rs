@@ -0,0 +1,103 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use std::cell::Cell;
+
+struct Counter(Cell<i64>);
+
+impl SequenceCalls for Counter {
+    fn next_value(&self, _sequence: &FullTableName) -> Result<i64, QueryExecutionError> {
+        self.0.set(self.0.get() + 1);
+        Ok(self.0.get())
+    }
+
+    fn current_value(&self, _sequence: &FullTableName) -> Result<i64, QueryExecutionError> {
+        Ok(self.0.get())
+    }
+
+    fn set_value(&self, _sequence: &FullTableName, value: i64, _is_called: bool) -> Result<i64, QueryExecutionError> {
+        self.0.set(value);
+        Ok(value)
+    }
+}
+
+fn sequence_call(function: SequenceFunction, value: Option<StaticTypedTree>) -> StaticTypedTree {
+    StaticTypedTree::Sequence {
+        function,
+        sequence: FullTableName::from((&"schema_name", &"sequence_name")),
+        value: value.map(Box::new),
+    }
+}
+
+fn big_int(value: i64) -> ScalarValue {
+    ScalarValue::Num {
+        value: BigDecimal::from(value),
+        type_family: SqlTypeFamily::BigInt,
+    }
+}
+
+#[test]
+fn next_value() {
+    let counter = Counter(Cell::new(0));
+    assert_eq!(
+        sequence_call(SequenceFunction::NextVal, None).eval_with(&[], &counter),
+        Ok(big_int(1))
+    );
+    assert_eq!(
+        sequence_call(SequenceFunction::CurrVal, None).eval_with(&[], &counter),
+        Ok(big_int(1))
+    );
+}
+
+#[test]
+fn set_value() {
+    let counter = Counter(Cell::new(0));
+    assert_eq!(
+        sequence_call(
+            SequenceFunction::SetVal { is_called: true },
+            Some(StaticTypedTree::Item(StaticTypedItem::Const(TypedValue::Num {
+                value: BigDecimal::from(10),
+                type_family: SqlTypeFamily::SmallInt,
+            })))
+        )
+        .eval_with(&[], &counter),
+        Ok(big_int(10))
+    );
+}
+
+#[test]
+fn set_null_value() {
+    let counter = Counter(Cell::new(5));
+    assert_eq!(
+        sequence_call(
+            SequenceFunction::SetVal { is_called: true },
+            Some(StaticTypedTree::Item(StaticTypedItem::Null(None)))
+        )
+        .eval_with(&[], &counter),
+        Ok(ScalarValue::Null)
+    );
+    assert_eq!(counter.0.get(), 5);
+}
+
+#[test]
+fn sequences_are_not_available_without_catalog() {
+    assert_eq!(
+        sequence_call(SequenceFunction::NextVal, None).eval(&[]),
+        Err(QueryExecutionError::sequence_does_not_exist(FullTableName::from((
+            &"schema_name",
+            &"sequence_name"
+        ))))
+    );
+}
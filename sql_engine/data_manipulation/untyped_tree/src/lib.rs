@@ -13,7 +13,8 @@
 // limitations under the License.
 
 use bigdecimal::BigDecimal;
-use data_manipulation_operators::{BiOperator, UnOperator};
+use data_manipulation_operators::{BiOperator, SequenceFunction, UnOperator};
+use definition::FullTableName;
 use std::{
     fmt,
     fmt::{Display, Formatter},
@@ -179,6 +180,12 @@ pub enum StaticUntypedTree {
         right: Box<StaticUntypedTree>,
     },
     Item(StaticUntypedItem),
+    /// call of a sequence function, `value` is the argument of `setval`
+    Sequence {
+        function: SequenceFunction,
+        sequence: FullTableName,
+        value: Option<Box<StaticUntypedTree>>,
+    },
 }
 
 impl StaticUntypedTree {
@@ -186,6 +193,7 @@ impl StaticUntypedTree {
         match self {
             StaticUntypedTree::UnOp { .. } => None,
             StaticUntypedTree::BiOp { .. } => None,
+            StaticUntypedTree::Sequence { .. } => Some(SqlTypeFamily::BigInt),
             StaticUntypedTree::Item(StaticUntypedItem::Const(value)) => value.kind(),
             StaticUntypedTree::Item(StaticUntypedItem::Param(_)) => None,
         }
@@ -14,13 +14,23 @@
 
 use catalog::CatalogHandler;
 use data_definition_execution_plan::{
-    ColumnInfo, CreateIndexQuery, CreateSchemaQuery, CreateTableQuery, DropSchemasQuery, DropTablesQuery, SchemaChange,
+    AlterTableAction, AlterTableQuery, AnalyzeQuery, CheckInfo, ColumnInfo, ConstraintInfo, CreateDatabaseQuery,
+    CreateIndexQuery, CreateSchemaQuery, CreateSequenceQuery, CreateTableQuery, DefaultInfo, DropDatabaseQuery,
+    DropIndexesQuery, DropSchemasQuery, DropSequencesQuery, DropTablesQuery, IdentityInfo, RenameSchemaQuery,
+    SchemaChange, SequenceOptions, TableStorage,
 };
-use definition::{FullTableName, SchemaName};
-use query_ast::{ColumnDef, Definition};
+use data_manipulation_operators::BiOperator;
+use data_manipulation_typed_tree::DynamicTypedTree;
+use definition::{ColumnIdentity, ConstraintKind, FullTableName, SchemaName};
+use query_analyzer::{analyze_check, analyze_default, analyze_expression, AnalysisError};
+use query_ast::{ColumnDef, Definition, Identity, TableConstraint};
+use query_processing_type_check::TypeChecker;
+use query_processing_type_coercion::TypeCoercion;
+use query_processing_type_inference::TypeInference;
 use query_response::QueryError;
-use storage::TransactionalDatabase;
-use types::SqlType;
+use std::str::FromStr;
+use storage::{Compression, TransactionalDatabase};
+use types::{SqlType, SqlTypeFamily};
 
 pub struct DefinitionPlanner<'p> {
     catalog: CatalogHandler<'p>,
@@ -41,23 +51,70 @@ impl<'p> DefinitionPlanner<'p> {
                 schema_name,
                 table_name,
                 columns,
+                constraints,
                 if_not_exists,
+                options,
             } => {
                 if !(self.catalog.schema_exists(&SchemaName::from(&schema_name))) {
                     Err(SchemaPlanError::schema_does_not_exist(&schema_name))
                 } else {
                     let full_table_name = FullTableName::from((&schema_name, &table_name));
-                    let column_defs = columns
-                        .into_iter()
-                        .map(|ColumnDef { name, data_type }| ColumnInfo {
-                            name,
-                            sql_type: SqlType::from(data_type),
-                        })
+                    let mut column_defs = vec![];
+                    for column in columns {
+                        column_defs.push(column_info(column)?);
+                    }
+                    let table_columns = column_defs
+                        .iter()
+                        .enumerate()
+                        .map(|(index, column)| definition::ColumnDef::new(column.name.clone(), column.sql_type, index))
                         .collect::<Vec<_>>();
+                    let mut table_constraints = vec![];
+                    for constraint in constraints {
+                        table_constraints.push(constraint_info(constraint, &table_columns)?);
+                    }
+                    // columns of primary key can't have nulls
+                    for constraint in table_constraints.iter() {
+                        if constraint.kind == ConstraintKind::PrimaryKey {
+                            for column in column_defs.iter_mut() {
+                                if constraint.column_names.contains(&column.name) {
+                                    column.not_null = true;
+                                }
+                            }
+                        }
+                    }
+                    let mut storage = TableStorage::default();
+                    let mut compressed = false;
+                    let mut threshold = None;
+                    for (name, value) in options {
+                        match (name.as_str(), value.as_str()) {
+                            ("storage", "row") => storage = TableStorage::Row,
+                            ("storage", "columnar") => storage = TableStorage::Columnar,
+                            ("storage", _) => return Err(SchemaPlanError::invalid_option_value(name, value)),
+                            ("compression", "lz4") => compressed = true,
+                            ("compression", "none") => compressed = false,
+                            ("compression", _) => return Err(SchemaPlanError::invalid_option_value(name, value)),
+                            ("compression_threshold", _) => match value.parse::<usize>() {
+                                Ok(bytes) => threshold = Some(bytes),
+                                Err(_) => return Err(SchemaPlanError::invalid_option_value(name, value)),
+                            },
+                            _ => return Err(SchemaPlanError::unrecognized_option(name)),
+                        }
+                    }
+                    let compression = match (compressed, threshold) {
+                        (false, _) => None,
+                        // columns are already compressed as a whole
+                        (true, _) if storage == TableStorage::Columnar => {
+                            return Err(SchemaPlanError::invalid_option_value("compression", "lz4"))
+                        }
+                        (true, threshold) => Some(threshold.unwrap_or_else(|| Compression::default().threshold)),
+                    };
                     Ok(SchemaChange::CreateTable(CreateTableQuery {
                         full_table_name,
                         column_defs,
                         if_not_exists,
+                        storage,
+                        compression,
+                        constraints: table_constraints,
                     }))
                 }
             }
@@ -73,6 +130,7 @@ impl<'p> DefinitionPlanner<'p> {
                 schema_name,
                 table_name,
                 column_names,
+                unique,
             } => {
                 let full_table_name = FullTableName::from((&schema_name, &table_name));
                 match self.catalog.table_definition(full_table_name.clone()) {
@@ -89,6 +147,7 @@ impl<'p> DefinitionPlanner<'p> {
                             name,
                             full_table_name,
                             column_names,
+                            unique,
                         }))
                     }
                 }
@@ -113,6 +172,72 @@ impl<'p> DefinitionPlanner<'p> {
                     if_exists,
                 }))
             }
+            Definition::Analyze { names } => {
+                let mut full_table_names = vec![];
+                for (schema_name, table_name) in names {
+                    let full_table_name = FullTableName::from((&schema_name, &table_name));
+                    match self.catalog.table_definition(full_table_name.clone()) {
+                        None => return Err(SchemaPlanError::schema_does_not_exist(&schema_name)),
+                        Some(None) => return Err(SchemaPlanError::table_does_not_exist(full_table_name)),
+                        Some(Some(_)) => full_table_names.push(full_table_name),
+                    }
+                }
+                Ok(SchemaChange::Analyze(AnalyzeQuery { full_table_names }))
+            }
+            Definition::CreateSequence {
+                schema_name,
+                sequence_name,
+                if_not_exists,
+                options,
+            } => {
+                if !(self.catalog.schema_exists(&SchemaName::from(&schema_name))) {
+                    Err(SchemaPlanError::schema_does_not_exist(&schema_name))
+                } else {
+                    Ok(SchemaChange::CreateSequence(CreateSequenceQuery {
+                        full_sequence_name: FullTableName::from((&schema_name, &sequence_name)),
+                        if_not_exists,
+                        options: sequence_options(None, options)?,
+                    }))
+                }
+            }
+            Definition::DropSequences {
+                names,
+                if_exists,
+                cascade,
+            } => {
+                let mut full_sequence_names = vec![];
+                for (schema_name, sequence_name) in names {
+                    if self.catalog.schema_exists(&SchemaName::from(&schema_name)) {
+                        full_sequence_names.push(FullTableName::from((&schema_name, &sequence_name)))
+                    } else {
+                        return Err(SchemaPlanError::schema_does_not_exist(&schema_name));
+                    }
+                }
+                Ok(SchemaChange::DropSequences(DropSequencesQuery {
+                    full_sequence_names,
+                    cascade,
+                    if_exists,
+                }))
+            }
+            Definition::DropIndexes {
+                names,
+                if_exists,
+                cascade,
+            } => {
+                let mut full_index_names = vec![];
+                for (schema_name, index_name) in names {
+                    if self.catalog.schema_exists(&SchemaName::from(&schema_name)) {
+                        full_index_names.push(FullTableName::from((&schema_name, &index_name)))
+                    } else {
+                        return Err(SchemaPlanError::schema_does_not_exist(&schema_name));
+                    }
+                }
+                Ok(SchemaChange::DropIndexes(DropIndexesQuery {
+                    full_index_names,
+                    cascade,
+                    if_exists,
+                }))
+            }
             Definition::DropSchemas {
                 names,
                 cascade,
@@ -125,8 +250,356 @@ impl<'p> DefinitionPlanner<'p> {
                     if_exists,
                 }))
             }
+            Definition::AlterTable {
+                schema_name,
+                table_name,
+                if_exists,
+                actions,
+            } => {
+                let full_table_name = FullTableName::from((&schema_name, &table_name));
+                let table_info = match self.catalog.table_definition(full_table_name.clone()) {
+                    None => return Err(SchemaPlanError::schema_does_not_exist(&schema_name)),
+                    Some(None) if if_exists => {
+                        return Ok(SchemaChange::AlterTable(AlterTableQuery {
+                            full_table_name,
+                            if_exists,
+                            actions: vec![],
+                        }))
+                    }
+                    Some(None) => return Err(SchemaPlanError::table_does_not_exist(full_table_name)),
+                    Some(Some(table_info)) => table_info,
+                };
+                // columns as they are after previous actions, `USING` expressions refer to them
+                let mut table_columns = table_info.columns().to_vec();
+                let mut planned = vec![];
+                for action in actions {
+                    planned.push(self.alter_table_action(&full_table_name, &mut table_columns, action)?);
+                }
+                Ok(SchemaChange::AlterTable(AlterTableQuery {
+                    full_table_name,
+                    if_exists,
+                    actions: planned,
+                }))
+            }
+            Definition::RenameSchema { schema_name, new_name } => Ok(SchemaChange::RenameSchema(RenameSchemaQuery {
+                schema_name: SchemaName::from(&schema_name),
+                new_name: SchemaName::from(&new_name),
+            })),
+            Definition::CreateDatabase { database_name } => {
+                Ok(SchemaChange::CreateDatabase(CreateDatabaseQuery { database_name }))
+            }
+            Definition::DropDatabase {
+                database_name,
+                if_exists,
+            } => Ok(SchemaChange::DropDatabase(DropDatabaseQuery {
+                database_name,
+                if_exists,
+            })),
+        }
+    }
+
+    fn alter_table_action(
+        &self,
+        full_table_name: &FullTableName,
+        table_columns: &mut Vec<definition::ColumnDef>,
+        action: query_ast::AlterTableAction,
+    ) -> Result<AlterTableAction, SchemaPlanError> {
+        let position = |name: &str| table_columns.iter().position(|column| column.has_name(name));
+        let action = match action {
+            query_ast::AlterTableAction::AddColumn { column, if_not_exists } => {
+                let column = column_info(column)?;
+                if position(&column.name).is_none() {
+                    let identity = column.identity.as_ref().map(|identity| {
+                        ColumnIdentity::new(
+                            FullTableName::from((
+                                &full_table_name.schema(),
+                                &format!("{}_{}_seq", full_table_name.table(), column.name),
+                            )),
+                            identity.always,
+                        )
+                    });
+                    table_columns.push(
+                        definition::ColumnDef::new(column.name.clone(), column.sql_type, table_columns.len())
+                            .with_identity(identity)
+                            .with_not_null(column.not_null)
+                            .with_default(column.default.as_ref().map(|default| default.text.clone())),
+                    );
+                }
+                AlterTableAction::AddColumn { column, if_not_exists }
+            }
+            query_ast::AlterTableAction::DropColumn {
+                name,
+                if_exists,
+                cascade,
+            } => {
+                if let Some(position) = position(&name) {
+                    table_columns.remove(position);
+                    renumber(table_columns);
+                }
+                AlterTableAction::DropColumn {
+                    name,
+                    if_exists,
+                    cascade,
+                }
+            }
+            query_ast::AlterTableAction::RenameColumn { old_name, new_name } => {
+                if let Some(position) = position(&old_name) {
+                    let column = &table_columns[position];
+                    table_columns[position] =
+                        definition::ColumnDef::new(new_name.clone(), column.sql_type(), column.index())
+                            .with_identity(column.identity().cloned())
+                            .with_not_null(column.not_null())
+                            .with_default(column.default().map(ToOwned::to_owned));
+                }
+                AlterTableAction::RenameColumn { old_name, new_name }
+            }
+            query_ast::AlterTableAction::AlterColumnType { name, data_type, using } => {
+                let position = match position(&name) {
+                    Some(position) => position,
+                    None => return Err(SchemaPlanError::column_not_found(&name)),
+                };
+                let sql_type = SqlType::from(data_type);
+                let column = &table_columns[position];
+                if column.identity().is_some() && !is_integer(sql_type) {
+                    return Err(SchemaPlanError::identity_column_type(&name));
+                }
+                let using = match using {
+                    None => None,
+                    Some(expr) => {
+                        let tree = analyze_expression(expr, table_columns)?;
+                        let tree = TypeInference::default().infer_dynamic(tree, &[]);
+                        let tree = TypeChecker.check_dynamic(tree);
+                        Some(TypeCoercion.coerce_dynamic(tree))
+                    }
+                };
+                table_columns[position] = definition::ColumnDef::new(name.clone(), sql_type, position)
+                    .with_identity(column.identity().cloned())
+                    .with_not_null(column.not_null())
+                    .with_default(column.default().map(ToOwned::to_owned));
+                // predicates of `CHECK` constraints over the column are analyzed again for its new type
+                let checks = self
+                    .catalog
+                    .table_constraints(full_table_name)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|constraint| constraint.column_names().contains(&name))
+                    .filter_map(|constraint| {
+                        let predicate = analyze_check(constraint.check()?, table_columns).map(|tree| {
+                            let tree = TypeInference::default().infer_dynamic(tree, &[]);
+                            let tree = TypeChecker.check_dynamic(tree);
+                            (constraint.name().to_owned(), TypeCoercion.coerce_dynamic(tree))
+                        });
+                        Some(predicate)
+                    })
+                    .collect::<Result<_, _>>()?;
+                AlterTableAction::AlterColumnType {
+                    name,
+                    sql_type,
+                    using,
+                    checks,
+                }
+            }
+            query_ast::AlterTableAction::RenameTable { new_name } => AlterTableAction::RenameTable { new_name },
+            query_ast::AlterTableAction::AddConstraint(constraint) => {
+                AlterTableAction::AddConstraint(constraint_info(constraint, table_columns)?)
+            }
+            query_ast::AlterTableAction::DropConstraint {
+                name,
+                if_exists,
+                cascade,
+            } => AlterTableAction::DropConstraint {
+                name,
+                if_exists,
+                cascade,
+            },
+        };
+        Ok(action)
+    }
+}
+
+/// column of `CREATE TABLE` or `ALTER TABLE ADD COLUMN`
+fn column_info(column: ColumnDef) -> Result<ColumnInfo, SchemaPlanError> {
+    let ColumnDef {
+        name,
+        data_type,
+        identity,
+        not_null,
+        default,
+    } = column;
+    let sql_type = SqlType::from(data_type);
+    let identity = match identity {
+        None => None,
+        Some(Identity { always, options }) if is_integer(sql_type) => Some(IdentityInfo {
+            always,
+            options: sequence_options(Some(sql_type), options)?,
+        }),
+        Some(_) => return Err(SchemaPlanError::identity_column_type(&name)),
+    };
+    let default = match default {
+        None => None,
+        Some(_) if identity.is_some() => return Err(SchemaPlanError::default_and_identity(&name)),
+        Some(expr) => {
+            let text = expr.to_string();
+            let tree = analyze_default(expr)?;
+            let tree = TypeInference::default().infer_static(tree, &[]);
+            let tree = TypeChecker.check_static(tree);
+            Some(DefaultInfo {
+                text,
+                value: TypeCoercion.coerce_static(tree, sql_type),
+            })
+        }
+    };
+    Ok(ColumnInfo {
+        name,
+        sql_type,
+        identity,
+        not_null,
+        default,
+    })
+}
+
+/// constraint of `CREATE TABLE` or `ALTER TABLE ADD CONSTRAINT` over the columns of the table
+fn constraint_info(
+    constraint: TableConstraint,
+    table_columns: &[definition::ColumnDef],
+) -> Result<ConstraintInfo, SchemaPlanError> {
+    let TableConstraint {
+        name,
+        kind,
+        column_names,
+    } = constraint;
+    let (kind, column_names, check) = match kind {
+        query_ast::ConstraintKind::PrimaryKey => (ConstraintKind::PrimaryKey, column_names, None),
+        query_ast::ConstraintKind::Unique => (ConstraintKind::Unique, column_names, None),
+        query_ast::ConstraintKind::Check(expr) => {
+            let text = expr.to_string();
+            let column_names = expr.column_names().into_iter().map(ToOwned::to_owned).collect();
+            let tree = analyze_expression(expr, table_columns)?;
+            let tree = TypeInference::default().infer_dynamic(tree, &[]);
+            let tree = TypeChecker.check_dynamic(tree);
+            let predicate = TypeCoercion.coerce_dynamic(tree);
+            match result_type(&predicate) {
+                None | Some(SqlTypeFamily::Bool) => {}
+                Some(other) => return Err(SchemaPlanError::check_is_not_boolean(other)),
+            }
+            (ConstraintKind::Check, column_names, Some(CheckInfo { text, predicate }))
+        }
+    };
+    if let Some(column) = column_names
+        .iter()
+        .find(|column| !table_columns.iter().any(|table_column| table_column.has_name(column)))
+    {
+        return Err(SchemaPlanError::column_not_found(column));
+    }
+    Ok(ConstraintInfo {
+        name,
+        kind,
+        column_names,
+        check,
+    })
+}
+
+/// type of values of the expression, operations have the type of their operands otherwise
+fn result_type(tree: &DynamicTypedTree) -> Option<SqlTypeFamily> {
+    match tree {
+        DynamicTypedTree::BiOp {
+            op: BiOperator::Comparison(_) | BiOperator::Logical(_) | BiOperator::Matching(_),
+            ..
+        } => Some(SqlTypeFamily::Bool),
+        DynamicTypedTree::BiOp {
+            op: BiOperator::StringOp(_),
+            ..
+        } => Some(SqlTypeFamily::String),
+        other => other.type_family(),
+    }
+}
+
+/// only integer columns can be generated by sequences
+fn is_integer(sql_type: SqlType) -> bool {
+    matches!(
+        sql_type.family(),
+        SqlTypeFamily::SmallInt | SqlTypeFamily::Integer | SqlTypeFamily::BigInt
+    )
+}
+
+/// ordinal numbers of columns after one of them is dropped
+fn renumber(table_columns: &mut [definition::ColumnDef]) {
+    for (index, column) in table_columns.iter_mut().enumerate() {
+        *column = definition::ColumnDef::new(column.name().to_owned(), column.sql_type(), index)
+            .with_identity(column.identity().cloned())
+            .with_not_null(column.not_null())
+            .with_default(column.default().map(ToOwned::to_owned));
+    }
+}
+
+/// sequence of `sql_type` values (or of `AS` option type) with options of `CREATE SEQUENCE` or of identity column.
+/// Options that are not given have the same defaults as in PostgreSQL
+fn sequence_options(
+    sql_type: Option<SqlType>,
+    options: Vec<(String, String)>,
+) -> Result<SequenceOptions, SchemaPlanError> {
+    fn integer(name: &str, value: &str) -> Result<i64, SchemaPlanError> {
+        i64::from_str(value).map_err(|_| SchemaPlanError::invalid_option_value(name, value))
+    }
+
+    let mut type_family = sql_type
+        .map(|sql_type| sql_type.family())
+        .unwrap_or(SqlTypeFamily::BigInt);
+    let mut start = None;
+    let mut increment = 1;
+    let mut min_value = None;
+    let mut max_value = None;
+    let mut cycle = false;
+    for (name, value) in options {
+        match name.as_str() {
+            "as" => {
+                type_family = match value.as_str() {
+                    "int2" => SqlTypeFamily::SmallInt,
+                    "int4" => SqlTypeFamily::Integer,
+                    "int8" => SqlTypeFamily::BigInt,
+                    _ => return Err(SchemaPlanError::invalid_option_value(name, value)),
+                }
+            }
+            "start" => start = Some(integer(&name, &value)?),
+            "increment" => match integer(&name, &value)? {
+                0 => return Err(SchemaPlanError::invalid_option_value(name, value)),
+                value => increment = value,
+            },
+            "minvalue" => min_value = Some(integer(&name, &value)?),
+            "maxvalue" => max_value = Some(integer(&name, &value)?),
+            "cycle" => cycle = value == "1" || value == "true",
+            // values are not cached by sessions
+            "cache" => {
+                integer(&name, &value)?;
+            }
+            _ => return Err(SchemaPlanError::unrecognized_option(name)),
         }
     }
+    let (type_min, type_max) = match type_family {
+        SqlTypeFamily::SmallInt => (i16::MIN as i64, i16::MAX as i64),
+        SqlTypeFamily::Integer => (i32::MIN as i64, i32::MAX as i64),
+        SqlTypeFamily::BigInt => (i64::MIN, i64::MAX),
+        _ => unreachable!("identity columns are integers"),
+    };
+    let min_value = min_value.unwrap_or(if increment > 0 { 1 } else { type_min });
+    let max_value = max_value.unwrap_or(if increment > 0 { type_max } else { -1 });
+    if min_value < type_min || min_value >= max_value {
+        return Err(SchemaPlanError::invalid_option_value("minvalue", min_value));
+    }
+    if max_value > type_max {
+        return Err(SchemaPlanError::invalid_option_value("maxvalue", max_value));
+    }
+    let start = start.unwrap_or(if increment > 0 { min_value } else { max_value });
+    if start < min_value || start > max_value {
+        return Err(SchemaPlanError::invalid_option_value("start", start));
+    }
+    Ok(SequenceOptions {
+        start,
+        increment,
+        min_value,
+        max_value,
+        cycle,
+    })
 }
 
 #[derive(Debug, PartialEq)]
@@ -134,6 +607,13 @@ pub enum SchemaPlanError {
     SchemaDoesNotExist(String),
     TableDoesNotExist(String),
     ColumnNotFound(String),
+    UnrecognizedOption(String),
+    InvalidOptionValue(String, String),
+    IdentityColumnType(String),
+    DefaultAndIdentity(String),
+    /// type of `CHECK` expression
+    CheckIsNotBoolean(String),
+    Analysis(AnalysisError),
 }
 
 impl SchemaPlanError {
@@ -148,6 +628,35 @@ impl SchemaPlanError {
     pub fn column_not_found<C: ToString>(column_name: C) -> SchemaPlanError {
         SchemaPlanError::ColumnNotFound(column_name.to_string())
     }
+
+    pub fn unrecognized_option<O: ToString>(option: O) -> SchemaPlanError {
+        SchemaPlanError::UnrecognizedOption(option.to_string())
+    }
+
+    pub fn invalid_option_value<O: ToString, V: ToString>(option: O, value: V) -> SchemaPlanError {
+        SchemaPlanError::InvalidOptionValue(option.to_string(), value.to_string())
+    }
+
+    /// only integer columns can be generated by sequences
+    pub fn identity_column_type<C: ToString>(column_name: C) -> SchemaPlanError {
+        SchemaPlanError::IdentityColumnType(column_name.to_string())
+    }
+
+    /// values of identity columns are generated only by their sequences
+    pub fn default_and_identity<C: ToString>(column_name: C) -> SchemaPlanError {
+        SchemaPlanError::DefaultAndIdentity(column_name.to_string())
+    }
+
+    /// rows satisfy `CHECK` constraint if its expression is `true`
+    pub fn check_is_not_boolean<T: ToString>(type_name: T) -> SchemaPlanError {
+        SchemaPlanError::CheckIsNotBoolean(type_name.to_string())
+    }
+}
+
+impl From<AnalysisError> for SchemaPlanError {
+    fn from(error: AnalysisError) -> SchemaPlanError {
+        SchemaPlanError::Analysis(error)
+    }
 }
 
 impl From<SchemaPlanError> for QueryError {
@@ -156,6 +665,23 @@ impl From<SchemaPlanError> for QueryError {
             SchemaPlanError::SchemaDoesNotExist(schema) => QueryError::schema_does_not_exist(schema),
             SchemaPlanError::TableDoesNotExist(table) => QueryError::table_does_not_exist(table),
             SchemaPlanError::ColumnNotFound(column) => QueryError::column_does_not_exist(column),
+            SchemaPlanError::UnrecognizedOption(option) => {
+                QueryError::invalid_parameter_value(format!("unrecognized parameter \"{}\"", option))
+            }
+            SchemaPlanError::InvalidOptionValue(option, value) => {
+                QueryError::invalid_parameter_value(format!("invalid value for option \"{}\": \"{}\"", option, value))
+            }
+            SchemaPlanError::IdentityColumnType(column) => QueryError::invalid_parameter_value(format!(
+                "identity column type must be smallint, integer, or bigint (column \"{}\")",
+                column
+            )),
+            SchemaPlanError::DefaultAndIdentity(column) => {
+                QueryError::syntax_error(format!("both default and identity specified for column \"{}\"", column))
+            }
+            SchemaPlanError::CheckIsNotBoolean(type_name) => {
+                QueryError::datatype_mismatch("CHECK".to_owned(), "boolean".to_owned(), type_name)
+            }
+            SchemaPlanError::Analysis(error) => error.into(),
         }
     }
 }
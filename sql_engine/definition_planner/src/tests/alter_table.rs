@@ -0,0 +1,361 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use data_definition_execution_plan::{AlterTableQuery, CheckInfo, ConstraintInfo, RenameSchemaQuery};
+use data_manipulation_operators::{BiOperator, Comparison};
+use data_manipulation_typed_tree::{DynamicTypedItem, DynamicTypedTree};
+use definition::ConstraintKind;
+use query_ast::{BinaryOperator, DataType, Expr, TableConstraint, Value};
+
+const DEFAULT_SCHEMA: &str = "public";
+
+fn alter_table(schema_name: &str, table_name: &str, actions: Vec<query_ast::AlterTableAction>) -> Definition {
+    Definition::AlterTable {
+        schema_name: schema_name.to_owned(),
+        table_name: table_name.to_owned(),
+        if_exists: false,
+        actions,
+    }
+}
+
+fn with_table(db: &TransactionalDatabase) {
+    CatalogHandler::from(db.clone())
+        .apply(create_table_ops(
+            DEFAULT_SCHEMA,
+            TABLE,
+            vec![("col_1", SqlType::integer()), ("col_2", SqlType::integer())],
+        ))
+        .unwrap();
+}
+
+fn alter_column_type(name: &str, data_type: DataType, using: Option<Expr>) -> query_ast::AlterTableAction {
+    query_ast::AlterTableAction::AlterColumnType {
+        name: name.to_owned(),
+        data_type,
+        using,
+    }
+}
+
+#[test]
+fn alter_table_in_not_existent_schema() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(alter_table("non_existent", TABLE, vec![])),
+            Err(SchemaPlanError::schema_does_not_exist("non_existent"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn alter_not_existent_table() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(alter_table(
+                DEFAULT_SCHEMA,
+                "non_existent",
+                vec![alter_column_type("col_1", DataType::Int, None)]
+            )),
+            Err(SchemaPlanError::table_does_not_exist(format!(
+                "{}.{}",
+                DEFAULT_SCHEMA, "non_existent"
+            )))
+        );
+        assert_eq!(
+            planner.plan(Definition::AlterTable {
+                schema_name: DEFAULT_SCHEMA.to_owned(),
+                table_name: "non_existent".to_owned(),
+                if_exists: true,
+                actions: vec![alter_column_type("col_1", DataType::Int, None)],
+            }),
+            Ok(SchemaChange::AlterTable(AlterTableQuery {
+                full_table_name: FullTableName::from((&DEFAULT_SCHEMA, &"non_existent")),
+                if_exists: true,
+                actions: vec![],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn add_column() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        with_table(&db);
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(alter_table(
+                DEFAULT_SCHEMA,
+                TABLE,
+                vec![query_ast::AlterTableAction::AddColumn {
+                    column: ColumnDef {
+                        name: "col_3".to_owned(),
+                        data_type: DataType::BigInt,
+                        identity: None,
+                        not_null: false,
+                        default: None,
+                    },
+                    if_not_exists: false,
+                }]
+            )),
+            Ok(SchemaChange::AlterTable(AlterTableQuery {
+                full_table_name: FullTableName::from((&DEFAULT_SCHEMA, &TABLE)),
+                if_exists: false,
+                actions: vec![AlterTableAction::AddColumn {
+                    column: ColumnInfo {
+                        name: "col_3".to_owned(),
+                        sql_type: SqlType::big_int(),
+                        identity: None,
+                        not_null: false,
+                        default: None,
+                    },
+                    if_not_exists: false,
+                }],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn alter_type_of_not_existent_column() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        with_table(&db);
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(alter_table(
+                DEFAULT_SCHEMA,
+                TABLE,
+                vec![alter_column_type("col_3", DataType::BigInt, None)]
+            )),
+            Err(SchemaPlanError::column_not_found("col_3"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn using_expression_refers_to_columns_after_previous_actions() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        with_table(&db);
+        let planner = DefinitionPlanner::from(db);
+        let plan = planner.plan(alter_table(
+            DEFAULT_SCHEMA,
+            TABLE,
+            vec![
+                query_ast::AlterTableAction::DropColumn {
+                    name: "col_1".to_owned(),
+                    if_exists: false,
+                    cascade: false,
+                },
+                query_ast::AlterTableAction::RenameColumn {
+                    old_name: "col_2".to_owned(),
+                    new_name: "id".to_owned(),
+                },
+                alter_column_type("id", DataType::BigInt, Some(Expr::Column("id".to_owned()))),
+            ],
+        ));
+        match plan {
+            Ok(SchemaChange::AlterTable(AlterTableQuery { actions, .. })) => assert_eq!(
+                actions[2],
+                AlterTableAction::AlterColumnType {
+                    name: "id".to_owned(),
+                    sql_type: SqlType::big_int(),
+                    using: Some(DynamicTypedTree::Item(DynamicTypedItem::Column {
+                        name: "id".to_owned(),
+                        sql_type: SqlTypeFamily::Integer,
+                        index: 0,
+                    })),
+                    checks: vec![],
+                }
+            ),
+            other => panic!("unexpected plan {:?}", other),
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn using_expression_with_not_existent_column() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        with_table(&db);
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(alter_table(
+                DEFAULT_SCHEMA,
+                TABLE,
+                vec![alter_column_type(
+                    "col_1",
+                    DataType::BigInt,
+                    Some(Expr::Column("col_3".to_owned()))
+                )]
+            )),
+            Err(SchemaPlanError::Analysis(AnalysisError::column_not_found("col_3")))
+        );
+        Ok(())
+    })
+}
+
+fn add_check(left: Expr, op: BinaryOperator, right: Expr) -> query_ast::AlterTableAction {
+    query_ast::AlterTableAction::AddConstraint(TableConstraint {
+        name: None,
+        kind: query_ast::ConstraintKind::Check(Expr::BinaryOp {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        }),
+        column_names: vec![],
+    })
+}
+
+#[test]
+fn add_check_constraint() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        with_table(&db);
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(alter_table(
+                DEFAULT_SCHEMA,
+                TABLE,
+                vec![add_check(
+                    Expr::Column("col_2".to_owned()),
+                    BinaryOperator::Gt,
+                    Expr::Column("col_1".to_owned())
+                )]
+            )),
+            Ok(SchemaChange::AlterTable(AlterTableQuery {
+                full_table_name: FullTableName::from((&DEFAULT_SCHEMA, &TABLE)),
+                if_exists: false,
+                actions: vec![AlterTableAction::AddConstraint(ConstraintInfo {
+                    name: None,
+                    kind: ConstraintKind::Check,
+                    column_names: vec!["col_2".to_owned(), "col_1".to_owned()],
+                    check: Some(CheckInfo {
+                        text: "(\"col_2\" > \"col_1\")".to_owned(),
+                        predicate: DynamicTypedTree::BiOp {
+                            type_family: SqlTypeFamily::Integer,
+                            left: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Column {
+                                name: "col_2".to_owned(),
+                                sql_type: SqlTypeFamily::Integer,
+                                index: 1,
+                            })),
+                            op: BiOperator::Comparison(Comparison::Gt),
+                            right: Box::new(DynamicTypedTree::Item(DynamicTypedItem::Column {
+                                name: "col_1".to_owned(),
+                                sql_type: SqlTypeFamily::Integer,
+                                index: 0,
+                            })),
+                        },
+                    }),
+                })],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn check_constraint_is_boolean() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        with_table(&db);
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(alter_table(
+                DEFAULT_SCHEMA,
+                TABLE,
+                vec![add_check(
+                    Expr::Column("col_1".to_owned()),
+                    BinaryOperator::Plus,
+                    Expr::Value(Value::Int(1))
+                )]
+            )),
+            Err(SchemaPlanError::check_is_not_boolean("integer"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn check_constraint_with_not_existent_column() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        with_table(&db);
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(alter_table(
+                DEFAULT_SCHEMA,
+                TABLE,
+                vec![add_check(
+                    Expr::Column("col_3".to_owned()),
+                    BinaryOperator::Gt,
+                    Expr::Value(Value::Int(0))
+                )]
+            )),
+            Err(SchemaPlanError::Analysis(AnalysisError::column_not_found("col_3")))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn identity_column_type_is_integer() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        with_table(&db);
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(alter_table(
+                DEFAULT_SCHEMA,
+                TABLE,
+                vec![
+                    query_ast::AlterTableAction::AddColumn {
+                        column: ColumnDef {
+                            name: "id".to_owned(),
+                            data_type: DataType::Int,
+                            identity: Some(Identity {
+                                always: true,
+                                options: vec![],
+                            }),
+                            not_null: false,
+                            default: None,
+                        },
+                        if_not_exists: false,
+                    },
+                    alter_column_type("id", DataType::VarChar(None), None)
+                ]
+            )),
+            Err(SchemaPlanError::identity_column_type("id"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn rename_schema() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(Definition::RenameSchema {
+                schema_name: SCHEMA.to_owned(),
+                new_name: "new_schema_name".to_owned(),
+            }),
+            Ok(SchemaChange::RenameSchema(RenameSchemaQuery {
+                schema_name: SchemaName::from(&SCHEMA),
+                new_name: SchemaName::from(&"new_schema_name"),
+            }))
+        );
+        Ok(())
+    })
+}
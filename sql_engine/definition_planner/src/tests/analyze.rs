@@ -0,0 +1,88 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use data_definition_execution_plan::AnalyzeQuery;
+
+fn analyze(names: Vec<(&str, &str)>) -> Definition {
+    Definition::Analyze {
+        names: names
+            .into_iter()
+            .map(|(schema, table)| (schema.to_owned(), table.to_owned()))
+            .collect(),
+    }
+}
+
+#[test]
+fn analyze_all_tables() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(analyze(vec![])),
+            Ok(SchemaChange::Analyze(AnalyzeQuery {
+                full_table_names: vec![]
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn analyze_table() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog
+            .apply(create_table_ops(SCHEMA, TABLE, vec![("col", SqlType::bool())]))
+            .unwrap();
+
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(analyze(vec![(SCHEMA, TABLE)])),
+            Ok(SchemaChange::Analyze(AnalyzeQuery {
+                full_table_names: vec![FullTableName::from((&SCHEMA, &TABLE))]
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn analyze_table_from_nonexistent_schema() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(analyze(vec![("non_existent_schema", TABLE)])),
+            Err(SchemaPlanError::schema_does_not_exist(&"non_existent_schema"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn analyze_nonexistent_table() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(analyze(vec![(SCHEMA, "non_existent")])),
+            Err(SchemaPlanError::table_does_not_exist(&format!(
+                "{}.{}",
+                SCHEMA, "non_existent"
+            )))
+        );
+        Ok(())
+    })
+}
@@ -0,0 +1,50 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use data_definition_execution_plan::{CreateDatabaseQuery, DropDatabaseQuery};
+
+#[test]
+fn create_database() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(Definition::CreateDatabase {
+                database_name: "database_name".to_owned()
+            }),
+            Ok(SchemaChange::CreateDatabase(CreateDatabaseQuery {
+                database_name: "database_name".to_owned()
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn drop_database() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(Definition::DropDatabase {
+                database_name: "database_name".to_owned(),
+                if_exists: true,
+            }),
+            Ok(SchemaChange::DropDatabase(DropDatabaseQuery {
+                database_name: "database_name".to_owned(),
+                if_exists: true,
+            }))
+        );
+        Ok(())
+    })
+}
@@ -23,6 +23,7 @@ fn create_index(index_name: &str, schema_name: &str, table_name: &str, columns:
         schema_name: schema_name.to_owned(),
         table_name: table_name.to_owned(),
         column_names: columns.into_iter().map(ToOwned::to_owned).collect(),
+        unique: false,
     }
 }
 
@@ -111,7 +112,8 @@ fn create_index_over_multiple_columns() -> TransactionResult<()> {
             Ok(SchemaChange::CreateIndex(CreateIndexQuery {
                 name: "index_name".to_owned(),
                 full_table_name: FullTableName::from((&DEFAULT_SCHEMA, &TABLE)),
-                column_names: vec!["col_1".to_owned(), "col_2".to_owned(), "col_3".to_owned()]
+                column_names: vec!["col_1".to_owned(), "col_2".to_owned(), "col_3".to_owned()],
+                unique: false,
             }))
         );
         Ok(())
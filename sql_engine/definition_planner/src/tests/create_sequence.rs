@@ -0,0 +1,171 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+const SEQUENCE: &str = "sequence_name";
+
+fn create_sequence(schema_name: &str, options: Vec<(&str, &str)>) -> Definition {
+    Definition::CreateSequence {
+        schema_name: schema_name.to_owned(),
+        sequence_name: SEQUENCE.to_owned(),
+        if_not_exists: false,
+        options: options
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect(),
+    }
+}
+
+fn drop_sequence_stmt(schema_name: &str) -> Definition {
+    Definition::DropSequences {
+        names: vec![(schema_name.to_owned(), SEQUENCE.to_owned())],
+        if_exists: false,
+        cascade: false,
+    }
+}
+
+#[test]
+fn create_sequence_in_nonexistent_schema() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_sequence("non_existent_schema", vec![])),
+            Err(SchemaPlanError::schema_does_not_exist("non_existent_schema"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_sequence_with_default_options() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_sequence(SCHEMA, vec![])),
+            Ok(SchemaChange::CreateSequence(CreateSequenceQuery {
+                full_sequence_name: FullTableName::from((&SCHEMA, &SEQUENCE)),
+                if_not_exists: false,
+                options: SequenceOptions::default(),
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_descending_sequence() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_sequence(
+                SCHEMA,
+                vec![
+                    ("as", "int4"),
+                    ("increment", "-2"),
+                    ("minvalue", "-100"),
+                    ("cycle", "1")
+                ]
+            )),
+            Ok(SchemaChange::CreateSequence(CreateSequenceQuery {
+                full_sequence_name: FullTableName::from((&SCHEMA, &SEQUENCE)),
+                if_not_exists: false,
+                options: SequenceOptions {
+                    start: -1,
+                    increment: -2,
+                    min_value: -100,
+                    max_value: -1,
+                    cycle: true,
+                },
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_sequence_with_zero_increment() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_sequence(SCHEMA, vec![("increment", "0")])),
+            Err(SchemaPlanError::invalid_option_value("increment", "0"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_sequence_with_start_out_of_bounds() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_sequence(SCHEMA, vec![("maxvalue", "10"), ("start", "11")])),
+            Err(SchemaPlanError::invalid_option_value("start", "11"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_sequence_with_unknown_option() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_sequence(SCHEMA, vec![("owned_by", "none")])),
+            Err(SchemaPlanError::unrecognized_option("owned_by"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn drop_sequence_from_nonexistent_schema() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(drop_sequence_stmt("non_existent_schema")),
+            Err(SchemaPlanError::schema_does_not_exist("non_existent_schema"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn drop_sequence() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(drop_sequence_stmt(SCHEMA)),
+            Ok(SchemaChange::DropSequences(DropSequencesQuery {
+                full_sequence_names: vec![FullTableName::from((&SCHEMA, &SEQUENCE))],
+                cascade: false,
+                if_exists: false,
+            }))
+        );
+        Ok(())
+    })
+}
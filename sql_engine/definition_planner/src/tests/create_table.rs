@@ -13,12 +13,18 @@
 // limitations under the License.
 
 use super::*;
-use query_ast::DataType;
+use data_definition_execution_plan::{ConstraintInfo, DefaultInfo};
+use data_manipulation_typed_tree::{StaticTypedItem, StaticTypedTree, TypedValue};
+use definition::ConstraintKind;
+use query_ast::{DataType, Expr, TableConstraint, Value};
 
 fn column(name: &str, data_type: DataType) -> ColumnDef {
     ColumnDef {
         name: name.to_owned(),
         data_type,
+        identity: None,
+        not_null: false,
+        default: None,
     }
 }
 
@@ -48,6 +54,9 @@ fn create_table_with_the_same_name() -> TransactionResult<()> {
                 full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
                 column_defs: vec![],
                 if_not_exists: false,
+                storage: TableStorage::Row,
+                compression: None,
+                constraints: vec![],
             }))
         );
         Ok(())
@@ -71,9 +80,15 @@ fn create_new_table_if_not_exist() -> TransactionResult<()> {
                 full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
                 column_defs: vec![ColumnInfo {
                     name: "column_name".to_owned(),
-                    sql_type: SqlType::small_int()
+                    sql_type: SqlType::small_int(),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 }],
                 if_not_exists: true,
+                storage: TableStorage::Row,
+                compression: None,
+                constraints: vec![],
             }))
         );
         Ok(())
@@ -96,11 +111,466 @@ fn successfully_create_table() -> TransactionResult<()> {
                 full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
                 column_defs: vec![ColumnInfo {
                     name: "column_name".to_owned(),
-                    sql_type: SqlType::small_int()
+                    sql_type: SqlType::small_int(),
+                    identity: None,
+                    not_null: false,
+                    default: None,
                 }],
                 if_not_exists: false,
+                storage: TableStorage::Row,
+                compression: None,
+                constraints: vec![],
             }))
         );
         Ok(())
     })
 }
+
+#[test]
+fn create_columnar_table() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table_with_options(
+                SCHEMA,
+                TABLE,
+                vec![column("column_name", DataType::SmallInt)],
+                vec![("storage", "columnar")]
+            )),
+            Ok(SchemaChange::CreateTable(CreateTableQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                column_defs: vec![ColumnInfo {
+                    name: "column_name".to_owned(),
+                    sql_type: SqlType::small_int(),
+                    identity: None,
+                    not_null: false,
+                    default: None,
+                }],
+                if_not_exists: false,
+                storage: TableStorage::Columnar,
+                compression: None,
+                constraints: vec![],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_unknown_storage() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table_with_options(
+                SCHEMA,
+                TABLE,
+                vec![],
+                vec![("storage", "heap")]
+            )),
+            Err(SchemaPlanError::invalid_option_value("storage", "heap"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_compressed_table() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table_with_options(
+                SCHEMA,
+                TABLE,
+                vec![],
+                vec![("compression", "lz4")]
+            )),
+            Ok(SchemaChange::CreateTable(CreateTableQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                column_defs: vec![],
+                if_not_exists: false,
+                storage: TableStorage::Row,
+                compression: Some(Compression::default().threshold),
+                constraints: vec![],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_compressed_table_with_threshold() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table_with_options(
+                SCHEMA,
+                TABLE,
+                vec![],
+                vec![("compression", "lz4"), ("compression_threshold", "512")]
+            )),
+            Ok(SchemaChange::CreateTable(CreateTableQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                column_defs: vec![],
+                if_not_exists: false,
+                storage: TableStorage::Row,
+                compression: Some(512),
+                constraints: vec![],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_without_compression() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table_with_options(
+                SCHEMA,
+                TABLE,
+                vec![],
+                vec![("compression", "none"), ("compression_threshold", "512")]
+            )),
+            Ok(SchemaChange::CreateTable(CreateTableQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                column_defs: vec![],
+                if_not_exists: false,
+                storage: TableStorage::Row,
+                compression: None,
+                constraints: vec![],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_unknown_compression() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table_with_options(
+                SCHEMA,
+                TABLE,
+                vec![],
+                vec![("compression", "zstd")]
+            )),
+            Err(SchemaPlanError::invalid_option_value("compression", "zstd"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_invalid_compression_threshold() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table_with_options(
+                SCHEMA,
+                TABLE,
+                vec![],
+                vec![("compression", "lz4"), ("compression_threshold", "-1")]
+            )),
+            Err(SchemaPlanError::invalid_option_value("compression_threshold", "-1"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_compressed_columnar_table() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table_with_options(
+                SCHEMA,
+                TABLE,
+                vec![],
+                vec![("storage", "columnar"), ("compression", "lz4")]
+            )),
+            Err(SchemaPlanError::invalid_option_value("compression", "lz4"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_unknown_option() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table_with_options(
+                SCHEMA,
+                TABLE,
+                vec![],
+                vec![("fillfactor", "70")]
+            )),
+            Err(SchemaPlanError::unrecognized_option("fillfactor"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_identity_column() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table(
+                SCHEMA,
+                TABLE,
+                vec![ColumnDef {
+                    name: "id".to_owned(),
+                    data_type: DataType::SmallInt,
+                    identity: Some(Identity {
+                        always: true,
+                        options: vec![("start".to_owned(), "10".to_owned())],
+                    }),
+                    not_null: false,
+                    default: None,
+                }]
+            )),
+            Ok(SchemaChange::CreateTable(CreateTableQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                column_defs: vec![ColumnInfo {
+                    name: "id".to_owned(),
+                    sql_type: SqlType::small_int(),
+                    identity: Some(IdentityInfo {
+                        always: true,
+                        options: SequenceOptions {
+                            start: 10,
+                            increment: 1,
+                            min_value: 1,
+                            max_value: i16::MAX as i64,
+                            cycle: false,
+                        }
+                    }),
+                    not_null: false,
+                    default: None,
+                }],
+                if_not_exists: false,
+                storage: TableStorage::Row,
+                compression: None,
+                constraints: vec![],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_identity_column_of_not_integer_type() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table(
+                SCHEMA,
+                TABLE,
+                vec![ColumnDef {
+                    name: "id".to_owned(),
+                    data_type: DataType::Bool,
+                    identity: Some(Identity {
+                        always: false,
+                        options: vec![],
+                    }),
+                    not_null: false,
+                    default: None,
+                }]
+            )),
+            Err(SchemaPlanError::identity_column_type("id"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_constraints() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(Definition::CreateTable {
+                schema_name: SCHEMA.to_owned(),
+                table_name: TABLE.to_owned(),
+                columns: vec![column("id", DataType::Int)],
+                if_not_exists: false,
+                constraints: vec![TableConstraint {
+                    name: Some("id_key".to_owned()),
+                    kind: query_ast::ConstraintKind::PrimaryKey,
+                    column_names: vec!["id".to_owned()],
+                }],
+                options: vec![],
+            }),
+            Ok(SchemaChange::CreateTable(CreateTableQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                column_defs: vec![ColumnInfo {
+                    name: "id".to_owned(),
+                    sql_type: SqlType::integer(),
+                    identity: None,
+                    not_null: true,
+                    default: None,
+                }],
+                if_not_exists: false,
+                storage: TableStorage::Row,
+                compression: None,
+                constraints: vec![ConstraintInfo {
+                    name: Some("id_key".to_owned()),
+                    kind: ConstraintKind::PrimaryKey,
+                    column_names: vec!["id".to_owned()],
+                    check: None,
+                }],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_constraint_on_not_existent_column() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(Definition::CreateTable {
+                schema_name: SCHEMA.to_owned(),
+                table_name: TABLE.to_owned(),
+                columns: vec![column("id", DataType::Int)],
+                if_not_exists: false,
+                constraints: vec![TableConstraint {
+                    name: None,
+                    kind: query_ast::ConstraintKind::Unique,
+                    column_names: vec!["id".to_owned(), "name".to_owned()],
+                }],
+                options: vec![],
+            }),
+            Err(SchemaPlanError::column_not_found("name"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_not_null_and_default_columns() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table(
+                SCHEMA,
+                TABLE,
+                vec![
+                    ColumnDef {
+                        not_null: true,
+                        ..column("id", DataType::Int)
+                    },
+                    ColumnDef {
+                        default: Some(Expr::Value(Value::String("none".to_owned()))),
+                        ..column("name", DataType::VarChar(Some(10)))
+                    }
+                ]
+            )),
+            Ok(SchemaChange::CreateTable(CreateTableQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                column_defs: vec![
+                    ColumnInfo {
+                        name: "id".to_owned(),
+                        sql_type: SqlType::integer(),
+                        identity: None,
+                        not_null: true,
+                        default: None,
+                    },
+                    ColumnInfo {
+                        name: "name".to_owned(),
+                        sql_type: SqlType::var_char(10),
+                        identity: None,
+                        not_null: false,
+                        default: Some(DefaultInfo {
+                            text: "'none'".to_owned(),
+                            value: StaticTypedTree::Item(StaticTypedItem::Const(TypedValue::String("none".to_owned()))),
+                        }),
+                    }
+                ],
+                if_not_exists: false,
+                storage: TableStorage::Row,
+                compression: None,
+                constraints: vec![],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_parameter_in_default() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table(
+                SCHEMA,
+                TABLE,
+                vec![ColumnDef {
+                    default: Some(Expr::Param(1)),
+                    ..column("id", DataType::Int)
+                }]
+            )),
+            Err(SchemaPlanError::Analysis(AnalysisError::feature_not_supported(
+                "parameters can't be used in DEFAULT expression"
+            )))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn create_table_with_default_of_identity_column() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(create_table(
+                SCHEMA,
+                TABLE,
+                vec![ColumnDef {
+                    identity: Some(Identity {
+                        always: false,
+                        options: vec![],
+                    }),
+                    default: Some(Expr::Value(Value::Int(1))),
+                    ..column("id", DataType::Int)
+                }]
+            )),
+            Err(SchemaPlanError::default_and_identity("id"))
+        );
+        Ok(())
+    })
+}
@@ -0,0 +1,61 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::*;
+use data_definition_execution_plan::DropIndexesQuery;
+
+const INDEX: &str = "index_name";
+
+fn drop_index(names: Vec<(&str, &str)>, if_exists: bool, cascade: bool) -> Definition {
+    Definition::DropIndexes {
+        names: names
+            .into_iter()
+            .map(|(schema, index)| (schema.to_owned(), index.to_owned()))
+            .collect(),
+        if_exists,
+        cascade,
+    }
+}
+
+#[test]
+fn drop_index_from_nonexistent_schema() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(drop_index(vec![("non_existent_schema", INDEX)], false, false)),
+            Err(SchemaPlanError::schema_does_not_exist("non_existent_schema"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn drop_indexes() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        let planner = DefinitionPlanner::from(db);
+        assert_eq!(
+            planner.plan(drop_index(vec![(SCHEMA, INDEX), (SCHEMA, "index_1")], true, true)),
+            Ok(SchemaChange::DropIndexes(DropIndexesQuery {
+                full_index_names: vec![
+                    FullTableName::from((&SCHEMA, &INDEX)),
+                    FullTableName::from((&SCHEMA, &"index_1"))
+                ],
+                cascade: true,
+                if_exists: true,
+            }))
+        );
+        Ok(())
+    })
+}
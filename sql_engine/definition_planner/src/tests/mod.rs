@@ -13,16 +13,28 @@
 // limitations under the License.
 
 use super::*;
-use data_definition_execution_plan::{ColumnInfo, CreateSchemaQuery, CreateTableQuery, DropSchemasQuery, SchemaChange};
+use data_definition_execution_plan::{
+    ColumnInfo, CreateSchemaQuery, CreateTableQuery, DropSchemasQuery, SchemaChange, TableStorage,
+};
 use storage::{Database, TransactionResult};
 
+#[cfg(test)]
+mod alter_table;
+#[cfg(test)]
+mod analyze;
+#[cfg(test)]
+mod create_database;
 #[cfg(test)]
 mod create_index;
 #[cfg(test)]
 mod create_schema;
 #[cfg(test)]
+mod create_sequence;
+#[cfg(test)]
 mod create_table;
 #[cfg(test)]
+mod drop_index;
+#[cfg(test)]
 mod drop_schema;
 #[cfg(test)]
 mod drop_table;
@@ -41,6 +53,27 @@ fn create_table_if_not_exists(
         table_name: table_name.to_owned(),
         columns,
         if_not_exists,
+        constraints: vec![],
+        options: vec![],
+    }
+}
+
+fn create_table_with_options(
+    schema_name: &str,
+    table_name: &str,
+    columns: Vec<ColumnDef>,
+    options: Vec<(&str, &str)>,
+) -> Definition {
+    Definition::CreateTable {
+        schema_name: schema_name.to_owned(),
+        table_name: table_name.to_owned(),
+        columns,
+        if_not_exists: false,
+        constraints: vec![],
+        options: options
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect(),
     }
 }
 
@@ -56,9 +89,15 @@ fn create_table_ops(schema_name: &str, table_name: &str, columns: Vec<(&str, Sql
             .map(|(name, sql_type)| ColumnInfo {
                 name: name.to_owned(),
                 sql_type,
+                identity: None,
+                not_null: false,
+                default: None,
             })
             .collect(),
         if_not_exists: true,
+        storage: TableStorage::Row,
+        compression: None,
+        constraints: vec![],
     })
 }
 
@@ -106,11 +106,36 @@ impl SchemaName {
     }
 }
 
+/// Sequence that generates values of a column when they are not given
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnIdentity {
+    sequence: FullTableName,
+    always: bool,
+}
+
+impl ColumnIdentity {
+    pub fn new(sequence: FullTableName, always: bool) -> ColumnIdentity {
+        ColumnIdentity { sequence, always }
+    }
+
+    pub fn sequence(&self) -> &FullTableName {
+        &self.sequence
+    }
+
+    /// values of `GENERATED ALWAYS` columns can't be given explicitly
+    pub fn always(&self) -> bool {
+        self.always
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ColumnDef {
     name: String,
     sql_type: SqlType,
     ord_num: usize,
+    identity: Option<ColumnIdentity>,
+    not_null: bool,
+    default: Option<String>,
 }
 
 impl ColumnDef {
@@ -119,9 +144,38 @@ impl ColumnDef {
             name,
             sql_type,
             ord_num,
+            identity: None,
+            not_null: false,
+            default: None,
         }
     }
 
+    pub fn with_identity(self, identity: Option<ColumnIdentity>) -> ColumnDef {
+        ColumnDef { identity, ..self }
+    }
+
+    pub fn with_not_null(self, not_null: bool) -> ColumnDef {
+        ColumnDef { not_null, ..self }
+    }
+
+    /// `default` is SQL text of the expression
+    pub fn with_default(self, default: Option<String>) -> ColumnDef {
+        ColumnDef { default, ..self }
+    }
+
+    pub fn identity(&self) -> Option<&ColumnIdentity> {
+        self.identity.as_ref()
+    }
+
+    pub fn not_null(&self) -> bool {
+        self.not_null
+    }
+
+    /// SQL text of the expression which value the column gets when it is not given
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -165,3 +219,91 @@ impl TableDef {
         self.columns.iter().any(|col| col.name == column_name)
     }
 }
+
+/// Secondary index of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexDef {
+    name: String,
+    column_names: Vec<String>,
+    unique: bool,
+    method: String,
+}
+
+impl IndexDef {
+    pub fn new(name: String, column_names: Vec<String>, unique: bool, method: String) -> IndexDef {
+        IndexDef {
+            name,
+            column_names,
+            unique,
+            method,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// indexed columns in the order of the index key
+    pub fn column_names(&self) -> &[String] {
+        &self.column_names
+    }
+
+    pub fn unique(&self) -> bool {
+        self.unique
+    }
+
+    /// access method, e.g. `btree`
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+    Check,
+}
+
+/// Constraint of a table, `PRIMARY KEY` and `UNIQUE` constraints are enforced by unique indexes
+/// that have the same names, `CHECK` constraints are evaluated on every written row
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstraintDef {
+    name: String,
+    kind: ConstraintKind,
+    column_names: Vec<String>,
+    check: Option<String>,
+}
+
+impl ConstraintDef {
+    pub fn new(name: String, kind: ConstraintKind, column_names: Vec<String>) -> ConstraintDef {
+        ConstraintDef {
+            name,
+            kind,
+            column_names,
+            check: None,
+        }
+    }
+
+    /// `check` is SQL text of the predicate
+    pub fn with_check(self, check: Option<String>) -> ConstraintDef {
+        ConstraintDef { check, ..self }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> ConstraintKind {
+        self.kind
+    }
+
+    pub fn column_names(&self) -> &[String] {
+        &self.column_names
+    }
+
+    /// SQL text of the predicate of `CHECK` constraint, the columns it refers to are the column names
+    pub fn check(&self) -> Option<&str> {
+        self.check.as_deref()
+    }
+}
@@ -47,6 +47,14 @@ impl DynamicTreeBuilder {
             Expr::Param(index) => Ok(DynamicUntypedTree::Item(DynamicUntypedItem::Param(
                 (index - 1) as usize,
             ))),
+            Expr::Function { name, .. } => match name.as_str() {
+                "nextval" | "currval" | "setval" => Err(AnalysisError::feature_not_supported(format!(
+                    "{} can be called only in VALUES of INSERT",
+                    name
+                ))),
+                _ => Err(AnalysisError::function_does_not_exist(name)),
+            },
+            Expr::Default => Err(AnalysisError::DefaultNotAllowed),
         }
     }
 
@@ -14,17 +14,18 @@
 
 use crate::{dynamic_tree_builder::DynamicTreeBuilder, static_tree_builder::StaticTreeBuilder};
 use catalog::CatalogHandler;
+use data_manipulation_operators::SequenceFunction;
 use data_manipulation_untyped_queries::{
     UntypedDeleteQuery, UntypedInsertQuery, UntypedQuery, UntypedSelectQuery, UntypedUpdateQuery,
 };
-use data_manipulation_untyped_tree::{DynamicUntypedItem, DynamicUntypedTree};
-use definition::FullTableName;
+use data_manipulation_untyped_tree::{DynamicUntypedItem, DynamicUntypedTree, StaticUntypedTree};
+use definition::{ColumnDef, FullTableName};
 use query_ast::{
-    Assignment, DeleteStatement, InsertSource, InsertStatement, Query, SelectItem, SelectStatement, UpdateStatement,
-    Values,
+    Assignment, DeleteStatement, Expr, InsertSource, InsertStatement, Query, SelectItem, SelectStatement,
+    UpdateStatement, Value, Values,
 };
 use query_response::QueryError;
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
 use storage::TransactionalDatabase;
 
 mod dynamic_tree_builder;
@@ -52,11 +53,17 @@ impl<'a> QueryAnalyzer<'a> {
                 columns,
             }) => {
                 let full_table_name = FullTableName::from((&schema_name, &table_name));
+                if self.catalog.system_view(&full_table_name).is_some() {
+                    return Err(AnalysisError::view_is_not_updatable(
+                        "insert into",
+                        full_table_name.table(),
+                    ));
+                }
                 match self.catalog.table_definition(full_table_name.clone()) {
                     None => Err(AnalysisError::schema_does_not_exist(full_table_name.schema())),
                     Some(None) => Err(AnalysisError::table_does_not_exist(full_table_name)),
                     Some(Some(table_info)) => {
-                        let table_columns = table_info.column_names();
+                        let table_columns = table_info.columns();
                         let column_names = if columns.is_empty() {
                             table_info.column_names().into_iter()
                         } else {
@@ -81,12 +88,31 @@ impl<'a> QueryAnalyzer<'a> {
                                 for insert_row in insert_rows {
                                     log::debug!("building static tree for {:?} row", insert_row);
                                     let mut row = vec![];
-                                    for table_column in &table_columns {
-                                        let value = match column_map.get(table_column) {
-                                            Some(index) if index < &insert_row.len() => {
-                                                Some(StaticTreeBuilder::build_from(insert_row[*index].clone())?)
+                                    for table_column in table_columns {
+                                        let given = column_map
+                                            .get(table_column.name())
+                                            .and_then(|index| insert_row.get(*index));
+                                        let value = match (given, table_column.identity()) {
+                                            // identity columns are filled by their sequences when values are not given
+                                            (None, Some(identity)) | (Some(Expr::Default), Some(identity)) => {
+                                                Some(StaticUntypedTree::Sequence {
+                                                    function: SequenceFunction::NextVal,
+                                                    sequence: identity.sequence().clone(),
+                                                    value: None,
+                                                })
+                                            }
+                                            (None, None) | (Some(Expr::Default), None) => {
+                                                match table_column.default() {
+                                                    Some(default) => {
+                                                        Some(StaticTreeBuilder::build_from(default_expr(default))?)
+                                                    }
+                                                    None => None,
+                                                }
+                                            }
+                                            (Some(_), Some(identity)) if identity.always() => {
+                                                return Err(AnalysisError::generated_always(table_column.name()));
                                             }
-                                            _ => None,
+                                            (Some(value), _) => Some(StaticTreeBuilder::build_from(value.clone())?),
                                         };
                                         row.push(value);
                                     }
@@ -109,6 +135,9 @@ impl<'a> QueryAnalyzer<'a> {
                 where_clause,
             }) => {
                 let full_table_name = FullTableName::from((&schema_name, &table_name));
+                if self.catalog.system_view(&full_table_name).is_some() {
+                    return Err(AnalysisError::view_is_not_updatable("update", full_table_name.table()));
+                }
                 match self.catalog.table_definition(full_table_name.clone()) {
                     None => Err(AnalysisError::schema_does_not_exist(full_table_name.schema())),
                     Some(None) => Err(AnalysisError::table_does_not_exist(full_table_name)),
@@ -119,7 +148,11 @@ impl<'a> QueryAnalyzer<'a> {
                             let mut found = false;
                             for stmt_assignment in stmt_assignments.iter() {
                                 if table_column.name() == stmt_assignment.column.as_str() {
-                                    temp_column_names.push(Some(stmt_assignment.value.clone()));
+                                    let value = match &stmt_assignment.value {
+                                        Expr::Default => column_default(table_column),
+                                        value => value.clone(),
+                                    };
+                                    temp_column_names.push(Some(value));
                                     found = true;
                                     break;
                                 }
@@ -202,6 +235,12 @@ impl<'a> QueryAnalyzer<'a> {
                 where_clause,
             }) => {
                 let full_table_name = FullTableName::from((&schema_name, &table_name));
+                if self.catalog.system_view(&full_table_name).is_some() {
+                    return Err(AnalysisError::view_is_not_updatable(
+                        "delete from",
+                        full_table_name.table(),
+                    ));
+                }
                 match self.catalog.table_definition(full_table_name.clone()) {
                     None => Err(AnalysisError::schema_does_not_exist(full_table_name.schema())),
                     Some(None) => Err(AnalysisError::table_does_not_exist(full_table_name)),
@@ -222,12 +261,65 @@ impl<'a> QueryAnalyzer<'a> {
     }
 }
 
+/// tree of the expression that is evaluated over rows of a table, e.g. `USING` expression of `ALTER TABLE`
+pub fn analyze_expression(expr: Expr, table_columns: &[ColumnDef]) -> Result<DynamicUntypedTree, AnalysisError> {
+    DynamicTreeBuilder::build_from(expr, table_columns)
+}
+
+/// tree of `DEFAULT` expression of a column, it is evaluated without rows and parameters
+pub fn analyze_default(expr: Expr) -> Result<StaticUntypedTree, AnalysisError> {
+    if has_params(&expr) {
+        return Err(AnalysisError::feature_not_supported(
+            "parameters can't be used in DEFAULT expression",
+        ));
+    }
+    StaticTreeBuilder::build_from(expr)
+}
+
+/// tree of `CHECK` predicate that the catalog keeps as SQL text, it is evaluated over written rows
+pub fn analyze_check(text: &str, table_columns: &[ColumnDef]) -> Result<DynamicUntypedTree, AnalysisError> {
+    let expr = Expr::from_str(text).expect("CHECK predicate is kept as valid text");
+    DynamicTreeBuilder::build_from(expr, table_columns)
+}
+
+fn has_params(expr: &Expr) -> bool {
+    match expr {
+        Expr::Param(_) => true,
+        Expr::BinaryOp { left, right, .. } => has_params(left) || has_params(right),
+        Expr::UnaryOp { expr, .. } | Expr::Cast { expr, .. } => has_params(expr),
+        Expr::Function { args, .. } => args.iter().any(has_params),
+        Expr::Value(_) | Expr::Column(_) | Expr::Default => false,
+    }
+}
+
+/// catalog keeps `DEFAULT` expressions as SQL text
+fn default_expr(text: &str) -> Expr {
+    Expr::from_str(text).expect("DEFAULT expression is kept as valid text")
+}
+
+/// expression of `UPDATE ... SET column = DEFAULT`
+fn column_default(column: &ColumnDef) -> Expr {
+    match (column.identity(), column.default()) {
+        (Some(identity), _) => Expr::Function {
+            name: "nextval".to_owned(),
+            args: vec![Expr::Value(Value::String(identity.sequence().to_string()))],
+        },
+        (None, Some(default)) => default_expr(default),
+        (None, None) => Expr::Value(Value::Null),
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum AnalysisError {
     SchemaDoesNotExist(String),
     TableDoesNotExist(String),
     ColumnNotFound(String),
     ColumnCantBeReferenced(String), // Error code: 42703
+    ViewIsNotUpdatable(String),
+    FunctionDoesNotExist(String),
+    FeatureNotSupported(String),
+    GeneratedAlways(String),
+    DefaultNotAllowed,
 }
 
 impl AnalysisError {
@@ -246,6 +338,24 @@ impl AnalysisError {
     pub fn column_cant_be_referenced<C: ToString>(column_name: C) -> AnalysisError {
         AnalysisError::ColumnCantBeReferenced(column_name.to_string())
     }
+
+    /// `action` is what the statement does to the view, e.g. `insert into`
+    pub fn view_is_not_updatable<V: ToString>(action: &str, view_name: V) -> AnalysisError {
+        AnalysisError::ViewIsNotUpdatable(format!("cannot {} view \"{}\"", action, view_name.to_string()))
+    }
+
+    pub fn function_does_not_exist<F: ToString>(function_name: F) -> AnalysisError {
+        AnalysisError::FunctionDoesNotExist(function_name.to_string())
+    }
+
+    pub fn feature_not_supported<M: ToString>(message: M) -> AnalysisError {
+        AnalysisError::FeatureNotSupported(message.to_string())
+    }
+
+    /// value is given to `GENERATED ALWAYS AS IDENTITY` column
+    pub fn generated_always<C: ToString>(column_name: C) -> AnalysisError {
+        AnalysisError::GeneratedAlways(column_name.to_string())
+    }
 }
 
 impl From<AnalysisError> for QueryError {
@@ -255,6 +365,11 @@ impl From<AnalysisError> for QueryError {
             AnalysisError::TableDoesNotExist(table_name) => QueryError::table_does_not_exist(table_name),
             AnalysisError::ColumnNotFound(column_name) => QueryError::column_does_not_exist(column_name),
             AnalysisError::ColumnCantBeReferenced(column_name) => QueryError::column_does_not_exist(column_name),
+            AnalysisError::ViewIsNotUpdatable(message) => QueryError::feature_not_supported(message),
+            AnalysisError::FunctionDoesNotExist(function_name) => QueryError::function_does_not_exist(function_name),
+            AnalysisError::FeatureNotSupported(message) => QueryError::feature_not_supported(message),
+            AnalysisError::GeneratedAlways(column_name) => QueryError::generated_always(column_name),
+            AnalysisError::DefaultNotAllowed => QueryError::default_not_allowed(),
         }
     }
 }
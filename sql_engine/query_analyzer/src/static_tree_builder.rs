@@ -14,8 +14,9 @@
 
 use crate::AnalysisError;
 use bigdecimal::BigDecimal;
-use data_manipulation_operators::{BiOperator, UnOperator};
+use data_manipulation_operators::{BiOperator, SequenceFunction, UnOperator};
 use data_manipulation_untyped_tree::{StaticUntypedItem, StaticUntypedTree, UntypedValue};
+use definition::FullTableName;
 use query_ast::{BinaryOperator, Expr, Value};
 use std::str::FromStr;
 use types::{Bool, SqlType};
@@ -41,9 +42,37 @@ impl StaticTreeBuilder {
                 item: Box::new(Self::inner_build(*expr)?),
             }),
             Expr::Param(index) => Ok(StaticUntypedTree::Item(StaticUntypedItem::Param((index - 1) as usize))),
+            Expr::Function { name, args } => Self::function(name, args),
+            Expr::Default => Err(AnalysisError::DefaultNotAllowed),
         }
     }
 
+    /// only sequence functions are supported, sequences are referred by names given as string literals
+    fn function(name: String, args: Vec<Expr>) -> Result<StaticUntypedTree, AnalysisError> {
+        let mut args = args.into_iter();
+        let sequence = match args.next() {
+            Some(Expr::Value(Value::String(sequence))) => sequence_name(&sequence),
+            _ => return Err(AnalysisError::function_does_not_exist(name)),
+        };
+        let (function, value) = match (name.as_str(), args.next(), args.next(), args.next()) {
+            ("nextval", None, None, None) => (SequenceFunction::NextVal, None),
+            ("currval", None, None, None) => (SequenceFunction::CurrVal, None),
+            ("setval", Some(value), None, None) => (SequenceFunction::SetVal { is_called: true }, Some(value)),
+            ("setval", Some(value), Some(Expr::Value(Value::Boolean(is_called))), None) => {
+                (SequenceFunction::SetVal { is_called }, Some(value))
+            }
+            _ => return Err(AnalysisError::function_does_not_exist(name)),
+        };
+        Ok(StaticUntypedTree::Sequence {
+            function,
+            sequence,
+            value: match value {
+                None => None,
+                Some(value) => Some(Box::new(Self::inner_build(value)?)),
+            },
+        })
+    }
+
     fn binary_op(operator: BinaryOperator, left: Expr, right: Expr) -> Result<StaticUntypedTree, AnalysisError> {
         let left = Self::inner_build(left)?;
         let right = Self::inner_build(right)?;
@@ -70,3 +99,12 @@ impl StaticTreeBuilder {
         }
     }
 }
+
+/// `schema_name.sequence_name` or `sequence_name` in `public` schema
+fn sequence_name(name: &str) -> FullTableName {
+    let name = name.to_lowercase();
+    match name.split_once('.') {
+        Some((schema_name, sequence_name)) => FullTableName::from((&schema_name, &sequence_name)),
+        None => FullTableName::from((&"public", &name)),
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+fn id(value: i16) -> Option<StaticUntypedTree> {
+    Some(StaticUntypedTree::Item(StaticUntypedItem::Const(UntypedValue::Number(
+        BigDecimal::from(value),
+    ))))
+}
+
+fn none() -> Option<StaticUntypedTree> {
+    Some(StaticUntypedTree::Item(StaticUntypedItem::Const(UntypedValue::String(
+        "none".to_owned(),
+    ))))
+}
+
+#[test]
+fn column_without_value_gets_default() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog.apply(create_table_with_default_ops(SCHEMA, TABLE)).unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(inner_insert(SCHEMA, TABLE, vec![vec![small_int(1)]], vec!["id"])),
+            Ok(UntypedQuery::Insert(UntypedInsertQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                values: vec![vec![id(1), none()]],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn default_keyword_in_values() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog.apply(create_table_with_default_ops(SCHEMA, TABLE)).unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(insert_with_values(
+                SCHEMA,
+                TABLE,
+                vec![vec![small_int(1), Expr::Default], vec![Expr::Default, string("name")]]
+            )),
+            Ok(UntypedQuery::Insert(UntypedInsertQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                values: vec![
+                    vec![id(1), none()],
+                    vec![
+                        None,
+                        Some(StaticUntypedTree::Item(StaticUntypedItem::Const(UntypedValue::String(
+                            "name".to_owned()
+                        ))))
+                    ]
+                ],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn default_keyword_inside_expression() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog.apply(create_table_with_default_ops(SCHEMA, TABLE)).unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(insert_with_values(
+                SCHEMA,
+                TABLE,
+                vec![vec![Expr::BinaryOp {
+                    left: Box::new(Expr::Default),
+                    op: BinaryOperator::Plus,
+                    right: Box::new(small_int(1)),
+                }]]
+            )),
+            Err(AnalysisError::DefaultNotAllowed)
+        );
+        Ok(())
+    })
+}
@@ -13,12 +13,17 @@
 // limitations under the License.
 
 use super::*;
+use data_manipulation_operators::SequenceFunction;
 use data_manipulation_untyped_tree::{StaticUntypedItem, StaticUntypedTree, UntypedValue};
 
+#[cfg(test)]
+mod defaults;
 #[cfg(test)]
 mod expressions;
 #[cfg(test)]
 mod general_cases;
+#[cfg(test)]
+mod sequences;
 
 fn small_int(value: i16) -> Expr {
     Expr::Value(number(value))
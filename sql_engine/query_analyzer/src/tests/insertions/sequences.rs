@@ -0,0 +1,215 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use data_definition_execution_plan::{IdentityInfo, SequenceOptions};
+
+fn create_identity_table_ops(always: bool) -> SchemaChange {
+    SchemaChange::CreateTable(CreateTableQuery {
+        full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+        column_defs: vec![
+            ColumnInfo {
+                name: "id".to_owned(),
+                sql_type: SqlType::integer(),
+                identity: Some(IdentityInfo {
+                    always,
+                    options: SequenceOptions::default(),
+                }),
+                not_null: false,
+                default: None,
+            },
+            ColumnInfo {
+                name: "col".to_owned(),
+                sql_type: SqlType::small_int(),
+                identity: None,
+                not_null: false,
+                default: None,
+            },
+        ],
+        if_not_exists: false,
+        storage: TableStorage::Row,
+        compression: None,
+        constraints: vec![],
+    })
+}
+
+fn function(name: &str, args: Vec<Expr>) -> Expr {
+    Expr::Function {
+        name: name.to_owned(),
+        args,
+    }
+}
+
+fn next_value(schema_name: &str, sequence_name: &str) -> StaticUntypedTree {
+    StaticUntypedTree::Sequence {
+        function: SequenceFunction::NextVal,
+        sequence: FullTableName::from((&schema_name, &sequence_name)),
+        value: None,
+    }
+}
+
+#[test]
+fn identity_column_without_value() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog.apply(create_identity_table_ops(false)).unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(inner_insert(SCHEMA, TABLE, vec![vec![small_int(1)]], vec!["col"])),
+            Ok(UntypedQuery::Insert(UntypedInsertQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                values: vec![vec![
+                    Some(next_value(SCHEMA, "table_name_id_seq")),
+                    Some(StaticUntypedTree::Item(StaticUntypedItem::Const(UntypedValue::Number(
+                        BigDecimal::from(1)
+                    ))))
+                ]],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn value_for_generated_by_default_identity_column() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog.apply(create_identity_table_ops(false)).unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(insert_with_values(
+                SCHEMA,
+                TABLE,
+                vec![vec![small_int(10), small_int(1)]]
+            )),
+            Ok(UntypedQuery::Insert(UntypedInsertQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                values: vec![vec![
+                    Some(StaticUntypedTree::Item(StaticUntypedItem::Const(UntypedValue::Number(
+                        BigDecimal::from(10)
+                    )))),
+                    Some(StaticUntypedTree::Item(StaticUntypedItem::Const(UntypedValue::Number(
+                        BigDecimal::from(1)
+                    ))))
+                ]],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn value_for_generated_always_identity_column() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog.apply(create_identity_table_ops(true)).unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(insert_with_values(
+                SCHEMA,
+                TABLE,
+                vec![vec![small_int(10), small_int(1)]]
+            )),
+            Err(AnalysisError::generated_always("id"))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn next_value_of_sequence() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog
+            .apply(create_table_ops(SCHEMA, TABLE, vec![("col", SqlType::big_int())]))
+            .unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(insert_with_values(
+                SCHEMA,
+                TABLE,
+                vec![vec![function("nextval", vec![string("Seq")])]]
+            )),
+            Ok(UntypedQuery::Insert(UntypedInsertQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                values: vec![vec![Some(next_value("public", "seq"))]],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn set_value_of_sequence() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog
+            .apply(create_table_ops(SCHEMA, TABLE, vec![("col", SqlType::big_int())]))
+            .unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(insert_with_values(
+                SCHEMA,
+                TABLE,
+                vec![vec![function(
+                    "setval",
+                    vec![string("schema_name.seq"), small_int(5), boolean(false)]
+                )]]
+            )),
+            Ok(UntypedQuery::Insert(UntypedInsertQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                values: vec![vec![Some(StaticUntypedTree::Sequence {
+                    function: SequenceFunction::SetVal { is_called: false },
+                    sequence: FullTableName::from((&SCHEMA, &"seq")),
+                    value: Some(Box::new(StaticUntypedTree::Item(StaticUntypedItem::Const(
+                        UntypedValue::Number(BigDecimal::from(5))
+                    )))),
+                })]],
+            }))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn unknown_function() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog
+            .apply(create_table_ops(SCHEMA, TABLE, vec![("col", SqlType::big_int())]))
+            .unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(insert_with_values(
+                SCHEMA,
+                TABLE,
+                vec![vec![function("lastval", vec![])]]
+            )),
+            Err(AnalysisError::function_does_not_exist("lastval"))
+        );
+        Ok(())
+    })
+}
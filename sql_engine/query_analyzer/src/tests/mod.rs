@@ -14,8 +14,11 @@
 
 use super::*;
 use bigdecimal::BigDecimal;
-use data_definition_execution_plan::{ColumnInfo, CreateSchemaQuery, CreateTableQuery, SchemaChange};
+use data_definition_execution_plan::{
+    ColumnInfo, CreateSchemaQuery, CreateTableQuery, DefaultInfo, SchemaChange, TableStorage,
+};
 use data_manipulation_operators::{BiArithmetic, BiLogical, BiOperator, Bitwise, Comparison, Concat, Matching};
+use data_manipulation_typed_tree::{StaticTypedItem, StaticTypedTree, TypedValue};
 use definition::SchemaName;
 use query_ast::{Assignment, BinaryOperator, Expr, Value};
 use storage::{Database, TransactionResult};
@@ -64,8 +67,44 @@ fn create_table_ops(schema_name: &str, table_name: &str, columns: Vec<(&str, Sql
             .map(|(name, sql_type)| ColumnInfo {
                 name: name.to_owned(),
                 sql_type,
+                identity: None,
+                not_null: false,
+                default: None,
             })
             .collect(),
         if_not_exists: true,
+        storage: TableStorage::Row,
+        compression: None,
+        constraints: vec![],
+    })
+}
+
+/// table with `id smallint, name varchar(10) DEFAULT 'none'` columns
+fn create_table_with_default_ops(schema_name: &str, table_name: &str) -> SchemaChange {
+    SchemaChange::CreateTable(CreateTableQuery {
+        full_table_name: FullTableName::from((&schema_name, &table_name)),
+        column_defs: vec![
+            ColumnInfo {
+                name: "id".to_owned(),
+                sql_type: SqlType::small_int(),
+                identity: None,
+                not_null: false,
+                default: None,
+            },
+            ColumnInfo {
+                name: "name".to_owned(),
+                sql_type: SqlType::var_char(10),
+                identity: None,
+                not_null: false,
+                default: Some(DefaultInfo {
+                    text: "'none'".to_owned(),
+                    value: StaticTypedTree::Item(StaticTypedItem::Const(TypedValue::String("none".to_owned()))),
+                }),
+            },
+        ],
+        if_not_exists: true,
+        storage: TableStorage::Row,
+        compression: None,
+        constraints: vec![],
     })
 }
@@ -426,3 +426,32 @@ mod multiple_values {
         })
     }
 }
+
+#[test]
+fn update_to_default() -> TransactionResult<()> {
+    Database::in_memory("").transaction(|db| {
+        let catalog = CatalogHandler::from(db.clone());
+        catalog.apply(create_schema_ops(SCHEMA)).unwrap();
+        catalog.apply(create_table_with_default_ops(SCHEMA, TABLE)).unwrap();
+        let analyzer = QueryAnalyzer::from(db);
+
+        assert_eq!(
+            analyzer.analyze(update_statement(
+                SCHEMA,
+                TABLE,
+                vec![("id", Expr::Default), ("name", Expr::Default)]
+            )),
+            Ok(UntypedQuery::Update(UntypedUpdateQuery {
+                full_table_name: FullTableName::from((&SCHEMA, &TABLE)),
+                assignments: vec![
+                    Some(DynamicUntypedTree::Item(DynamicUntypedItem::Const(UntypedValue::Null))),
+                    Some(DynamicUntypedTree::Item(DynamicUntypedItem::Const(
+                        UntypedValue::String("none".to_owned())
+                    )))
+                ],
+                filter: None
+            }))
+        );
+        Ok(())
+    })
+}
@@ -12,18 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use catalog::CatalogHandler;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use catalog::{CatalogHandler, CurrentValues};
+use data_manipulation_operators::{BiLogical, BiOperator, Comparison};
 use data_manipulation_query_plan::{
-    ConstraintValidator, DeleteQueryPlan, DynamicValues, Filter, FullTableScan, InsertQueryPlan, Projection, QueryPlan,
-    Repeater, SelectQueryPlan, StaticExpressionEval, StaticValues, TableRecordKeys, UpdateQueryPlan,
+    CheckConstraints, ColumnScan, ConstraintValidator, DeleteQueryPlan, DynamicValues, Filter, Flow, FullTableScan,
+    IndexScan, InsertQueryPlan, Projection, QueryPlan, Repeater, SelectQueryPlan, SharedScan, StaticExpressionEval,
+    StaticValues, TableRecordKeys, UpdateQueryPlan, ViewScan,
 };
 use data_manipulation_typed_queries::TypedQuery;
-use data_manipulation_typed_tree::{DynamicTypedItem, DynamicTypedTree};
-use storage::TransactionalDatabase;
+use data_manipulation_typed_tree::{DynamicTypedItem, DynamicTypedTree, TypedValue};
+use definition::{ColumnDef, ConstraintKind, FullTableName};
+use query_analyzer::{analyze_check, AnalysisError};
+use query_processing_type_check::TypeChecker;
+use query_processing_type_coercion::TypeCoercion;
+use query_processing_type_inference::TypeInference;
+use query_response::QueryError;
+use std::convert::TryFrom;
+use storage::{repr::Datum, Binary, Table, TransactionalDatabase, TreeLayout};
+use types::SqlTypeFamily;
+
+/// cost of reading a row through an index relative to reading a row during a full table scan
+const INDEX_ROW_COST: f64 = 4.0;
 
 pub struct QueryPlanner<'p> {
     database: TransactionalDatabase<'p>,
     catalog: CatalogHandler<'p>,
+    current_values: CurrentValues,
 }
 
 impl<'p> From<TransactionalDatabase<'p>> for QueryPlanner<'p> {
@@ -31,48 +46,84 @@ impl<'p> From<TransactionalDatabase<'p>> for QueryPlanner<'p> {
         QueryPlanner {
             database: database.clone(),
             catalog: CatalogHandler::from(database),
+            current_values: CurrentValues::default(),
         }
     }
 }
 
 impl<'p> QueryPlanner<'p> {
-    pub fn plan(&self, query: TypedQuery) -> QueryPlan {
-        match query {
+    /// values that sequences returned earlier in the session, `currval` reads them
+    pub fn with_current_values(self, current_values: CurrentValues) -> QueryPlanner<'p> {
+        QueryPlanner { current_values, ..self }
+    }
+
+    /// fails if `CHECK` constraints of the table can't be analyzed over its current columns
+    pub fn plan(&self, query: TypedQuery) -> Result<QueryPlan, QueryError> {
+        let plan = match query {
             TypedQuery::Insert(insert) => {
                 let table = self.database.table(&insert.full_table_name);
+                let checks = self.checks(&insert.full_table_name)?;
                 QueryPlan::Insert(InsertQueryPlan::new(
                     ConstraintValidator::new(
-                        StaticExpressionEval::new(StaticValues::new(insert.values)),
-                        self.catalog.columns(&insert.full_table_name),
+                        StaticExpressionEval::new(
+                            StaticValues::new(insert.values),
+                            self.catalog.sequences(self.current_values.clone()),
+                        ),
+                        insert.full_table_name.table().to_owned(),
+                        self.column_defs(&insert.full_table_name),
                     ),
+                    checks,
                     table,
                 ))
             }
             TypedQuery::Delete(delete) => {
                 let table = self.database.table(&delete.full_table_name);
+                let scan = self.scan(&table, &delete.full_table_name, delete.filter.as_ref());
                 QueryPlan::Delete(DeleteQueryPlan::new(
-                    TableRecordKeys::new(Filter::new(Projection::new(FullTableScan::new(&table)), delete.filter)),
+                    TableRecordKeys::new(Filter::new(Projection::new(scan), delete.filter)),
                     table,
                 ))
             }
             TypedQuery::Update(update) => {
                 let table = self.database.table(&update.full_table_name);
+                let checks = self.checks(&update.full_table_name)?;
+                let (scan, records) =
+                    SharedScan::new(self.scan(&table, &update.full_table_name, update.filter.as_ref()));
                 QueryPlan::Update(UpdateQueryPlan::new(
                     ConstraintValidator::new(
                         DynamicValues::new(
                             Repeater::new(update.assignments),
-                            Filter::new(Projection::new(FullTableScan::new(&table)), update.filter),
+                            Filter::new(Projection::new(scan), update.filter),
                         ),
-                        self.catalog.columns(&update.full_table_name),
+                        update.full_table_name.table().to_owned(),
+                        self.column_defs(&update.full_table_name),
                     ),
-                    FullTableScan::new(&table),
+                    records,
+                    checks,
                     table,
                 ))
             }
             TypedQuery::Select(select) => {
-                let table = self.database.table(&select.full_table_name);
+                let scan = match self.catalog.system_view(&select.full_table_name) {
+                    // views do not have a tree to read from
+                    Some(view) => ViewScan::new(self.catalog.system_view_rows(view)),
+                    None => {
+                        let table = self.database.table(&select.full_table_name);
+                        match self.index_scan(&table, &select.full_table_name, select.filter.as_ref()) {
+                            Some(index_scan) => index_scan,
+                            None if table.layout() == TreeLayout::Columns => {
+                                let mut columns = vec![];
+                                for item in select.projection_items.iter().chain(select.filter.as_ref()) {
+                                    collect_columns(item, &mut columns);
+                                }
+                                ColumnScan::new(&table, columns)
+                            }
+                            None => FullTableScan::new(&table),
+                        }
+                    }
+                };
                 QueryPlan::Select(SelectQueryPlan::new(
-                    Filter::new(Projection::new(FullTableScan::new(&table)), select.filter),
+                    Filter::new(Projection::new(scan), select.filter),
                     select
                         .projection_items
                         .into_iter()
@@ -84,6 +135,175 @@ impl<'p> QueryPlanner<'p> {
                     self.catalog.columns_short(&select.full_table_name),
                 ))
             }
+        };
+        Ok(plan)
+    }
+
+    /// columns of the table that values of inserted and updated rows are checked against
+    fn column_defs(&self, full_table_name: &FullTableName) -> Vec<ColumnDef> {
+        self.catalog
+            .table_definition(full_table_name.clone())
+            .flatten()
+            .map(|table| table.columns().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// predicates of `CHECK` constraints of the table over its columns. Columns can change
+    /// after constraints are added, so predicates that no longer refer to them are an error
+    fn checks(&self, full_table_name: &FullTableName) -> Result<CheckConstraints, AnalysisError> {
+        let columns = self.column_defs(full_table_name);
+        let predicates = self
+            .catalog
+            .table_constraints(full_table_name)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|constraint| constraint.kind() == ConstraintKind::Check)
+            .filter_map(|constraint| {
+                let tree = analyze_check(constraint.check()?, &columns).map(|tree| {
+                    let tree = TypeInference::default().infer_dynamic(tree, &[]);
+                    let tree = TypeChecker.check_dynamic(tree);
+                    (constraint.name().to_owned(), TypeCoercion.coerce_dynamic(tree))
+                });
+                Some(tree)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(CheckConstraints::new(full_table_name.table().to_owned(), predicates))
+    }
+
+    /// reads rows through an index when statistics of the table say that the filter
+    /// selects few enough rows, otherwise the whole table is read
+    fn scan(
+        &self,
+        table: &Table,
+        full_table_name: &FullTableName,
+        filter: Option<&DynamicTypedTree>,
+    ) -> Box<dyn Flow<Output = (Binary, Binary)>> {
+        match self.index_scan(table, full_table_name, filter) {
+            Some(index_scan) => index_scan,
+            None => FullTableScan::new(table),
+        }
+    }
+
+    fn index_scan(
+        &self,
+        table: &Table,
+        full_table_name: &FullTableName,
+        filter: Option<&DynamicTypedTree>,
+    ) -> Option<Box<dyn Flow<Output = (Binary, Binary)>>> {
+        filter
+            .and_then(|filter| self.cheapest_index(full_table_name, filter))
+            .and_then(|(index, values)| IndexScan::new(table, &index, values))
+            .map(|index_scan| index_scan as Box<dyn Flow<Output = (Binary, Binary)>>)
+    }
+
+    /// index which first column is compared with a constant in the filter and that is expected
+    /// to be cheaper to read than the whole table. Tables without statistics are always read fully
+    fn cheapest_index(
+        &self,
+        full_table_name: &FullTableName,
+        filter: &DynamicTypedTree,
+    ) -> Option<(String, Vec<Datum>)> {
+        let statistics = self.catalog.table_statistics(full_table_name)?;
+        let mut equalities = vec![];
+        collect_equalities(filter, &mut equalities);
+        let full_scan_cost = statistics.rows as f64;
+        self.catalog
+            .table_indexes(full_table_name)?
+            .into_iter()
+            .filter_map(|index| {
+                let first_column = index.column_names().first()?;
+                let (_column, name, sql_type, value) =
+                    equalities.iter().find(|(_column, name, ..)| name == first_column)?;
+                let values = lookup_values(value, *sql_type);
+                let column = statistics.column(name)?;
+                let rows = column.eq_selectivity(values.first()?) * full_scan_cost;
+                let cost = rows * INDEX_ROW_COST;
+                log::debug!(
+                    "{} INDEX IS EXPECTED TO READ {} ROWS OF {}",
+                    index.name(),
+                    rows,
+                    full_scan_cost
+                );
+                if cost < full_scan_cost {
+                    Some((cost, index.name().to_owned(), values))
+                } else {
+                    None
+                }
+            })
+            .min_by(|(left, ..), (right, ..)| left.partial_cmp(right).unwrap())
+            .map(|(_cost, index, values)| (index, values))
+    }
+}
+
+/// `column = constant` conditions that every row selected by the filter satisfies
+fn collect_equalities<'f>(
+    filter: &'f DynamicTypedTree,
+    equalities: &mut Vec<(usize, &'f str, SqlTypeFamily, &'f TypedValue)>,
+) {
+    match filter {
+        DynamicTypedTree::BiOp {
+            left,
+            op: BiOperator::Logical(BiLogical::And),
+            right,
+            ..
+        } => {
+            collect_equalities(left, equalities);
+            collect_equalities(right, equalities);
+        }
+        DynamicTypedTree::BiOp {
+            left,
+            op: BiOperator::Comparison(Comparison::Eq),
+            right,
+            ..
+        } => match (left.as_ref(), right.as_ref()) {
+            (
+                DynamicTypedTree::Item(DynamicTypedItem::Column { name, sql_type, index }),
+                DynamicTypedTree::Item(DynamicTypedItem::Const(value)),
+            )
+            | (
+                DynamicTypedTree::Item(DynamicTypedItem::Const(value)),
+                DynamicTypedTree::Item(DynamicTypedItem::Column { name, sql_type, index }),
+            ) => equalities.push((*index, name.as_str(), *sql_type, value)),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// positions of table columns that the expression reads
+fn collect_columns(tree: &DynamicTypedTree, columns: &mut Vec<usize>) {
+    match tree {
+        DynamicTypedTree::BiOp { left, right, .. } => {
+            collect_columns(left, columns);
+            collect_columns(right, columns);
+        }
+        DynamicTypedTree::UnOp { item, .. } => collect_columns(item, columns),
+        DynamicTypedTree::Item(DynamicTypedItem::Column { index, .. }) => columns.push(*index),
+        DynamicTypedTree::Item(_) => {}
+    }
+}
+
+/// values that rows can have in an index when the column is equal to the constant.
+/// Integers are stored with the smallest type that fits them, so all of the types are looked up
+fn lookup_values(value: &TypedValue, column: SqlTypeFamily) -> Vec<Datum> {
+    match (value, column) {
+        (TypedValue::Num { value, .. }, SqlTypeFamily::SmallInt)
+        | (TypedValue::Num { value, .. }, SqlTypeFamily::Integer)
+        | (TypedValue::Num { value, .. }, SqlTypeFamily::BigInt) => {
+            let mut values = vec![];
+            if let Some(integer) = value.to_i64().filter(|integer| BigDecimal::from(*integer) == *value) {
+                if let Ok(small) = i16::try_from(integer) {
+                    values.push(Datum::from_i16(small));
+                }
+                if let Ok(int) = i32::try_from(integer) {
+                    values.push(Datum::from_i32(int));
+                }
+                values.push(Datum::from_i64(integer));
+            }
+            values
         }
+        (TypedValue::String(value), SqlTypeFamily::String) => vec![Datum::from_string(value.clone())],
+        (TypedValue::Bool(value), SqlTypeFamily::Bool) => vec![Datum::from_bool(*value)],
+        _ => vec![],
     }
 }
@@ -96,6 +96,9 @@ impl TypeInference {
             DynamicUntypedTree::Item(DynamicUntypedItem::Const(UntypedValue::Bool(Bool(boolean)))) => {
                 DynamicTypedTree::Item(DynamicTypedItem::Const(TypedValue::Bool(boolean)))
             }
+            DynamicUntypedTree::Item(DynamicUntypedItem::Const(UntypedValue::Null)) => {
+                DynamicTypedTree::Item(DynamicTypedItem::Null(None))
+            }
             DynamicUntypedTree::BiOp { left, op, right } => {
                 log::debug!("LEFT TREE {:#?}", left);
                 log::debug!("RIGHT TREE {:#?}", right);
@@ -123,7 +126,6 @@ impl TypeInference {
                 op,
                 item: Box::new(self.infer_dynamic(*item, param_types)),
             },
-            _ => unimplemented!(),
         }
     }
 
@@ -189,15 +191,26 @@ impl TypeInference {
             StaticUntypedTree::Item(StaticUntypedItem::Const(UntypedValue::Bool(Bool(boolean)))) => {
                 StaticTypedTree::Item(StaticTypedItem::Const(TypedValue::Bool(boolean)))
             }
+            StaticUntypedTree::Item(StaticUntypedItem::Const(UntypedValue::Null)) => {
+                StaticTypedTree::Item(StaticTypedItem::Null(None))
+            }
             StaticUntypedTree::Item(StaticUntypedItem::Param(index)) => StaticTypedTree::Item(StaticTypedItem::Param {
                 index,
                 type_family: Some(param_types[index]),
             }),
-            StaticUntypedTree::Item(_) => unimplemented!(),
             StaticUntypedTree::UnOp { op, item } => StaticTypedTree::UnOp {
                 op,
                 item: Box::new(self.infer_static(*item, param_types)),
             },
+            StaticUntypedTree::Sequence {
+                function,
+                sequence,
+                value,
+            } => StaticTypedTree::Sequence {
+                function,
+                sequence,
+                value: value.map(|value| Box::new(self.infer_static(*value, param_types))),
+            },
         }
     }
 }
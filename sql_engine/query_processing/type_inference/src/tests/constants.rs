@@ -81,3 +81,14 @@ fn string() {
         StaticTypedTree::Item(StaticTypedItem::Const(TypedValue::String("str".to_owned())))
     );
 }
+
+#[test]
+fn null() {
+    let type_inference = TypeInference::default();
+    let tree = StaticUntypedTree::Item(StaticUntypedItem::Const(UntypedValue::Null));
+
+    assert_eq!(
+        type_inference.infer_static(tree, &[]),
+        StaticTypedTree::Item(StaticTypedItem::Null(None))
+    );
+}
@@ -12,11 +12,55 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::{Duration, UNIX_EPOCH};
+use storage::{KeySource, Location, RecoveryTarget};
+
 fn main() {
     simple_logger::SimpleLogger::from_env()
         .init()
         .expect("to initialize logger");
 
     let root_path = std::env::var("ROOT_PATH").unwrap_or_else(|_| "./root_directory".to_owned());
-    node_engine::start(storage::Database::persistent(root_path.as_str()));
+    let backend = std::env::var("STORAGE_BACKEND").unwrap_or_else(|_| "persistent".to_owned());
+    let mut location = Location {
+        path: root_path.clone(),
+        wal_archive: std::env::var("WAL_ARCHIVE_PATH").ok(),
+        recovery_target: None,
+        encryption_keys: std::env::var("ENCRYPTION_KEY_FILE")
+            .ok()
+            .map(KeySource::File)
+            .or_else(|| std::env::var_os("ENCRYPTION_KEYS").map(|_keys| KeySource::Env("ENCRYPTION_KEYS".to_owned()))),
+    };
+
+    let args = std::env::args().collect::<Vec<_>>();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [_, "restore", backup] => {
+            let file = std::fs::File::open(backup).unwrap_or_else(|error| panic!("can't open {:?}: {}", backup, error));
+            storage::Backends::default()
+                .restore(backend.as_str(), location, file)
+                .unwrap_or_else(|error| panic!("can't restore {:?} into {:?}: {}", backup, root_path, error));
+            log::info!("{:?} is restored into {:?}", backup, root_path);
+            return;
+        }
+        [_, "recover", "time", seconds] => {
+            let seconds = seconds
+                .parse::<f64>()
+                .expect("recovery target time to be seconds since UNIX epoch");
+            location.recovery_target = Some(RecoveryTarget::Time(UNIX_EPOCH + Duration::from_secs_f64(seconds)));
+        }
+        [_, "recover", "transaction", timestamp] => {
+            let timestamp = timestamp
+                .parse::<u64>()
+                .expect("recovery target transaction to be a commit timestamp");
+            location.recovery_target = Some(RecoveryTarget::Transaction(timestamp));
+        }
+        [_] => {}
+        _ => panic!("unknown arguments {:?}", &args[1..]),
+    }
+
+    // the database is open for reads and writes right after recovery
+    let database = storage::Backends::default()
+        .open(backend.as_str(), location)
+        .unwrap_or_else(|error| panic!("can't open {:?} storage in {:?}: {}", backend, root_path, error));
+    node_engine::start(database);
 }
@@ -12,48 +12,192 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use binary::Binary;
+use crate::version::VersionChain;
+use binary::{repr::Datum, Binary, UnpackError};
 use std::{
-    fmt::{self, Debug, Formatter},
-    iter::FromIterator,
+    fmt::{self, Debug, Display, Formatter},
+    ops::{Bound, RangeBounds},
+    sync::Arc,
+    time::SystemTime,
 };
 
+pub mod version;
+
 pub type Key = Binary;
 pub type Value = Binary;
 pub type TransactionResult<R> = Result<R, TransactionError>;
 pub type ConflictableTransactionResult<R> = Result<R, ConflictableTransactionError>;
+pub type StorageResult<R> = Result<R, StorageError>;
+/// Rows of a tree as they are read from a storage backend
+pub type TreeScan = Box<dyn Iterator<Item = StorageResult<(Key, Value)>>>;
+/// changes of many keys of a tree, `None` removes a key
+pub type WriteBatch = Vec<(Key, Option<Value>)>;
 
 #[derive(Debug, PartialEq)]
 pub enum TransactionError {
     Abort,
-    Storage,
+    Storage(StorageError),
 }
 
 #[derive(Debug, PartialEq)]
 pub enum ConflictableTransactionError {
     Abort,
-    Storage,
+    Storage(StorageError),
     Conflict,
 }
 
-pub struct Cursor {
-    source: Box<dyn Iterator<Item = (Binary, Binary)>>,
+/// Failures of a storage backend
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageError {
+    /// backend could not read or write its data, e.g. disk is full
+    Io(String),
+    /// there is no tree with such name
+    TreeNotFound(String),
+    /// stored data can't be decoded
+    Corrupted(String),
+    /// encryption keys are malformed or do not match the data
+    Encryption(String),
 }
 
-impl Debug for Cursor {
+impl Display for StorageError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Data Cursor")
+        match self {
+            StorageError::Io(message) => write!(f, "I/O error: {}", message),
+            StorageError::TreeNotFound(name) => write!(f, "tree {:?} does not exist", name),
+            StorageError::Corrupted(message) => write!(f, "data is corrupted: {}", message),
+            StorageError::Encryption(message) => write!(f, "encryption error: {}", message),
+        }
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(error: std::io::Error) -> StorageError {
+        StorageError::Io(error.to_string())
+    }
+}
+
+impl From<UnpackError> for StorageError {
+    fn from(error: UnpackError) -> StorageError {
+        StorageError::Corrupted(error.to_string())
+    }
+}
+
+impl From<StorageError> for ConflictableTransactionError {
+    fn from(error: StorageError) -> ConflictableTransactionError {
+        ConflictableTransactionError::Storage(error)
+    }
+}
+
+/// Point in the history of the database that it can be recovered to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecoveryTarget {
+    /// the last transaction that was committed at or before the time
+    Time(SystemTime),
+    /// the transaction with the logical commit timestamp
+    Transaction(u64),
+}
+
+/// Reasons why a table change can't be applied
+#[derive(Debug, PartialEq)]
+pub enum WriteError {
+    /// the same values are already stored in the unique index
+    UniqueViolation(String),
+}
+
+/// How a tree keeps rows of a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TreeLayout {
+    /// every row is stored as a whole under its key
+    #[default]
+    Rows,
+    /// values of each column are stored together and compressed, scans can read only some of the columns
+    Columns,
+}
+
+/// Bounds of keys that a range scan reads
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyRange {
+    pub start: Bound<Key>,
+    pub end: Bound<Key>,
+}
+
+impl KeyRange {
+    pub fn new(start: Bound<Key>, end: Bound<Key>) -> KeyRange {
+        KeyRange { start, end }
+    }
+
+    pub fn all() -> KeyRange {
+        KeyRange::new(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// all keys that start with the `prefix` bytes
+    pub fn prefix(prefix: &Key) -> KeyRange {
+        let mut upper = prefix.as_ref().to_vec();
+        while let Some(last) = upper.pop() {
+            if last < u8::MAX {
+                upper.push(last + 1);
+                return KeyRange::new(
+                    Bound::Included(prefix.clone()),
+                    Bound::Excluded(Binary::with_data(upper)),
+                );
+            }
+        }
+        KeyRange::new(Bound::Included(prefix.clone()), Bound::Unbounded)
+    }
+
+    /// `BTreeMap::range` panics on such ranges
+    pub fn is_empty(&self) -> bool {
+        match (&self.start, &self.end) {
+            (Bound::Included(start), Bound::Included(end)) => start > end,
+            (Bound::Included(start), Bound::Excluded(end))
+            | (Bound::Excluded(start), Bound::Included(end))
+            | (Bound::Excluded(start), Bound::Excluded(end)) => start >= end,
+            _ => false,
+        }
     }
 }
 
-impl FromIterator<(Binary, Binary)> for Cursor {
-    fn from_iter<T: IntoIterator<Item = (Binary, Binary)>>(iter: T) -> Cursor {
+impl RangeBounds<Key> for KeyRange {
+    fn start_bound(&self) -> Bound<&Key> {
+        self.start.as_ref()
+    }
+
+    fn end_bound(&self) -> Bound<&Key> {
+        self.end.as_ref()
+    }
+}
+
+impl From<UnpackError> for TransactionError {
+    fn from(error: UnpackError) -> TransactionError {
+        TransactionError::Storage(error.into())
+    }
+}
+
+impl From<UnpackError> for ConflictableTransactionError {
+    fn from(error: UnpackError) -> ConflictableTransactionError {
+        ConflictableTransactionError::Storage(error.into())
+    }
+}
+
+pub struct Cursor {
+    source: Box<dyn Iterator<Item = (Binary, Binary)>>,
+}
+
+impl Cursor {
+    /// rows are produced lazily as the cursor is advanced
+    pub fn new<I: Iterator<Item = (Binary, Binary)> + 'static>(source: I) -> Cursor {
         Cursor {
-            source: Box::new(iter.into_iter().collect::<Vec<(Binary, Binary)>>().into_iter()),
+            source: Box::new(source),
         }
     }
 }
 
+impl Debug for Cursor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Data Cursor")
+    }
+}
+
 impl Iterator for Cursor {
     type Item = (Binary, Binary);
 
@@ -62,26 +206,124 @@ impl Iterator for Cursor {
     }
 }
 
+/// Ordered key-value collection of a storage engine.
+/// Transactions and versions of rows are managed on top of it by `storage` crate
 pub trait Tree {
-    fn remove(&self, key: &Binary) -> Option<Value>;
+    fn get(&self, key: &Binary) -> StorageResult<Option<Value>>;
+
+    fn next_key(&self) -> Key;
+
+    /// `count` unique keys reserved at once
+    fn next_keys(&self, count: usize) -> Vec<Key> {
+        (0..count).map(|_| self.next_key()).collect()
+    }
+
+    fn remove(&self, key: &Binary) -> StorageResult<Option<Value>>;
 
-    fn insert_key(&self, key: Binary, row: Binary) -> Option<Value>;
+    fn insert_key(&self, key: Binary, row: Binary) -> StorageResult<Option<Value>>;
 
-    fn select(&self) -> Cursor;
+    fn select(&self) -> TreeScan;
 
-    fn insert(&self, data: Vec<Value>) -> Vec<Key>;
+    /// rows within the range in ascending key order
+    fn select_range(&self, range: KeyRange) -> TreeScan;
 
-    fn update(&self, data: Vec<(Key, Value)>) -> usize;
+    /// rows within the range in descending key order
+    fn select_range_rev(&self, range: KeyRange) -> TreeScan;
 
-    fn delete(&self, data: Vec<Key>) -> usize;
+    /// rows within the range in ascending key order with only values of `columns`,
+    /// see `VersionChain::project`. Backends that store columns apart do not read the other ones
+    fn select_columns(&self, range: KeyRange, columns: &[usize]) -> TreeScan {
+        let columns = columns.to_vec();
+        Box::new(self.select_range(range).map(move |row| {
+            let (key, chain) = row?;
+            let chain = VersionChain::decode(&chain)?.project(&columns)?;
+            Ok((key, chain.encode().unwrap_or_default()))
+        }))
+    }
+
+    fn insert(&self, data: Vec<Value>) -> StorageResult<Vec<Key>>;
+
+    fn update(&self, data: Vec<(Key, Value)>) -> StorageResult<usize>;
+
+    fn delete(&self, data: Vec<Key>) -> StorageResult<usize>;
+
+    /// keys that were written with `insert_key` or `apply_batch` are not returned
+    /// by `next_key` afterwards, e.g. after rows were loaded from a backup
+    fn reset_next_key(&self) -> StorageResult<()> {
+        Ok(())
+    }
+
+    /// applies all changes of the batch as one operation of the backend.
+    /// Backends that can't do it atomically fall back to changing keys one by one
+    fn apply_batch(&self, batch: WriteBatch) -> StorageResult<()> {
+        for (key, value) in batch {
+            match value {
+                None => self.remove(&key)?,
+                Some(value) => self.insert_key(key, value)?,
+            };
+        }
+        Ok(())
+    }
 }
 
-pub trait Storage {
-    type Tree: Tree;
+/// Turns values of indexed columns into keys of index entries. Index is scanned by a prefix
+/// of its columns, so keys of the first values have to be a prefix of keys of all of them
+pub trait IndexKeys: Debug + Send + Sync {
+    fn key(&self, values: &[Datum]) -> Key;
+}
 
-    fn lookup_tree<T: Into<String>>(&self, table: T) -> Self::Tree;
+/// index entries are keyed by values themselves
+#[derive(Debug)]
+pub struct PackedIndexKeys;
 
-    fn drop_tree<T: Into<String>>(&self, table: T);
+impl IndexKeys for PackedIndexKeys {
+    fn key(&self, values: &[Datum]) -> Key {
+        Binary::pack_key(values)
+    }
+}
+
+/// Storage engine that the database runs on. Implementations are used as `Box<dyn Storage>`
+/// and shared between all connections
+pub trait Storage: Send + Sync {
+    /// fails with `StorageError::TreeNotFound` if the tree was not created
+    fn lookup_tree(&self, name: &str) -> StorageResult<Box<dyn Tree>>;
+
+    fn drop_tree(&self, name: &str) -> StorageResult<()>;
+
+    /// `false` if the tree already exists, nothing is changed then
+    fn create_tree(&self, name: &str) -> StorageResult<bool>;
+
+    /// backends that can't lay out trees by columns keep all of them as rows
+    fn create_tree_with(&self, name: &str, _layout: TreeLayout) -> StorageResult<bool> {
+        self.create_tree(name)
+    }
 
-    fn create_tree<T: Into<String>>(&self, table: T);
+    /// layout that the tree was created with
+    fn tree_layout(&self, _name: &str) -> StorageResult<TreeLayout> {
+        Ok(TreeLayout::Rows)
+    }
+
+    /// names of all created trees
+    fn tree_names(&self) -> StorageResult<Vec<String>>;
+
+    /// backends that keep data private do not write indexed values into keys of index trees
+    fn index_keys(&self) -> Arc<dyn IndexKeys> {
+        Arc::new(PackedIndexKeys)
+    }
+
+    /// values are encrypted before they are written
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+
+    /// all changes made since the previous commit have to be recovered together or not at all.
+    /// `timestamp` is the logical time of the committed transaction
+    fn commit(&self, _timestamp: u64) -> StorageResult<()> {
+        Ok(())
+    }
+
+    /// blocks until committed changes are durable
+    fn sync(&self) -> StorageResult<()> {
+        Ok(())
+    }
 }
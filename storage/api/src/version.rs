@@ -0,0 +1,148 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::Value;
+use binary::{repr::Datum, Binary, UnpackError};
+use std::convert::TryInto;
+
+/// Logical time of the database. Every committed transaction increments it by one.
+pub type Timestamp = u64;
+
+const TIMESTAMP_SIZE: usize = std::mem::size_of::<Timestamp>();
+const LEN_SIZE: usize = std::mem::size_of::<u32>();
+
+const DELETED: u8 = 0;
+const PRESENT: u8 = 1;
+
+/// All committed versions of a single row, the newest one first.
+/// A version without a value marks the row as deleted at that point in time.
+/// Every value is stored in the on-disk row format of `binary` crate and checked on read.
+/// Trees store encoded chains as their values, backends that lay out rows by columns decode them
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct VersionChain {
+    versions: Vec<(Timestamp, Option<Value>)>,
+}
+
+impl VersionChain {
+    pub fn decode(data: &Binary) -> Result<VersionChain, UnpackError> {
+        fn take<'d>(data: &'d [u8], index: &mut usize, len: usize) -> Result<&'d [u8], UnpackError> {
+            let slice = data.get(*index..*index + len).ok_or(UnpackError::UnexpectedEnd)?;
+            *index += len;
+            Ok(slice)
+        }
+
+        let data = data.as_ref();
+        let mut index = 0;
+        let mut versions = vec![];
+        while index < data.len() {
+            let timestamp = Timestamp::from_le_bytes(take(data, &mut index, TIMESTAMP_SIZE)?.try_into().unwrap());
+            let value = match take(data, &mut index, 1)?[0] {
+                PRESENT => {
+                    let len = u32::from_le_bytes(take(data, &mut index, LEN_SIZE)?.try_into().unwrap()) as usize;
+                    Some(Binary::from_disk(take(data, &mut index, len)?)?)
+                }
+                DELETED => None,
+                flag => return Err(UnpackError::UnknownTypeTag(flag)),
+            };
+            versions.push((timestamp, value));
+        }
+        Ok(VersionChain { versions })
+    }
+
+    /// returns `None` when there is nothing to store
+    pub fn encode(&self) -> Option<Binary> {
+        if self.versions.is_empty() {
+            return None;
+        }
+        let mut data = vec![];
+        for (timestamp, value) in self.versions.iter() {
+            data.extend_from_slice(&timestamp.to_le_bytes());
+            match value {
+                None => data.push(DELETED),
+                Some(value) => {
+                    let value = value.to_disk();
+                    data.push(PRESENT);
+                    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                    data.extend_from_slice(&value);
+                }
+            }
+        }
+        Some(Binary::with_data(data))
+    }
+
+    /// versions of the row, the newest one first
+    pub fn versions(&self) -> &[(Timestamp, Option<Value>)] {
+        &self.versions
+    }
+
+    /// the same versions with only values of `columns`, see `project`
+    pub fn project(&self, columns: &[usize]) -> Result<VersionChain, UnpackError> {
+        let mut versions = vec![];
+        for (timestamp, value) in self.versions.iter() {
+            let value = match value {
+                None => None,
+                Some(value) => Some(project(value, columns)?),
+            };
+            versions.push((*timestamp, value));
+        }
+        Ok(VersionChain { versions })
+    }
+
+    pub fn latest(&self) -> Option<Timestamp> {
+        self.versions.first().map(|(timestamp, _value)| *timestamp)
+    }
+
+    /// the newest value that was committed before the snapshot was taken
+    pub fn visible_at(&self, snapshot: Timestamp) -> Option<&Value> {
+        self.versions
+            .iter()
+            .find(|(timestamp, _value)| *timestamp <= snapshot)
+            .and_then(|(_timestamp, value)| value.as_ref())
+    }
+
+    pub fn push(&mut self, timestamp: Timestamp, value: Option<Value>) {
+        debug_assert!(
+            self.latest().map(|latest| latest < timestamp).unwrap_or(true),
+            "versions has to be pushed in commit order"
+        );
+        self.versions.insert(0, (timestamp, value));
+    }
+
+    /// removes versions that none of running transactions can see.
+    /// `horizon` is the snapshot of the oldest running transaction
+    pub fn prune(&mut self, horizon: Timestamp) {
+        if let Some(oldest_visible) = self
+            .versions
+            .iter()
+            .position(|(timestamp, _value)| *timestamp <= horizon)
+        {
+            self.versions.truncate(oldest_visible + 1);
+            // nobody can tell apart deleted row from a row that has never existed
+            if let Some((_timestamp, None)) = self.versions.last() {
+                self.versions.pop();
+            }
+        }
+    }
+}
+
+/// values of `columns` of the row, in the order of `columns`.
+/// Rows that are shorter than a column have NULL in its place
+pub fn project(row: &Value, columns: &[usize]) -> Result<Value, UnpackError> {
+    let row = row.try_unpack()?;
+    let projected = columns
+        .iter()
+        .map(|column| row.get(*column).cloned().unwrap_or_else(Datum::from_null))
+        .collect::<Vec<_>>();
+    Ok(Binary::pack(&projected))
+}
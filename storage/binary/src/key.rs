@@ -0,0 +1,369 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Order preserving (memcomparable) encoding of keys.
+//!
+//! `Binary::pack` is compact but bytes of packed values do not sort the same way as
+//! the values themselves. Keys packed with `Binary::pack_key` can be compared byte by byte:
+//! the order of encoded keys is the same as the order of their `Datum`s column by column.
+
+use crate::{repr::Datum, Binary};
+use ordered_float::OrderedFloat;
+
+const NULL_LOW: u8 = 0x00;
+const FALSE: u8 = 0x02;
+const TRUE: u8 = 0x03;
+const I16: u8 = 0x04;
+const I32: u8 = 0x05;
+const I64: u8 = 0x06;
+const F32: u8 = 0x07;
+const F64: u8 = 0x08;
+const STR: u8 = 0x09;
+const NULL_HIGH: u8 = 0xFF;
+
+const STR_ESCAPE: u8 = 0x00;
+const STR_ESCAPED_ZERO: u8 = 0xFF;
+const STR_TERMINATOR: u8 = 0x01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Nulls {
+    First,
+    Last,
+}
+
+/// How a key column is ordered. Defaults are the same as in PostgreSQL:
+/// `NULL`s go last for ascending and first for descending columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortOrder {
+    direction: Direction,
+    nulls: Nulls,
+}
+
+impl SortOrder {
+    pub fn new(direction: Direction, nulls: Nulls) -> SortOrder {
+        SortOrder { direction, nulls }
+    }
+
+    pub fn asc() -> SortOrder {
+        SortOrder::new(Direction::Asc, Nulls::Last)
+    }
+
+    pub fn desc() -> SortOrder {
+        SortOrder::new(Direction::Desc, Nulls::First)
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn nulls(&self) -> Nulls {
+        self.nulls
+    }
+
+    /// descending columns are encoded as ascending ones with every byte inverted
+    fn mask(&self) -> u8 {
+        match self.direction {
+            Direction::Asc => 0x00,
+            Direction::Desc => 0xFF,
+        }
+    }
+
+    fn null_tag(&self) -> u8 {
+        match (self.direction, self.nulls) {
+            (Direction::Asc, Nulls::First) | (Direction::Desc, Nulls::Last) => NULL_LOW,
+            (Direction::Asc, Nulls::Last) | (Direction::Desc, Nulls::First) => NULL_HIGH,
+        }
+    }
+}
+
+impl Default for SortOrder {
+    fn default() -> SortOrder {
+        SortOrder::asc()
+    }
+}
+
+impl Binary {
+    /// packs all columns in ascending order
+    pub fn pack_key(datums: &[Datum]) -> Binary {
+        Binary::pack_key_with(datums, &[])
+    }
+
+    /// columns that don't have an explicit sort order are packed in ascending order
+    pub fn pack_key_with(datums: &[Datum], orders: &[SortOrder]) -> Binary {
+        let mut data = Vec::with_capacity(datums.iter().map(Datum::size).sum());
+        for (index, datum) in datums.iter().enumerate() {
+            let order = orders.get(index).copied().unwrap_or_default();
+            let start = data.len();
+            encode(&mut data, datum, order);
+            let mask = order.mask();
+            for byte in data[start..].iter_mut() {
+                *byte ^= mask;
+            }
+        }
+        Binary(data)
+    }
+
+    pub fn unpack_key(&self) -> Vec<Datum> {
+        self.unpack_key_with(&[])
+    }
+
+    pub fn unpack_key_with(&self, orders: &[SortOrder]) -> Vec<Datum> {
+        let mut reader = Reader {
+            data: self.0.as_slice(),
+            index: 0,
+            mask: 0,
+        };
+        let mut datums = vec![];
+        while reader.index < reader.data.len() {
+            let order = orders.get(datums.len()).copied().unwrap_or_default();
+            reader.mask = order.mask();
+            datums.push(reader.datum(order));
+        }
+        datums
+    }
+}
+
+fn encode(data: &mut Vec<u8>, datum: &Datum, order: SortOrder) {
+    match datum {
+        Datum::Null => data.push(order.null_tag()),
+        Datum::False => data.push(FALSE),
+        Datum::True => data.push(TRUE),
+        Datum::Int16(val) => {
+            data.push(I16);
+            data.extend_from_slice(&((*val as u16) ^ (1 << 15)).to_be_bytes());
+        }
+        Datum::Int32(val) => {
+            data.push(I32);
+            data.extend_from_slice(&((*val as u32) ^ (1 << 31)).to_be_bytes());
+        }
+        Datum::Int64(val) => {
+            data.push(I64);
+            data.extend_from_slice(&((*val as u64) ^ (1 << 63)).to_be_bytes());
+        }
+        Datum::Float32(val) => {
+            data.push(F32);
+            let bits = val.to_bits();
+            let bits = if bits >> 31 == 1 { !bits } else { bits ^ (1 << 31) };
+            data.extend_from_slice(&bits.to_be_bytes());
+        }
+        Datum::Float64(val) => {
+            data.push(F64);
+            let bits = val.to_bits();
+            let bits = if bits >> 63 == 1 { !bits } else { bits ^ (1 << 63) };
+            data.extend_from_slice(&bits.to_be_bytes());
+        }
+        Datum::String(val) => {
+            data.push(STR);
+            // zero byte is escaped so the terminator sorts before any other content
+            for byte in val.as_bytes() {
+                data.push(*byte);
+                if *byte == STR_ESCAPE {
+                    data.push(STR_ESCAPED_ZERO);
+                }
+            }
+            data.push(STR_ESCAPE);
+            data.push(STR_TERMINATOR);
+        }
+    }
+}
+
+struct Reader<'d> {
+    data: &'d [u8],
+    index: usize,
+    mask: u8,
+}
+
+impl<'d> Reader<'d> {
+    fn byte(&mut self) -> u8 {
+        let byte = self.data[self.index] ^ self.mask;
+        self.index += 1;
+        byte
+    }
+
+    fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0; N];
+        for byte in bytes.iter_mut() {
+            *byte = self.byte();
+        }
+        bytes
+    }
+
+    fn datum(&mut self, order: SortOrder) -> Datum {
+        match self.byte() {
+            tag if tag == order.null_tag() => Datum::from_null(),
+            FALSE => Datum::from_bool(false),
+            TRUE => Datum::from_bool(true),
+            I16 => Datum::from_i16((u16::from_be_bytes(self.bytes()) ^ (1 << 15)) as i16),
+            I32 => Datum::from_i32((u32::from_be_bytes(self.bytes()) ^ (1 << 31)) as i32),
+            I64 => Datum::from_i64((u64::from_be_bytes(self.bytes()) ^ (1 << 63)) as i64),
+            F32 => {
+                let bits = u32::from_be_bytes(self.bytes());
+                let bits = if bits >> 31 == 1 { bits ^ (1 << 31) } else { !bits };
+                Datum::Float32(OrderedFloat(f32::from_bits(bits)))
+            }
+            F64 => {
+                let bits = u64::from_be_bytes(self.bytes());
+                let bits = if bits >> 63 == 1 { bits ^ (1 << 63) } else { !bits };
+                Datum::Float64(OrderedFloat(f64::from_bits(bits)))
+            }
+            STR => {
+                let mut bytes = vec![];
+                loop {
+                    match self.byte() {
+                        STR_ESCAPE => match self.byte() {
+                            STR_TERMINATOR => break,
+                            _escaped_zero => bytes.push(STR_ESCAPE),
+                        },
+                        byte => bytes.push(byte),
+                    }
+                }
+                Datum::from_string(String::from_utf8(bytes).expect("key string is valid UTF-8"))
+            }
+            tag => panic!("unknown key tag {:?}", tag),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_sorted(datums: Vec<Datum>, order: SortOrder) {
+        let keys = datums
+            .iter()
+            .map(|datum| Binary::pack_key_with(std::slice::from_ref(datum), &[order]))
+            .collect::<Vec<_>>();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(sorted, keys);
+        for (datum, key) in datums.into_iter().zip(keys) {
+            assert_eq!(key.unpack_key_with(&[order]), vec![datum]);
+        }
+    }
+
+    #[test]
+    fn integers() {
+        assert_sorted(
+            vec![
+                Datum::from_i64(i64::MIN),
+                Datum::from_i64(-256),
+                Datum::from_i64(-1),
+                Datum::from_i64(0),
+                Datum::from_i64(1),
+                Datum::from_i64(256),
+                Datum::from_i64(i64::MAX),
+            ],
+            SortOrder::asc(),
+        );
+        assert_sorted(
+            vec![Datum::from_i16(-1), Datum::from_i16(0), Datum::from_i16(300)],
+            SortOrder::asc(),
+        );
+        assert_sorted(
+            vec![Datum::from_i32(-70_000), Datum::from_i32(-1), Datum::from_i32(70_000)],
+            SortOrder::asc(),
+        );
+    }
+
+    #[test]
+    fn floats() {
+        assert_sorted(
+            vec![
+                Datum::from_f64(f64::NEG_INFINITY),
+                Datum::from_f64(-100.5),
+                Datum::from_f64(-0.25),
+                Datum::from_f64(0.0),
+                Datum::from_f64(0.25),
+                Datum::from_f64(100.5),
+                Datum::from_f64(f64::INFINITY),
+            ],
+            SortOrder::asc(),
+        );
+        assert_sorted(
+            vec![Datum::from_f32(-1.5), Datum::from_f32(0.0), Datum::from_f32(1.5)],
+            SortOrder::asc(),
+        );
+    }
+
+    #[test]
+    fn strings() {
+        assert_sorted(
+            vec![
+                Datum::from_string("".to_owned()),
+                Datum::from_string("a".to_owned()),
+                Datum::from_string("a\0".to_owned()),
+                Datum::from_string("a\0b".to_owned()),
+                Datum::from_string("ab".to_owned()),
+                Datum::from_string("b".to_owned()),
+            ],
+            SortOrder::asc(),
+        );
+    }
+
+    #[test]
+    fn booleans_and_nulls() {
+        assert_sorted(
+            vec![Datum::from_bool(false), Datum::from_bool(true), Datum::from_null()],
+            SortOrder::asc(),
+        );
+        assert_sorted(
+            vec![Datum::from_null(), Datum::from_bool(false), Datum::from_bool(true)],
+            SortOrder::new(Direction::Asc, Nulls::First),
+        );
+    }
+
+    #[test]
+    fn descending() {
+        assert_sorted(
+            vec![
+                Datum::from_null(),
+                Datum::from_string("b".to_owned()),
+                Datum::from_string("ab".to_owned()),
+                Datum::from_string("a".to_owned()),
+            ],
+            SortOrder::desc(),
+        );
+        assert_sorted(
+            vec![Datum::from_i32(10), Datum::from_i32(-10), Datum::from_null()],
+            SortOrder::new(Direction::Desc, Nulls::Last),
+        );
+    }
+
+    #[test]
+    fn composite_keys() {
+        let orders = [SortOrder::asc(), SortOrder::desc()];
+        let keys = vec![
+            vec![Datum::from_string("a".to_owned()), Datum::from_i32(2)],
+            vec![Datum::from_string("a".to_owned()), Datum::from_i32(1)],
+            vec![Datum::from_string("ab".to_owned()), Datum::from_i32(3)],
+            vec![Datum::from_null(), Datum::from_i32(0)],
+        ];
+        let packed = keys
+            .iter()
+            .map(|key| Binary::pack_key_with(key, &orders))
+            .collect::<Vec<_>>();
+        let mut sorted = packed.clone();
+        sorted.sort();
+        assert_eq!(sorted, packed);
+        for (key, packed) in keys.into_iter().zip(packed) {
+            assert_eq!(packed.unpack_key_with(&orders), key);
+        }
+    }
+}
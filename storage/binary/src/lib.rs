@@ -13,9 +13,20 @@
 // limitations under the License.
 
 use crate::repr::Datum;
+use std::{
+    convert::TryInto,
+    fmt::{self, Display, Formatter},
+};
 
+pub mod key;
 pub mod repr;
 
+/// version of the on-disk row format. It is bumped on every incompatible change
+pub const FORMAT_VERSION: u8 = 1;
+
+const VERSION_SIZE: usize = std::mem::size_of::<u8>();
+const CHECKSUM_SIZE: usize = std::mem::size_of::<u32>();
+
 #[repr(u8)]
 enum TypeTag {
     Null = 0,
@@ -27,43 +38,80 @@ enum TypeTag {
     F32,
     F64,
     Str,
+    /// LZ4 block of a string value prefixed with the size of the value
+    CompressedStr,
 }
 
-fn assert_copy<T: Copy>(_t: T) {}
+impl TypeTag {
+    fn from_u8(tag: u8) -> Option<TypeTag> {
+        match tag {
+            0 => Some(TypeTag::Null),
+            1 => Some(TypeTag::True),
+            2 => Some(TypeTag::False),
+            3 => Some(TypeTag::I16),
+            4 => Some(TypeTag::I32),
+            5 => Some(TypeTag::I64),
+            6 => Some(TypeTag::F32),
+            7 => Some(TypeTag::F64),
+            8 => Some(TypeTag::Str),
+            9 => Some(TypeTag::CompressedStr),
+            _ => None,
+        }
+    }
+}
 
-fn push_tag(data: &mut Vec<u8>, tag: TypeTag) {
-    data.push(tag as u8);
+/// Data that can't be decoded. It is either corrupted or written by an incompatible version
+#[derive(Debug, PartialEq)]
+pub enum UnpackError {
+    UnsupportedFormat(u8),
+    ChecksumMismatch { expected: u32, actual: u32 },
+    UnexpectedEnd,
+    UnknownTypeTag(u8),
+    InvalidUtf8,
+    InvalidCompressedValue,
 }
 
-macro_rules! push_copy {
-    ($ptr:expr, $val:expr, $T:ty) => {{
-        let t = $val;
-        assert_copy(t);
-        $ptr.extend_from_slice(&unsafe { std::mem::transmute::<_, [u8; std::mem::size_of::<$T>()]>(t) })
-    }};
+impl Display for UnpackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UnpackError::UnsupportedFormat(version) => write!(f, "unsupported row format version {}", version),
+            UnpackError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "row checksum mismatch: expected {:#010x}, actual {:#010x}",
+                expected, actual
+            ),
+            UnpackError::UnexpectedEnd => write!(f, "row data ended unexpectedly"),
+            UnpackError::UnknownTypeTag(tag) => write!(f, "unknown type tag {}", tag),
+            UnpackError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            UnpackError::InvalidCompressedValue => write!(f, "compressed value can't be decompressed"),
+        }
+    }
 }
 
-unsafe fn read<T>(data: &[u8], idx: &mut usize) -> T {
-    debug_assert!(data.len() >= *idx + std::mem::size_of::<T>());
-    let ptr = data.as_ptr().add(*idx);
-    *idx += std::mem::size_of::<T>();
-    (ptr as *const T).read_unaligned()
+/// String values of a row that are longer than `threshold` bytes are compressed with LZ4,
+/// values that do not get smaller are kept as they are
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Compression {
+    pub threshold: usize,
 }
 
-unsafe fn read_string<'a>(data: &'a [u8], idx: &mut usize) -> &'a str {
-    let len = read::<usize>(data, idx);
-    let data = &data[*idx..*idx + len];
-    *idx += len;
-    std::str::from_utf8_unchecked(data)
+impl Default for Compression {
+    /// the same threshold as values of PostgreSQL rows are compressed after
+    fn default() -> Compression {
+        Compression { threshold: 2048 }
+    }
 }
 
-fn read_tag(data: &[u8], idx: &mut usize) -> TypeTag {
-    unsafe { read::<TypeTag>(data, idx) }
+/// Compressed values of a row
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CompressionStats {
+    pub values: u64,
+    /// difference between sizes of values before and after compression
+    pub bytes_saved: u64,
 }
 
-/// in-memory runtime representation of a table row. It is unable to deserialize
-/// the row without knowing the types of each column, which makes this unsafe
-/// however it is more memory efficient.
+/// Packed row. Values are written in little-endian byte order with their type tags
+/// so data can be moved between machines.
 #[derive(Debug, Clone, PartialEq, Eq, Default, PartialOrd, Ord)]
 pub struct Binary(Vec<u8>);
 
@@ -88,94 +136,201 @@ impl Binary {
     }
 
     pub fn pack(other: &[Datum]) -> Binary {
+        Binary::pack_with(other, None)
+    }
+
+    /// packs the row compressing its long string values
+    pub fn pack_compressed(other: &[Datum], compression: Compression) -> Binary {
+        Binary::pack_with(other, Some(compression))
+    }
+
+    /// the same row with long string values compressed
+    pub fn compress(&self, compression: Compression) -> Binary {
+        Binary::pack_compressed(&self.unpack(), compression)
+    }
+
+    fn pack_with(other: &[Datum], compression: Option<Compression>) -> Binary {
         let size = other.iter().map(Datum::size).sum();
         let mut data = Vec::with_capacity(size);
         for datum in other {
             match datum {
-                Datum::True => {
-                    push_tag(&mut data, TypeTag::True);
-                }
-                Datum::False => {
-                    push_tag(&mut data, TypeTag::False);
-                }
+                Datum::True => data.push(TypeTag::True as u8),
+                Datum::False => data.push(TypeTag::False as u8),
                 Datum::Int16(val) => {
-                    push_tag(&mut data, TypeTag::I16);
-                    push_copy!(&mut data, *val, i16);
+                    data.push(TypeTag::I16 as u8);
+                    data.extend_from_slice(&val.to_le_bytes());
                 }
                 Datum::Int32(val) => {
-                    push_tag(&mut data, TypeTag::I32);
-                    push_copy!(&mut data, *val, i32);
+                    data.push(TypeTag::I32 as u8);
+                    data.extend_from_slice(&val.to_le_bytes());
                 }
                 Datum::Int64(val) => {
-                    push_tag(&mut data, TypeTag::I64);
-                    push_copy!(&mut data, *val, i64);
+                    data.push(TypeTag::I64 as u8);
+                    data.extend_from_slice(&val.to_le_bytes());
                 }
                 Datum::Float32(val) => {
-                    push_tag(&mut data, TypeTag::F32);
-                    push_copy!(&mut data, **val, f32)
+                    data.push(TypeTag::F32 as u8);
+                    data.extend_from_slice(&val.to_le_bytes());
                 }
                 Datum::Float64(val) => {
-                    push_tag(&mut data, TypeTag::F64);
-                    push_copy!(&mut data, **val, f64)
+                    data.push(TypeTag::F64 as u8);
+                    data.extend_from_slice(&val.to_le_bytes());
                 }
                 Datum::String(val) => {
-                    push_tag(&mut data, TypeTag::Str);
-                    push_copy!(&mut data, val.len(), usize);
-                    data.extend_from_slice(val.as_bytes());
+                    let compressed = match compression {
+                        Some(compression) if val.len() > compression.threshold => {
+                            Some(lz4_flex::compress_prepend_size(val.as_bytes()))
+                                .filter(|compressed| compressed.len() < val.len())
+                        }
+                        _ => None,
+                    };
+                    match compressed {
+                        Some(compressed) => {
+                            data.push(TypeTag::CompressedStr as u8);
+                            data.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+                            data.extend_from_slice(&compressed);
+                        }
+                        None => {
+                            data.push(TypeTag::Str as u8);
+                            data.extend_from_slice(&(val.len() as u32).to_le_bytes());
+                            data.extend_from_slice(val.as_bytes());
+                        }
+                    }
                 }
-                Datum::Null => push_tag(&mut data, TypeTag::Null),
+                Datum::Null => data.push(TypeTag::Null as u8),
             }
         }
 
         Self(data)
     }
 
+    /// panics on corrupted data, use [`Binary::try_unpack`] for data that was read from disk
     pub fn unpack(&self) -> Vec<Datum> {
-        unpack_raw(self.0.as_slice())
+        match self.try_unpack() {
+            Ok(datums) => datums,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    pub fn try_unpack(&self) -> Result<Vec<Datum>, UnpackError> {
+        let mut reader = Reader {
+            data: self.0.as_slice(),
+            index: 0,
+        };
+        let mut res = Vec::new();
+        while reader.index < reader.data.len() {
+            let tag = reader.byte()?;
+            let datum = match TypeTag::from_u8(tag).ok_or(UnpackError::UnknownTypeTag(tag))? {
+                TypeTag::Null => Datum::from_null(),
+                TypeTag::True => Datum::from_bool(true),
+                TypeTag::False => Datum::from_bool(false),
+                TypeTag::I16 => Datum::from_i16(i16::from_le_bytes(reader.bytes()?)),
+                TypeTag::I32 => Datum::from_i32(i32::from_le_bytes(reader.bytes()?)),
+                TypeTag::I64 => Datum::from_i64(i64::from_le_bytes(reader.bytes()?)),
+                TypeTag::F32 => Datum::from_f32(f32::from_le_bytes(reader.bytes()?)),
+                TypeTag::F64 => Datum::from_f64(f64::from_le_bytes(reader.bytes()?)),
+                TypeTag::Str => {
+                    let len = u32::from_le_bytes(reader.bytes()?) as usize;
+                    let val = std::str::from_utf8(reader.slice(len)?).map_err(|_| UnpackError::InvalidUtf8)?;
+                    Datum::from_string(val.to_owned())
+                }
+                TypeTag::CompressedStr => {
+                    let len = u32::from_le_bytes(reader.bytes()?) as usize;
+                    let val = lz4_flex::decompress_size_prepended(reader.slice(len)?)
+                        .map_err(|_| UnpackError::InvalidCompressedValue)?;
+                    Datum::from_string(String::from_utf8(val).map_err(|_| UnpackError::InvalidUtf8)?)
+                }
+            };
+            res.push(datum)
+        }
+        Ok(res)
+    }
+
+    /// compressed values of the row, they are counted without decompression
+    pub fn compression_stats(&self) -> Result<CompressionStats, UnpackError> {
+        let mut reader = Reader {
+            data: self.0.as_slice(),
+            index: 0,
+        };
+        let mut stats = CompressionStats::default();
+        while reader.index < reader.data.len() {
+            let tag = reader.byte()?;
+            let size = match TypeTag::from_u8(tag).ok_or(UnpackError::UnknownTypeTag(tag))? {
+                TypeTag::Null | TypeTag::True | TypeTag::False => 0,
+                TypeTag::I16 => std::mem::size_of::<i16>(),
+                TypeTag::I32 | TypeTag::F32 => std::mem::size_of::<i32>(),
+                TypeTag::I64 | TypeTag::F64 => std::mem::size_of::<i64>(),
+                TypeTag::Str => u32::from_le_bytes(reader.bytes()?) as usize,
+                TypeTag::CompressedStr => {
+                    let len = u32::from_le_bytes(reader.bytes()?) as usize;
+                    let original = u32::from_le_bytes(reader.bytes()?) as u64;
+                    stats.values += 1;
+                    stats.bytes_saved += original.saturating_sub(len as u64);
+                    len.checked_sub(std::mem::size_of::<u32>())
+                        .ok_or(UnpackError::InvalidCompressedValue)?
+                }
+            };
+            reader.slice(size)?;
+        }
+        Ok(stats)
     }
 
     pub fn starts_with(&self, other: &Binary) -> bool {
         self.0.starts_with(&other.0)
     }
+
+    /// row as it is written to disk: format version, checksum of the row and the row itself
+    pub fn to_disk(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(VERSION_SIZE + CHECKSUM_SIZE + self.0.len());
+        data.push(FORMAT_VERSION);
+        data.extend_from_slice(&checksum(&self.0).to_le_bytes());
+        data.extend_from_slice(&self.0);
+        data
+    }
+
+    pub fn from_disk(data: &[u8]) -> Result<Binary, UnpackError> {
+        let mut reader = Reader { data, index: 0 };
+        let version = reader.byte()?;
+        if version != FORMAT_VERSION {
+            return Err(UnpackError::UnsupportedFormat(version));
+        }
+        let expected = u32::from_le_bytes(reader.bytes()?);
+        let row = &data[reader.index..];
+        let actual = checksum(row);
+        if expected != actual {
+            return Err(UnpackError::ChecksumMismatch { expected, actual });
+        }
+        Ok(Binary(row.to_vec()))
+    }
 }
 
-fn unpack_raw(data: &[u8]) -> Vec<Datum> {
-    let mut index = 0;
-    let mut res = Vec::new();
-    while index < data.len() {
-        let tag = read_tag(data, &mut index);
-        let datum = match tag {
-            TypeTag::Null => Datum::from_null(),
-            TypeTag::True => Datum::from_bool(true),
-            TypeTag::False => Datum::from_bool(false),
-            TypeTag::Str => {
-                let val = unsafe { read_string(data, &mut index) };
-                Datum::String(val.to_owned())
-            }
-            TypeTag::I16 => {
-                let val = unsafe { read::<i16>(data, &mut index) };
-                Datum::from_i16(val)
-            }
-            TypeTag::I32 => {
-                let val = unsafe { read::<i32>(data, &mut index) };
-                Datum::from_i32(val)
-            }
-            TypeTag::I64 => {
-                let val = unsafe { read::<i64>(data, &mut index) };
-                Datum::from_i64(val)
-            }
-            TypeTag::F32 => {
-                let val = unsafe { read::<f32>(data, &mut index) };
-                Datum::from_f32(val)
-            }
-            TypeTag::F64 => {
-                let val = unsafe { read::<f64>(data, &mut index) };
-                Datum::from_f64(val)
-            }
-        };
-        res.push(datum)
+fn checksum(data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Bounds checked reading of packed data
+struct Reader<'d> {
+    data: &'d [u8],
+    index: usize,
+}
+
+impl<'d> Reader<'d> {
+    fn byte(&mut self) -> Result<u8, UnpackError> {
+        Ok(self.slice(1)?[0])
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], UnpackError> {
+        Ok(self.slice(N)?.try_into().unwrap())
+    }
+
+    fn slice(&mut self, len: usize) -> Result<&'d [u8], UnpackError> {
+        let end = self.index.checked_add(len).ok_or(UnpackError::UnexpectedEnd)?;
+        let slice = self.data.get(self.index..end).ok_or(UnpackError::UnexpectedEnd)?;
+        self.index = end;
+        Ok(slice)
     }
-    res
 }
 
 #[cfg(test)]
@@ -237,4 +392,161 @@ mod tests {
             );
         }
     }
+
+    #[cfg(test)]
+    mod compression {
+        use super::*;
+
+        fn long_string() -> Datum {
+            Datum::from_string("a".repeat(100))
+        }
+
+        #[test]
+        fn long_strings_are_compressed() {
+            let data = vec![
+                Datum::from_i32(1),
+                long_string(),
+                Datum::from_string("short".to_owned()),
+            ];
+            let row = Binary::pack_compressed(&data, Compression { threshold: 10 });
+
+            assert!(row.to_bytes().len() < Binary::pack(&data).to_bytes().len());
+            assert_eq!(row.unpack(), data);
+        }
+
+        #[test]
+        fn strings_under_threshold_are_not_compressed() {
+            let data = vec![long_string()];
+
+            assert_eq!(
+                Binary::pack_compressed(&data, Compression { threshold: 100 }),
+                Binary::pack(&data)
+            );
+        }
+
+        #[test]
+        fn incompressible_strings_are_kept() {
+            let data = vec![Datum::from_string("abcdefghijklmnopqrstuvwxyz".to_owned())];
+
+            assert_eq!(
+                Binary::pack_compressed(&data, Compression { threshold: 10 }),
+                Binary::pack(&data)
+            );
+        }
+
+        #[test]
+        fn compress_packed_row() {
+            let row = Binary::pack(&[long_string(), Datum::from_i64(1)]);
+
+            assert_eq!(
+                row.compress(Compression { threshold: 10 }),
+                Binary::pack_compressed(&row.unpack(), Compression { threshold: 10 })
+            );
+        }
+
+        #[test]
+        fn stats() {
+            let data = vec![long_string(), Datum::from_null(), long_string(), Datum::from_f64(1.0)];
+            let row = Binary::pack_compressed(&data, Compression { threshold: 10 });
+
+            let stats = row.compression_stats().unwrap();
+            assert_eq!(stats.values, 2);
+            assert_eq!(
+                stats.bytes_saved as usize,
+                Binary::pack(&data).to_bytes().len() - row.to_bytes().len()
+            );
+            assert_eq!(Binary::pack(&data).compression_stats(), Ok(CompressionStats::default()));
+        }
+
+        #[test]
+        fn corrupted_value() {
+            let mut data = Binary::pack_compressed(&[long_string()], Compression { threshold: 10 }).0;
+            // token of the first LZ4 sequence
+            data[9] = 0xFF;
+
+            assert_eq!(
+                Binary::with_data(data).try_unpack(),
+                Err(UnpackError::InvalidCompressedValue)
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod corrupted_data {
+        use super::*;
+
+        #[test]
+        fn unknown_type_tag() {
+            assert_eq!(
+                Binary::with_data(vec![100]).try_unpack(),
+                Err(UnpackError::UnknownTypeTag(100))
+            );
+        }
+
+        #[test]
+        fn truncated_value() {
+            let mut data = Binary::pack(&[Datum::from_i64(10_000)]).0;
+            data.pop();
+            assert_eq!(Binary::with_data(data).try_unpack(), Err(UnpackError::UnexpectedEnd));
+        }
+
+        #[test]
+        fn string_longer_than_data() {
+            let mut data = Binary::pack(&[Datum::from_string("string".to_owned())]).0;
+            data[1] = 100;
+            assert_eq!(Binary::with_data(data).try_unpack(), Err(UnpackError::UnexpectedEnd));
+        }
+
+        #[test]
+        fn invalid_utf8() {
+            let mut data = Binary::pack(&[Datum::from_string("string".to_owned())]).0;
+            data[5] = 0xFF;
+            assert_eq!(Binary::with_data(data).try_unpack(), Err(UnpackError::InvalidUtf8));
+        }
+    }
+
+    #[cfg(test)]
+    mod disk_format {
+        use super::*;
+
+        #[test]
+        fn little_endian() {
+            assert_eq!(
+                Binary::pack(&[Datum::from_i32(1), Datum::from_string("a".to_owned())]).to_bytes(),
+                &[TypeTag::I32 as u8, 1, 0, 0, 0, TypeTag::Str as u8, 1, 0, 0, 0, b'a']
+            );
+        }
+
+        #[test]
+        fn round_trip() {
+            let row = Binary::pack(&[Datum::from_i16(1), Datum::from_string("string".to_owned())]);
+            assert_eq!(Binary::from_disk(&row.to_disk()), Ok(row));
+        }
+
+        #[test]
+        fn unsupported_format() {
+            let mut data = Binary::pack(&[Datum::from_i16(1)]).to_disk();
+            data[0] = FORMAT_VERSION + 1;
+            assert_eq!(
+                Binary::from_disk(&data),
+                Err(UnpackError::UnsupportedFormat(FORMAT_VERSION + 1))
+            );
+        }
+
+        #[test]
+        fn checksum_mismatch() {
+            let mut data = Binary::pack(&[Datum::from_i16(1)]).to_disk();
+            let last = data.len() - 1;
+            data[last] ^= 0xFF;
+            assert!(matches!(
+                Binary::from_disk(&data),
+                Err(UnpackError::ChecksumMismatch { .. })
+            ));
+        }
+
+        #[test]
+        fn truncated_header() {
+            assert_eq!(Binary::from_disk(&[FORMAT_VERSION, 0]), Err(UnpackError::UnexpectedEnd));
+        }
+    }
 }
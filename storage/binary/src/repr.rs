@@ -43,7 +43,7 @@ impl Datum {
             Self::Int64(_) => 1 + std::mem::size_of::<i64>(),
             Self::Float32(_) => 1 + std::mem::size_of::<f32>(),
             Self::Float64(_) => 1 + std::mem::size_of::<f64>(),
-            Self::String(val) => 1 + std::mem::size_of::<usize>() + val.len(),
+            Self::String(val) => 1 + std::mem::size_of::<u32>() + val.len(),
         }
     }
 
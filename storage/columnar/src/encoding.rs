@@ -0,0 +1,280 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use binary::{repr::Datum, Binary};
+use std::collections::HashMap;
+use storage_api::{StorageError, StorageResult};
+
+const PLAIN: u8 = 0;
+const RUN_LENGTH: u8 = 1;
+const DICTIONARY: u8 = 2;
+const DELTA: u8 = 3;
+
+/// Compresses values of a column with whichever encoding takes the least space:
+/// * plain - values are packed one after another
+/// * run-length - a value is stored once for every run of equal values together with its length
+/// * dictionary - distinct values are stored once and rows refer to them by their number
+/// * delta - integers of the same width are stored as differences with the previous value
+///
+/// Encoded column starts with a tag of its encoding, numbers are written as LEB128 varints
+pub(crate) fn encode(values: &[Datum]) -> Vec<u8> {
+    let mut candidates = vec![plain(values), run_length(values), dictionary(values)];
+    candidates.extend(delta(values));
+    candidates
+        .into_iter()
+        .min_by_key(Vec::len)
+        .expect("plain encoding is always possible")
+}
+
+pub(crate) fn decode(data: &[u8]) -> StorageResult<Vec<Datum>> {
+    let mut index = 1;
+    match data.first() {
+        Some(&PLAIN) => unpack(&data[index..]),
+        Some(&RUN_LENGTH) => {
+            let runs = varint(data, &mut index)? as usize;
+            let mut lengths = Vec::with_capacity(runs);
+            for _ in 0..runs {
+                lengths.push(varint(data, &mut index)? as usize);
+            }
+            let heads = unpack(&data[index..])?;
+            if heads.len() != runs {
+                return Err(corrupted("number of runs does not match"));
+            }
+            Ok(heads
+                .into_iter()
+                .zip(lengths)
+                .flat_map(|(value, length)| std::iter::repeat_n(value, length))
+                .collect())
+        }
+        Some(&DICTIONARY) => {
+            let rows = varint(data, &mut index)? as usize;
+            let mut codes = Vec::with_capacity(rows);
+            for _ in 0..rows {
+                codes.push(varint(data, &mut index)? as usize);
+            }
+            let dictionary = unpack(&data[index..])?;
+            codes
+                .into_iter()
+                .map(|code| {
+                    dictionary
+                        .get(code)
+                        .cloned()
+                        .ok_or_else(|| corrupted("value is not in dictionary"))
+                })
+                .collect()
+        }
+        Some(&DELTA) => {
+            let width = *data.get(index).ok_or_else(|| corrupted("ended unexpectedly"))?;
+            index += 1;
+            let rows = varint(data, &mut index)? as usize;
+            let mut values = Vec::with_capacity(rows);
+            let mut current = 0i64;
+            for _ in 0..rows {
+                current = current.wrapping_add(unzigzag(varint(data, &mut index)?));
+                values.push(match width {
+                    2 => Datum::from_i16(current as i16),
+                    4 => Datum::from_i32(current as i32),
+                    8 => Datum::from_i64(current),
+                    _ => return Err(corrupted("has unknown integer width")),
+                });
+            }
+            Ok(values)
+        }
+        Some(tag) => Err(corrupted(&format!("has unknown encoding {}", tag))),
+        None => Err(corrupted("is empty")),
+    }
+}
+
+fn plain(values: &[Datum]) -> Vec<u8> {
+    let mut data = vec![PLAIN];
+    data.extend_from_slice(Binary::pack(values).as_ref());
+    data
+}
+
+fn run_length(values: &[Datum]) -> Vec<u8> {
+    let mut runs: Vec<(&Datum, u64)> = vec![];
+    for value in values {
+        match runs.last_mut() {
+            Some((last, length)) if *last == value => *length += 1,
+            _ => runs.push((value, 1)),
+        }
+    }
+    let mut data = vec![RUN_LENGTH];
+    put_varint(&mut data, runs.len() as u64);
+    for (_value, length) in runs.iter() {
+        put_varint(&mut data, *length);
+    }
+    let heads = runs
+        .into_iter()
+        .map(|(value, _length)| value.clone())
+        .collect::<Vec<_>>();
+    data.extend_from_slice(Binary::pack(&heads).as_ref());
+    data
+}
+
+fn dictionary(values: &[Datum]) -> Vec<u8> {
+    let mut codes = HashMap::new();
+    let mut dictionary = vec![];
+    let mut data = vec![DICTIONARY];
+    put_varint(&mut data, values.len() as u64);
+    for value in values {
+        let code = *codes.entry(value).or_insert_with(|| {
+            dictionary.push(value.clone());
+            dictionary.len() as u64 - 1
+        });
+        put_varint(&mut data, code);
+    }
+    data.extend_from_slice(Binary::pack(&dictionary).as_ref());
+    data
+}
+
+/// `None` unless all values are integers of the same width
+fn delta(values: &[Datum]) -> Option<Vec<u8>> {
+    let width = match values.first()? {
+        Datum::Int16(_) => 2,
+        Datum::Int32(_) => 4,
+        Datum::Int64(_) => 8,
+        _ => return None,
+    };
+    let mut data = vec![DELTA, width];
+    put_varint(&mut data, values.len() as u64);
+    let mut previous = 0i64;
+    for value in values {
+        let current = match (value, width) {
+            (Datum::Int16(value), 2) => *value as i64,
+            (Datum::Int32(value), 4) => *value as i64,
+            (Datum::Int64(value), 8) => *value,
+            _ => return None,
+        };
+        put_varint(&mut data, zigzag(current.wrapping_sub(previous)));
+        previous = current;
+    }
+    Some(data)
+}
+
+fn unpack(data: &[u8]) -> StorageResult<Vec<Datum>> {
+    Ok(Binary::with_data(data.to_vec()).try_unpack()?)
+}
+
+pub(crate) fn put_varint(data: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+pub(crate) fn varint(data: &[u8], index: &mut usize) -> StorageResult<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*index).ok_or_else(|| corrupted("ended unexpectedly"))?;
+        *index += 1;
+        if shift >= 64 {
+            return Err(corrupted("has too long number"));
+        }
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+/// small negative numbers become small positive ones
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+pub(crate) fn corrupted(message: &str) -> StorageError {
+    StorageError::Corrupted(format!("column segment {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integers(values: &[i64]) -> Vec<Datum> {
+        values.iter().map(|value| Datum::from_i64(*value)).collect()
+    }
+
+    fn strings(values: &[&str]) -> Vec<Datum> {
+        values
+            .iter()
+            .map(|value| Datum::from_string((*value).to_owned()))
+            .collect()
+    }
+
+    fn round_trip(values: Vec<Datum>) -> u8 {
+        let data = encode(&values);
+        assert_eq!(decode(&data), Ok(values));
+        data[0]
+    }
+
+    #[test]
+    fn repeated_values_are_run_length_encoded() {
+        let mut values = strings(&["a"; 100]);
+        values.extend(strings(&["b"; 100]));
+        assert_eq!(round_trip(values), RUN_LENGTH);
+    }
+
+    #[test]
+    fn few_distinct_values_are_dictionary_encoded() {
+        let values = (0..100)
+            .map(|index| ["first value", "second value", "third value"][index % 3])
+            .collect::<Vec<_>>();
+        assert_eq!(round_trip(strings(&values)), DICTIONARY);
+    }
+
+    #[test]
+    fn growing_integers_are_delta_encoded() {
+        let values = (0..100).map(|value| 1_000_000 + value * 3).collect::<Vec<_>>();
+        assert_eq!(round_trip(integers(&values)), DELTA);
+    }
+
+    #[test]
+    fn integers_keep_their_width() {
+        let values = vec![Datum::from_i16(i16::MIN), Datum::from_i16(0), Datum::from_i16(i16::MAX)];
+        round_trip(values);
+        let values = vec![Datum::from_i32(i32::MAX), Datum::from_i32(i32::MIN)];
+        round_trip(values);
+        round_trip(integers(&[i64::MIN, i64::MAX, 0]));
+    }
+
+    #[test]
+    fn mixed_values() {
+        round_trip(vec![
+            Datum::from_i16(1),
+            Datum::from_null(),
+            Datum::from_string("value".to_owned()),
+            Datum::from_bool(true),
+            Datum::from_f64(1.5),
+            Datum::from_i64(1),
+        ]);
+    }
+
+    #[test]
+    fn empty_column() {
+        round_trip(vec![]);
+    }
+
+    #[test]
+    fn unknown_encoding() {
+        assert!(matches!(decode(&[42]), Err(StorageError::Corrupted(_))));
+    }
+}
@@ -0,0 +1,842 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::encoding::{corrupted, put_varint, varint};
+use binary::{repr::Datum, Binary};
+use std::{
+    cmp::Ordering as KeyOrdering,
+    collections::{hash_map::Entry, BTreeSet, HashMap},
+    convert::TryInto,
+    iter::Peekable,
+    ops::{Bound, RangeBounds},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    vec,
+};
+use storage_api::{
+    version::VersionChain, IndexKeys, Key, KeyRange, Storage, StorageResult, Tree, TreeLayout, TreeScan, Value,
+    WriteBatch,
+};
+
+mod encoding;
+
+/// names of trees that are laid out by columns
+const LAYOUTS_TREE: &str = "__COLUMNAR";
+/// number of rows that are moved from delta into a stripe at once
+const STRIPE_ROWS: usize = 1024;
+
+/// prefix of keys of delta rows in a backend tree
+const DELTA: u8 = 0;
+/// prefix of keys of stripe parts in a backend tree
+const STRIPE: u8 = 1;
+
+/// keys, commit timestamps and number of values of rows of a stripe
+const HEADER: u32 = 0;
+/// rows of a stripe that were changed after the stripe was written
+const REMOVED: u32 = 1;
+/// values of the first column of a stripe, the other columns follow it
+const FIRST_COLUMN: u32 = 2;
+
+/// Keeps trees that were created with `TreeLayout::Columns` column by column in another storage
+/// backend. Other trees are stored by the backend as they are.
+///
+/// Rows of a column oriented tree are written into a delta first. Once enough of them are written
+/// after the last stripe they are moved into a new stripe, where values of every column are compressed
+/// together and stored under a key of their own, so a scan reads only the columns that it needs.
+/// A row that is changed after it was moved into a stripe is marked as removed there and is written
+/// into the delta again. Rows that have more than one version are kept in the delta
+pub struct ColumnarStorage {
+    base: Arc<dyn Storage>,
+    /// column oriented trees
+    trees: RwLock<HashMap<String, Arc<TreeState>>>,
+}
+
+impl ColumnarStorage {
+    pub fn new(base: Box<dyn Storage>) -> StorageResult<ColumnarStorage> {
+        let base: Arc<dyn Storage> = Arc::from(base);
+        base.create_tree(LAYOUTS_TREE)?;
+        let mut trees = HashMap::new();
+        for row in base.lookup_tree(LAYOUTS_TREE)?.select() {
+            let (name, _empty) = row?;
+            let name =
+                String::from_utf8(name.to_bytes().to_vec()).map_err(|_| corrupted("tree name is not valid UTF-8"))?;
+            let state = TreeState::load(base.lookup_tree(&name)?.as_ref())?;
+            trees.insert(name, Arc::new(state));
+        }
+        Ok(ColumnarStorage {
+            base,
+            trees: RwLock::new(trees),
+        })
+    }
+
+    fn state(&self, name: &str) -> Option<Arc<TreeState>> {
+        self.trees.read().unwrap().get(name).cloned()
+    }
+}
+
+impl Storage for ColumnarStorage {
+    fn lookup_tree(&self, name: &str) -> StorageResult<Box<dyn Tree>> {
+        let base = self.base.lookup_tree(name)?;
+        match self.state(name) {
+            None => Ok(base),
+            Some(state) => Ok(Box::new(ColumnarTree {
+                name: name.to_owned(),
+                base,
+                storage: self.base.clone(),
+                state,
+            })),
+        }
+    }
+
+    fn drop_tree(&self, name: &str) -> StorageResult<()> {
+        self.base.drop_tree(name)?;
+        if self.trees.write().unwrap().remove(name).is_some() {
+            self.base.lookup_tree(LAYOUTS_TREE)?.remove(&layout_key(name))?;
+        }
+        Ok(())
+    }
+
+    fn create_tree(&self, name: &str) -> StorageResult<bool> {
+        self.base.create_tree(name)
+    }
+
+    fn create_tree_with(&self, name: &str, layout: TreeLayout) -> StorageResult<bool> {
+        if layout == TreeLayout::Rows {
+            return self.create_tree(name);
+        }
+        let mut trees = self.trees.write().unwrap();
+        if trees.contains_key(name) || !self.base.create_tree(name)? {
+            return Ok(false);
+        }
+        self.base
+            .lookup_tree(LAYOUTS_TREE)?
+            .insert_key(layout_key(name), Binary::new())?;
+        trees.insert(name.to_owned(), Arc::default());
+        Ok(true)
+    }
+
+    fn tree_layout(&self, name: &str) -> StorageResult<TreeLayout> {
+        if self.trees.read().unwrap().contains_key(name) {
+            Ok(TreeLayout::Columns)
+        } else {
+            Ok(TreeLayout::Rows)
+        }
+    }
+
+    fn tree_names(&self) -> StorageResult<Vec<String>> {
+        Ok(self
+            .base
+            .tree_names()?
+            .into_iter()
+            .filter(|name| name != LAYOUTS_TREE)
+            .collect())
+    }
+
+    fn index_keys(&self) -> Arc<dyn IndexKeys> {
+        self.base.index_keys()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.base.is_encrypted()
+    }
+
+    fn commit(&self, timestamp: u64) -> StorageResult<()> {
+        self.base.commit(timestamp)
+    }
+
+    fn sync(&self) -> StorageResult<()> {
+        self.base.sync()
+    }
+}
+
+/// Shared by all handles of a column oriented tree
+#[derive(Debug, Default)]
+struct TreeState {
+    layout: RwLock<Layout>,
+    next_key: AtomicU64,
+    /// scans that can read stripes, stripes without rows are not deleted while there are any
+    scans: AtomicUsize,
+}
+
+impl TreeState {
+    fn load(base: &dyn Tree) -> StorageResult<TreeState> {
+        let mut layout = Layout::default();
+        // only headers are read, values of columns are skipped over
+        while let Some(row) = base
+            .select_range(KeyRange::new(
+                Bound::Included(segment_key(layout.next_stripe, HEADER)),
+                Bound::Excluded(Binary::with_data(vec![STRIPE + 1])),
+            ))
+            .next()
+        {
+            let (key, data) = row?;
+            let (id, part) = parse_segment_key(&key)?;
+            if part != HEADER {
+                return Err(corrupted("does not have a header"));
+            }
+            let header = Header::decode(&data)?;
+            let removed = match base.get(&segment_key(id, REMOVED))? {
+                None => vec![false; header.keys.len()],
+                Some(data) => decode_removed(&data, header.keys.len())?,
+            };
+            layout.stripes.push(Stripe {
+                id,
+                first: header.keys.first().cloned().unwrap_or_default(),
+                last: header.keys.last().cloned().unwrap_or_default(),
+                columns: header.columns,
+                removed,
+            });
+            layout.next_stripe = id + 1;
+        }
+        for row in base.select_range(delta_range(&KeyRange::all())) {
+            let (key, _chain) = row?;
+            if layout
+                .stripes
+                .last()
+                .map(|stripe| delta_key(&stripe.last) < key)
+                .unwrap_or(true)
+            {
+                layout.pending += 1;
+            }
+        }
+        let state = TreeState {
+            layout: RwLock::new(layout),
+            ..TreeState::default()
+        };
+        state.reset_next_key(base)?;
+        Ok(state)
+    }
+
+    fn reset_next_key(&self, base: &dyn Tree) -> StorageResult<()> {
+        let layout = self.layout.read().unwrap();
+        let mut next_key = layout
+            .stripes
+            .iter()
+            .filter_map(|stripe| record_id(&stripe.last))
+            .max()
+            .map(|id| id + 1)
+            .unwrap_or_default();
+        for row in base.select_range(delta_range(&KeyRange::all())) {
+            let (key, _chain) = row?;
+            if let Some(id) = record_id(&Binary::with_data(key.to_bytes()[1..].to_vec())) {
+                next_key = next_key.max(id + 1);
+            }
+        }
+        self.next_key.store(next_key, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Layout {
+    /// ordered by keys, key ranges of stripes do not overlap
+    stripes: Vec<Stripe>,
+    next_stripe: u64,
+    /// rows that were written into delta after the last stripe
+    pending: usize,
+}
+
+/// Rows of a stripe are ordered by key. A row is either in a stripe or in delta but not in both
+#[derive(Debug, Clone)]
+struct Stripe {
+    id: u64,
+    first: Key,
+    last: Key,
+    columns: usize,
+    removed: Vec<bool>,
+}
+
+impl Stripe {
+    fn is_empty(&self) -> bool {
+        self.removed.iter().all(|removed| *removed)
+    }
+
+    fn overlaps(&self, range: &KeyRange) -> bool {
+        let before = match &range.start {
+            Bound::Included(start) => self.last < *start,
+            Bound::Excluded(start) => self.last <= *start,
+            Bound::Unbounded => false,
+        };
+        let after = match &range.end {
+            Bound::Included(end) => self.first > *end,
+            Bound::Excluded(end) => self.first >= *end,
+            Bound::Unbounded => false,
+        };
+        !before && !after
+    }
+}
+
+/// `[rows][columns]` followed by keys, commit timestamps and number of values of every row
+#[derive(Debug, PartialEq)]
+struct Header {
+    keys: Vec<Key>,
+    timestamps: Vec<Datum>,
+    widths: Vec<Datum>,
+    columns: usize,
+}
+
+impl Header {
+    fn encode(&self) -> Binary {
+        let mut data = vec![];
+        put_varint(&mut data, self.keys.len() as u64);
+        put_varint(&mut data, self.columns as u64);
+        for key in self.keys.iter() {
+            put_varint(&mut data, key.to_bytes().len() as u64);
+            data.extend_from_slice(key.to_bytes());
+        }
+        let timestamps = encoding::encode(&self.timestamps);
+        put_varint(&mut data, timestamps.len() as u64);
+        data.extend_from_slice(&timestamps);
+        data.extend_from_slice(&encoding::encode(&self.widths));
+        Binary::with_data(data)
+    }
+
+    fn decode(data: &Binary) -> StorageResult<Header> {
+        fn bytes<'d>(data: &'d [u8], index: &mut usize) -> StorageResult<&'d [u8]> {
+            let len = varint(data, index)? as usize;
+            let bytes = data
+                .get(*index..*index + len)
+                .ok_or_else(|| corrupted("header ended unexpectedly"))?;
+            *index += len;
+            Ok(bytes)
+        }
+
+        let data = data.to_bytes();
+        let mut index = 0;
+        let rows = varint(data, &mut index)? as usize;
+        let columns = varint(data, &mut index)? as usize;
+        let mut keys = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            keys.push(Binary::with_data(bytes(data, &mut index)?.to_vec()));
+        }
+        let timestamps = encoding::decode(bytes(data, &mut index)?)?;
+        let widths = encoding::decode(&data[index..])?;
+        if timestamps.len() != rows || widths.len() != rows {
+            return Err(corrupted("header has different number of rows"));
+        }
+        Ok(Header {
+            keys,
+            timestamps,
+            widths,
+            columns,
+        })
+    }
+}
+
+/// Column oriented tree on top of a backend tree with the same name
+pub struct ColumnarTree {
+    name: String,
+    base: Box<dyn Tree>,
+    storage: Arc<dyn Storage>,
+    state: Arc<TreeState>,
+}
+
+impl ColumnarTree {
+    /// stripe and position of the row if it is stored in a stripe and was not removed from it
+    fn find(
+        &self,
+        layout: &Layout,
+        key: &Key,
+        headers: &mut HashMap<u64, Header>,
+    ) -> StorageResult<Option<(usize, usize)>> {
+        let position = layout.stripes.partition_point(|stripe| stripe.last < *key);
+        let stripe = match layout.stripes.get(position) {
+            Some(stripe) if stripe.first <= *key => stripe,
+            _ => return Ok(None),
+        };
+        let header = match headers.entry(stripe.id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Header::decode(&segment(self.base.as_ref(), stripe.id, HEADER)?)?),
+        };
+        match header.keys.binary_search(key) {
+            Ok(row) if !stripe.removed[row] => Ok(Some((position, row))),
+            _ => Ok(None),
+        }
+    }
+
+    /// moves rows from delta into new stripes, the rest of them are left for the next time
+    fn compact(&self, layout: &mut Layout) -> StorageResult<()> {
+        let start = match layout.stripes.last() {
+            Some(stripe) => Bound::Excluded(stripe.last.clone()),
+            None => Bound::Unbounded,
+        };
+        let mut rows = vec![];
+        for row in self
+            .base
+            .select_range(delta_range(&KeyRange::new(start, Bound::Unbounded)))
+        {
+            let (key, chain) = row?;
+            if let Some((timestamp, values)) = single_version(&chain) {
+                rows.push((Binary::with_data(key.to_bytes()[1..].to_vec()), timestamp, values));
+            }
+        }
+        while rows.len() >= STRIPE_ROWS {
+            let stripe_rows = rows.drain(..STRIPE_ROWS).collect::<Vec<_>>();
+            self.write_stripe(layout, stripe_rows)?;
+        }
+        layout.pending = rows.len();
+        Ok(())
+    }
+
+    fn write_stripe(&self, layout: &mut Layout, rows: Vec<(Key, u64, Vec<Datum>)>) -> StorageResult<()> {
+        let id = layout.next_stripe;
+        let columns = rows
+            .iter()
+            .map(|(_key, _timestamp, values)| values.len())
+            .max()
+            .unwrap_or(0);
+        let mut batch = vec![];
+        for column in 0..columns {
+            let values = rows
+                .iter()
+                .map(|(_key, _timestamp, values)| values.get(column).cloned().unwrap_or_else(Datum::from_null))
+                .collect::<Vec<_>>();
+            batch.push((
+                segment_key(id, FIRST_COLUMN + column as u32),
+                Some(Binary::with_data(encoding::encode(&values))),
+            ));
+        }
+        let mut header = Header {
+            keys: vec![],
+            timestamps: vec![],
+            widths: vec![],
+            columns,
+        };
+        for (key, timestamp, values) in rows {
+            batch.push((delta_key(&key), None));
+            header.keys.push(key);
+            header.timestamps.push(Datum::from_u64(timestamp));
+            header.widths.push(Datum::from_u64(values.len() as u64));
+        }
+        batch.push((segment_key(id, HEADER), Some(header.encode())));
+        self.base.apply_batch(batch)?;
+        log::debug!(
+            "{:?} ROWS ARE MOVED INTO {} STRIPE OF {:?}",
+            header.keys.len(),
+            id,
+            self.name
+        );
+        layout.stripes.push(Stripe {
+            id,
+            first: header.keys.first().cloned().unwrap_or_default(),
+            last: header.keys.last().cloned().unwrap_or_default(),
+            columns,
+            removed: vec![false; header.keys.len()],
+        });
+        layout.next_stripe = id + 1;
+        Ok(())
+    }
+
+    /// stripes are not deleted while they can be read by a scan
+    fn delete_empty_stripes(&self, layout: &mut Layout) -> StorageResult<()> {
+        if self.state.scans.load(Ordering::SeqCst) != 0 {
+            return Ok(());
+        }
+        let mut batch = vec![];
+        for stripe in layout.stripes.iter().filter(|stripe| stripe.is_empty()) {
+            batch.push((segment_key(stripe.id, HEADER), None));
+            batch.push((segment_key(stripe.id, REMOVED), None));
+            for column in 0..stripe.columns {
+                batch.push((segment_key(stripe.id, FIRST_COLUMN + column as u32), None));
+            }
+        }
+        if !batch.is_empty() {
+            self.base.apply_batch(batch)?;
+            layout.stripes.retain(|stripe| !stripe.is_empty());
+        }
+        Ok(())
+    }
+
+    fn scan(&self, range: KeyRange, columns: Option<Vec<usize>>, reverse: bool) -> TreeScan {
+        if range.is_empty() {
+            return Box::new(std::iter::empty());
+        }
+        let base = match self.storage.lookup_tree(&self.name) {
+            Ok(base) => base,
+            Err(error) => return Box::new(std::iter::once(Err(error))),
+        };
+        let layout = self.state.layout.read().unwrap();
+        // delta is small, it is read right away so rows that are moved into stripes are not missed
+        let delta = base
+            .select_range(delta_range(&range))
+            .map(|row| {
+                let (key, chain) = row?;
+                let chain = match &columns {
+                    None => chain,
+                    Some(columns) => VersionChain::decode(&chain)?
+                        .project(columns)?
+                        .encode()
+                        .unwrap_or_default(),
+                };
+                Ok((Binary::with_data(key.to_bytes()[1..].to_vec()), chain))
+            })
+            .collect::<StorageResult<Vec<_>>>();
+        let mut delta = match delta {
+            Ok(delta) => delta,
+            Err(error) => return Box::new(std::iter::once(Err(error))),
+        };
+        let mut stripes = layout
+            .stripes
+            .iter()
+            .filter(|stripe| stripe.overlaps(&range))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.state.scans.fetch_add(1, Ordering::SeqCst);
+        drop(layout);
+        if reverse {
+            delta.reverse();
+            stripes.reverse();
+        }
+        Box::new(ColumnScan {
+            base,
+            state: self.state.clone(),
+            range,
+            columns,
+            reverse,
+            delta: delta.into_iter().peekable(),
+            stripes: stripes.into_iter(),
+            rows: vec![].into_iter().peekable(),
+            failed: false,
+        })
+    }
+}
+
+impl Tree for ColumnarTree {
+    fn get(&self, key: &Binary) -> StorageResult<Option<Value>> {
+        let layout = self.state.layout.read().unwrap();
+        if let Some(chain) = self.base.get(&delta_key(key))? {
+            return Ok(Some(chain));
+        }
+        let mut headers = HashMap::new();
+        match self.find(&layout, key, &mut headers)? {
+            None => Ok(None),
+            Some((position, row)) => {
+                let stripe = &layout.stripes[position];
+                let header = headers.remove(&stripe.id).expect("header is read by find");
+                let rows = read_rows(self.base.as_ref(), stripe, header, None, |index, _key| index == row)?;
+                Ok(rows.into_iter().next().map(|(_key, chain)| chain))
+            }
+        }
+    }
+
+    fn next_key(&self) -> Key {
+        record_key(self.state.next_key.fetch_add(1, Ordering::SeqCst))
+    }
+
+    fn next_keys(&self, count: usize) -> Vec<Key> {
+        let first = self.state.next_key.fetch_add(count as u64, Ordering::SeqCst);
+        (first..first + count as u64).map(record_key).collect()
+    }
+
+    fn remove(&self, key: &Binary) -> StorageResult<Option<Value>> {
+        let old = self.get(key)?;
+        self.apply_batch(vec![(key.clone(), None)])?;
+        Ok(old)
+    }
+
+    fn insert_key(&self, key: Binary, row: Binary) -> StorageResult<Option<Value>> {
+        let old = self.get(&key)?;
+        self.apply_batch(vec![(key, Some(row))])?;
+        Ok(old)
+    }
+
+    fn select(&self) -> TreeScan {
+        self.select_range(KeyRange::all())
+    }
+
+    fn select_range(&self, range: KeyRange) -> TreeScan {
+        log::debug!("[SCAN] COLUMNAR TABLE NAME {:?} RANGE {:?}", self.name, range);
+        self.scan(range, None, false)
+    }
+
+    fn select_range_rev(&self, range: KeyRange) -> TreeScan {
+        log::debug!("[SCAN] COLUMNAR TABLE NAME {:?} REVERSE RANGE {:?}", self.name, range);
+        self.scan(range, None, true)
+    }
+
+    fn select_columns(&self, range: KeyRange, columns: &[usize]) -> TreeScan {
+        log::debug!(
+            "[SCAN] COLUMNAR TABLE NAME {:?} RANGE {:?} COLUMNS {:?}",
+            self.name,
+            range,
+            columns
+        );
+        self.scan(range, Some(columns.to_vec()), false)
+    }
+
+    fn insert(&self, data: Vec<Value>) -> StorageResult<Vec<Key>> {
+        let keys = self.next_keys(data.len());
+        self.apply_batch(keys.iter().cloned().zip(data.into_iter().map(Some)).collect())?;
+        Ok(keys)
+    }
+
+    fn update(&self, data: Vec<(Key, Value)>) -> StorageResult<usize> {
+        let len = data.len();
+        self.apply_batch(data.into_iter().map(|(key, value)| (key, Some(value))).collect())?;
+        Ok(len)
+    }
+
+    fn delete(&self, data: Vec<Key>) -> StorageResult<usize> {
+        let mut size = 0;
+        for key in data.iter() {
+            if self.remove(key)?.is_some() {
+                size += 1;
+            }
+        }
+        Ok(size)
+    }
+
+    fn reset_next_key(&self) -> StorageResult<()> {
+        self.state.reset_next_key(self.base.as_ref())
+    }
+
+    fn apply_batch(&self, batch: WriteBatch) -> StorageResult<()> {
+        let mut layout = self.state.layout.write().unwrap();
+        let mut headers = HashMap::new();
+        let mut touched = BTreeSet::new();
+        let mut changes = vec![];
+        for (key, value) in batch {
+            if let Some((position, row)) = self.find(&layout, &key, &mut headers)? {
+                layout.stripes[position].removed[row] = true;
+                touched.insert(position);
+            }
+            if value.is_some() {
+                layout.pending += 1;
+            }
+            changes.push((delta_key(&key), value));
+        }
+        for position in touched {
+            let stripe = &layout.stripes[position];
+            changes.push((segment_key(stripe.id, REMOVED), Some(encode_removed(&stripe.removed))));
+        }
+        self.base.apply_batch(changes)?;
+        self.delete_empty_stripes(&mut layout)?;
+        if layout.pending >= STRIPE_ROWS {
+            self.compact(&mut layout)?;
+        }
+        Ok(())
+    }
+}
+
+/// Merges rows of stripes that are read one by one with delta rows
+struct ColumnScan {
+    base: Box<dyn Tree>,
+    state: Arc<TreeState>,
+    range: KeyRange,
+    columns: Option<Vec<usize>>,
+    reverse: bool,
+    delta: Peekable<vec::IntoIter<(Key, Value)>>,
+    stripes: vec::IntoIter<Stripe>,
+    /// rows of the current stripe
+    rows: Peekable<vec::IntoIter<(Key, Value)>>,
+    failed: bool,
+}
+
+impl Iterator for ColumnScan {
+    type Item = StorageResult<(Key, Value)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        while self.rows.peek().is_none() {
+            let stripe = match self.stripes.next() {
+                Some(stripe) => stripe,
+                None => break,
+            };
+            let range = &self.range;
+            let header = segment(self.base.as_ref(), stripe.id, HEADER).and_then(|header| Header::decode(&header));
+            let rows = header.and_then(|header| {
+                read_rows(
+                    self.base.as_ref(),
+                    &stripe,
+                    header,
+                    self.columns.as_deref(),
+                    |index, key| !stripe.removed[index] && range.contains(key),
+                )
+            });
+            match rows {
+                Ok(mut rows) => {
+                    if self.reverse {
+                        rows.reverse();
+                    }
+                    self.rows = rows.into_iter().peekable();
+                }
+                Err(error) => {
+                    self.failed = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+        let order = match (self.delta.peek(), self.rows.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => KeyOrdering::Less,
+            (None, Some(_)) => KeyOrdering::Greater,
+            (Some((delta, _)), Some((stripe, _))) if self.reverse => stripe.cmp(delta),
+            (Some((delta, _)), Some((stripe, _))) => delta.cmp(stripe),
+        };
+        match order {
+            KeyOrdering::Greater => self.rows.next().map(Ok),
+            // the row was written into delta after the scan read the stripe as it was
+            KeyOrdering::Equal => {
+                self.rows.next();
+                self.delta.next().map(Ok)
+            }
+            KeyOrdering::Less => self.delta.next().map(Ok),
+        }
+    }
+}
+
+impl Drop for ColumnScan {
+    fn drop(&mut self) {
+        self.state.scans.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// rows of the stripe that pass the filter as version chains, only `columns` are read if they are given
+fn read_rows<F: Fn(usize, &Key) -> bool>(
+    base: &dyn Tree,
+    stripe: &Stripe,
+    header: Header,
+    columns: Option<&[usize]>,
+    filter: F,
+) -> StorageResult<Vec<(Key, Value)>> {
+    let all = (0..stripe.columns).collect::<Vec<_>>();
+    let read = columns.unwrap_or(&all);
+    let mut values = vec![];
+    for column in read.iter() {
+        if *column < stripe.columns {
+            let column_values = encoding::decode(segment(base, stripe.id, FIRST_COLUMN + *column as u32)?.to_bytes())?;
+            if column_values.len() != header.keys.len() {
+                return Err(corrupted("has different number of rows"));
+            }
+            values.push(column_values);
+        } else {
+            values.push(vec![]);
+        }
+    }
+    let mut rows = vec![];
+    for (index, key) in header.keys.into_iter().enumerate() {
+        if !filter(index, &key) {
+            continue;
+        }
+        let width = header.widths[index].as_u64() as usize;
+        let row = read
+            .iter()
+            .zip(values.iter())
+            .take(if columns.is_some() { read.len() } else { width })
+            .map(|(column, values)| {
+                if *column < width {
+                    values[index].clone()
+                } else {
+                    Datum::from_null()
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut chain = VersionChain::default();
+        chain.push(header.timestamps[index].as_u64(), Some(Binary::pack(&row)));
+        rows.push((key, chain.encode().unwrap_or_default()));
+    }
+    Ok(rows)
+}
+
+/// timestamp and values of a row that has the only version
+fn single_version(chain: &Value) -> Option<(u64, Vec<Datum>)> {
+    let chain = VersionChain::decode(chain).ok()?;
+    match chain.versions() {
+        [(timestamp, Some(value))] => Some((*timestamp, value.try_unpack().ok()?)),
+        _ => None,
+    }
+}
+
+fn segment(base: &dyn Tree, stripe: u64, part: u32) -> StorageResult<Binary> {
+    base.get(&segment_key(stripe, part))?
+        .ok_or_else(|| corrupted(&format!("{} of {} stripe is missing", part, stripe)))
+}
+
+fn layout_key(name: &str) -> Key {
+    Binary::with_data(name.as_bytes().to_vec())
+}
+
+fn delta_key(key: &Key) -> Key {
+    Binary::with_data([&[DELTA], key.to_bytes()].concat())
+}
+
+/// keys of delta rows that are within the range
+fn delta_range(range: &KeyRange) -> KeyRange {
+    let start = match &range.start {
+        Bound::Included(start) => Bound::Included(delta_key(start)),
+        Bound::Excluded(start) => Bound::Excluded(delta_key(start)),
+        Bound::Unbounded => Bound::Included(Binary::with_data(vec![DELTA])),
+    };
+    let end = match &range.end {
+        Bound::Included(end) => Bound::Included(delta_key(end)),
+        Bound::Excluded(end) => Bound::Excluded(delta_key(end)),
+        Bound::Unbounded => Bound::Excluded(Binary::with_data(vec![STRIPE])),
+    };
+    KeyRange::new(start, end)
+}
+
+/// `[STRIPE][stripe id][part]`, numbers are big-endian so parts of a stripe are next to each other
+fn segment_key(stripe: u64, part: u32) -> Key {
+    let mut key = vec![STRIPE];
+    key.extend_from_slice(&stripe.to_be_bytes());
+    key.extend_from_slice(&part.to_be_bytes());
+    Binary::with_data(key)
+}
+
+fn parse_segment_key(key: &Key) -> StorageResult<(u64, u32)> {
+    match key.to_bytes() {
+        [STRIPE, rest @ ..] if rest.len() == 12 => Ok((
+            u64::from_be_bytes(rest[..8].try_into().unwrap()),
+            u32::from_be_bytes(rest[8..].try_into().unwrap()),
+        )),
+        _ => Err(corrupted("key is malformed")),
+    }
+}
+
+fn encode_removed(removed: &[bool]) -> Binary {
+    let mut data = vec![0u8; removed.len().div_ceil(8)];
+    for (index, removed) in removed.iter().enumerate() {
+        if *removed {
+            data[index / 8] |= 1 << (index % 8);
+        }
+    }
+    Binary::with_data(data)
+}
+
+fn decode_removed(data: &Binary, rows: usize) -> StorageResult<Vec<bool>> {
+    let data = data.to_bytes();
+    if data.len() != rows.div_ceil(8) {
+        return Err(corrupted("has removed rows of different stripe"));
+    }
+    Ok((0..rows)
+        .map(|index| data[index / 8] & (1 << (index % 8)) != 0)
+        .collect())
+}
+
+/// Keys are packed numbers the same as keys of other trees, but bytes of the numbers are swapped,
+/// so that packed keys sort in the order they were generated and new rows go after the last stripe
+fn record_key(record_id: u64) -> Key {
+    Binary::pack(&[Datum::from_u64(record_id.swap_bytes())])
+}
+
+fn record_id(key: &Key) -> Option<u64> {
+    match key.try_unpack().as_deref() {
+        Ok([Datum::Int64(id)]) => Some((*id as u64).swap_bytes()),
+        _ => None,
+    }
+}
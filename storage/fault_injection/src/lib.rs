@@ -0,0 +1,321 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use binary::Binary;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use storage_api::{
+    IndexKeys, Key, KeyRange, Storage, StorageError, StorageResult, Tree, TreeLayout, TreeScan, Value, WriteBatch,
+};
+
+/// Operations of a storage backend that faults can be injected into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    /// reading a value by its key or a row of a scan
+    Read,
+    /// any change of a tree
+    Write,
+    /// creating, looking up, listing and dropping trees
+    Trees,
+    /// committing changes and waiting until they are durable
+    Sync,
+}
+
+/// What happens to an operation when a fault is injected into it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// operation fails with `StorageError::Io` and does not change anything
+    Fail,
+    /// operation waits before it is performed
+    Delay(Duration),
+    /// only the first half of a value is read or written
+    Tear,
+    /// every bit of a value is flipped when it is read or written
+    Corrupt,
+}
+
+/// Misbehaviour of storage that happens to matching operations.
+/// Values are torn or corrupted only by reads and writes, other operations ignore such faults
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fault {
+    pub operation: Operation,
+    /// only operations on the tree are affected, all trees are affected if it is `None`
+    pub tree: Option<String>,
+    /// number of matching operations that succeed before the fault happens,
+    /// e.g. to fail the third write `skip` has to be 2
+    pub skip: u64,
+    /// how many times the fault happens in a row, it happens every time if it is `None`
+    pub times: Option<u64>,
+    pub effect: Effect,
+}
+
+impl Fault {
+    /// fault that happens once to the next matching operation on any tree
+    pub fn new(operation: Operation, effect: Effect) -> Fault {
+        Fault {
+            operation,
+            tree: None,
+            skip: 0,
+            times: Some(1),
+            effect,
+        }
+    }
+}
+
+struct Injected {
+    fault: Fault,
+    /// number of matching operations since the fault was injected
+    seen: u64,
+}
+
+/// Faults that are injected into storage. Clones share the same faults,
+/// so they can be changed while the storage is used
+#[derive(Clone, Default)]
+pub struct Faults {
+    injected: Arc<Mutex<Vec<Injected>>>,
+    happened: Arc<AtomicU64>,
+}
+
+impl Faults {
+    pub fn inject(&self, fault: Fault) {
+        self.injected.lock().unwrap().push(Injected { fault, seen: 0 });
+    }
+
+    /// storage behaves normally afterwards
+    pub fn clear(&self) {
+        self.injected.lock().unwrap().clear();
+    }
+
+    /// how many times injected faults happened
+    pub fn happened(&self) -> u64 {
+        self.happened.load(Ordering::SeqCst)
+    }
+
+    /// effect of the first fault that happens to the operation. Every matching fault counts the operation
+    fn next(&self, operation: Operation, tree: &str) -> Option<Effect> {
+        let mut effect = None;
+        for injected in self.injected.lock().unwrap().iter_mut() {
+            let fault = &injected.fault;
+            if fault.operation != operation || matches!(&fault.tree, Some(name) if name != tree) {
+                continue;
+            }
+            injected.seen += 1;
+            let happens = injected.seen > fault.skip
+                && fault
+                    .times
+                    .map(|times| injected.seen <= fault.skip + times)
+                    .unwrap_or(true);
+            if happens && effect.is_none() {
+                effect = Some(fault.effect);
+            }
+        }
+        if effect.is_some() {
+            log::debug!("{:?} FAULT OF {:?} ON {:?}", effect, operation, tree);
+            self.happened.fetch_add(1, Ordering::SeqCst);
+        }
+        effect
+    }
+
+    /// fails the operation or waits out a delay, effects that damage values are returned
+    fn happen(&self, operation: Operation, tree: &str) -> StorageResult<Option<Effect>> {
+        match self.next(operation, tree) {
+            None => Ok(None),
+            Some(Effect::Fail) => Err(StorageError::Io(format!(
+                "injected failure of {:?} operation on {:?}",
+                operation, tree
+            ))),
+            Some(Effect::Delay(delay)) => {
+                thread::sleep(delay);
+                Ok(None)
+            }
+            Some(effect) => Ok(Some(effect)),
+        }
+    }
+}
+
+fn damage(effect: Option<Effect>, value: Value) -> Value {
+    match effect {
+        Some(Effect::Tear) => {
+            let bytes = value.as_ref();
+            Binary::with_data(bytes[..bytes.len() / 2].to_vec())
+        }
+        Some(Effect::Corrupt) => Binary::with_data(value.as_ref().iter().map(|byte| !byte).collect()),
+        _ => value,
+    }
+}
+
+/// Storage that passes operations to the base storage and misbehaves when injected faults happen
+pub struct FaultyStorage {
+    base: Box<dyn Storage>,
+    faults: Faults,
+}
+
+impl FaultyStorage {
+    pub fn new(base: Box<dyn Storage>, faults: Faults) -> FaultyStorage {
+        FaultyStorage { base, faults }
+    }
+}
+
+impl Storage for FaultyStorage {
+    fn lookup_tree(&self, name: &str) -> StorageResult<Box<dyn Tree>> {
+        self.faults.happen(Operation::Trees, name)?;
+        Ok(Box::new(FaultyTree {
+            base: self.base.lookup_tree(name)?,
+            name: name.to_owned(),
+            faults: self.faults.clone(),
+        }))
+    }
+
+    fn drop_tree(&self, name: &str) -> StorageResult<()> {
+        self.faults.happen(Operation::Trees, name)?;
+        self.base.drop_tree(name)
+    }
+
+    fn create_tree(&self, name: &str) -> StorageResult<bool> {
+        self.faults.happen(Operation::Trees, name)?;
+        self.base.create_tree(name)
+    }
+
+    fn create_tree_with(&self, name: &str, layout: TreeLayout) -> StorageResult<bool> {
+        self.faults.happen(Operation::Trees, name)?;
+        self.base.create_tree_with(name, layout)
+    }
+
+    fn tree_layout(&self, name: &str) -> StorageResult<TreeLayout> {
+        self.faults.happen(Operation::Trees, name)?;
+        self.base.tree_layout(name)
+    }
+
+    fn tree_names(&self) -> StorageResult<Vec<String>> {
+        self.faults.happen(Operation::Trees, "")?;
+        self.base.tree_names()
+    }
+
+    fn index_keys(&self) -> Arc<dyn IndexKeys> {
+        self.base.index_keys()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.base.is_encrypted()
+    }
+
+    fn commit(&self, timestamp: u64) -> StorageResult<()> {
+        self.faults.happen(Operation::Sync, "")?;
+        self.base.commit(timestamp)
+    }
+
+    fn sync(&self) -> StorageResult<()> {
+        self.faults.happen(Operation::Sync, "")?;
+        self.base.sync()
+    }
+}
+
+struct FaultyTree {
+    base: Box<dyn Tree>,
+    name: String,
+    faults: Faults,
+}
+
+impl FaultyTree {
+    fn scan(&self, scan: TreeScan) -> TreeScan {
+        let name = self.name.clone();
+        let faults = self.faults.clone();
+        Box::new(scan.map(move |row| {
+            let (key, value) = row?;
+            let effect = faults.happen(Operation::Read, &name)?;
+            Ok((key, damage(effect, value)))
+        }))
+    }
+}
+
+impl Tree for FaultyTree {
+    fn get(&self, key: &Binary) -> StorageResult<Option<Value>> {
+        let effect = self.faults.happen(Operation::Read, &self.name)?;
+        Ok(self.base.get(key)?.map(|value| damage(effect, value)))
+    }
+
+    fn next_key(&self) -> Key {
+        self.base.next_key()
+    }
+
+    fn next_keys(&self, count: usize) -> Vec<Key> {
+        self.base.next_keys(count)
+    }
+
+    fn remove(&self, key: &Binary) -> StorageResult<Option<Value>> {
+        self.faults.happen(Operation::Write, &self.name)?;
+        self.base.remove(key)
+    }
+
+    fn insert_key(&self, key: Binary, row: Binary) -> StorageResult<Option<Value>> {
+        let effect = self.faults.happen(Operation::Write, &self.name)?;
+        self.base.insert_key(key, damage(effect, row))
+    }
+
+    fn select(&self) -> TreeScan {
+        self.scan(self.base.select())
+    }
+
+    fn select_range(&self, range: KeyRange) -> TreeScan {
+        self.scan(self.base.select_range(range))
+    }
+
+    fn select_range_rev(&self, range: KeyRange) -> TreeScan {
+        self.scan(self.base.select_range_rev(range))
+    }
+
+    fn select_columns(&self, range: KeyRange, columns: &[usize]) -> TreeScan {
+        self.scan(self.base.select_columns(range, columns))
+    }
+
+    fn insert(&self, data: Vec<Value>) -> StorageResult<Vec<Key>> {
+        let effect = self.faults.happen(Operation::Write, &self.name)?;
+        self.base
+            .insert(data.into_iter().map(|value| damage(effect, value)).collect())
+    }
+
+    fn update(&self, data: Vec<(Key, Value)>) -> StorageResult<usize> {
+        let effect = self.faults.happen(Operation::Write, &self.name)?;
+        self.base.update(
+            data.into_iter()
+                .map(|(key, value)| (key, damage(effect, value)))
+                .collect(),
+        )
+    }
+
+    fn delete(&self, data: Vec<Key>) -> StorageResult<usize> {
+        self.faults.happen(Operation::Write, &self.name)?;
+        self.base.delete(data)
+    }
+
+    fn reset_next_key(&self) -> StorageResult<()> {
+        self.base.reset_next_key()
+    }
+
+    fn apply_batch(&self, batch: WriteBatch) -> StorageResult<()> {
+        let effect = self.faults.happen(Operation::Write, &self.name)?;
+        self.base.apply_batch(
+            batch
+                .into_iter()
+                .map(|(key, value)| (key, value.map(|value| damage(effect, value))))
+                .collect(),
+        )
+    }
+}
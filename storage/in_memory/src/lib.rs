@@ -13,63 +13,149 @@
 // limitations under the License.
 
 use binary::{repr::Datum, Binary};
-use dashmap::DashMap;
+use dashmap::{mapref::entry::Entry, DashMap};
 use std::{
     collections::BTreeMap,
+    io,
+    ops::Bound,
+    path::Path,
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc, RwLock,
     },
 };
 use storage_api::*;
+use wal::{CheckpointWriter, Record, Wal};
 
-const DEFINITION_SCHEMA: &str = "DEFINITION_SCHEMA";
-const SCHEMATA_TABLE: &str = "SCHEMATA";
-const TABLES_TABLE: &str = "TABLES";
-const INDEXES_TABLE: &str = "TABLES";
-const COLUMNS_TABLE: &str = "COLUMNS";
+mod wal;
 
 pub struct InMemoryDatabase {
     trees: DashMap<String, InMemoryTree>,
+    wal: Option<Arc<Wal>>,
 }
 
 impl InMemoryDatabase {
     pub fn create() -> InMemoryDatabase {
-        let this = InMemoryDatabase {
+        InMemoryDatabase {
             trees: DashMap::default(),
-        };
+            wal: None,
+        }
+    }
 
-        // database bootstrap
-        this.create_tree(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE));
-        this.lookup_tree(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE))
-            .insert(vec![Binary::pack(&[
-                Datum::from_string("IN_MEMORY".to_owned()),
-                Datum::from_string("public".to_owned()),
-            ])]);
-        this.create_tree(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE));
-        this.create_tree(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE));
-        this.create_tree(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE));
+    /// restores the database from the last checkpoint and write-ahead log in `path` directory.
+    /// All further changes are logged there
+    pub fn with_wal(path: &str) -> InMemoryDatabase {
+        let (wal, records) = Wal::open(Path::new(path), None).expect("to open write-ahead log");
+        InMemoryDatabase::replay(wal, records)
+    }
 
+    /// the same as `with_wal` but log segments are archived into `archive` directory
+    /// and the database can be recovered to a point in time
+    pub fn with_archived_wal(path: &str, archive: &str) -> InMemoryDatabase {
+        let (wal, records) = Wal::open(Path::new(path), Some(Path::new(archive))).expect("to open write-ahead log");
+        InMemoryDatabase::replay(wal, records)
+    }
+
+    /// database in `path` directory as it was at the target, recovered from `archive` directory.
+    /// Changes are logged and archived the same way as by `with_archived_wal` afterwards
+    pub fn recover(path: &str, archive: &str, target: RecoveryTarget) -> StorageResult<InMemoryDatabase> {
+        let (wal, records) = Wal::recover(Path::new(path), Path::new(archive), target)?;
+        Ok(InMemoryDatabase::replay(wal, records))
+    }
+
+    fn replay(wal: Wal, records: Vec<Record>) -> InMemoryDatabase {
+        let mut this = InMemoryDatabase::create();
+        for record in records {
+            match record {
+                Record::CreateTree(name) => this.create_tree(&name).map(|_created| ()),
+                Record::DropTree(name) => this.drop_tree(&name),
+                Record::Put(name, key, value) => this
+                    .lookup_tree(&name)
+                    .and_then(|tree| tree.insert_key(key, value))
+                    .map(|_old| ()),
+                Record::Remove(name, key) => this
+                    .lookup_tree(&name)
+                    .and_then(|tree| tree.remove(&key))
+                    .map(|_old| ()),
+                Record::Commit(_) => Ok(()),
+            }
+            .expect("to replay write-ahead log");
+        }
+
+        let wal = Arc::new(wal);
+        for mut tree in this.trees.iter_mut() {
+            tree.restore_record_ids();
+            tree.wal = Some(wal.clone());
+        }
+        this.wal = Some(wal.clone());
+        wal.checkpoint(|writer| this.snapshot(writer))
+            .expect("to write checkpoint after recovery");
         this
     }
+
+    fn snapshot(&self, writer: &mut CheckpointWriter) -> io::Result<()> {
+        for tree in self.trees.iter() {
+            writer.write(&Record::CreateTree(tree.name.clone()))?;
+            for (key, value) in tree.inner.records.read().unwrap().iter() {
+                writer.write(&Record::Put(tree.name.clone(), key.clone(), value.clone()))?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Storage for InMemoryDatabase {
-    type Tree = InMemoryTree;
+    fn lookup_tree(&self, name: &str) -> StorageResult<Box<dyn Tree>> {
+        log::trace!("LOOKUP {:?}", name);
+        match self.trees.get(name) {
+            Some(tree) => Ok(Box::new(tree.clone())),
+            None => Err(StorageError::TreeNotFound(name.to_owned())),
+        }
+    }
 
-    fn lookup_tree<T: Into<String>>(&self, table: T) -> InMemoryTree {
-        let table = table.into();
-        log::trace!("LOOKUP {:?}", table);
-        self.trees.get(&table).unwrap().clone()
+    fn drop_tree(&self, name: &str) -> StorageResult<()> {
+        let record = self.wal.as_ref().map(|_| Record::DropTree(name.to_owned()));
+        logged(&self.wal, record, || {
+            self.trees.remove(name);
+        });
+        Ok(())
     }
 
-    fn drop_tree<T: Into<String>>(&self, table: T) {
-        self.trees.remove(&table.into());
+    fn create_tree(&self, name: &str) -> StorageResult<bool> {
+        let name = name.to_owned();
+        let record = self.wal.as_ref().map(|_| Record::CreateTree(name.clone()));
+        Ok(logged(&self.wal, record, || match self.trees.entry(name.clone()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(InMemoryTree::with_name(name, self.wal.clone()));
+                true
+            }
+        }))
     }
 
-    fn create_tree<T: Into<String>>(&self, table: T) {
-        let name = table.into();
-        self.trees.insert(name.clone(), InMemoryTree::with_name(name));
+    fn tree_names(&self) -> StorageResult<Vec<String>> {
+        Ok(self.trees.iter().map(|tree| tree.key().clone()).collect())
+    }
+
+    fn commit(&self, timestamp: u64) -> StorageResult<()> {
+        if let Some(wal) = &self.wal {
+            wal.commit(timestamp, |writer| self.snapshot(writer))?;
+        }
+        Ok(())
+    }
+
+    fn sync(&self) -> StorageResult<()> {
+        if let Some(wal) = &self.wal {
+            wal.sync()?;
+        }
+        Ok(())
+    }
+}
+
+fn logged<R>(wal: &Option<Arc<Wal>>, records: impl IntoIterator<Item = Record>, apply: impl FnOnce() -> R) -> R {
+    match wal {
+        Some(wal) => wal.append(records, apply),
+        None => apply(),
     }
 }
 
@@ -77,114 +163,185 @@ impl Storage for InMemoryDatabase {
 pub struct InMemoryTree {
     name: String,
     inner: Arc<InMemoryTableHandleInner>,
-    indexes: Arc<DashMap<String, Arc<InMemoryIndex>>>,
+    wal: Option<Arc<Wal>>,
 }
 
 impl InMemoryTree {
-    pub(crate) fn with_name(name: String) -> InMemoryTree {
+    pub(crate) fn with_name(name: String, wal: Option<Arc<Wal>>) -> InMemoryTree {
         InMemoryTree {
             name,
             inner: Arc::new(InMemoryTableHandleInner::default()),
-            indexes: Arc::new(DashMap::default()),
+            wal,
         }
     }
 
-    #[allow(dead_code)]
-    pub(crate) fn index(&self, index: &str) -> Arc<InMemoryIndex> {
-        self.indexes.get(index).unwrap().clone()
+    fn restore_record_ids(&self) {
+        let next_id = self
+            .inner
+            .records
+            .read()
+            .unwrap()
+            .keys()
+            .filter_map(|key| match key.try_unpack().as_deref() {
+                Ok([Datum::Int64(id)]) => Some(*id as u64 + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default();
+        self.inner.record_ids.store(next_id, Ordering::SeqCst);
     }
 }
 
 impl Tree for InMemoryTree {
-    fn remove(&self, key: &Binary) -> Option<Binary> {
-        self.inner.records.write().unwrap().remove(&key)
+    fn get(&self, key: &Binary) -> StorageResult<Option<Binary>> {
+        Ok(self.inner.records.read().unwrap().get(key).cloned())
     }
 
-    fn insert_key(&self, key: Binary, row: Binary) -> Option<Binary> {
-        self.inner.records.write().unwrap().insert(key, row)
+    fn next_key(&self) -> Key {
+        let record_id = self.inner.record_ids.fetch_add(1, Ordering::SeqCst);
+        Binary::pack(&[Datum::from_u64(record_id)])
     }
 
-    fn select(&self) -> Cursor {
-        log::debug!("[SCAN] TABLE NAME {:?}", self.name);
-        self.inner
-            .records
-            .read()
-            .unwrap()
-            .iter()
-            .map(|(key, value)| {
-                log::debug!("[SCAN] TABLE RECORD - ({:?}, {:?})", key, value);
-                (key.clone(), value.clone())
-            })
-            .collect::<Cursor>()
-    }
-
-    fn insert(&self, data: Vec<Value>) -> Vec<Key> {
-        let mut rw = self.inner.records.write().unwrap();
-        let mut keys = vec![];
-        for value in data {
-            let record_id = self.inner.record_ids.fetch_add(1, Ordering::SeqCst);
-            let key = Binary::pack(&[Datum::from_u64(record_id)]);
-            debug_assert!(
-                matches!(rw.insert(key.clone(), value), None),
-                "insert operation should insert nonexistent key"
-            );
-            keys.push(key);
-        }
+    fn next_keys(&self, count: usize) -> Vec<Key> {
+        let first = self.inner.record_ids.fetch_add(count as u64, Ordering::SeqCst);
+        (first..first + count as u64)
+            .map(|record_id| Binary::pack(&[Datum::from_u64(record_id)]))
+            .collect()
+    }
 
-        keys
+    fn remove(&self, key: &Binary) -> StorageResult<Option<Binary>> {
+        let record = self
+            .wal
+            .as_ref()
+            .map(|_| Record::Remove(self.name.clone(), key.clone()));
+        Ok(logged(&self.wal, record, || {
+            self.inner.records.write().unwrap().remove(key)
+        }))
     }
 
-    fn update(&self, data: Vec<(Key, Value)>) -> usize {
+    fn insert_key(&self, key: Binary, row: Binary) -> StorageResult<Option<Binary>> {
+        let record = self
+            .wal
+            .as_ref()
+            .map(|_| Record::Put(self.name.clone(), key.clone(), row.clone()));
+        Ok(logged(&self.wal, record, || {
+            self.inner.records.write().unwrap().insert(key, row)
+        }))
+    }
+
+    fn select(&self) -> TreeScan {
+        self.select_range(KeyRange::all())
+    }
+
+    fn select_range(&self, range: KeyRange) -> TreeScan {
+        log::debug!("[SCAN] TABLE NAME {:?} RANGE {:?}", self.name, range);
+        Box::new(InMemoryScan {
+            inner: self.inner.clone(),
+            range,
+            reverse: false,
+        })
+    }
+
+    fn select_range_rev(&self, range: KeyRange) -> TreeScan {
+        log::debug!("[SCAN] TABLE NAME {:?} REVERSE RANGE {:?}", self.name, range);
+        Box::new(InMemoryScan {
+            inner: self.inner.clone(),
+            range,
+            reverse: true,
+        })
+    }
+
+    fn insert(&self, data: Vec<Value>) -> StorageResult<Vec<Key>> {
+        let keys = self.next_keys(data.len());
+        self.apply_batch(keys.iter().cloned().zip(data.into_iter().map(Some)).collect())?;
+        Ok(keys)
+    }
+
+    fn update(&self, data: Vec<(Key, Value)>) -> StorageResult<usize> {
         let len = data.len();
-        let mut rw = self.inner.records.write().unwrap();
         for (key, value) in data {
-            debug_assert!(
-                matches!(rw.insert(key, value), Some(_)),
-                "update operation should change already existed key"
-            );
+            let old = self.insert_key(key, value)?;
+            debug_assert!(old.is_some(), "update operation should change already existed key");
         }
-        len
+        Ok(len)
     }
 
-    fn delete(&self, data: Vec<Key>) -> usize {
-        let mut rw = self.inner.records.write().unwrap();
+    fn delete(&self, data: Vec<Key>) -> StorageResult<usize> {
         let mut size = 0;
-        let keys = rw
-            .iter()
-            .filter(|(key, _value)| data.contains(key))
-            .map(|(key, _value)| key.clone())
-            .collect::<Vec<Binary>>();
-        for key in keys.iter() {
-            debug_assert!(matches!(rw.remove(key), Some(_)), "delete operation delete existed key");
-            size += 1;
+        for key in data.iter() {
+            if self.remove(key)?.is_some() {
+                size += 1;
+            }
         }
-        size
+        Ok(size)
     }
-}
 
-#[derive(Default, Debug)]
-struct InMemoryTableHandleInner {
-    records: RwLock<BTreeMap<Binary, Binary>>,
-    record_ids: AtomicU64,
-    column_ords: AtomicU64,
+    fn reset_next_key(&self) -> StorageResult<()> {
+        self.restore_record_ids();
+        Ok(())
+    }
+
+    fn apply_batch(&self, batch: WriteBatch) -> StorageResult<()> {
+        let records = match &self.wal {
+            None => vec![],
+            Some(_) => batch
+                .iter()
+                .map(|(key, value)| match value {
+                    None => Record::Remove(self.name.clone(), key.clone()),
+                    Some(value) => Record::Put(self.name.clone(), key.clone(), value.clone()),
+                })
+                .collect(),
+        };
+        logged(&self.wal, records, || {
+            let mut rows = self.inner.records.write().unwrap();
+            for (key, value) in batch {
+                match value {
+                    None => rows.remove(&key),
+                    Some(value) => rows.insert(key, value),
+                };
+            }
+        });
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
-pub struct InMemoryIndex {
-    records: RwLock<BTreeMap<Binary, Binary>>,
-    column: usize,
+/// Walks over tree records one by one without holding the lock between them.
+/// Transactions filter out records that were changed after the scan started
+struct InMemoryScan {
+    inner: Arc<InMemoryTableHandleInner>,
+    /// shrinks as the scan goes
+    range: KeyRange,
+    reverse: bool,
 }
 
-impl InMemoryIndex {
-    #[allow(dead_code)]
-    pub(crate) fn new(column: usize) -> InMemoryIndex {
-        InMemoryIndex {
-            records: RwLock::default(),
-            column,
+impl Iterator for InMemoryScan {
+    type Item = StorageResult<(Binary, Binary)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.range.is_empty() {
+            return None;
         }
+        let records = self.inner.records.read().unwrap();
+        let mut range = records.range(self.range.clone());
+        let (key, value) = if self.reverse { range.next_back() } else { range.next() }
+            .map(|(key, value)| (key.clone(), value.clone()))?;
+        log::debug!("[SCAN] TABLE RECORD - ({:?}, {:?})", key, value);
+        if self.reverse {
+            self.range.end = Bound::Excluded(key.clone());
+        } else {
+            self.range.start = Bound::Excluded(key.clone());
+        }
+        Some(Ok((key, value)))
     }
 }
 
+#[derive(Default, Debug)]
+struct InMemoryTableHandleInner {
+    records: RwLock<BTreeMap<Binary, Binary>>,
+    record_ids: AtomicU64,
+    column_ords: AtomicU64,
+}
+
 impl PartialEq for InMemoryTree {
     fn eq(&self, other: &InMemoryTree) -> bool {
         self.name == other.name
@@ -0,0 +1,600 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use binary::Binary;
+use std::{
+    convert::TryInto,
+    fmt::{self, Debug, Formatter},
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    mem,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use storage_api::RecoveryTarget;
+
+const CHECKPOINT: &str = "checkpoint";
+const CHECKPOINT_TMP: &str = "checkpoint.tmp";
+const SEGMENT_PREFIX: &str = "wal.";
+/// prefix of checkpoints in WAL archive
+const CHECKPOINT_PREFIX: &str = "checkpoint.";
+const DISCARDED_PREFIX: &str = "discarded.";
+/// size of a log segment after which the whole database is written into a checkpoint
+const CHECKPOINT_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+const HEADER_SIZE: usize = 2 * std::mem::size_of::<u32>();
+
+const CREATE_TREE: u8 = 1;
+const DROP_TREE: u8 = 2;
+const PUT: u8 = 3;
+const REMOVE: u8 = 4;
+const COMMIT: u8 = 5;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Record {
+    CreateTree(String),
+    DropTree(String),
+    Put(String, Binary, Binary),
+    Remove(String, Binary),
+    /// all records since the previous commit are applied together or not at all
+    Commit(Commit),
+}
+
+/// Logical timestamp of a committed transaction and the time when it was committed
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Commit {
+    timestamp: u64,
+    /// milliseconds since UNIX epoch
+    time: u64,
+}
+
+impl Commit {
+    fn now(timestamp: u64) -> Commit {
+        Commit {
+            timestamp,
+            time: millis(SystemTime::now()),
+        }
+    }
+
+    fn is_after(&self, target: RecoveryTarget) -> bool {
+        match target {
+            RecoveryTarget::Transaction(timestamp) => self.timestamp > timestamp,
+            RecoveryTarget::Time(time) => self.time > millis(time),
+        }
+    }
+}
+
+/// milliseconds since UNIX epoch
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+impl Record {
+    /// `[payload len][payload checksum][payload]`, all numbers are little-endian
+    fn encode(&self, data: &mut Vec<u8>) {
+        fn bytes(payload: &mut Vec<u8>, bytes: &[u8]) {
+            payload.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            payload.extend_from_slice(bytes);
+        }
+
+        let mut payload = vec![];
+        match self {
+            Record::CreateTree(name) => {
+                payload.push(CREATE_TREE);
+                bytes(&mut payload, name.as_bytes());
+            }
+            Record::DropTree(name) => {
+                payload.push(DROP_TREE);
+                bytes(&mut payload, name.as_bytes());
+            }
+            Record::Put(name, key, value) => {
+                payload.push(PUT);
+                bytes(&mut payload, name.as_bytes());
+                bytes(&mut payload, key.as_ref());
+                bytes(&mut payload, value.as_ref());
+            }
+            Record::Remove(name, key) => {
+                payload.push(REMOVE);
+                bytes(&mut payload, name.as_bytes());
+                bytes(&mut payload, key.as_ref());
+            }
+            Record::Commit(commit) => {
+                payload.push(COMMIT);
+                payload.extend_from_slice(&commit.timestamp.to_le_bytes());
+                payload.extend_from_slice(&commit.time.to_le_bytes());
+            }
+        }
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(&checksum(&payload).to_le_bytes());
+        data.extend_from_slice(&payload);
+    }
+
+    /// reads records until the data ends or a torn or corrupted record is found
+    fn decode_all(data: &[u8]) -> Vec<Record> {
+        let mut records = vec![];
+        let mut index = 0;
+        while let Some(header) = data.get(index..index + HEADER_SIZE) {
+            let len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+            let expected = u32::from_le_bytes(header[4..8].try_into().unwrap());
+            let payload = match data.get(index + HEADER_SIZE..index + HEADER_SIZE + len) {
+                Some(payload) if checksum(payload) == expected => payload,
+                _ => break,
+            };
+            match Record::decode(payload) {
+                Some(record) => records.push(record),
+                None => break,
+            }
+            index += HEADER_SIZE + len;
+        }
+        if index < data.len() {
+            log::warn!(
+                "{} bytes at the end of WAL segment were not replayed",
+                data.len() - index
+            );
+        }
+        records
+    }
+
+    fn decode(payload: &[u8]) -> Option<Record> {
+        fn bytes<'p>(payload: &'p [u8], index: &mut usize) -> Option<&'p [u8]> {
+            let len = u32::from_le_bytes(payload.get(*index..*index + 4)?.try_into().unwrap()) as usize;
+            let bytes = payload.get(*index + 4..*index + 4 + len)?;
+            *index += 4 + len;
+            Some(bytes)
+        }
+        fn name(payload: &[u8], index: &mut usize) -> Option<String> {
+            String::from_utf8(bytes(payload, index)?.to_vec()).ok()
+        }
+        fn binary(payload: &[u8], index: &mut usize) -> Option<Binary> {
+            Some(Binary::with_data(bytes(payload, index)?.to_vec()))
+        }
+        fn number(payload: &[u8], index: &mut usize) -> Option<u64> {
+            let number = u64::from_le_bytes(payload.get(*index..*index + 8)?.try_into().unwrap());
+            *index += 8;
+            Some(number)
+        }
+
+        let mut index = 1;
+        match *payload.first()? {
+            CREATE_TREE => Some(Record::CreateTree(name(payload, &mut index)?)),
+            DROP_TREE => Some(Record::DropTree(name(payload, &mut index)?)),
+            PUT => Some(Record::Put(
+                name(payload, &mut index)?,
+                binary(payload, &mut index)?,
+                binary(payload, &mut index)?,
+            )),
+            REMOVE => Some(Record::Remove(name(payload, &mut index)?, binary(payload, &mut index)?)),
+            // logs that were written before commits had timestamps
+            COMMIT if payload.len() == 1 => Some(Record::Commit(Commit::default())),
+            COMMIT => Some(Record::Commit(Commit {
+                timestamp: number(payload, &mut index)?,
+                time: number(payload, &mut index)?,
+            })),
+            _ => None,
+        }
+    }
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Streams the database content into a checkpoint file
+pub(crate) struct CheckpointWriter {
+    file: BufWriter<File>,
+    buffer: Vec<u8>,
+}
+
+impl CheckpointWriter {
+    pub(crate) fn write(&mut self, record: &Record) -> io::Result<()> {
+        self.buffer.clear();
+        record.encode(&mut self.buffer);
+        self.file.write_all(&self.buffer)
+    }
+}
+
+/// Write-ahead log of the in-memory database.
+///
+/// Changes are appended into a buffer and written to the current log segment when a
+/// transaction waits for its commit to become durable. All transactions that are waiting
+/// at the same time are written with a single `fsync`. When a segment grows too big the
+/// whole database is written into a checkpoint and the log starts from a new segment.
+pub(crate) struct Wal {
+    dir: PathBuf,
+    /// directory where segments and checkpoints are copied before they are removed
+    archive: Option<PathBuf>,
+    state: Mutex<State>,
+    /// position in the log up to which everything is on disk.
+    /// Only one thread at a time writes into log files.
+    durable: Mutex<u64>,
+}
+
+impl Debug for Wal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "WAL in {:?}", self.dir)
+    }
+}
+
+struct State {
+    file: File,
+    generation: u64,
+    buffer: Vec<u8>,
+    /// position in the log of the last appended record
+    appended: u64,
+    segment_size: u64,
+    last_commit: Commit,
+}
+
+impl State {
+    fn push(&mut self, record: &Record) {
+        let before = self.buffer.len();
+        record.encode(&mut self.buffer);
+        let size = (self.buffer.len() - before) as u64;
+        self.appended += size;
+        self.segment_size += size;
+    }
+}
+
+impl Wal {
+    /// returns committed records of the last checkpoint and log segments after it.
+    /// Segments and checkpoints are copied into `archive` directory before they are removed
+    pub(crate) fn open(dir: &Path, archive: Option<&Path>) -> io::Result<(Wal, Vec<Record>)> {
+        fs::create_dir_all(dir)?;
+        if let Some(archive) = archive {
+            fs::create_dir_all(archive)?;
+        }
+        let (first_generation, checkpoint) = Wal::read_checkpoint(&dir.join(CHECKPOINT))?.unwrap_or_default();
+        let mut records = vec![];
+        let mut last_commit = Commit::default();
+        apply(checkpoint, &mut records, &mut last_commit);
+
+        let mut last_generation = first_generation;
+        for generation in Wal::generations(dir, SEGMENT_PREFIX)? {
+            if generation < first_generation {
+                continue;
+            }
+            let mut segment = Record::decode_all(&fs::read(Wal::segment_path(dir, generation))?);
+            segment.truncate(committed(&segment, None).0);
+            apply(segment, &mut records, &mut last_commit);
+            log::debug!("{} WAL SEGMENT REPLAYED", generation);
+            last_generation = generation;
+        }
+
+        let wal = Wal::create(dir, archive, last_generation + 1, last_commit)?;
+        Ok((wal, records))
+    }
+
+    /// rebuilds the database as it was at the target from archived checkpoints and segments.
+    /// Archived history after the target is moved into a `discarded.<time>` directory of the archive
+    /// and the log continues from the target
+    pub(crate) fn recover(dir: &Path, archive: &Path, target: RecoveryTarget) -> io::Result<(Wal, Vec<Record>)> {
+        fs::create_dir_all(dir)?;
+        fs::create_dir_all(archive)?;
+        // segments that were not archived yet, e.g. the database was stopped before a checkpoint
+        let archived = Wal::generations(archive, SEGMENT_PREFIX)?;
+        let mut last_generation = archived.last().copied().unwrap_or_default();
+        for generation in Wal::generations(dir, SEGMENT_PREFIX)? {
+            if !archived.contains(&generation) {
+                archive_file(
+                    archive,
+                    &Wal::segment_path(dir, generation),
+                    &Wal::segment_name(generation),
+                )?;
+            }
+            fs::remove_file(Wal::segment_path(dir, generation))?;
+            last_generation = last_generation.max(generation);
+        }
+
+        let checkpoints = Wal::generations(archive, CHECKPOINT_PREFIX)?;
+        let mut base = None;
+        for generation in checkpoints.iter().rev() {
+            if let Some((_, checkpoint)) = Wal::read_checkpoint(&archive.join(Wal::checkpoint_name(*generation)))? {
+                let last_commit = checkpoint
+                    .iter()
+                    .rev()
+                    .find_map(|record| match record {
+                        Record::Commit(commit) => Some(*commit),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+                if !last_commit.is_after(target) {
+                    base = Some((*generation, checkpoint));
+                    break;
+                }
+            }
+        }
+        let (first_generation, checkpoint) = base.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "WAL archive does not have a checkpoint before the recovery target",
+            )
+        })?;
+        let mut records = vec![];
+        let mut last_commit = Commit::default();
+        apply(checkpoint, &mut records, &mut last_commit);
+
+        let mut discarded = vec![];
+        let mut reached = None;
+        for generation in Wal::generations(archive, SEGMENT_PREFIX)? {
+            if generation < first_generation {
+                continue;
+            }
+            if reached.is_some() {
+                discarded.push(Wal::segment_name(generation));
+                continue;
+            }
+            let mut segment = Record::decode_all(&fs::read(archive.join(Wal::segment_name(generation)))?);
+            let (committed, target_reached) = committed(&segment, Some(target));
+            segment.truncate(committed);
+            if target_reached {
+                let mut data = vec![];
+                for record in segment.iter() {
+                    record.encode(&mut data);
+                }
+                reached = Some((generation, data));
+            }
+            apply(segment, &mut records, &mut last_commit);
+            log::debug!("{} ARCHIVED WAL SEGMENT REPLAYED", generation);
+        }
+
+        match reached {
+            None => log::warn!("recovery target is not reached, the whole WAL archive is replayed"),
+            Some((generation, data)) => {
+                for checkpoint in checkpoints.into_iter().filter(|checkpoint| *checkpoint > generation) {
+                    discarded.push(Wal::checkpoint_name(checkpoint));
+                }
+                let discarded_dir = archive.join(format!("{}{}", DISCARDED_PREFIX, millis(SystemTime::now())));
+                fs::create_dir_all(&discarded_dir)?;
+                let name = Wal::segment_name(generation);
+                fs::rename(archive.join(&name), discarded_dir.join(&name))?;
+                // the archive keeps transactions that were committed before the target
+                let partial = archive.join(format!("{}.partial", name));
+                let mut file = File::create(&partial)?;
+                file.write_all(&data)?;
+                file.sync_all()?;
+                fs::rename(&partial, archive.join(&name))?;
+                for name in discarded {
+                    fs::rename(archive.join(&name), discarded_dir.join(&name))?;
+                }
+                File::open(archive)?.sync_all()?;
+                log::info!(
+                    "WAL archive after the recovery target is moved into {:?}",
+                    discarded_dir
+                );
+            }
+        }
+
+        let wal = Wal::create(dir, Some(archive), last_generation + 1, last_commit)?;
+        Ok((wal, records))
+    }
+
+    fn create(dir: &Path, archive: Option<&Path>, generation: u64, last_commit: Commit) -> io::Result<Wal> {
+        Ok(Wal {
+            dir: dir.to_path_buf(),
+            archive: archive.map(Path::to_path_buf),
+            state: Mutex::new(State {
+                file: Wal::create_segment(dir, generation)?,
+                generation,
+                buffer: vec![],
+                appended: 0,
+                segment_size: 0,
+                last_commit,
+            }),
+            durable: Mutex::new(0),
+        })
+    }
+
+    /// generation of the first segment after the checkpoint and the checkpoint records
+    fn read_checkpoint(path: &Path) -> io::Result<Option<(u64, Vec<Record>)>> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let generation = data
+            .get(0..8)
+            .map(|header| u64::from_le_bytes(header.try_into().unwrap()))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "checkpoint is corrupted"))?;
+        Ok(Some((generation, Record::decode_all(&data[8..]))))
+    }
+
+    /// `apply` is run under the same lock so the order of records in the log
+    /// is the same as the order of changes
+    pub(crate) fn append<R>(&self, records: impl IntoIterator<Item = Record>, apply: impl FnOnce() -> R) -> R {
+        let mut state = self.state.lock().unwrap();
+        let result = apply();
+        for record in records {
+            state.push(&record);
+        }
+        result
+    }
+
+    pub(crate) fn commit(
+        &self,
+        timestamp: u64,
+        snapshot: impl FnOnce(&mut CheckpointWriter) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let segment_size = {
+            let mut state = self.state.lock().unwrap();
+            let commit = Commit::now(timestamp);
+            state.push(&Record::Commit(commit));
+            state.last_commit = commit;
+            state.segment_size
+        };
+        if segment_size >= CHECKPOINT_THRESHOLD {
+            self.checkpoint(snapshot)?;
+        }
+        Ok(())
+    }
+
+    /// waits until everything that was appended before the call is on disk
+    pub(crate) fn sync(&self) -> io::Result<()> {
+        let target = self.state.lock().unwrap().appended;
+        let mut durable = self.durable.lock().unwrap();
+        if *durable >= target {
+            // some other transaction has written our records
+            return Ok(());
+        }
+        let (buffer, mut file, appended) = {
+            let mut state = self.state.lock().unwrap();
+            let file = state.file.try_clone()?;
+            (mem::take(&mut state.buffer), file, state.appended)
+        };
+        if let Err(error) = write_records(&mut file, &buffer) {
+            // records are written again by the next sync
+            self.state.lock().unwrap().buffer.splice(0..0, buffer);
+            return Err(error);
+        }
+        *durable = appended;
+        Ok(())
+    }
+
+    /// `snapshot` has to write the whole database content. Changes are blocked until it is done
+    pub(crate) fn checkpoint(&self, snapshot: impl FnOnce(&mut CheckpointWriter) -> io::Result<()>) -> io::Result<()> {
+        let mut durable = self.durable.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
+        // records stay in the buffer until they are on disk, so the next sync writes them if this one fails
+        let State { file, buffer, .. } = &mut *state;
+        write_records(file, buffer)?;
+        buffer.clear();
+
+        let generation = state.generation + 1;
+        let file = Wal::create_segment(&self.dir, generation)?;
+
+        let mut writer = CheckpointWriter {
+            file: BufWriter::new(File::create(self.dir.join(CHECKPOINT_TMP))?),
+            buffer: vec![],
+        };
+        writer.file.write_all(&generation.to_le_bytes())?;
+        snapshot(&mut writer)?;
+        // tells point in time of the checkpoint when it is archived
+        writer.write(&Record::Commit(state.last_commit))?;
+        let checkpoint = writer.file.into_inner().map_err(|error| error.into_error())?;
+        checkpoint.sync_all()?;
+        fs::rename(self.dir.join(CHECKPOINT_TMP), self.dir.join(CHECKPOINT))?;
+        File::open(&self.dir)?.sync_all()?;
+        log::debug!("CHECKPOINT WRITTEN, LOG CONTINUES FROM {} SEGMENT", generation);
+
+        if let Some(archive) = &self.archive {
+            archive_file(archive, &self.dir.join(CHECKPOINT), &Wal::checkpoint_name(generation))?;
+        }
+        for old in Wal::generations(&self.dir, SEGMENT_PREFIX)?
+            .into_iter()
+            .filter(|old| *old < generation)
+        {
+            if let Some(archive) = &self.archive {
+                archive_file(archive, &Wal::segment_path(&self.dir, old), &Wal::segment_name(old))?;
+            }
+            fs::remove_file(Wal::segment_path(&self.dir, old))?;
+        }
+
+        state.file = file;
+        state.generation = generation;
+        state.segment_size = 0;
+        *durable = state.appended;
+        Ok(())
+    }
+
+    /// generations of segments or checkpoints in the directory in ascending order
+    fn generations(dir: &Path, prefix: &str) -> io::Result<Vec<u64>> {
+        let mut generations = vec![];
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            if let Some(generation) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(prefix))
+                .and_then(|generation| generation.parse::<u64>().ok())
+            {
+                generations.push(generation);
+            }
+        }
+        generations.sort_unstable();
+        Ok(generations)
+    }
+
+    fn segment_name(generation: u64) -> String {
+        format!("{}{:020}", SEGMENT_PREFIX, generation)
+    }
+
+    fn segment_path(dir: &Path, generation: u64) -> PathBuf {
+        dir.join(Wal::segment_name(generation))
+    }
+
+    /// archived checkpoint is a state of the database before the segment of the same generation
+    fn checkpoint_name(generation: u64) -> String {
+        format!("{}{:020}", CHECKPOINT_PREFIX, generation)
+    }
+
+    fn create_segment(dir: &Path, generation: u64) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Wal::segment_path(dir, generation))
+    }
+}
+
+/// number of records at the start of a segment that belong to transactions committed before
+/// the target and whether the target was reached. The rest of records belong to later transactions
+/// or to the one that was not committed at all
+fn committed(segment: &[Record], target: Option<RecoveryTarget>) -> (usize, bool) {
+    let mut committed = 0;
+    for (index, record) in segment.iter().enumerate() {
+        if let Record::Commit(commit) = record {
+            if matches!(target, Some(target) if commit.is_after(target)) {
+                return (committed, true);
+            }
+            committed = index + 1;
+        }
+    }
+    (committed, false)
+}
+
+fn apply(committed: Vec<Record>, records: &mut Vec<Record>, last_commit: &mut Commit) {
+    for record in committed {
+        match record {
+            Record::Commit(commit) => *last_commit = commit,
+            record => records.push(record),
+        }
+    }
+}
+
+/// appends the records to the segment and waits until they are on disk. Records of a failed write
+/// are cut off the segment, otherwise a torn record would stop replay of the ones written after it
+fn write_records(file: &mut File, records: &[u8]) -> io::Result<()> {
+    let len = file.metadata()?.len();
+    if let Err(error) = file.write_all(records).and_then(|()| file.sync_data()) {
+        if let Err(truncate_error) = file.set_len(len) {
+            log::error!("failed write is not cut off WAL segment: {}", truncate_error);
+        }
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// the copy is durable when the function returns
+fn archive_file(archive: &Path, file: &Path, name: &str) -> io::Result<()> {
+    let partial = archive.join(format!("{}.partial", name));
+    fs::copy(file, &partial)?;
+    File::open(&partial)?.sync_all()?;
+    fs::rename(&partial, archive.join(name))?;
+    File::open(archive)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,98 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("isomorphicdb-wal-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        TempDir(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn put(key: &str) -> Record {
+    Record::Put(
+        "tree".to_owned(),
+        Binary::with_data(key.as_bytes().to_vec()),
+        Binary::with_data(vec![]),
+    )
+}
+
+/// next writes into the segment fail until it is opened again
+fn break_segment(wal: &Wal) {
+    let mut state = wal.state.lock().unwrap();
+    state.file = File::open(Wal::segment_path(&wal.dir, state.generation)).unwrap();
+}
+
+fn repair_segment(wal: &Wal) {
+    let mut state = wal.state.lock().unwrap();
+    state.file = Wal::create_segment(&wal.dir, state.generation).unwrap();
+}
+
+#[test]
+fn records_of_failed_checkpoint_are_written_by_next_sync() {
+    let dir = TempDir::new("failed-checkpoint");
+    let (wal, _) = Wal::open(&dir.0, None).unwrap();
+    wal.append(vec![Record::CreateTree("tree".to_owned()), put("key")], || ());
+    wal.commit(1, |_| Ok(())).unwrap();
+
+    break_segment(&wal);
+    assert!(wal.checkpoint(|_| Ok(())).is_err());
+
+    repair_segment(&wal);
+    wal.sync().unwrap();
+    drop(wal);
+
+    let (_, records) = Wal::open(&dir.0, None).unwrap();
+    assert_eq!(
+        records
+            .into_iter()
+            .filter(|record| !matches!(record, Record::Commit(_)))
+            .collect::<Vec<_>>(),
+        vec![Record::CreateTree("tree".to_owned()), put("key")]
+    );
+}
+
+#[test]
+fn records_of_failed_sync_are_written_by_next_sync() {
+    let dir = TempDir::new("failed-sync");
+    let (wal, _) = Wal::open(&dir.0, None).unwrap();
+    wal.append(vec![Record::CreateTree("tree".to_owned()), put("key")], || ());
+    wal.commit(1, |_| Ok(())).unwrap();
+
+    break_segment(&wal);
+    assert!(wal.sync().is_err());
+
+    repair_segment(&wal);
+    wal.sync().unwrap();
+    drop(wal);
+
+    let (_, records) = Wal::open(&dir.0, None).unwrap();
+    assert_eq!(
+        records
+            .into_iter()
+            .filter(|record| !matches!(record, Record::Commit(_)))
+            .collect::<Vec<_>>(),
+        vec![Record::CreateTree("tree".to_owned()), put("key")]
+    );
+}
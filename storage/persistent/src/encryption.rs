@@ -0,0 +1,301 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use aes_gcm::{
+    aead::{Aead, NewAead, Payload},
+    Aes256Gcm,
+};
+use binary::{repr::Datum, Binary};
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Formatter},
+    fs,
+    sync::Arc,
+};
+use storage_api::{IndexKeys, Key, StorageError, StorageResult};
+
+pub(crate) const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+/// key id and nonce go before the ciphertext
+const HEADER_LEN: usize = 4 + NONCE_LEN;
+
+/// AES-256-GCM keys of the storage by their ids. Values are encrypted with the key that
+/// has the greatest id, other keys are kept to read values that were written before rotation.
+///
+/// Keys are written one per line, or separated by commas, as `<id>:<64 hex digits>`.
+/// Empty lines and lines that start with `#` are skipped
+#[derive(Clone)]
+pub struct Keyring {
+    keys: BTreeMap<u32, Aes256Gcm>,
+}
+
+impl Keyring {
+    pub fn parse(keys: &str) -> StorageResult<Keyring> {
+        let mut keyring = BTreeMap::new();
+        for line in keys.split(['\n', ',']).map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (id, key) = match line.find(':') {
+                Some(colon) => (&line[..colon], &line[colon + 1..]),
+                None => return Err(malformed("key has no id")),
+            };
+            let id = id
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| malformed("key id is not a number"))?;
+            let key = hex(key.trim()).ok_or_else(|| malformed("key has to be 64 hex digits"))?;
+            let cipher = Aes256Gcm::new_from_slice(&key).map_err(|_| malformed("key has to be 64 hex digits"))?;
+            if keyring.insert(id, cipher).is_some() {
+                return Err(malformed(&format!("key {} is repeated", id)));
+            }
+        }
+        if keyring.is_empty() {
+            return Err(malformed("there are no keys"));
+        }
+        Ok(Keyring { keys: keyring })
+    }
+
+    pub fn from_file(path: &str) -> StorageResult<Keyring> {
+        let keys = fs::read_to_string(path)
+            .map_err(|error| StorageError::Encryption(format!("can't read key file {:?}: {}", path, error)))?;
+        Keyring::parse(&keys)
+    }
+
+    pub fn from_env(variable: &str) -> StorageResult<Keyring> {
+        let keys = std::env::var(variable)
+            .map_err(|error| StorageError::Encryption(format!("can't read keys from {:?}: {}", variable, error)))?;
+        Keyring::parse(&keys)
+    }
+
+    /// id of the key that encrypts new values
+    pub fn active(&self) -> u32 {
+        *self.keys.keys().next_back().expect("keyring has at least one key")
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.keys.contains_key(&id)
+    }
+
+    /// `context` is authenticated together with the value, so the value can't be
+    /// read back in another context, e.g. under another key of a tree
+    pub fn encrypt(&self, context: &[u8], value: &[u8]) -> Vec<u8> {
+        let id = self.active();
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::getrandom(&mut nonce).expect("to generate nonce");
+        let ciphertext = self.keys[&id]
+            .encrypt(
+                &nonce.into(),
+                Payload {
+                    msg: value,
+                    aad: context,
+                },
+            )
+            .expect("to encrypt value");
+        let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        data.extend_from_slice(&id.to_be_bytes());
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        data
+    }
+
+    pub fn decrypt(&self, context: &[u8], data: &[u8]) -> StorageResult<Vec<u8>> {
+        let id = key_id(data).ok_or_else(|| StorageError::Corrupted("encrypted value is too short".to_owned()))?;
+        let cipher = self
+            .keys
+            .get(&id)
+            .ok_or_else(|| StorageError::Encryption(format!("value is encrypted with unknown key {}", id)))?;
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(&data[4..HEADER_LEN]);
+        cipher
+            .decrypt(
+                &nonce.into(),
+                Payload {
+                    msg: &data[HEADER_LEN..],
+                    aad: context,
+                },
+            )
+            .map_err(|_| StorageError::Corrupted(format!("value can't be decrypted with key {}", id)))
+    }
+}
+
+impl Debug for Keyring {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keyring")
+            .field("ids", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Encrypts values of a tree. Values are bound to the tree and to their keys
+#[derive(Debug, Clone)]
+pub(crate) struct Cipher {
+    keyring: Arc<Keyring>,
+    tree: Vec<u8>,
+}
+
+impl Cipher {
+    pub(crate) fn new(keyring: Arc<Keyring>, tree: &[u8]) -> Cipher {
+        Cipher {
+            keyring,
+            tree: tree.to_vec(),
+        }
+    }
+
+    pub(crate) fn encrypt(&self, key: &[u8], value: &[u8]) -> Vec<u8> {
+        self.keyring.encrypt(&self.context(key), value)
+    }
+
+    pub(crate) fn decrypt(&self, key: &[u8], data: &[u8]) -> StorageResult<Vec<u8>> {
+        self.keyring.decrypt(&self.context(key), data)
+    }
+
+    fn context(&self, key: &[u8]) -> Vec<u8> {
+        let mut context = Vec::with_capacity(4 + self.tree.len() + key.len());
+        context.extend_from_slice(&(self.tree.len() as u32).to_be_bytes());
+        context.extend_from_slice(&self.tree);
+        context.extend_from_slice(key);
+        context
+    }
+}
+
+/// Keys index entries with HMAC-SHA256 digests of indexed values instead of the values.
+/// Each value has its own digest, so entries of the first values are a prefix of entries of all of them
+#[derive(Clone)]
+pub(crate) struct IndexDigest {
+    mac: Hmac<Sha256>,
+}
+
+impl IndexDigest {
+    pub(crate) fn new(key: &[u8]) -> StorageResult<IndexDigest> {
+        if key.len() != KEY_LEN {
+            return Err(StorageError::Corrupted("key of index entries is malformed".to_owned()));
+        }
+        Ok(IndexDigest {
+            mac: Hmac::new_from_slice(key).expect("HMAC takes keys of any length"),
+        })
+    }
+}
+
+impl Debug for IndexDigest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexDigest").finish()
+    }
+}
+
+impl IndexKeys for IndexDigest {
+    fn key(&self, values: &[Datum]) -> Key {
+        let mut key = Vec::with_capacity(values.len() * KEY_LEN);
+        for value in values {
+            let mut mac = self.mac.clone();
+            mac.update(Binary::pack_key(std::slice::from_ref(value)).as_ref());
+            key.extend_from_slice(&mac.finalize().into_bytes());
+        }
+        Binary::with_data(key)
+    }
+}
+
+/// id of the key that the value was encrypted with
+pub(crate) fn key_id(data: &[u8]) -> Option<u32> {
+    if data.len() < HEADER_LEN {
+        return None;
+    }
+    let mut id = [0u8; 4];
+    id.copy_from_slice(&data[..4]);
+    Some(u32::from_be_bytes(id))
+}
+
+fn hex(digits: &str) -> Option<Vec<u8>> {
+    if digits.len() != KEY_LEN * 2 || !digits.is_ascii() {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).ok())
+        .collect()
+}
+
+fn malformed(message: &str) -> StorageError {
+    StorageError::Encryption(format!("malformed keys: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str = "1:000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const SECOND: &str = "2:F0F1F2F3F4F5F6F7F8F9FAFBFCFDFEFF000102030405060708090A0B0C0D0E0F";
+
+    #[test]
+    fn newest_key_is_active() {
+        let keyring = Keyring::parse(&format!("# rotated keys\n{}\n\n{}\n", SECOND, FIRST)).unwrap();
+        assert_eq!(keyring.active(), 2);
+        assert!(keyring.contains(1));
+    }
+
+    #[test]
+    fn comma_separated_keys() {
+        let keyring = Keyring::parse(&format!("{}, {}", FIRST, SECOND)).unwrap();
+        assert_eq!(keyring.active(), 2);
+    }
+
+    #[test]
+    fn malformed_keys() {
+        assert!(matches!(Keyring::parse(""), Err(StorageError::Encryption(_))));
+        assert!(matches!(Keyring::parse("1:0011"), Err(StorageError::Encryption(_))));
+        assert!(matches!(Keyring::parse(&FIRST[2..]), Err(StorageError::Encryption(_))));
+        assert!(matches!(
+            Keyring::parse(&format!("{}\n{}", FIRST, FIRST)),
+            Err(StorageError::Encryption(_))
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        let keyring = Keyring::parse(FIRST).unwrap();
+        let data = keyring.encrypt(b"context", b"value");
+        assert_eq!(key_id(&data), Some(1));
+        assert_eq!(keyring.decrypt(b"context", &data), Ok(b"value".to_vec()));
+    }
+
+    #[test]
+    fn values_are_encrypted_with_different_nonces() {
+        let keyring = Keyring::parse(FIRST).unwrap();
+        assert_ne!(
+            keyring.encrypt(b"context", b"value"),
+            keyring.encrypt(b"context", b"value")
+        );
+    }
+
+    #[test]
+    fn value_of_another_context() {
+        let keyring = Keyring::parse(FIRST).unwrap();
+        let data = keyring.encrypt(b"context", b"value");
+        assert!(matches!(
+            keyring.decrypt(b"another context", &data),
+            Err(StorageError::Corrupted(_))
+        ));
+    }
+
+    #[test]
+    fn value_of_removed_key() {
+        let data = Keyring::parse(FIRST).unwrap().encrypt(b"context", b"value");
+        assert!(matches!(
+            Keyring::parse(SECOND).unwrap().decrypt(b"context", &data),
+            Err(StorageError::Encryption(_))
+        ));
+    }
+}
@@ -12,158 +12,432 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::encryption::{key_id, Cipher, IndexDigest, KEY_LEN};
 use binary::{repr::Datum, Binary};
-use sled::{Db as SledDb, Tree as SledTree};
+use dashmap::DashMap;
+use sled::{Db as SledDb, IVec, Tree as SledTree};
 use std::{
-    convert::TryInto,
-    sync::atomic::{AtomicU64, Ordering},
+    ops::Bound,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
-use storage_api::{Cursor, Key, Storage, Tree, Value};
+use storage_api::{
+    IndexKeys, Key, KeyRange, PackedIndexKeys, Storage, StorageError, StorageResult, Tree, TreeScan, Value, WriteBatch,
+};
+
+pub use encryption::Keyring;
+
+mod encryption;
 
-const DEFINITION_SCHEMA: &str = "DEFINITION_SCHEMA";
-const SCHEMATA_TABLE: &str = "SCHEMATA";
-const TABLES_TABLE: &str = "TABLES";
-const INDEXES_TABLE: &str = "TABLES";
-const COLUMNS_TABLE: &str = "COLUMNS";
+/// key of the default tree that records how values are encrypted
+const ENCRYPTION: &[u8] = b"__ENCRYPTION";
+/// all values are encrypted with the key
+const ENCRYPTED: u8 = 0;
+/// values are being re-encrypted with the key, some of them are still encrypted with older keys
+const ROTATING: u8 = 1;
+/// values are being encrypted with the key, some of them can still be in plain text
+const ENCRYPTING: u8 = 2;
+/// value encrypted with the key of the database. Keys that do not match it are rejected on start
+const KEY_CHECK: &[u8] = b"isomorphicdb";
+/// key of the default tree that keeps the key of index entries encrypted with the active key
+const INDEX_KEY: &[u8] = b"__INDEX_KEY";
+/// values are re-encrypted in batches of the size
+const BATCH_SIZE: usize = 1024;
+
+fn storage_error(error: sled::Error) -> StorageError {
+    match error {
+        sled::Error::Io(error) => StorageError::from(error),
+        sled::Error::Corruption { .. } => StorageError::Corrupted(error.to_string()),
+        error => StorageError::Io(error.to_string()),
+    }
+}
 
 pub struct PersistentDatabase {
     sled_db: SledDb,
+    /// every created tree, sled creates a tree when it is opened for the first time
+    trees: DashMap<String, OpenedTree>,
+    keyring: Option<Arc<Keyring>>,
+    index_keys: Arc<dyn IndexKeys>,
+    /// trees are created and dropped one at a time, so only one caller creates a tree
+    tree_changes: Mutex<()>,
 }
 
 impl PersistentDatabase {
     pub fn new(path: &str) -> PersistentDatabase {
-        let sled_db = sled::open(path).unwrap();
-        let new_database = !sled_db.was_recovered();
-
-        let this = PersistentDatabase { sled_db };
-
-        if new_database {
-            // database bootstrap
-            this.create_tree(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE));
-            this.lookup_tree(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE))
-                .insert(vec![Binary::pack(&[
-                    Datum::from_string("IN_MEMORY".to_owned()),
-                    Datum::from_string("public".to_owned()),
-                ])]);
-            this.create_tree(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE));
-            this.create_tree(format!("{}.{}", DEFINITION_SCHEMA, COLUMNS_TABLE));
-            this.create_tree(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE));
+        PersistentDatabase::open(path, None).unwrap()
+    }
+
+    /// values of all trees are encrypted with the active key of the keyring.
+    /// Values that were written in plain text or with other keys are encrypted again before
+    /// the database is opened, after that older keys can be removed from the keyring.
+    /// Keys of trees stay in plain text to keep them ordered, except keys of index trees that are
+    /// digests of indexed values. Previous contents of values can stay in unused space of data files
+    /// until sled reuses it
+    pub fn open(path: &str, keyring: Option<Keyring>) -> StorageResult<PersistentDatabase> {
+        let mut database = PersistentDatabase {
+            sled_db: sled::open(path).map_err(storage_error)?,
+            trees: DashMap::default(),
+            keyring: None,
+            index_keys: Arc::new(PackedIndexKeys),
+            tree_changes: Mutex::default(),
+        };
+        let encryption = database.sled_db.get(ENCRYPTION).map_err(storage_error)?;
+        match (encryption, keyring) {
+            (None, None) => {}
+            (Some(_), None) => {
+                return Err(StorageError::Encryption(
+                    "database is encrypted and can't be opened without keys".to_owned(),
+                ))
+            }
+            (encryption, Some(keyring)) => {
+                let keyring = Arc::new(keyring);
+                database.encrypt(encryption, &keyring)?;
+                database.index_keys = Arc::new(database.index_digest(&keyring)?);
+                database.keyring = Some(keyring);
+            }
         }
-        this
+        for name in database.tree_names()? {
+            let sled_tree = database.sled_db.open_tree(&name).map_err(storage_error)?;
+            database.trees.insert(name, OpenedTree::new(sled_tree)?);
+        }
+        Ok(database)
+    }
+
+    fn encrypt(&self, encryption: Option<IVec>, keyring: &Keyring) -> StorageResult<()> {
+        let state = match encryption {
+            None => ENCRYPTING,
+            Some(encryption) if encryption.is_empty() => {
+                return Err(StorageError::Corrupted("encryption state is malformed".to_owned()))
+            }
+            Some(encryption) => {
+                let check = &encryption[1..];
+                match key_id(check) {
+                    Some(id) if !keyring.contains(id) => {
+                        return Err(StorageError::Encryption(format!(
+                            "database is encrypted with key {} that is not in the keyring",
+                            id
+                        )))
+                    }
+                    Some(id) => {
+                        if keyring.decrypt(ENCRYPTION, check).ok().as_deref() != Some(KEY_CHECK) {
+                            return Err(StorageError::Encryption(format!(
+                                "key {} does not match the key database is encrypted with",
+                                id
+                            )));
+                        }
+                        if encryption[0] == ENCRYPTED && id == keyring.active() {
+                            return Ok(());
+                        }
+                    }
+                    None => return Err(StorageError::Corrupted("encryption state is malformed".to_owned())),
+                }
+                encryption[0]
+            }
+        };
+        // values that can't be decrypted are in plain text until every value is encrypted once
+        let plain_text = state != ENCRYPTED && state != ROTATING;
+        log::info!("ENCRYPTING VALUES WITH KEY {}", keyring.active());
+        self.record_encryption(if plain_text { ENCRYPTING } else { ROTATING }, keyring)?;
+        let keyring = Arc::new(keyring.clone());
+        for name in self.tree_names()? {
+            let sled_tree = self.sled_db.open_tree(&name).map_err(storage_error)?;
+            let cipher = Cipher::new(keyring.clone(), &sled_tree.name());
+            let mut batch = sled::Batch::default();
+            let mut size = 0;
+            for row in sled_tree.iter() {
+                let (key, value) = row.map_err(storage_error)?;
+                let value = match cipher.decrypt(&key, &value) {
+                    Ok(_) if key_id(&value) == Some(keyring.active()) => continue,
+                    Ok(value) => value,
+                    Err(_) if plain_text => value.to_vec(),
+                    Err(error) => return Err(error),
+                };
+                batch.insert(key.as_ref(), cipher.encrypt(&key, &value));
+                size += 1;
+                if size == BATCH_SIZE {
+                    sled_tree
+                        .apply_batch(std::mem::take(&mut batch))
+                        .map_err(storage_error)?;
+                    size = 0;
+                }
+            }
+            sled_tree.apply_batch(batch).map_err(storage_error)?;
+        }
+        self.record_encryption(ENCRYPTED, &keyring)
+    }
+
+    /// index entries are keyed with a random key that is generated once,
+    /// it is encrypted again when the active key changes
+    fn index_digest(&self, keyring: &Keyring) -> StorageResult<IndexDigest> {
+        let key = match self.sled_db.get(INDEX_KEY).map_err(storage_error)? {
+            Some(data) if key_id(&data) == Some(keyring.active()) => {
+                return IndexDigest::new(&keyring.decrypt(INDEX_KEY, &data)?)
+            }
+            Some(data) => keyring.decrypt(INDEX_KEY, &data)?,
+            None => {
+                let mut key = vec![0u8; KEY_LEN];
+                getrandom::getrandom(&mut key).expect("to generate key of index entries");
+                key
+            }
+        };
+        self.sled_db
+            .insert(INDEX_KEY, keyring.encrypt(INDEX_KEY, &key))
+            .map_err(storage_error)?;
+        self.sled_db.flush().map_err(storage_error)?;
+        IndexDigest::new(&key)
+    }
+
+    fn record_encryption(&self, state: u8, keyring: &Keyring) -> StorageResult<()> {
+        let mut encryption = vec![state];
+        encryption.extend(keyring.encrypt(ENCRYPTION, KEY_CHECK));
+        self.sled_db.insert(ENCRYPTION, encryption).map_err(storage_error)?;
+        self.sled_db.flush().map_err(storage_error)?;
+        Ok(())
     }
 }
 
 impl Storage for PersistentDatabase {
-    type Tree = PersistentTable;
+    fn lookup_tree(&self, table: &str) -> StorageResult<Box<dyn Tree>> {
+        let OpenedTree { sled_tree, key_index } = match self.trees.get(table) {
+            Some(tree) => tree.clone(),
+            None => return Err(StorageError::TreeNotFound(table.to_owned())),
+        };
+        let cipher = self
+            .keyring
+            .as_ref()
+            .map(|keyring| Cipher::new(keyring.clone(), table.as_bytes()));
+        Ok(Box::new(PersistentTable {
+            sled_tree,
+            key_index,
+            cipher,
+        }))
+    }
+
+    fn drop_tree(&self, table: &str) -> StorageResult<()> {
+        let _guard = self.tree_changes.lock().unwrap();
+        self.trees.remove(table);
+        self.sled_db.drop_tree(table).map_err(storage_error)?;
+        Ok(())
+    }
 
-    fn lookup_tree<T: Into<String>>(&self, table: T) -> Self::Tree {
-        PersistentTable::from(self.sled_db.open_tree(table.into()).unwrap())
+    fn create_tree(&self, table: &str) -> StorageResult<bool> {
+        let _guard = self.tree_changes.lock().unwrap();
+        if self.trees.contains_key(table) {
+            return Ok(false);
+        }
+        let sled_tree = self.sled_db.open_tree(table).map_err(storage_error)?;
+        self.trees.insert(table.to_owned(), OpenedTree::new(sled_tree)?);
+        Ok(true)
     }
 
-    fn drop_tree<T: Into<String>>(&self, table: T) {
-        self.sled_db.drop_tree(table.into()).unwrap();
+    fn tree_names(&self) -> StorageResult<Vec<String>> {
+        let default_tree = self.sled_db.name();
+        Ok(self
+            .sled_db
+            .tree_names()
+            .into_iter()
+            .filter(|name| *name != default_tree)
+            .map(|name| String::from_utf8_lossy(&name).into_owned())
+            .collect())
     }
 
-    fn create_tree<T: Into<String>>(&self, table: T) {
-        self.sled_db.open_tree(table.into()).unwrap();
+    fn index_keys(&self) -> Arc<dyn IndexKeys> {
+        self.index_keys.clone()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.keyring.is_some()
+    }
+}
+
+/// Every handle of the same tree has to share key generator
+/// otherwise concurrent transactions would produce the same keys
+#[derive(Clone)]
+struct OpenedTree {
+    sled_tree: SledTree,
+    key_index: Arc<AtomicU64>,
+}
+
+impl OpenedTree {
+    fn new(sled_tree: SledTree) -> StorageResult<OpenedTree> {
+        let key_index = PersistentTable::next_key_index(&sled_tree)?;
+        Ok(OpenedTree {
+            sled_tree,
+            key_index: Arc::new(AtomicU64::new(key_index)),
+        })
     }
 }
 
 #[derive(Debug)]
 pub struct PersistentTable {
     sled_tree: SledTree,
-    key_index: AtomicU64,
+    key_index: Arc<AtomicU64>,
+    cipher: Option<Cipher>,
 }
 
-impl From<SledTree> for PersistentTable {
-    fn from(sled_tree: SledTree) -> PersistentTable {
-        let key_index = sled_tree.last();
-
-        let key_index = key_index
-            .map(|option| {
-                option
-                    .map(|(key, _value)| u64::from_be_bytes((&key[1..9]).try_into().unwrap()))
-                    .unwrap_or_default()
-                    + 1
-            })
-            .ok()
-            .unwrap_or_default();
+impl PersistentTable {
+    /// generated keys sort in the order of their numbers, the next one goes after the last key.
+    /// Keys of index trees are not generated
+    fn next_key_index(sled_tree: &SledTree) -> StorageResult<u64> {
+        let key_index = match sled_tree.last().map_err(storage_error)? {
+            Some((key, _value)) => generated_key_index(&Binary::with_data(key.to_vec())).map_or(0, |id| id + 1),
+            None => 0,
+        };
         log::debug!(
             "{:?} KEY INDEX INIT WITH {:?}",
-            String::from_utf8(sled_tree.name().to_vec()).unwrap(),
+            String::from_utf8_lossy(&sled_tree.name()),
             key_index
         );
-        PersistentTable {
-            sled_tree,
-            key_index: AtomicU64::from(key_index),
+        Ok(key_index)
+    }
+
+    fn sled_range(&self, range: KeyRange) -> impl DoubleEndedIterator<Item = StorageResult<(Binary, Binary)>> {
+        let to_bytes = |bound: Bound<Key>| match bound {
+            Bound::Included(key) => Bound::Included(key.as_ref().to_vec()),
+            Bound::Excluded(key) => Bound::Excluded(key.as_ref().to_vec()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let cipher = self.cipher.clone();
+        self.sled_tree
+            .range((to_bytes(range.start), to_bytes(range.end)))
+            .map(move |row| {
+                let (key, value) = row.map_err(storage_error)?;
+                let value = decrypt(cipher.as_ref(), &key, value)?;
+                Ok((Binary::with_data(key.to_vec()), value))
+            })
+    }
+
+    fn encrypt(&self, key: &[u8], value: &Binary) -> Vec<u8> {
+        match &self.cipher {
+            None => value.as_ref().to_vec(),
+            Some(cipher) => cipher.encrypt(key, value.as_ref()),
         }
     }
+
+    fn decrypt(&self, key: &[u8], value: Option<IVec>) -> StorageResult<Option<Binary>> {
+        value.map(|value| decrypt(self.cipher.as_ref(), key, value)).transpose()
+    }
+}
+
+/// Keys are packed numbers with swapped bytes, so that they sort in the order they were generated.
+/// Keys written by older versions are read as other numbers, the next generated key still goes after them
+fn generated_key(key_index: u64) -> Key {
+    Binary::pack(&[Datum::from_u64(key_index.swap_bytes())])
+}
+
+fn generated_key_index(key: &Key) -> Option<u64> {
+    match key.try_unpack().as_deref() {
+        Ok([Datum::Int64(id)]) => Some((*id as u64).swap_bytes()),
+        _ => None,
+    }
+}
+
+fn decrypt(cipher: Option<&Cipher>, key: &[u8], value: IVec) -> StorageResult<Binary> {
+    match cipher {
+        None => Ok(Binary::with_data(value.to_vec())),
+        Some(cipher) => Ok(Binary::with_data(cipher.decrypt(key, &value)?)),
+    }
 }
 
 impl Tree for PersistentTable {
-    fn remove(&self, key: &Binary) -> Option<Binary> {
-        self.sled_tree
-            .remove(&key)
-            .unwrap()
-            .map(|v| Binary::with_data(v.to_vec()))
+    fn get(&self, key: &Binary) -> StorageResult<Option<Binary>> {
+        let value = self.sled_tree.get(key.as_ref()).map_err(storage_error)?;
+        self.decrypt(key.as_ref(), value)
     }
 
-    fn insert_key(&self, key: Binary, row: Binary) -> Option<Binary> {
-        self.sled_tree
-            .insert(key.as_ref(), row.as_ref())
-            .unwrap()
-            .map(|v| Binary::with_data(v.to_vec()))
+    fn next_key(&self) -> Key {
+        let key_index = self.key_index.fetch_add(1, Ordering::SeqCst);
+        log::debug!(
+            "{:?} NEXT KEY ID {:?}",
+            String::from_utf8_lossy(&self.sled_tree.name()),
+            key_index
+        );
+        generated_key(key_index)
     }
 
-    fn select(&self) -> Cursor {
-        self.sled_tree
-            .iter()
-            .map(Result::unwrap)
-            .map(|(key, value)| (Binary::with_data(key.to_vec()), Binary::with_data(value.to_vec())))
-            .collect()
-    }
-
-    fn insert(&self, data: Vec<Value>) -> Vec<Key> {
-        let mut keys = vec![];
-        for datum in data {
-            let key_index = self.key_index.fetch_add(1, Ordering::SeqCst);
-            log::debug!(
-                "{:?} NEXT KEY ID {:?}",
-                String::from_utf8(self.sled_tree.name().to_vec()).unwrap(),
-                key_index
-            );
-            let key = Binary::pack(&[Datum::from_u64(key_index)]);
-            self.sled_tree.insert(key.clone().as_ref(), datum.as_ref()).unwrap();
-            keys.push(key);
+    fn next_keys(&self, count: usize) -> Vec<Key> {
+        let first = self.key_index.fetch_add(count as u64, Ordering::SeqCst);
+        log::debug!(
+            "{:?} NEXT {} KEY IDS FROM {:?}",
+            String::from_utf8_lossy(&self.sled_tree.name()),
+            count,
+            first
+        );
+        (first..first + count as u64).map(generated_key).collect()
+    }
+
+    fn remove(&self, key: &Binary) -> StorageResult<Option<Binary>> {
+        let value = self.sled_tree.remove(key.as_ref()).map_err(storage_error)?;
+        self.decrypt(key.as_ref(), value)
+    }
+
+    fn insert_key(&self, key: Binary, row: Binary) -> StorageResult<Option<Binary>> {
+        let value = self
+            .sled_tree
+            .insert(key.as_ref(), self.encrypt(key.as_ref(), &row))
+            .map_err(storage_error)?;
+        self.decrypt(key.as_ref(), value)
+    }
+
+    fn select(&self) -> TreeScan {
+        self.select_range(KeyRange::all())
+    }
+
+    fn select_range(&self, range: KeyRange) -> TreeScan {
+        if range.is_empty() {
+            return Box::new(std::iter::empty());
+        }
+        Box::new(self.sled_range(range))
+    }
+
+    fn select_range_rev(&self, range: KeyRange) -> TreeScan {
+        if range.is_empty() {
+            return Box::new(std::iter::empty());
         }
-        keys
+        Box::new(self.sled_range(range).rev())
     }
 
-    fn update(&self, data: Vec<(Key, Value)>) -> usize {
+    fn insert(&self, data: Vec<Value>) -> StorageResult<Vec<Key>> {
+        let keys = self.next_keys(data.len());
+        self.apply_batch(keys.iter().cloned().zip(data.into_iter().map(Some)).collect())?;
+        Ok(keys)
+    }
+
+    fn update(&self, data: Vec<(Key, Value)>) -> StorageResult<usize> {
         let len = data.len();
         for (key, value) in data {
-            debug_assert!(
-                matches!(self.sled_tree.insert(key.as_ref(), value.as_ref()), Ok(Some(_))),
-                "update operation should change already existed key"
-            );
+            let old = self.insert_key(key, value)?;
+            debug_assert!(old.is_some(), "update operation should change already existed key");
         }
-        len
+        Ok(len)
     }
 
-    fn delete(&self, data: Vec<Key>) -> usize {
+    fn delete(&self, data: Vec<Key>) -> StorageResult<usize> {
         let mut size = 0;
-        let keys = self
-            .select()
-            .filter(|(key, _value)| data.contains(key))
-            .map(|(key, _value)| key)
-            .collect::<Vec<Binary>>();
-        for key in keys.iter() {
-            debug_assert!(
-                matches!(self.sled_tree.remove(key), Ok(Some(_))),
-                "delete operation delete existed key"
-            );
-            size += 1;
+        for key in data.iter() {
+            if self.remove(key)?.is_some() {
+                size += 1;
+            }
+        }
+        Ok(size)
+    }
+
+    fn reset_next_key(&self) -> StorageResult<()> {
+        self.key_index
+            .store(PersistentTable::next_key_index(&self.sled_tree)?, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn apply_batch(&self, batch: WriteBatch) -> StorageResult<()> {
+        let mut sled_batch = sled::Batch::default();
+        for (key, value) in batch {
+            match value {
+                None => sled_batch.remove(key.as_ref()),
+                Some(value) => sled_batch.insert(key.as_ref(), self.encrypt(key.as_ref(), &value)),
+            }
         }
-        size
+        self.sled_tree.apply_batch(sled_batch).map_err(storage_error)
     }
 }
@@ -0,0 +1,216 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use binary::Binary;
+use std::{
+    convert::TryInto,
+    io::{self, BufReader, BufWriter, Read, Write},
+};
+use storage_api::{Key, StorageError, StorageResult, TreeLayout, Value};
+
+const MAGIC: &[u8; 8] = b"IDBACKUP";
+const FORMAT_VERSION: u32 = 1;
+
+const TREE: u8 = 1;
+const ROW: u8 = 2;
+const END: u8 = 3;
+
+const ROWS_LAYOUT: u8 = 0;
+const COLUMNS_LAYOUT: u8 = 1;
+
+/// Content of a backup. Every tree is followed by its rows
+#[derive(Debug, PartialEq)]
+pub(crate) enum Entry {
+    Tree(String, TreeLayout),
+    Row(Key, Value),
+}
+
+fn corrupted(message: &str) -> StorageError {
+    StorageError::Corrupted(format!("backup {}", message))
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Backup starts with `[magic][format version]` and is followed by
+/// `[tag][payload len][payload][payload checksum]` entries, all numbers are little-endian.
+/// The last entry holds the number of rows in the backup, so a truncated one is never restored
+pub(crate) struct BackupWriter<W: Write> {
+    writer: BufWriter<W>,
+    payload: Vec<u8>,
+    rows: u64,
+}
+
+impl<W: Write> BackupWriter<W> {
+    pub(crate) fn new(writer: W) -> io::Result<BackupWriter<W>> {
+        let mut writer = BufWriter::new(writer);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        Ok(BackupWriter {
+            writer,
+            payload: vec![],
+            rows: 0,
+        })
+    }
+
+    pub(crate) fn write(&mut self, entry: &Entry) -> io::Result<()> {
+        fn bytes(payload: &mut Vec<u8>, bytes: &[u8]) {
+            payload.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            payload.extend_from_slice(bytes);
+        }
+
+        self.payload.clear();
+        let tag = match entry {
+            Entry::Tree(name, layout) => {
+                bytes(&mut self.payload, name.as_bytes());
+                self.payload.push(match layout {
+                    TreeLayout::Rows => ROWS_LAYOUT,
+                    TreeLayout::Columns => COLUMNS_LAYOUT,
+                });
+                TREE
+            }
+            Entry::Row(key, value) => {
+                bytes(&mut self.payload, key.as_ref());
+                bytes(&mut self.payload, &value.to_disk());
+                self.rows += 1;
+                ROW
+            }
+        };
+        self.write_payload(tag)
+    }
+
+    /// returns the number of written rows
+    pub(crate) fn finish(mut self) -> io::Result<u64> {
+        self.payload.clear();
+        self.payload.extend_from_slice(&self.rows.to_le_bytes());
+        self.write_payload(END)?;
+        self.writer.flush()?;
+        Ok(self.rows)
+    }
+
+    fn write_payload(&mut self, tag: u8) -> io::Result<()> {
+        self.writer.write_all(&[tag])?;
+        self.writer.write_all(&(self.payload.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&self.payload)?;
+        self.writer.write_all(&checksum(&self.payload).to_le_bytes())
+    }
+}
+
+/// Entries of a backup in the order they were written.
+/// Stops with an error if the backup is damaged or incomplete
+pub(crate) struct BackupReader<R: Read> {
+    reader: BufReader<R>,
+    rows: u64,
+    done: bool,
+}
+
+impl<R: Read> BackupReader<R> {
+    pub(crate) fn new(reader: R) -> StorageResult<BackupReader<R>> {
+        let mut reader = BufReader::new(reader);
+        let mut header = [0u8; 12];
+        read(&mut reader, &mut header)?;
+        if &header[0..8] != MAGIC {
+            return Err(corrupted("has unknown format"));
+        }
+        let version = u32::from_le_bytes(header[8..].try_into().unwrap());
+        if version != FORMAT_VERSION {
+            return Err(corrupted(&format!("format version {} is not supported", version)));
+        }
+        Ok(BackupReader {
+            reader,
+            rows: 0,
+            done: false,
+        })
+    }
+
+    fn entry(&mut self) -> StorageResult<Option<Entry>> {
+        fn bytes<'p>(payload: &'p [u8], index: &mut usize) -> StorageResult<&'p [u8]> {
+            let len = payload
+                .get(*index..*index + 4)
+                .map(|len| u32::from_le_bytes(len.try_into().unwrap()) as usize)
+                .ok_or_else(|| corrupted("entry is malformed"))?;
+            let bytes = payload
+                .get(*index + 4..*index + 4 + len)
+                .ok_or_else(|| corrupted("entry is malformed"))?;
+            *index += 4 + len;
+            Ok(bytes)
+        }
+
+        let mut header = [0u8; 5];
+        read(&mut self.reader, &mut header)?;
+        let mut payload = vec![0u8; u32::from_le_bytes(header[1..].try_into().unwrap()) as usize];
+        read(&mut self.reader, &mut payload)?;
+        let mut expected = [0u8; 4];
+        read(&mut self.reader, &mut expected)?;
+        if checksum(&payload) != u32::from_le_bytes(expected) {
+            return Err(corrupted("entry checksum does not match"));
+        }
+
+        let mut index = 0;
+        match header[0] {
+            TREE => {
+                let name = String::from_utf8(bytes(&payload, &mut index)?.to_vec())
+                    .map_err(|_| corrupted("tree name is not valid UTF-8"))?;
+                // backups that were made before tables could be laid out by columns do not have a layout
+                let layout = match payload.get(index) {
+                    None | Some(&ROWS_LAYOUT) => TreeLayout::Rows,
+                    Some(&COLUMNS_LAYOUT) => TreeLayout::Columns,
+                    Some(layout) => return Err(corrupted(&format!("tree has unknown layout {}", layout))),
+                };
+                Ok(Some(Entry::Tree(name, layout)))
+            }
+            ROW => {
+                let key = Binary::with_data(bytes(&payload, &mut index)?.to_vec());
+                let value = Binary::from_disk(bytes(&payload, &mut index)?)?;
+                self.rows += 1;
+                Ok(Some(Entry::Row(key, value)))
+            }
+            END => {
+                let rows = payload
+                    .as_slice()
+                    .try_into()
+                    .map(u64::from_le_bytes)
+                    .map_err(|_| corrupted("entry is malformed"))?;
+                if rows != self.rows {
+                    return Err(corrupted(&format!("has {} rows but {} were read", rows, self.rows)));
+                }
+                Ok(None)
+            }
+            tag => Err(corrupted(&format!("entry has unknown tag {}", tag))),
+        }
+    }
+}
+
+impl<R: Read> Iterator for BackupReader<R> {
+    type Item = StorageResult<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let entry = self.entry();
+        self.done = !matches!(entry, Ok(Some(_)));
+        entry.transpose()
+    }
+}
+
+fn read<R: Read>(reader: &mut R, buffer: &mut [u8]) -> StorageResult<()> {
+    reader.read_exact(buffer).map_err(|error| match error.kind() {
+        io::ErrorKind::UnexpectedEof => corrupted("is incomplete"),
+        _ => StorageError::from(error),
+    })
+}
@@ -12,201 +12,757 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::{
+    backup::{BackupReader, BackupWriter},
+    transaction::{Engine, Failure, Transaction, TransactionalTree},
+};
+use binary::repr::Datum;
 pub use binary::*;
+pub use columnar::*;
+pub use fault_injection::*;
 #[cfg(feature = "in_memory")]
 pub use in_memory::*;
+pub use metrics::{DatabaseMetrics, TreeMetrics};
 #[cfg(feature = "persistent")]
 pub use persistent::*;
 use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    io::{Read, Write},
     rc::Rc,
-    sync::{Arc, Mutex, MutexGuard},
+    sync::Arc,
 };
 pub use storage_api::*;
 
+mod backup;
+mod metrics;
+mod transaction;
+
+#[cfg(all(test, feature = "in_memory"))]
+mod tests;
+
+/// database that exists in every cluster, its trees are named as they were before databases were introduced
+pub const DEFAULT_DATABASE: &str = "postgres";
+/// separates name of a database from names of its trees in the storage
+pub const DATABASE_SEPARATOR: char = '/';
+
+const DEFINITION_SCHEMA: &str = "DEFINITION_SCHEMA";
+const DATABASES_TABLE: &str = "DATABASES";
+const SCHEMATA_TABLE: &str = "SCHEMATA";
+const TABLES_TABLE: &str = "TABLES";
+const INDEXES_TABLE: &str = "INDEXES";
+const COLUMNS_TABLE: &str = "COLUMNS";
+const STATISTICS_TABLE: &str = "STATISTICS";
+const SEQUENCES_TABLE: &str = "SEQUENCES";
+const TABLE_CONSTRAINTS_TABLE: &str = "TABLE_CONSTRAINTS";
+/// unique index over names of catalog records. Concurrent transactions that create objects
+/// with the same name write the same index entry, so only the first one to commit succeeds
+pub const NAMES_INDEX: &str = "NAMES";
+
+/// Opens a storage engine at the location
+pub type Backend = Box<dyn Fn(&Location) -> StorageResult<Box<dyn Storage>> + Send + Sync>;
+
+/// Where a storage backend keeps the data
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Location {
+    /// e.g. a path of a data directory
+    pub path: String,
+    /// directory where write-ahead log is archived. Backends without a log ignore it
+    pub wal_archive: Option<String>,
+    /// the database is recovered from WAL archive as it was at the target
+    pub recovery_target: Option<RecoveryTarget>,
+    /// data is encrypted with the keys. Only persistent backend supports encryption.
+    /// Backups are written in plain text and `Database::backup` refuses to write them for an encrypted
+    /// database unless it is asked to. A backup that is restored without keys is not encrypted
+    pub encryption_keys: Option<KeySource>,
+}
+
+/// Where encryption keys are read from, see `Keyring` for their format
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeySource {
+    /// path of a key file
+    File(String),
+    /// name of an environment variable
+    Env(String),
+}
+
+impl From<&str> for Location {
+    fn from(path: &str) -> Location {
+        Location {
+            path: path.to_owned(),
+            ..Location::default()
+        }
+    }
+}
+
+/// Storage engines that the database can be started on, looked up by name.
+/// The default registry has every backend that is enabled by crate features
+pub struct Backends {
+    backends: HashMap<String, Arc<Backend>>,
+}
+
+impl Backends {
+    pub fn empty() -> Backends {
+        Backends {
+            backends: HashMap::new(),
+        }
+    }
+
+    /// replaces a backend that was registered under the same name
+    pub fn register<F>(&mut self, name: &str, open: F)
+    where
+        F: Fn(&Location) -> StorageResult<Box<dyn Storage>> + Send + Sync + 'static,
+    {
+        self.backends.insert(name.to_owned(), Arc::new(Box::new(open)));
+    }
+
+    /// registers `name` backend that opens `base` backend and injects the faults into it
+    pub fn register_faulty(&mut self, name: &str, base: &str, faults: Faults) -> Result<(), OpenError> {
+        let base = self
+            .backends
+            .get(base)
+            .cloned()
+            .ok_or_else(|| OpenError::UnknownBackend(base.to_owned()))?;
+        self.register(name, move |location| {
+            Ok(Box::new(FaultyStorage::new(base(location)?, faults.clone())))
+        });
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.backends.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn open<L: Into<Location>>(&self, name: &str, location: L) -> Result<Database, OpenError> {
+        let open = self
+            .backends
+            .get(name)
+            .ok_or_else(|| OpenError::UnknownBackend(name.to_owned()))?;
+        Database::new(open(&location.into())?).map_err(OpenError::Storage)
+    }
+
+    /// restores a backup into a new location, e.g. an empty data directory
+    pub fn restore<L: Into<Location>, R: Read>(
+        &self,
+        name: &str,
+        location: L,
+        backup: R,
+    ) -> Result<Database, OpenError> {
+        let open = self
+            .backends
+            .get(name)
+            .ok_or_else(|| OpenError::UnknownBackend(name.to_owned()))?;
+        Database::restore(open(&location.into())?, backup)
+    }
+}
+
+impl Default for Backends {
+    fn default() -> Backends {
+        #[allow(unused_mut)]
+        let mut backends = Backends::empty();
+        #[cfg(feature = "in_memory")]
+        backends.register("in_memory", |location| {
+            if location.encryption_keys.is_some() {
+                return Err(StorageError::Encryption(
+                    "in memory storage does not support encryption".to_owned(),
+                ));
+            }
+            let storage = match (location.path.as_str(), &location.wal_archive, location.recovery_target) {
+                (_, None, Some(_)) => {
+                    return Err(StorageError::Io("point-in-time recovery needs WAL archive".to_owned()))
+                }
+                ("", _, _) => InMemoryDatabase::create(),
+                (path, None, None) => InMemoryDatabase::with_wal(path),
+                (path, Some(archive), None) => InMemoryDatabase::with_archived_wal(path, archive),
+                (path, Some(archive), Some(target)) => InMemoryDatabase::recover(path, archive, target)?,
+            };
+            Ok(Box::new(storage))
+        });
+        #[cfg(feature = "persistent")]
+        backends.register("persistent", |location| {
+            if location.recovery_target.is_some() {
+                return Err(StorageError::Io(
+                    "persistent storage can't be recovered to a point in time".to_owned(),
+                ));
+            }
+            let keyring = match &location.encryption_keys {
+                None => None,
+                Some(KeySource::File(path)) => Some(Keyring::from_file(path)?),
+                Some(KeySource::Env(variable)) => Some(Keyring::from_env(variable)?),
+            };
+            Ok(Box::new(PersistentDatabase::open(location.path.as_str(), keyring)?))
+        });
+        backends
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OpenError {
+    UnknownBackend(String),
+    /// backup can't be restored over existing data
+    NotEmpty,
+    Storage(StorageError),
+}
+
+impl From<StorageError> for OpenError {
+    fn from(error: StorageError) -> OpenError {
+        OpenError::Storage(error)
+    }
+}
+
+impl Display for OpenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenError::UnknownBackend(name) => write!(f, "unknown storage backend {:?}", name),
+            OpenError::NotEmpty => write!(f, "storage already has data"),
+            OpenError::Storage(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Approximate size of a tree as of the last commit
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TreeStats {
+    /// number of rows that are visible to new transactions
+    pub rows: u64,
+    /// bytes taken by keys and all versions of rows
+    pub bytes: u64,
+}
+
+impl TreeStats {
+    pub(crate) fn size(key: &Key, chain: Option<&Binary>) -> u64 {
+        chain
+            .map(|chain| (key.as_ref().len() + chain.as_ref().len()) as u64)
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn change(&mut self, rows: i64, bytes: i64) {
+        self.rows = (self.rows as i64 + rows).max(0) as u64;
+        self.bytes = (self.bytes as i64 + bytes).max(0) as u64;
+    }
+
+    pub(crate) fn encode(&self) -> Binary {
+        Binary::pack(&[Datum::from_u64(self.rows), Datum::from_u64(self.bytes)])
+    }
+
+    pub(crate) fn decode(data: &Binary) -> StorageResult<TreeStats> {
+        match data.try_unpack()?.as_slice() {
+            [Datum::Int64(rows), Datum::Int64(bytes)] => Ok(TreeStats {
+                rows: *rows as u64,
+                bytes: *bytes as u64,
+            }),
+            _ => Err(StorageError::Corrupted("tree stats are malformed".to_owned())),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Database {
-    inner: DatabaseInner,
+    engine: Arc<Engine>,
 }
 
 impl Database {
+    /// database on top of the storage engine, system tables are created on the first start.
+    /// Tables can be laid out by columns on any storage engine
+    pub fn new(storage: Box<dyn Storage>) -> StorageResult<Database> {
+        Database::open(Box::new(ColumnarStorage::new(storage)?))
+    }
+
+    fn open(storage: Box<dyn Storage>) -> StorageResult<Database> {
+        let database = Database {
+            engine: Arc::new(Engine::new(storage)?),
+        };
+        database.bootstrap()?;
+        Ok(database)
+    }
+
+    /// changes are logged into `path` directory and recovered on the next start.
+    /// Nothing is written on disk when `path` is empty
     #[cfg(feature = "in_memory")]
-    pub fn in_memory(_path: &str) -> Database {
-        Database {
-            inner: DatabaseInner::InMemory(Arc::new(Mutex::new(InMemoryDatabase::create()))),
-        }
+    pub fn in_memory(path: &str) -> Database {
+        Backends::default()
+            .open("in_memory", path)
+            .expect("to open in memory database")
     }
 
     #[cfg(feature = "persistent")]
     pub fn persistent(path: &str) -> Database {
-        Database {
-            inner: DatabaseInner::Persistent(Arc::new(Mutex::new(PersistentDatabase::new(path)))),
+        Backends::default()
+            .open("persistent", path)
+            .expect("to open persistent database")
+    }
+
+    /// database filled from a backup that was made by `Database::backup`. The storage has to be empty
+    pub fn restore<R: Read>(storage: Box<dyn Storage>, backup: R) -> Result<Database, OpenError> {
+        if !storage.tree_names()?.is_empty() {
+            return Err(OpenError::NotEmpty);
+        }
+        let storage = Box::new(ColumnarStorage::new(storage)?);
+        Engine::restore(storage.as_ref(), BackupReader::new(backup)?)?;
+        Ok(Database::open(storage)?)
+    }
+
+    /// writes a consistent snapshot of the database while other transactions keep running.
+    /// Backups are not encrypted, an encrypted database is backed up only when `plaintext` is set.
+    /// Returns the number of rows in the backup
+    pub fn backup<W: Write>(&self, backup: W, plaintext: bool) -> StorageResult<u64> {
+        if self.engine.is_encrypted() && !plaintext {
+            return Err(StorageError::Encryption(
+                "backup of encrypted database would be written in plain text".to_owned(),
+            ));
         }
+        let mut backup = BackupWriter::new(backup)?;
+        self.engine.backup(&mut backup)?;
+        Ok(backup.finish()?)
+    }
+
+    fn bootstrap(&self) -> StorageResult<()> {
+        let is_empty = self.engine.is_empty();
+        self.transaction(|db| {
+            // data directories of older versions do not have the list of databases
+            db.inner.create_tree(databases_tree());
+            if db.inner.table(databases_tree()).indexes().is_empty() {
+                db.inner
+                    .create_index(&databases_tree(), NAMES_INDEX, &[0], true)
+                    .expect("names of databases are unique");
+            }
+            if is_empty {
+                db.create_catalog();
+            } else {
+                for name in db.databases() {
+                    db.database(&name).expect("database exists").upgrade_catalog();
+                }
+            }
+            Ok(())
+        })
+        .map_err(|error| match error {
+            TransactionError::Storage(error) => error,
+            TransactionError::Abort => unreachable!("bootstrap is never aborted"),
+        })
     }
 
-    pub fn transaction<F, R>(&self, mut f: F) -> TransactionResult<R>
+    pub fn transaction<F, R>(&self, f: F) -> TransactionResult<R>
     where
-        // TODO: make it Fn otherwise it won't work with sled
         F: FnMut(TransactionalDatabase) -> ConflictableTransactionResult<R>,
     {
-        loop {
-            match f(self.transactional()) {
-                Ok(result) => return Ok(result),
-                Err(ConflictableTransactionError::Storage) => return Err(TransactionError::Storage),
-                Err(ConflictableTransactionError::Abort) => return Err(TransactionError::Abort),
-                Err(ConflictableTransactionError::Conflict) => {}
-            }
-        }
+        run(&self.engine, DEFAULT_DATABASE, f)
     }
+}
 
-    fn transactional(&self) -> TransactionalDatabase {
-        match &self.inner {
-            #[cfg(feature = "in_memory")]
-            DatabaseInner::InMemory(db) => TransactionalDatabase::from(db.lock().unwrap()),
-            #[cfg(feature = "persistent")]
-            DatabaseInner::Persistent(db) => TransactionalDatabase::from(db.lock().unwrap()),
+/// runs `f` in transactions of the database until one of them commits or fails
+fn run<F, R>(engine: &Arc<Engine>, name: &str, mut f: F) -> TransactionResult<R>
+where
+    F: FnMut(TransactionalDatabase) -> ConflictableTransactionResult<R>,
+{
+    loop {
+        let transaction = TransactionalDatabase {
+            inner: Rc::new(engine.begin()),
+            engine: engine.clone(),
+            name: name.to_owned(),
+        };
+        transaction.inner.work_in(name);
+        let result = f(transaction.clone()).and_then(|result| transaction.inner.commit().map(|()| result));
+        let database = transaction.inner.database();
+        match result {
+            Ok(result) => {
+                engine.metrics.committed(&database);
+                return Ok(result);
+            }
+            Err(error) => {
+                transaction.inner.rollback();
+                match error {
+                    ConflictableTransactionError::Storage(error) => {
+                        engine.metrics.rolled_back(&database);
+                        return Err(TransactionError::Storage(error));
+                    }
+                    ConflictableTransactionError::Abort => {
+                        engine.metrics.rolled_back(&database);
+                        return Err(TransactionError::Abort);
+                    }
+                    ConflictableTransactionError::Conflict => engine.metrics.conflict(&database),
+                }
+            }
         }
     }
 }
 
-#[derive(Clone)]
-enum DatabaseInner {
-    #[cfg(feature = "in_memory")]
-    InMemory(Arc<Mutex<InMemoryDatabase>>),
-    #[cfg(feature = "persistent")]
-    Persistent(Arc<Mutex<PersistentDatabase>>),
+fn databases_tree() -> String {
+    format!("{}.{}", DEFINITION_SCHEMA, DATABASES_TABLE)
 }
 
+/// Snapshot of the database. Changes made through it are visible to others only
+/// after `Database::transaction` closure returned successfully
 #[derive(Clone)]
 pub struct TransactionalDatabase<'t> {
-    inner: Rc<TransactionalDatabaseInner<'t>>,
+    inner: Rc<Transaction<'t>>,
+    engine: Arc<Engine>,
+    /// trees of the database are kept apart from trees of other databases in the storage
+    name: String,
 }
 
 impl<'t> TransactionalDatabase<'t> {
-    pub fn table<T: Into<String>>(&self, full_table_name: T) -> Table {
-        match &*self.inner {
-            #[cfg(feature = "in_memory")]
-            TransactionalDatabaseInner::InMemory(database) => Table::from(database.lookup_tree(full_table_name)),
-            #[cfg(feature = "persistent")]
-            TransactionalDatabaseInner::Persistent(database) => Table::from(database.lookup_tree(full_table_name)),
-        }
+    pub fn name(&self) -> &str {
+        self.name.as_str()
     }
 
-    pub fn drop_tree<T: Into<String>>(&self, full_table_name: T) {
-        match &*self.inner {
-            #[cfg(feature = "in_memory")]
-            TransactionalDatabaseInner::InMemory(database) => database.drop_tree(full_table_name),
-            #[cfg(feature = "persistent")]
-            TransactionalDatabaseInner::Persistent(database) => database.drop_tree(full_table_name),
+    /// another database seen by the same transaction, `None` if it does not exist
+    pub fn database(&self, name: &str) -> Option<TransactionalDatabase<'t>> {
+        if self.databases().iter().any(|database| database == name) {
+            Some(TransactionalDatabase {
+                inner: self.inner.clone(),
+                engine: self.engine.clone(),
+                name: name.to_owned(),
+            })
+        } else {
+            None
         }
     }
 
-    pub fn create_tree<T: Into<String>>(&self, full_table_name: T) {
-        match &*self.inner {
-            #[cfg(feature = "in_memory")]
-            TransactionalDatabaseInner::InMemory(database) => database.create_tree(full_table_name),
-            #[cfg(feature = "persistent")]
-            TransactionalDatabaseInner::Persistent(database) => database.create_tree(full_table_name),
+    /// another database that the transaction works in, commits and rollbacks of the transaction
+    /// are counted for it. `None` if it does not exist
+    pub fn use_database(&self, name: &str) -> Option<TransactionalDatabase<'t>> {
+        let database = self.database(name)?;
+        self.inner.work_in(name);
+        Some(database)
+    }
+
+    /// a storage error happened, the transaction fails with it when it is committed.
+    /// Scans stop at the first error
+    pub fn is_failed(&self) -> bool {
+        self.inner.failure().is_recorded()
+    }
+
+    /// runs transactions in the database that are committed apart from this one
+    pub fn autonomous(&self) -> Autonomous {
+        Autonomous {
+            engine: self.engine.clone(),
+            name: self.name.clone(),
+            failure: self.inner.failure(),
         }
     }
-}
 
-#[cfg(feature = "in_memory")]
-impl<'t> From<MutexGuard<'t, InMemoryDatabase>> for TransactionalDatabase<'t> {
-    fn from(guard: MutexGuard<'t, InMemoryDatabase>) -> TransactionalDatabase {
+    /// names of all databases of the cluster
+    pub fn databases(&self) -> Vec<String> {
+        let mut databases = vec![DEFAULT_DATABASE.to_owned()];
+        databases.extend(
+            self.inner
+                .table(databases_tree())
+                .scan()
+                .map(|(_key, value)| value.unpack()[0].as_string()),
+        );
+        databases
+    }
+
+    /// empty database that has only `public` schema. The name can't contain `DATABASE_SEPARATOR`
+    pub fn create_database(&self, name: &str) {
+        assert!(
+            !name.contains(DATABASE_SEPARATOR),
+            "name of database {:?} contains separator of tree names",
+            name
+        );
+        self.inner
+            .table(databases_tree())
+            .write(Binary::pack(&[Datum::from_string(name.to_owned())]))
+            .expect("database does not exist");
         TransactionalDatabase {
-            inner: Rc::new(TransactionalDatabaseInner::InMemory(guard)),
+            inner: self.inner.clone(),
+            engine: self.engine.clone(),
+            name: name.to_owned(),
         }
+        .create_catalog();
     }
-}
 
-#[cfg(feature = "persistent")]
-impl<'t> From<MutexGuard<'t, PersistentDatabase>> for TransactionalDatabase<'t> {
-    fn from(guard: MutexGuard<'t, PersistentDatabase>) -> TransactionalDatabase {
-        TransactionalDatabase {
-            inner: Rc::new(TransactionalDatabaseInner::Persistent(guard)),
+    /// drops the database together with all of its trees
+    pub fn drop_database(&self, name: &str) {
+        let databases = self.inner.table(databases_tree());
+        let keys = databases
+            .scan()
+            .filter(|(_key, value)| value.unpack()[0] == name)
+            .map(|(key, _value)| key)
+            .collect::<Vec<_>>();
+        for key in keys {
+            databases
+                .write_key(key, None)
+                .expect("removed rows do not violate unique indexes");
+        }
+        let prefix = database_prefix(name);
+        for tree in self.inner.tree_names() {
+            if tree.starts_with(&prefix) {
+                self.inner.drop_tree(tree);
+            }
         }
     }
-}
 
-enum TransactionalDatabaseInner<'t> {
-    #[cfg(feature = "in_memory")]
-    InMemory(MutexGuard<'t, InMemoryDatabase>),
-    #[cfg(feature = "persistent")]
-    Persistent(MutexGuard<'t, PersistentDatabase>),
-}
+    fn create_catalog(&self) {
+        self.create_catalog_trees();
+        self.table(format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE))
+            .write(Binary::pack(&[
+                Datum::from_string(self.name.clone()),
+                Datum::from_string("public".to_owned()),
+            ]))
+            .expect("catalog of a new database is empty");
+        self.index_names();
+    }
 
-#[derive(Debug)]
-pub struct Table {
-    inner: TableInner,
-}
+    /// trees that already exist are kept as they are
+    fn create_catalog_trees(&self) {
+        for catalog_table in &[
+            SCHEMATA_TABLE,
+            TABLES_TABLE,
+            COLUMNS_TABLE,
+            INDEXES_TABLE,
+            STATISTICS_TABLE,
+            SEQUENCES_TABLE,
+            TABLE_CONSTRAINTS_TABLE,
+        ] {
+            self.create_tree(format!("{}.{}", DEFINITION_SCHEMA, catalog_table));
+        }
+    }
 
-#[cfg(feature = "in_memory")]
-impl From<InMemoryTree> for Table {
-    fn from(table: InMemoryTree) -> Table {
-        Table {
-            inner: TableInner::InMemory(table),
+    /// names of schemas, tables and sequences are unique within the database.
+    /// Indexes that already exist are kept as they are
+    fn index_names(&self) {
+        for (catalog_table, columns) in &[
+            (SCHEMATA_TABLE, &[0, 1][..]),
+            (TABLES_TABLE, &[0, 1, 2][..]),
+            (SEQUENCES_TABLE, &[0, 1, 2][..]),
+        ] {
+            let full_table_name = format!("{}.{}", DEFINITION_SCHEMA, catalog_table);
+            if self.table(full_table_name.as_str()).indexes().is_empty() {
+                if let Err(error) = self.create_index(&full_table_name, NAMES_INDEX, columns, true) {
+                    log::error!("names of {:?} records are not unique: {:?}", full_table_name, error);
+                }
+            }
         }
     }
-}
 
-#[cfg(feature = "persistent")]
-impl From<PersistentTable> for Table {
-    fn from(table: PersistentTable) -> Table {
+    /// catalogs of older versions kept records of indexes together with records of tables,
+    /// did not have statistics, sequences or constraints and did not index names
+    fn upgrade_catalog(&self) {
+        self.create_catalog_trees();
+        let tables = self.table(format!("{}.{}", DEFINITION_SCHEMA, TABLES_TABLE));
+        let indexes = self.table(format!("{}.{}", DEFINITION_SCHEMA, INDEXES_TABLE));
+        let records = tables
+            .scan()
+            .map(|(key, value)| (key, value.unpack()))
+            .collect::<Vec<_>>();
+        // database, schema, table, index and comma separated names of its columns
+        for (key, record) in records.iter().filter(|(_key, record)| record.len() == 5) {
+            tables
+                .write_key(key.clone(), None)
+                .expect("removed rows do not violate unique indexes");
+            let table_id = records
+                .iter()
+                .find(|(_key, table)| table.len() == 3 && table[1] == record[1] && table[2] == record[2])
+                .map(|(key, _table)| key.unpack());
+            if let Some(mut index_key) = table_id {
+                let name = record[3].as_string();
+                let unique = self
+                    .table(format!("{}.{}", record[1], record[2]))
+                    .indexes()
+                    .iter()
+                    .any(|index| index.name == name && index.unique);
+                let mut index = record[..4].to_vec();
+                index.push(Datum::from_bool(unique));
+                index.push(Datum::from_string("btree".to_owned()));
+                index.extend(
+                    record[4]
+                        .as_string()
+                        .split(", ")
+                        .map(|column| Datum::from_string(column.to_owned())),
+                );
+                index_key.push(record[3].clone());
+                indexes
+                    .write_key(Binary::pack(&index_key), Some(Binary::pack(&index)))
+                    .expect("catalog tables do not have unique indexes");
+            }
+        }
+        self.index_names();
+    }
+
+    /// name of the tree in the storage
+    fn tree_name<T: Into<String>>(&self, name: T) -> String {
+        if self.name == DEFAULT_DATABASE {
+            name.into()
+        } else {
+            format!("{}{}", database_prefix(&self.name), name.into())
+        }
+    }
+
+    pub fn table<T: Into<String>>(&self, full_table_name: T) -> Table {
         Table {
-            inner: TableInner::Persistent(table),
+            inner: self.inner.table(self.tree_name(full_table_name)),
         }
     }
+
+    /// row count and size of the tree that are kept up to date by every commit
+    /// activity of the tree since the database was started. Reads are counted as they happen
+    /// and changes when they are committed
+    pub fn tree_metrics(&self, full_table_name: &str) -> TreeMetrics {
+        self.inner.tree_metrics(&self.tree_name(full_table_name))
+    }
+
+    /// transactions that worked in the database, see `use_database`
+    pub fn database_metrics(&self) -> DatabaseMetrics {
+        self.inner.database_metrics(&self.name)
+    }
+
+    pub fn tree_stats(&self, full_table_name: &str) -> TreeStats {
+        self.inner.tree_stats(&self.tree_name(full_table_name))
+    }
+
+    pub fn drop_tree<T: Into<String>>(&self, full_table_name: T) {
+        self.inner.drop_tree(self.tree_name(full_table_name))
+    }
+
+    pub fn create_tree<T: Into<String>>(&self, full_table_name: T) {
+        self.inner.create_tree(self.tree_name(full_table_name))
+    }
+
+    /// tree of a table that keeps its rows with the layout
+    pub fn create_tree_with<T: Into<String>>(&self, full_table_name: T, layout: TreeLayout) {
+        self.inner.create_tree_with(self.tree_name(full_table_name), layout)
+    }
+
+    /// long string values of rows that are written into the table are compressed. Rows are read back
+    /// as they were written
+    pub fn compress_tree<T: Into<String>>(&self, full_table_name: T, compression: Compression) {
+        self.inner.compress_tree(&self.tree_name(full_table_name), compression)
+    }
+
+    /// the table is available under the new name with all of its rows, indexes and compression
+    pub fn rename_tree(&self, full_table_name: &str, new_full_table_name: &str) -> Result<(), WriteError> {
+        self.inner
+            .rename_tree(&self.tree_name(full_table_name), &self.tree_name(new_full_table_name))
+    }
+
+    /// secondary index over `columns` of the table, `columns` are positions of values in table rows.
+    /// Existing rows are indexed right away and fail index creation if they violate uniqueness
+    pub fn create_index(
+        &self,
+        full_table_name: &str,
+        index_name: &str,
+        columns: &[usize],
+        unique: bool,
+    ) -> Result<(), WriteError> {
+        self.inner
+            .create_index(&self.tree_name(full_table_name), index_name, columns, unique)
+    }
+
+    pub fn drop_index(&self, full_table_name: &str, index_name: &str) {
+        self.inner.drop_index(&self.tree_name(full_table_name), index_name)
+    }
+}
+
+/// trees of databases other than the default one are named `<database>/<tree>`. Names of databases
+/// can't contain the separator, otherwise trees of one database could start with the prefix of another
+fn database_prefix(name: &str) -> String {
+    format!("{}{}", name, DATABASE_SEPARATOR)
+}
+
+/// Secondary index of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexInfo {
+    pub name: String,
+    /// positions of indexed values in table rows
+    pub columns: Vec<usize>,
+    pub unique: bool,
+}
+
+/// Transactions that commit on their own, changes they made stay when the transaction
+/// that started them is rolled back
+#[derive(Clone)]
+pub struct Autonomous {
+    engine: Arc<Engine>,
+    /// database that the transactions work in
+    name: String,
+    /// storage errors fail the transaction that started them as well
+    failure: Rc<Failure>,
+}
+
+impl Autonomous {
+    pub fn transaction<F, R>(&self, f: F) -> TransactionResult<R>
+    where
+        F: FnMut(TransactionalDatabase) -> ConflictableTransactionResult<R>,
+    {
+        run(&self.engine, &self.name, f).inspect_err(|error| {
+            if let TransactionError::Storage(error) = error {
+                self.failure.record(error.clone());
+            }
+        })
+    }
 }
 
 #[derive(Debug)]
-enum TableInner {
-    #[cfg(feature = "in_memory")]
-    InMemory(InMemoryTree),
-    #[cfg(feature = "persistent")]
-    Persistent(PersistentTable),
+pub struct Table {
+    inner: TransactionalTree,
 }
 
 impl Table {
-    pub fn write(&self, row: Value) -> Key {
-        match &self.inner {
-            #[cfg(feature = "in_memory")]
-            TableInner::InMemory(table) => table.insert(vec![row]).remove(0),
-            #[cfg(feature = "persistent")]
-            TableInner::Persistent(table) => table.insert(vec![row]).remove(0),
-        }
-    }
-
-    pub fn write_key(&self, key: Binary, row: Option<Binary>) {
-        match &self.inner {
-            #[cfg(feature = "in_memory")]
-            TableInner::InMemory(table) => match row {
-                None => {
-                    let result = table.remove(&key);
-                    debug_assert!(matches!(result, Some(_)), "nothing were found for {:?} key", key);
-                }
-                Some(row) => {
-                    let _result = table.insert_key(key, row);
-                }
-            },
-            #[cfg(feature = "persistent")]
-            TableInner::Persistent(table) => match row {
-                None => {
-                    let result = table.remove(&key);
-                    debug_assert!(matches!(result, Some(_)), "nothing were found for {:?} key", key);
-                }
-                Some(row) => {
-                    let _result = table.insert_key(key, row);
-                }
-            },
-        }
+    /// keeps indexes of the table in sync with the row
+    pub fn write(&self, row: Value) -> Result<Key, WriteError> {
+        self.inner.write(row)
+    }
+
+    /// writes many rows at once, either all of them or none
+    pub fn write_batch(&self, rows: Vec<Value>) -> Result<Vec<Key>, WriteError> {
+        self.inner.write_batch(rows)
+    }
+
+    pub fn write_key(&self, key: Binary, row: Option<Binary>) -> Result<(), WriteError> {
+        self.inner.write_key(key, row)
+    }
+
+    /// the row as it is seen by the transaction
+    pub fn get(&self, key: &Key) -> Option<Value> {
+        self.inner.get(key)
+    }
+
+    /// the transaction wrote or removed the row
+    pub fn is_changed(&self, key: &Key) -> bool {
+        self.inner.is_changed(key)
+    }
+
+    /// replaces many rows at once, either all of them or none
+    pub fn update_batch(&self, rows: Vec<(Binary, Binary)>) -> Result<(), WriteError> {
+        self.inner.update_batch(rows)
     }
 
     pub fn scan(&self) -> Cursor {
-        match &self.inner {
-            #[cfg(feature = "in_memory")]
-            TableInner::InMemory(table) => table.select(),
-            #[cfg(feature = "persistent")]
-            TableInner::Persistent(table) => table.select(),
-        }
+        self.inner.scan()
+    }
+
+    pub fn scan_range(&self, range: KeyRange) -> Cursor {
+        self.inner.scan_range(range, false)
+    }
+
+    pub fn scan_range_rev(&self, range: KeyRange) -> Cursor {
+        self.inner.scan_range(range, true)
+    }
+
+    /// rows with only values of `columns`, in the order of `columns`.
+    /// Tables that are laid out by columns do not read the other ones
+    pub fn scan_columns(&self, columns: &[usize]) -> Cursor {
+        self.inner.scan_columns(columns)
+    }
+
+    pub fn layout(&self) -> TreeLayout {
+        self.inner.layout()
+    }
+
+    pub fn scan_prefix(&self, prefix: &Key) -> Cursor {
+        self.scan_range(KeyRange::prefix(prefix))
+    }
+
+    pub fn indexes(&self) -> Vec<IndexInfo> {
+        self.inner.indexes()
+    }
+
+    /// rows which columns of `index_name` index are equal to `values`. Prefix of index columns
+    /// can be used for multi-column indexes. Returns `None` if the table does not have such index
+    pub fn scan_index(&self, index_name: &str, values: &[Datum]) -> Option<Cursor> {
+        self.inner.scan_index(index_name, values)
     }
 }
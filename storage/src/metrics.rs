@@ -0,0 +1,197 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+/// Activity of a tree since the database was started. Counters are kept only in memory
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TreeMetrics {
+    /// number of times the tree was read from the beginning or from a key
+    pub scans: u64,
+    /// rows that were returned by the scans
+    pub rows_read: u64,
+    /// number of times rows were looked up through indexes of the tree
+    pub index_scans: u64,
+    /// rows that were found through indexes of the tree
+    pub index_rows_read: u64,
+    pub rows_inserted: u64,
+    pub rows_updated: u64,
+    pub rows_deleted: u64,
+    /// size of committed versions of rows
+    pub bytes_written: u64,
+    /// string values that were compressed in committed versions of rows
+    pub values_compressed: u64,
+    /// how much smaller the compressed values are
+    pub bytes_saved: u64,
+}
+
+/// Outcomes of transactions that worked in a database since the database was started
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DatabaseMetrics {
+    pub committed: u64,
+    /// transactions that were aborted or failed
+    pub rolled_back: u64,
+    /// transactions that were retried because they changed the same rows as concurrent ones
+    pub conflicts: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct TreeCounters {
+    scans: AtomicU64,
+    rows_read: AtomicU64,
+    index_scans: AtomicU64,
+    index_rows_read: AtomicU64,
+    rows_inserted: AtomicU64,
+    rows_updated: AtomicU64,
+    rows_deleted: AtomicU64,
+    bytes_written: AtomicU64,
+    values_compressed: AtomicU64,
+    bytes_saved: AtomicU64,
+}
+
+impl TreeCounters {
+    pub(crate) fn scan(&self) {
+        add(&self.scans, 1);
+    }
+
+    pub(crate) fn row_read(&self) {
+        add(&self.rows_read, 1);
+    }
+
+    pub(crate) fn index_scan(&self) {
+        add(&self.index_scans, 1);
+    }
+
+    pub(crate) fn index_row_read(&self) {
+        add(&self.index_rows_read, 1);
+    }
+
+    pub(crate) fn committed(&self, changes: &TreeMetrics) {
+        add(&self.rows_inserted, changes.rows_inserted);
+        add(&self.rows_updated, changes.rows_updated);
+        add(&self.rows_deleted, changes.rows_deleted);
+        add(&self.bytes_written, changes.bytes_written);
+        add(&self.values_compressed, changes.values_compressed);
+        add(&self.bytes_saved, changes.bytes_saved);
+    }
+
+    fn snapshot(&self) -> TreeMetrics {
+        TreeMetrics {
+            scans: get(&self.scans),
+            rows_read: get(&self.rows_read),
+            index_scans: get(&self.index_scans),
+            index_rows_read: get(&self.index_rows_read),
+            rows_inserted: get(&self.rows_inserted),
+            rows_updated: get(&self.rows_updated),
+            rows_deleted: get(&self.rows_deleted),
+            bytes_written: get(&self.bytes_written),
+            values_compressed: get(&self.values_compressed),
+            bytes_saved: get(&self.bytes_saved),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TransactionCounters {
+    committed: AtomicU64,
+    rolled_back: AtomicU64,
+    conflicts: AtomicU64,
+}
+
+impl TransactionCounters {
+    fn snapshot(&self) -> DatabaseMetrics {
+        DatabaseMetrics {
+            committed: get(&self.committed),
+            rolled_back: get(&self.rolled_back),
+            conflicts: get(&self.conflicts),
+        }
+    }
+}
+
+/// Counters of all trees and of transactions of every database
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    trees: RwLock<HashMap<String, Arc<TreeCounters>>>,
+    databases: RwLock<HashMap<String, Arc<TransactionCounters>>>,
+}
+
+impl Metrics {
+    pub(crate) fn tree(&self, name: &str) -> Arc<TreeCounters> {
+        if let Some(counters) = self.trees.read().unwrap().get(name) {
+            return counters.clone();
+        }
+        self.trees.write().unwrap().entry(name.to_owned()).or_default().clone()
+    }
+
+    /// counters start from zero when a tree with the same name is created again
+    pub(crate) fn drop_tree(&self, name: &str) {
+        self.trees.write().unwrap().remove(name);
+    }
+
+    pub(crate) fn tree_metrics(&self, name: &str) -> TreeMetrics {
+        self.trees
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|counters| counters.snapshot())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn committed(&self, database: &str) {
+        add(&self.database(database).committed, 1);
+    }
+
+    pub(crate) fn rolled_back(&self, database: &str) {
+        add(&self.database(database).rolled_back, 1);
+    }
+
+    pub(crate) fn conflict(&self, database: &str) {
+        add(&self.database(database).conflicts, 1);
+    }
+
+    pub(crate) fn database_metrics(&self, database: &str) -> DatabaseMetrics {
+        self.databases
+            .read()
+            .unwrap()
+            .get(database)
+            .map(|counters| counters.snapshot())
+            .unwrap_or_default()
+    }
+
+    fn database(&self, name: &str) -> Arc<TransactionCounters> {
+        if let Some(counters) = self.databases.read().unwrap().get(name) {
+            return counters.clone();
+        }
+        self.databases
+            .write()
+            .unwrap()
+            .entry(name.to_owned())
+            .or_default()
+            .clone()
+    }
+}
+
+fn add(counter: &AtomicU64, value: u64) {
+    counter.fetch_add(value, Ordering::Relaxed);
+}
+
+fn get(counter: &AtomicU64) -> u64 {
+    counter.load(Ordering::Relaxed)
+}
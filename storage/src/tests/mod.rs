@@ -0,0 +1,2471 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+use std::ops::Bound;
+
+const TABLE: &str = "schema_name.table_name";
+
+fn row(value: &str) -> Binary {
+    Binary::pack(&[Datum::from_string(value.to_owned())])
+}
+
+fn database() -> Database {
+    let database = Database::in_memory("");
+    database
+        .transaction(|db| {
+            db.create_tree(TABLE);
+            Ok(())
+        })
+        .unwrap();
+    database
+}
+
+fn scan(database: &Database) -> Vec<Binary> {
+    database
+        .transaction(|db| Ok(db.table(TABLE).scan().map(|(_key, value)| value).collect()))
+        .unwrap()
+}
+
+#[test]
+fn read_own_writes() -> TransactionResult<()> {
+    database().transaction(|db| {
+        let table = db.table(TABLE);
+        let key = table.write(row("first")).unwrap();
+        table.write(row("second")).unwrap();
+        table.write_key(key, None).unwrap();
+
+        assert_eq!(
+            table.scan().map(|(_key, value)| value).collect::<Vec<_>>(),
+            vec![row("second")]
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn write_batch() -> TransactionResult<()> {
+    let database = database();
+    let keys = database.transaction(|db| {
+        let table = db.table(TABLE);
+        table.write(row("first")).unwrap();
+        Ok(table.write_batch(vec![row("second"), row("third")]).unwrap())
+    })?;
+
+    assert_eq!(keys.len(), 2);
+    assert_eq!(scan(&database), vec![row("first"), row("second"), row("third")]);
+    Ok(())
+}
+
+#[test]
+fn uncommitted_writes_are_not_visible() -> TransactionResult<()> {
+    let database = database();
+    database.transaction(|db| {
+        db.table(TABLE).write(row("uncommitted")).unwrap();
+
+        assert_eq!(scan(&database), Vec::<Binary>::new());
+        Ok(())
+    })?;
+
+    assert_eq!(scan(&database), vec![row("uncommitted")]);
+    Ok(())
+}
+
+#[test]
+fn snapshot_does_not_see_later_commits() -> TransactionResult<()> {
+    let database = database();
+    database.transaction(|db| {
+        db.table(TABLE).write(row("before")).unwrap();
+        Ok(())
+    })?;
+
+    database.transaction(|db| {
+        let table = db.table(TABLE);
+        database
+            .transaction(|other| {
+                other.table(TABLE).write(row("after")).unwrap();
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(
+            table.scan().map(|(_key, value)| value).collect::<Vec<_>>(),
+            vec![row("before")]
+        );
+        Ok(())
+    })?;
+
+    assert_eq!(scan(&database), vec![row("before"), row("after")]);
+    Ok(())
+}
+
+#[test]
+fn rolled_back_writes_are_discarded() {
+    let database = database();
+    assert_eq!(
+        database.transaction(|db| {
+            db.table(TABLE).write(row("aborted")).unwrap();
+            Err::<(), _>(ConflictableTransactionError::Abort)
+        }),
+        Err(TransactionError::Abort)
+    );
+
+    assert_eq!(scan(&database), Vec::<Binary>::new());
+}
+
+#[test]
+fn autonomous_writes_survive_rollback() {
+    let database = database();
+    assert_eq!(
+        database.transaction(|db| {
+            db.autonomous()
+                .transaction(|autonomous| {
+                    autonomous.table(TABLE).write(row("autonomous")).unwrap();
+                    Ok(())
+                })
+                .unwrap();
+            db.table(TABLE).write(row("aborted")).unwrap();
+            Err::<(), _>(ConflictableTransactionError::Abort)
+        }),
+        Err(TransactionError::Abort)
+    );
+
+    assert_eq!(scan(&database), vec![row("autonomous")]);
+}
+
+#[test]
+fn first_committer_wins() -> TransactionResult<()> {
+    let database = database();
+    let key = database.transaction(|db| Ok(db.table(TABLE).write(row("initial")).unwrap()))?;
+
+    let mut attempts = 0;
+    database.transaction(|db| {
+        attempts += 1;
+        let table = db.table(TABLE);
+        if attempts == 1 {
+            database
+                .transaction(|other| {
+                    other
+                        .table(TABLE)
+                        .write_key(key.clone(), Some(row("concurrent")))
+                        .unwrap();
+                    Ok(())
+                })
+                .unwrap();
+        }
+        table.write_key(key.clone(), Some(row("retried"))).unwrap();
+        Ok(())
+    })?;
+
+    assert_eq!(attempts, 2);
+    assert_eq!(scan(&database), vec![row("retried")]);
+    Ok(())
+}
+
+#[test]
+fn deleted_row_is_visible_to_older_snapshot() -> TransactionResult<()> {
+    let database = database();
+    let key = database.transaction(|db| Ok(db.table(TABLE).write(row("deleted")).unwrap()))?;
+
+    database.transaction(|db| {
+        let table = db.table(TABLE);
+        database
+            .transaction(|other| {
+                other.table(TABLE).write_key(key.clone(), None).unwrap();
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(
+            table.scan().map(|(_key, value)| value).collect::<Vec<_>>(),
+            vec![row("deleted")]
+        );
+        Ok(())
+    })?;
+
+    assert_eq!(scan(&database), Vec::<Binary>::new());
+    Ok(())
+}
+
+#[test]
+fn write_while_scanning() -> TransactionResult<()> {
+    let database = database();
+    database.transaction(|db| {
+        let table = db.table(TABLE);
+        table.write(row("first")).unwrap();
+        table.write(row("second")).unwrap();
+        Ok(())
+    })?;
+
+    database.transaction(|db| {
+        let table = db.table(TABLE);
+        for (key, _value) in table.scan() {
+            table.write_key(key, Some(row("updated"))).unwrap();
+            table.write(row("inserted")).unwrap();
+        }
+        Ok(())
+    })?;
+
+    assert_eq!(
+        scan(&database),
+        vec![row("updated"), row("updated"), row("inserted"), row("inserted")]
+    );
+    Ok(())
+}
+
+#[test]
+fn own_writes_after_scan_is_opened_are_not_scanned() -> TransactionResult<()> {
+    let database = database();
+    database.transaction(|db| {
+        let table = db.table(TABLE);
+        let first = table.write(row("first")).unwrap();
+        table.write(row("second")).unwrap();
+
+        let mut rows = vec![];
+        for (_key, value) in table.scan() {
+            table.write_key(first.clone(), None).unwrap();
+            table.write(row("inserted")).unwrap();
+            rows.push(value);
+        }
+        assert_eq!(rows, vec![row("first"), row("second")]);
+        Ok(())
+    })?;
+
+    assert_eq!(scan(&database), vec![row("second"), row("inserted"), row("inserted")]);
+    Ok(())
+}
+
+#[test]
+fn range_scans() -> TransactionResult<()> {
+    let database = database();
+    let keys = database.transaction(|db| {
+        let table = db.table(TABLE);
+        Ok(vec![
+            table.write(row("first")).unwrap(),
+            table.write(row("second")).unwrap(),
+            table.write(row("third")).unwrap(),
+        ])
+    })?;
+
+    database.transaction(|db| {
+        let table = db.table(TABLE);
+        table.write_key(keys[1].clone(), None).unwrap();
+        table.write(row("fourth")).unwrap();
+        let values = |cursor: Cursor| cursor.map(|(_key, value)| value).collect::<Vec<_>>();
+
+        assert_eq!(
+            values(table.scan_range(KeyRange::new(Bound::Included(keys[1].clone()), Bound::Unbounded))),
+            vec![row("third"), row("fourth")]
+        );
+        assert_eq!(
+            values(table.scan_range(KeyRange::new(
+                Bound::Excluded(keys[0].clone()),
+                Bound::Included(keys[2].clone())
+            ))),
+            vec![row("third")]
+        );
+        assert_eq!(
+            values(table.scan_range_rev(KeyRange::all())),
+            vec![row("fourth"), row("third"), row("first")]
+        );
+        assert_eq!(values(table.scan_prefix(&keys[0])), vec![row("first")]);
+        assert_eq!(
+            values(table.scan_range(KeyRange::new(
+                Bound::Included(keys[2].clone()),
+                Bound::Excluded(keys[0].clone())
+            ))),
+            vec![]
+        );
+        Ok(())
+    })
+}
+
+#[test]
+fn missing_tree_fails_transaction() {
+    let database = database();
+    assert_eq!(
+        database.transaction(|db| {
+            let table = db.table("schema_name.missing");
+            assert_eq!(table.scan().count(), 0);
+            table.write(row("lost")).unwrap();
+            Ok(())
+        }),
+        Err(TransactionError::Storage(StorageError::TreeNotFound(
+            "schema_name.missing".to_owned()
+        )))
+    );
+
+    database
+        .transaction(|db| {
+            db.table(TABLE).write(row("next")).unwrap();
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(scan(&database), vec![row("next")]);
+}
+
+#[cfg(test)]
+mod created_trees {
+    use super::*;
+
+    fn tree_exists(database: &Database) -> bool {
+        database
+            .transaction(|db| Ok(db.inner.tree_names()))
+            .unwrap()
+            .contains(&TABLE.to_owned())
+    }
+
+    #[test]
+    fn tree_of_rolled_back_transaction_is_dropped() {
+        let database = Database::in_memory("");
+        assert_eq!(
+            database.transaction(|db| {
+                db.create_tree(TABLE);
+                Err::<(), _>(ConflictableTransactionError::Abort)
+            }),
+            Err(TransactionError::Abort)
+        );
+
+        assert!(!tree_exists(&database));
+    }
+
+    #[test]
+    fn tree_committed_by_concurrent_transaction_is_kept() {
+        let database = Database::in_memory("");
+        assert_eq!(
+            database.transaction(|db| {
+                db.create_tree(TABLE);
+                database
+                    .transaction(|other| {
+                        other.create_tree(TABLE);
+                        other.table(TABLE).write(row("committed")).unwrap();
+                        Ok(())
+                    })
+                    .unwrap();
+                Err::<(), _>(ConflictableTransactionError::Abort)
+            }),
+            Err(TransactionError::Abort)
+        );
+
+        assert!(tree_exists(&database));
+        assert_eq!(scan(&database), vec![row("committed")]);
+    }
+
+    #[test]
+    fn tree_is_kept_when_concurrent_transaction_aborts() -> TransactionResult<()> {
+        let database = Database::in_memory("");
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            assert_eq!(
+                database.transaction(|other| {
+                    other.create_tree(TABLE);
+                    Err::<(), _>(ConflictableTransactionError::Abort)
+                }),
+                Err(TransactionError::Abort)
+            );
+            db.table(TABLE).write(row("committed")).unwrap();
+            Ok(())
+        })?;
+
+        assert_eq!(scan(&database), vec![row("committed")]);
+        Ok(())
+    }
+
+    #[test]
+    fn tree_of_concurrent_transactions_that_rolled_back_is_dropped() {
+        let database = Database::in_memory("");
+        assert_eq!(
+            database.transaction(|db| {
+                db.create_tree(TABLE);
+                assert_eq!(
+                    database.transaction(|other| {
+                        other.create_tree(TABLE);
+                        Err::<(), _>(ConflictableTransactionError::Abort)
+                    }),
+                    Err(TransactionError::Abort)
+                );
+                Err::<(), _>(ConflictableTransactionError::Abort)
+            }),
+            Err(TransactionError::Abort)
+        );
+
+        assert!(!tree_exists(&database));
+    }
+}
+
+#[cfg(test)]
+mod stats {
+    use super::*;
+
+    fn stats(database: &Database) -> TreeStats {
+        database.transaction(|db| Ok(db.tree_stats(TABLE))).unwrap()
+    }
+
+    #[test]
+    fn empty_tree() {
+        assert_eq!(stats(&database()), TreeStats::default());
+    }
+
+    #[test]
+    fn stats_follow_committed_changes() -> TransactionResult<()> {
+        let database = database();
+        let key = database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            table.write(row("second")).unwrap();
+            Ok(table.write(row("third")).unwrap())
+        })?;
+        let inserted = stats(&database);
+        assert_eq!(inserted.rows, 3);
+        assert!(inserted.bytes > 0);
+
+        database.transaction(|db| {
+            db.table(TABLE).write_key(key.clone(), None).unwrap();
+            assert_eq!(db.tree_stats(TABLE), inserted);
+            Ok(())
+        })?;
+        // versions of the deleted row take space until they are pruned
+        assert_eq!(stats(&database).rows, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn rolled_back_changes_are_not_counted() {
+        let database = database();
+        assert_eq!(
+            database.transaction::<_, ()>(|db| {
+                db.table(TABLE).write(row("rolled back")).unwrap();
+                Err(ConflictableTransactionError::Abort)
+            }),
+            Err(TransactionError::Abort)
+        );
+
+        assert_eq!(stats(&database), TreeStats::default());
+    }
+
+    #[test]
+    fn stats_are_dropped_with_tree() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.table(TABLE).write(row("dropped")).unwrap();
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.drop_tree(TABLE);
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            Ok(())
+        })?;
+
+        assert_eq!(stats(&database), TreeStats::default());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod metrics {
+    use super::*;
+
+    fn metrics(database: &Database) -> TreeMetrics {
+        database.transaction(|db| Ok(db.tree_metrics(TABLE))).unwrap()
+    }
+
+    fn database_metrics(database: &Database) -> DatabaseMetrics {
+        database.transaction(|db| Ok(db.database_metrics())).unwrap()
+    }
+
+    #[test]
+    fn committed_changes_are_counted() -> TransactionResult<()> {
+        let database = database();
+        let key = database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            Ok(table.write(row("second")).unwrap())
+        })?;
+        database.transaction(|db| {
+            db.table(TABLE).write_key(key.clone(), Some(row("updated"))).unwrap();
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.table(TABLE).write_key(key.clone(), None).unwrap();
+            Ok(())
+        })?;
+
+        let metrics = metrics(&database);
+        assert_eq!(
+            (metrics.rows_inserted, metrics.rows_updated, metrics.rows_deleted),
+            (2, 1, 1)
+        );
+        assert!(metrics.bytes_written > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn scans_and_read_rows() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            table.write(row("second")).unwrap();
+            Ok(())
+        })?;
+        assert_eq!(scan(&database).len(), 2);
+        assert_eq!(scan(&database).len(), 2);
+
+        let metrics = metrics(&database);
+        assert_eq!((metrics.scans, metrics.rows_read), (2, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn index_scans() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "index_name", &[0], false).unwrap();
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            table.write(row("second")).unwrap();
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            let found = db
+                .table(TABLE)
+                .scan_index("index_name", &[Datum::from_string("first".to_owned())])
+                .unwrap()
+                .count();
+            assert_eq!(found, 1);
+            Ok(())
+        })?;
+
+        let metrics = metrics(&database);
+        assert_eq!((metrics.index_scans, metrics.index_rows_read), (1, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn index_rows_are_read_as_cursor_is_advanced() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "index_name", &[0], false).unwrap();
+            let table = db.table(TABLE);
+            table.write(row("same")).unwrap();
+            table.write(row("same")).unwrap();
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            let mut cursor = db
+                .table(TABLE)
+                .scan_index("index_name", &[Datum::from_string("same".to_owned())])
+                .unwrap();
+            assert!(cursor.next().is_some());
+            Ok(())
+        })?;
+
+        let metrics = metrics(&database);
+        assert_eq!((metrics.index_scans, metrics.index_rows_read), (1, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn rolled_back_changes_are_not_counted() {
+        let database = database();
+        let before = database_metrics(&database);
+        assert_eq!(
+            database.transaction::<_, ()>(|db| {
+                db.table(TABLE).write(row("rolled back")).unwrap();
+                Err(ConflictableTransactionError::Abort)
+            }),
+            Err(TransactionError::Abort)
+        );
+
+        assert_eq!(metrics(&database).rows_inserted, 0);
+        assert_eq!(database_metrics(&database).rolled_back, before.rolled_back + 1);
+    }
+
+    #[test]
+    fn transactions_are_counted() -> TransactionResult<()> {
+        let database = database();
+        let before = database_metrics(&database);
+        let mut attempts = 0;
+        database.transaction(|db| {
+            attempts += 1;
+            let table = db.table(TABLE);
+            let key = table.write(row("first")).unwrap();
+            if attempts == 1 {
+                database
+                    .transaction(|other| {
+                        other.table(TABLE).write_key(key.clone(), Some(row("other"))).unwrap();
+                        Ok(())
+                    })
+                    .unwrap();
+            }
+            Ok(())
+        })?;
+
+        let after = database_metrics(&database);
+        assert_eq!(attempts, 2);
+        assert_eq!(after.conflicts, before.conflicts + 1);
+        // the concurrent transaction and the retried one, metrics are read by one more
+        assert_eq!(after.committed, before.committed + 3);
+        Ok(())
+    }
+
+    #[test]
+    fn transactions_are_counted_for_their_database() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_database("other");
+            Ok(())
+        })?;
+        let before = database_metrics(&database);
+        database.transaction(|db| {
+            let other = db.use_database("other").unwrap();
+            other.create_tree(TABLE);
+            other.table(TABLE).write(row("other")).unwrap();
+            Ok(())
+        })?;
+        database
+            .transaction::<_, ()>(|db| {
+                db.use_database("other").unwrap();
+                Err(ConflictableTransactionError::Abort)
+            })
+            .unwrap_err();
+
+        let other = database.transaction(|db| Ok(db.database("other").unwrap().database_metrics()))?;
+        assert_eq!((other.committed, other.rolled_back), (1, 1));
+        // only transactions that read metrics are counted for the default database
+        let after = database_metrics(&database);
+        assert_eq!(
+            (after.committed, after.rolled_back),
+            (before.committed + 2, before.rolled_back)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn metrics_are_dropped_with_tree() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.table(TABLE).write(row("dropped")).unwrap();
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.drop_tree(TABLE);
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            Ok(())
+        })?;
+
+        assert_eq!(metrics(&database), TreeMetrics::default());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod backends {
+    use super::*;
+
+    #[test]
+    fn default_backends() {
+        assert!(Backends::default().names().contains(&"in_memory"));
+    }
+
+    #[test]
+    fn unknown_backend() {
+        assert_eq!(
+            Backends::default().open("unknown", "").err(),
+            Some(OpenError::UnknownBackend("unknown".to_owned()))
+        );
+    }
+
+    #[test]
+    fn custom_backend() -> TransactionResult<()> {
+        let mut backends = Backends::empty();
+        backends.register("custom", |_location| Ok(Box::new(InMemoryDatabase::create())));
+        assert_eq!(backends.names(), vec!["custom"]);
+
+        let database = backends.open("custom", "").unwrap();
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            db.table(TABLE).write(row("first")).unwrap();
+            Ok(())
+        })?;
+        assert_eq!(scan(&database), vec![row("first")]);
+        Ok(())
+    }
+
+    #[test]
+    fn backend_that_fails_to_open() {
+        let mut backends = Backends::empty();
+        backends.register("broken", |location| {
+            Err(StorageError::Io(format!("{} is read only", location.path)))
+        });
+
+        assert_eq!(
+            backends.open("broken", "/data").err(),
+            Some(OpenError::Storage(StorageError::Io("/data is read only".to_owned())))
+        );
+    }
+}
+
+#[cfg(test)]
+mod fault_injection {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn faulty(faults: &Faults) -> Database {
+        let mut backends = Backends::default();
+        backends.register_faulty("faulty", "in_memory", faults.clone()).unwrap();
+        let database = backends.open("faulty", "").unwrap();
+        database
+            .transaction(|db| {
+                db.create_tree(TABLE);
+                Ok(())
+            })
+            .unwrap();
+        database
+    }
+
+    fn write(database: &Database, value: &str) -> TransactionResult<()> {
+        database.transaction(|db| {
+            db.table(TABLE).write(row(value)).unwrap();
+            Ok(())
+        })
+    }
+
+    fn on_table(operation: Operation, effect: Effect) -> Fault {
+        Fault {
+            tree: Some(TABLE.to_owned()),
+            ..Fault::new(operation, effect)
+        }
+    }
+
+    #[test]
+    fn unknown_base_backend() {
+        assert_eq!(
+            Backends::default().register_faulty("faulty", "unknown", Faults::default()),
+            Err(OpenError::UnknownBackend("unknown".to_owned()))
+        );
+    }
+
+    #[test]
+    fn storage_without_faults() -> TransactionResult<()> {
+        let faults = Faults::default();
+        let database = faulty(&faults);
+        write(&database, "first")?;
+
+        assert_eq!(scan(&database), vec![row("first")]);
+        assert_eq!(faults.happened(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn nth_write_fails() -> TransactionResult<()> {
+        let faults = Faults::default();
+        let database = faulty(&faults);
+        faults.inject(Fault {
+            skip: 1,
+            ..on_table(Operation::Write, Effect::Fail)
+        });
+
+        write(&database, "first")?;
+        assert!(matches!(
+            write(&database, "second"),
+            Err(TransactionError::Storage(StorageError::Io(_)))
+        ));
+        assert_eq!(faults.happened(), 1);
+        // storage behaves again but the failed commit could be applied partially
+        assert_eq!(
+            write(&database, "third"),
+            Err(TransactionError::Storage(StorageError::Io(
+                "previous commit was not applied completely, database has to be restarted".to_owned()
+            )))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn failed_lookup_of_tree() -> TransactionResult<()> {
+        let faults = Faults::default();
+        let database = faulty(&faults);
+        faults.inject(on_table(Operation::Trees, Effect::Fail));
+
+        assert!(matches!(
+            write(&database, "first"),
+            Err(TransactionError::Storage(StorageError::Io(_)))
+        ));
+        write(&database, "second")?;
+        assert_eq!(scan(&database), vec![row("second")]);
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted_read() -> TransactionResult<()> {
+        let faults = Faults::default();
+        let database = faulty(&faults);
+        write(&database, "first")?;
+        faults.inject(on_table(Operation::Read, Effect::Corrupt));
+
+        assert!(matches!(
+            database.transaction(|db| Ok(db.table(TABLE).scan().count())),
+            Err(TransactionError::Storage(StorageError::Corrupted(_)))
+        ));
+        assert_eq!(scan(&database), vec![row("first")]);
+        Ok(())
+    }
+
+    #[test]
+    fn torn_write() -> TransactionResult<()> {
+        let faults = Faults::default();
+        let database = faulty(&faults);
+        faults.inject(on_table(Operation::Write, Effect::Tear));
+        write(&database, "first")?;
+
+        assert!(matches!(
+            database.transaction(|db| Ok(db.table(TABLE).scan().count())),
+            Err(TransactionError::Storage(StorageError::Corrupted(_)))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn faults_of_other_trees() -> TransactionResult<()> {
+        let faults = Faults::default();
+        let database = faulty(&faults);
+        faults.inject(Fault {
+            tree: Some("schema_name.other_table".to_owned()),
+            times: None,
+            ..Fault::new(Operation::Write, Effect::Fail)
+        });
+        write(&database, "first")?;
+
+        assert_eq!(scan(&database), vec![row("first")]);
+        assert_eq!(faults.happened(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn fault_happens_given_number_of_times() -> TransactionResult<()> {
+        let faults = Faults::default();
+        let database = faulty(&faults);
+        write(&database, "first")?;
+        faults.inject(Fault {
+            times: Some(2),
+            ..on_table(Operation::Read, Effect::Fail)
+        });
+
+        assert!(database.transaction(|db| Ok(db.table(TABLE).scan().count())).is_err());
+        assert!(database.transaction(|db| Ok(db.table(TABLE).scan().count())).is_err());
+        assert_eq!(scan(&database), vec![row("first")]);
+        assert_eq!(faults.happened(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn delayed_commit() -> TransactionResult<()> {
+        let faults = Faults::default();
+        let database = faulty(&faults);
+        faults.inject(Fault::new(Operation::Sync, Effect::Delay(Duration::from_millis(50))));
+
+        let start = Instant::now();
+        write(&database, "first")?;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(scan(&database), vec![row("first")]);
+        Ok(())
+    }
+
+    #[test]
+    fn cleared_faults() -> TransactionResult<()> {
+        let faults = Faults::default();
+        let database = faulty(&faults);
+        faults.inject(Fault {
+            times: None,
+            ..on_table(Operation::Write, Effect::Fail)
+        });
+        faults.clear();
+        write(&database, "first")?;
+
+        assert_eq!(scan(&database), vec![row("first")]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod indexes {
+    use super::*;
+
+    fn pair(first: &str, second: &str) -> Binary {
+        Binary::pack(&[
+            Datum::from_string(first.to_owned()),
+            Datum::from_string(second.to_owned()),
+        ])
+    }
+
+    fn string(value: &str) -> Datum {
+        Datum::from_string(value.to_owned())
+    }
+
+    fn lookup(db: &TransactionalDatabase, index: &str, values: &[Datum]) -> Vec<Binary> {
+        db.table(TABLE)
+            .scan_index(index, values)
+            .unwrap()
+            .map(|(_key, value)| value)
+            .collect()
+    }
+
+    #[test]
+    fn index_is_filled_with_existing_rows() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            table.write(row("second")).unwrap();
+            table.write(row("first")).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            db.create_index(TABLE, "index_name", &[0], false).unwrap();
+            assert_eq!(
+                lookup(&db, "index_name", &[string("first")]),
+                vec![row("first"), row("first")]
+            );
+            assert_eq!(lookup(&db, "index_name", &[string("second")]), vec![row("second")]);
+            assert_eq!(lookup(&db, "index_name", &[string("third")]), Vec::<Binary>::new());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn index_follows_table_changes() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "index_name", &[0], false).unwrap();
+            Ok(())
+        })?;
+
+        let (updated, deleted) = database.transaction(|db| {
+            let table = db.table(TABLE);
+            Ok((table.write(row("first")).unwrap(), table.write(row("second")).unwrap()))
+        })?;
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write_key(updated.clone(), Some(row("updated"))).unwrap();
+            table.write_key(deleted.clone(), None).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            assert_eq!(lookup(&db, "index_name", &[string("first")]), Vec::<Binary>::new());
+            assert_eq!(lookup(&db, "index_name", &[string("second")]), Vec::<Binary>::new());
+            assert_eq!(lookup(&db, "index_name", &[string("updated")]), vec![row("updated")]);
+            assert_eq!(
+                db.table(TABLE).scan_index("unknown", &[]).map(|cursor| cursor.count()),
+                None
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn multi_column_index() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "index_name", &[1, 0], false).unwrap();
+            let table = db.table(TABLE);
+            table.write(pair("a", "x")).unwrap();
+            table.write(pair("b", "x")).unwrap();
+            table.write(pair("a", "y")).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            assert_eq!(
+                lookup(&db, "index_name", &[string("x"), string("b")]),
+                vec![pair("b", "x")]
+            );
+            assert_eq!(
+                lookup(&db, "index_name", &[string("x")]),
+                vec![pair("a", "x"), pair("b", "x")]
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn table_lists_its_indexes() -> TransactionResult<()> {
+        database().transaction(|db| {
+            db.create_index(TABLE, "index_name", &[1, 0], true).unwrap();
+            assert_eq!(
+                db.table(TABLE).indexes(),
+                vec![IndexInfo {
+                    name: "index_name".to_owned(),
+                    columns: vec![1, 0],
+                    unique: true,
+                }]
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn unique_index_rejects_duplicates() -> TransactionResult<()> {
+        let database = database();
+        let key = database.transaction(|db| {
+            db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            Ok(table.write(row("second")).unwrap())
+        })?;
+
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            assert_eq!(
+                table.write(row("first")),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            assert_eq!(
+                table.write_key(key.clone(), Some(row("first"))),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            assert_eq!(table.write_key(key.clone(), Some(row("second"))), Ok(()));
+            table.write_key(key.clone(), None).unwrap();
+            assert!(table.write(row("second")).is_ok());
+            Ok(())
+        })?;
+
+        assert_eq!(scan(&database), vec![row("first"), row("second")]);
+        Ok(())
+    }
+
+    #[test]
+    fn batch_with_duplicates_is_not_written() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            assert_eq!(
+                table.write_batch(vec![row("second"), row("first")]),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            assert_eq!(
+                table.write_batch(vec![row("third"), row("third")]),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            Ok(())
+        })?;
+
+        assert_eq!(scan(&database), vec![row("first")]);
+        Ok(())
+    }
+
+    #[test]
+    fn nulls_are_not_duplicates() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+            let table = db.table(TABLE);
+            table.write(Binary::pack(&[Datum::from_null()])).unwrap();
+            table.write(Binary::pack(&[Datum::from_null()])).unwrap();
+            assert_eq!(lookup(&db, "unique_index", &[Datum::from_null()]).len(), 2);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn unique_index_is_not_created_over_duplicates() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            table.write(row("first")).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            assert_eq!(
+                db.create_index(TABLE, "unique_index", &[0], true),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            assert!(db.table(TABLE).scan_index("unique_index", &[]).is_none());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn concurrent_duplicates_conflict() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+            Ok(())
+        })?;
+
+        let mut attempts = 0;
+        let result = database.transaction(|db| {
+            attempts += 1;
+            let table = db.table(TABLE);
+            if attempts == 1 {
+                database
+                    .transaction(|other| {
+                        other.table(TABLE).write(row("duplicate")).unwrap();
+                        Ok(())
+                    })
+                    .unwrap();
+            }
+            Ok(table.write(row("duplicate")))
+        })?;
+
+        assert_eq!(attempts, 2);
+        assert_eq!(result, Err(WriteError::UniqueViolation("unique_index".to_owned())));
+        assert_eq!(scan(&database), vec![row("duplicate")]);
+        Ok(())
+    }
+
+    #[test]
+    fn batch_update_swaps_unique_values() -> TransactionResult<()> {
+        let database = database();
+        let (first, second) = database.transaction(|db| {
+            db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+            let table = db.table(TABLE);
+            Ok((table.write(row("first")).unwrap(), table.write(row("second")).unwrap()))
+        })?;
+
+        database.transaction(|db| {
+            db.table(TABLE)
+                .update_batch(vec![(first.clone(), row("second")), (second.clone(), row("first"))])
+                .unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            assert_eq!(lookup(&db, "unique_index", &[string("first")]), vec![row("first")]);
+            assert_eq!(
+                db.table(TABLE)
+                    .scan_index("unique_index", &[string("second")])
+                    .unwrap()
+                    .map(|(key, _value)| key)
+                    .collect::<Vec<_>>(),
+                vec![first.clone()]
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn batch_update_is_not_applied_partially() -> TransactionResult<()> {
+        let database = database();
+        let (first, second) = database.transaction(|db| {
+            db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+            let table = db.table(TABLE);
+            table.write(row("third")).unwrap();
+            Ok((table.write(row("first")).unwrap(), table.write(row("second")).unwrap()))
+        })?;
+
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            assert_eq!(
+                table.update_batch(vec![(first.clone(), row("fourth")), (second.clone(), row("third"))]),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            assert_eq!(
+                table.update_batch(vec![(first.clone(), row("fourth")), (second.clone(), row("fourth"))]),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            Ok(())
+        })?;
+
+        assert_eq!(scan(&database), vec![row("third"), row("first"), row("second")]);
+        Ok(())
+    }
+
+    #[test]
+    fn indexes_are_dropped_with_table() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+            db.table(TABLE).write(row("first")).unwrap();
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.drop_tree(TABLE);
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            assert!(table.scan_index("unique_index", &[]).is_none());
+            assert!(table.write(row("first")).is_ok());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn index_is_recreated_in_the_same_transaction() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(pair("first", "a")).unwrap();
+            table.write(pair("second", "a")).unwrap();
+            db.create_index(TABLE, "index_name", &[0], true).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            db.drop_index(TABLE, "index_name");
+            db.create_index(TABLE, "index_name", &[1], false).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            assert_eq!(
+                lookup(&db, "index_name", &[string("a")]),
+                vec![pair("first", "a"), pair("second", "a")]
+            );
+            assert_eq!(lookup(&db, "index_name", &[string("first")]), Vec::<Binary>::new());
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod columnar {
+    use super::*;
+
+    /// enough rows to fill a few stripes
+    const ROWS: i64 = 2500;
+
+    fn wide_row(id: i64) -> Binary {
+        Binary::pack(&[
+            Datum::from_i64(id),
+            Datum::from_string(format!("name {}", id)),
+            Datum::from_bool(id % 2 == 0),
+            Datum::from_i16((id % 7) as i16),
+        ])
+    }
+
+    fn columnar_database() -> TransactionResult<Database> {
+        let database = Database::in_memory("");
+        database.transaction(|db| {
+            db.create_tree_with(TABLE, TreeLayout::Columns);
+            db.table(TABLE).write_batch((0..ROWS).map(wide_row).collect()).unwrap();
+            Ok(())
+        })?;
+        Ok(database)
+    }
+
+    fn projected(database: &Database, columns: &[usize]) -> Vec<Vec<Datum>> {
+        database
+            .transaction(|db| {
+                Ok(db
+                    .table(TABLE)
+                    .scan_columns(columns)
+                    .map(|(_key, value)| value.unpack())
+                    .collect())
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn rows_are_read_back() -> TransactionResult<()> {
+        let database = columnar_database()?;
+        assert_eq!(scan(&database), (0..ROWS).map(wide_row).collect::<Vec<_>>());
+        database.transaction(|db| {
+            assert_eq!(db.table(TABLE).layout(), TreeLayout::Columns);
+            let reversed = db
+                .table(TABLE)
+                .scan_range_rev(KeyRange::all())
+                .map(|(_key, value)| value)
+                .collect::<Vec<_>>();
+            assert_eq!(reversed, (0..ROWS).rev().map(wide_row).collect::<Vec<_>>());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn scan_reads_projected_columns() -> TransactionResult<()> {
+        let database = columnar_database()?;
+        assert_eq!(
+            projected(&database, &[3, 0]),
+            (0..ROWS)
+                .map(|id| vec![Datum::from_i16((id % 7) as i16), Datum::from_i64(id)])
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn projection_of_own_changes() -> TransactionResult<()> {
+        let database = columnar_database()?;
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(wide_row(ROWS)).unwrap();
+            assert_eq!(
+                table.scan_columns(&[1, 4]).last().map(|(_key, value)| value.unpack()),
+                Some(vec![Datum::from_string(format!("name {}", ROWS)), Datum::from_null()])
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn rows_changed_after_compaction() -> TransactionResult<()> {
+        let database = columnar_database()?;
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            let keys = table.scan().map(|(key, _value)| key).collect::<Vec<_>>();
+            table.write_key(keys[1].clone(), None).unwrap();
+            table.write_key(keys[2].clone(), Some(wide_row(-2))).unwrap();
+            Ok(())
+        })?;
+        let mut expected = (0..ROWS).map(wide_row).collect::<Vec<_>>();
+        expected[2] = wide_row(-2);
+        expected.remove(1);
+        assert_eq!(scan(&database), expected);
+        assert_eq!(
+            projected(&database, &[0])[..2],
+            [vec![Datum::from_i64(0)], vec![Datum::from_i64(-2)]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn emptied_table() -> TransactionResult<()> {
+        let database = columnar_database()?;
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            for (key, _value) in table.scan() {
+                table.write_key(key, None).unwrap();
+            }
+            Ok(())
+        })?;
+        assert_eq!(scan(&database), Vec::<Binary>::new());
+        database.transaction(|db| {
+            db.table(TABLE).write(wide_row(1)).unwrap();
+            Ok(())
+        })?;
+        assert_eq!(scan(&database), vec![wide_row(1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn rows_of_different_width() -> TransactionResult<()> {
+        let database = columnar_database()?;
+        let short = Binary::pack(&[Datum::from_i64(-1)]);
+        database.transaction(|db| {
+            db.table(TABLE).write_batch(vec![short.clone(); ROWS as usize]).unwrap();
+            Ok(())
+        })?;
+        let rows = scan(&database);
+        assert_eq!(rows.len(), 2 * ROWS as usize);
+        assert_eq!(rows.last(), Some(&short));
+        assert_eq!(
+            projected(&database, &[0, 1]).last(),
+            Some(&vec![Datum::from_i64(-1), Datum::from_null()])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn projection_of_row_table() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            assert_eq!(table.layout(), TreeLayout::Rows);
+            Ok(())
+        })?;
+        assert_eq!(
+            projected(&database, &[0, 0]),
+            vec![vec![Datum::from_string("first".to_owned()); 2]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dropped_table() -> TransactionResult<()> {
+        let database = columnar_database()?;
+        database.transaction(|db| {
+            db.drop_tree(TABLE);
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            db.table(TABLE).write(row("first")).unwrap();
+            assert_eq!(db.table(TABLE).layout(), TreeLayout::Rows);
+            Ok(())
+        })?;
+        assert_eq!(scan(&database), vec![row("first")]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod recovery {
+    use super::*;
+    use std::{
+        fs,
+        path::PathBuf,
+        thread,
+        time::{Duration, SystemTime},
+    };
+
+    pub(super) struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub(super) fn new(name: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("isomorphicdb-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            TempDir(path)
+        }
+
+        pub(super) fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn committed_changes_survive_restart() -> TransactionResult<()> {
+        let dir = TempDir::new("committed-changes");
+        {
+            let database = Database::in_memory(dir.path());
+            database.transaction(|db| {
+                db.create_tree(TABLE);
+                let table = db.table(TABLE);
+                let key = table.write(row("deleted")).unwrap();
+                table.write(row("first")).unwrap();
+                table.write_key(key, None).unwrap();
+                Ok(())
+            })?;
+            database.transaction(|db| {
+                db.table(TABLE).write(row("second")).unwrap();
+                Ok(())
+            })?;
+        }
+
+        let database = Database::in_memory(dir.path());
+        assert_eq!(scan(&database), vec![row("first"), row("second")]);
+        database.transaction(|db| {
+            db.table(TABLE).write(row("third")).unwrap();
+            Ok(())
+        })?;
+        assert_eq!(scan(&database), vec![row("first"), row("second"), row("third")]);
+        Ok(())
+    }
+
+    #[test]
+    fn rolled_back_changes_are_not_recovered() -> TransactionResult<()> {
+        let dir = TempDir::new("rolled-back-changes");
+        {
+            let database = Database::in_memory(dir.path());
+            database.transaction(|db| {
+                db.create_tree(TABLE);
+                db.table(TABLE).write(row("committed")).unwrap();
+                Ok(())
+            })?;
+            assert_eq!(
+                database.transaction(|db| {
+                    db.table(TABLE).write(row("aborted")).unwrap();
+                    db.create_tree("schema_name.aborted");
+                    Err::<(), _>(ConflictableTransactionError::Abort)
+                }),
+                Err(TransactionError::Abort)
+            );
+        }
+
+        let database = Database::in_memory(dir.path());
+        assert_eq!(scan(&database), vec![row("committed")]);
+        Ok(())
+    }
+
+    #[test]
+    fn indexes_survive_restart() -> TransactionResult<()> {
+        let dir = TempDir::new("indexes");
+        {
+            let database = Database::in_memory(dir.path());
+            database.transaction(|db| {
+                db.create_tree(TABLE);
+                db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+                db.table(TABLE).write(row("first")).unwrap();
+                Ok(())
+            })?;
+        }
+
+        let database = Database::in_memory(dir.path());
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            assert_eq!(
+                table.write(row("first")),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            assert_eq!(
+                table
+                    .scan_index("unique_index", &[Datum::from_string("first".to_owned())])
+                    .map(|cursor| cursor.count()),
+                Some(1)
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn columnar_tables_survive_restart() -> TransactionResult<()> {
+        let dir = TempDir::new("columnar-tables");
+        let rows = (0..3000)
+            .map(|id| Binary::pack(&[Datum::from_i64(id), Datum::from_i64(id % 3)]))
+            .collect::<Vec<_>>();
+        {
+            let database = Database::in_memory(dir.path());
+            database.transaction(|db| {
+                db.create_tree_with(TABLE, TreeLayout::Columns);
+                db.table(TABLE).write_batch(rows.clone()).unwrap();
+                Ok(())
+            })?;
+        }
+
+        let database = Database::in_memory(dir.path());
+        assert_eq!(scan(&database), rows);
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            assert_eq!(table.layout(), TreeLayout::Columns);
+            table.write(row("last")).unwrap();
+            assert_eq!(table.scan().last().map(|(_key, value)| value), Some(row("last")));
+            Ok(())
+        })
+    }
+
+    fn archived(dir: &TempDir, archive: &TempDir, recovery_target: Option<RecoveryTarget>) -> Database {
+        Backends::default()
+            .open(
+                "in_memory",
+                Location {
+                    path: dir.path().to_owned(),
+                    wal_archive: Some(archive.path().to_owned()),
+                    recovery_target,
+                    encryption_keys: None,
+                },
+            )
+            .unwrap()
+    }
+
+    fn write(database: &Database, value: &str) -> TransactionResult<()> {
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            db.table(TABLE).write(row(value)).unwrap();
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn recovery_to_transaction() -> TransactionResult<()> {
+        let dir = TempDir::new("recovery-to-transaction");
+        let archive = TempDir::new("recovery-to-transaction-archive");
+        // schema is bootstrapped by the first transaction
+        write(&archived(&dir, &archive, None), "first")?;
+        write(&archived(&dir, &archive, None), "second")?;
+
+        let database = archived(&dir, &archive, Some(RecoveryTarget::Transaction(2)));
+        assert_eq!(scan(&database), vec![row("first")]);
+        write(&database, "third")?;
+        drop(database);
+
+        let database = archived(&dir, &archive, Some(RecoveryTarget::Transaction(3)));
+        assert_eq!(scan(&database), vec![row("first"), row("third")]);
+        Ok(())
+    }
+
+    #[test]
+    fn recovery_to_time() -> TransactionResult<()> {
+        let dir = TempDir::new("recovery-to-time");
+        let archive = TempDir::new("recovery-to-time-archive");
+        let database = archived(&dir, &archive, None);
+        write(&database, "first")?;
+        thread::sleep(Duration::from_millis(10));
+        let target = SystemTime::now();
+        thread::sleep(Duration::from_millis(10));
+        write(&database, "second")?;
+        drop(database);
+
+        let database = archived(&dir, &archive, Some(RecoveryTarget::Time(target)));
+        assert_eq!(scan(&database), vec![row("first")]);
+        Ok(())
+    }
+
+    #[test]
+    fn recovery_needs_wal_archive() {
+        let dir = TempDir::new("recovery-without-archive");
+        assert_eq!(
+            Backends::default()
+                .open(
+                    "in_memory",
+                    Location {
+                        path: dir.path().to_owned(),
+                        wal_archive: None,
+                        recovery_target: Some(RecoveryTarget::Transaction(1)),
+                        encryption_keys: None,
+                    },
+                )
+                .err(),
+            Some(OpenError::Storage(StorageError::Io(
+                "point-in-time recovery needs WAL archive".to_owned()
+            )))
+        );
+    }
+
+    #[test]
+    fn schema_is_bootstrapped_once() -> TransactionResult<()> {
+        let dir = TempDir::new("bootstrap");
+        let schemata = format!("{}.{}", DEFINITION_SCHEMA, SCHEMATA_TABLE);
+        Database::in_memory(dir.path());
+
+        let database = Database::in_memory(dir.path());
+        database.transaction(|db| {
+            assert_eq!(db.table(schemata.as_str()).scan().count(), 1);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(all(test, feature = "persistent"))]
+mod encryption {
+    use super::{recovery::TempDir, *};
+    use std::{fs, path::Path};
+
+    const FIRST_KEY: &str = "1:000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const SECOND_KEY: &str = "2:f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff000102030405060708090a0b0c0d0e0f";
+    const OTHER_FIRST_KEY: &str = "1:1111111111111111111111111111111111111111111111111111111111111111";
+    const SECRET: &str = "customer secret";
+
+    /// keys are passed through an environment variable which is unique for the data directory
+    fn open(dir: &TempDir, keys: Option<&str>) -> Result<Database, OpenError> {
+        let encryption_keys = keys.map(|keys| {
+            let variable = format!(
+                "ISOMORPHICDB_KEYS_{}",
+                dir.path().replace(|c: char| !c.is_alphanumeric(), "_")
+            );
+            std::env::set_var(&variable, keys);
+            KeySource::Env(variable)
+        });
+        let location = Location {
+            path: dir.path().to_owned(),
+            encryption_keys,
+            ..Location::default()
+        };
+        // sled releases the lock of its files in background threads after the database is dropped
+        for _attempt in 0..100 {
+            match Backends::default().open("persistent", location.clone()) {
+                Err(OpenError::Storage(StorageError::Io(message))) if message.contains("could not acquire lock") => {
+                    std::thread::sleep(std::time::Duration::from_millis(10))
+                }
+                result => return result,
+            }
+        }
+        panic!("{:?} is locked", dir.path())
+    }
+
+    fn write(database: &Database, value: &str) -> TransactionResult<()> {
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            db.table(TABLE).write(row(value)).unwrap();
+            Ok(())
+        })
+    }
+
+    fn write_indexed(database: &Database, value: &str) -> TransactionResult<()> {
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+            db.table(TABLE).write(row(value)).unwrap();
+            Ok(())
+        })
+    }
+
+    fn find(database: &Database, value: &str) -> Vec<Binary> {
+        database
+            .transaction(|db| {
+                Ok(db
+                    .table(TABLE)
+                    .scan_index("unique_index", &[Datum::from_string(value.to_owned())])
+                    .unwrap()
+                    .map(|(_key, value)| value)
+                    .collect())
+            })
+            .unwrap()
+    }
+
+    fn contains_on_disk(path: &Path, value: &str) -> bool {
+        fs::read_dir(path).unwrap().any(|entry| {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                contains_on_disk(&path, value)
+            } else {
+                fs::read(&path)
+                    .unwrap()
+                    .windows(value.len())
+                    .any(|window| window == value.as_bytes())
+            }
+        })
+    }
+
+    fn encryption_error(result: Result<Database, OpenError>) -> bool {
+        matches!(result, Err(OpenError::Storage(StorageError::Encryption(_))))
+    }
+
+    #[test]
+    fn values_are_not_stored_in_plain_text() -> TransactionResult<()> {
+        let plain = TempDir::new("plain-text-values");
+        write(&open(&plain, None).unwrap(), SECRET)?;
+        assert!(contains_on_disk(Path::new(plain.path()), SECRET));
+
+        let encrypted = TempDir::new("encrypted-values");
+        write(&open(&encrypted, Some(FIRST_KEY)).unwrap(), SECRET)?;
+        assert!(!contains_on_disk(Path::new(encrypted.path()), SECRET));
+
+        assert_eq!(scan(&open(&encrypted, Some(FIRST_KEY)).unwrap()), vec![row(SECRET)]);
+        Ok(())
+    }
+
+    #[test]
+    fn indexed_values_are_not_stored_in_plain_text() -> TransactionResult<()> {
+        let plain = TempDir::new("plain-text-index");
+        write_indexed(&open(&plain, None).unwrap(), SECRET)?;
+        assert!(contains_on_disk(Path::new(plain.path()), SECRET));
+
+        let encrypted = TempDir::new("encrypted-index");
+        let database = open(&encrypted, Some(FIRST_KEY)).unwrap();
+        write_indexed(&database, SECRET)?;
+        drop(database);
+        assert!(!contains_on_disk(Path::new(encrypted.path()), SECRET));
+
+        let database = open(&encrypted, Some(&format!("{}\n{}", FIRST_KEY, SECOND_KEY))).unwrap();
+        assert_eq!(find(&database, SECRET), vec![row(SECRET)]);
+        assert_eq!(find(&database, "other"), Vec::<Binary>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn indexes_of_plain_text_database_are_filled_again_when_it_is_encrypted() -> TransactionResult<()> {
+        let dir = TempDir::new("plain-text-index-encrypted");
+        write_indexed(&open(&dir, None).unwrap(), "plain")?;
+
+        let database = open(&dir, Some(FIRST_KEY)).unwrap();
+        assert_eq!(find(&database, "plain"), vec![row("plain")]);
+        database.transaction(|db| {
+            assert_eq!(
+                db.table(TABLE).write(row("plain")),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn encrypted_database_needs_keys() -> TransactionResult<()> {
+        let dir = TempDir::new("encrypted-needs-keys");
+        write(&open(&dir, Some(FIRST_KEY)).unwrap(), SECRET)?;
+
+        assert!(encryption_error(open(&dir, None)));
+        assert!(encryption_error(open(&dir, Some(OTHER_FIRST_KEY))));
+        assert!(encryption_error(open(&dir, Some(SECOND_KEY))));
+        assert_eq!(scan(&open(&dir, Some(FIRST_KEY)).unwrap()), vec![row(SECRET)]);
+        Ok(())
+    }
+
+    #[test]
+    fn key_rotation() -> TransactionResult<()> {
+        let dir = TempDir::new("key-rotation");
+        write(&open(&dir, Some(FIRST_KEY)).unwrap(), "first")?;
+        // values are encrypted with the new key on start
+        write(
+            &open(&dir, Some(&format!("{}\n{}", FIRST_KEY, SECOND_KEY))).unwrap(),
+            "second",
+        )?;
+
+        let database = open(&dir, Some(SECOND_KEY)).unwrap();
+        assert_eq!(scan(&database), vec![row("first"), row("second")]);
+        drop(database);
+        assert!(encryption_error(open(&dir, Some(FIRST_KEY))));
+        Ok(())
+    }
+
+    #[test]
+    fn plain_text_database_is_encrypted() -> TransactionResult<()> {
+        let dir = TempDir::new("plain-text-database");
+        write(&open(&dir, None).unwrap(), "plain")?;
+        write(&open(&dir, Some(FIRST_KEY)).unwrap(), "encrypted")?;
+
+        assert!(encryption_error(open(&dir, None)));
+        assert_eq!(
+            scan(&open(&dir, Some(FIRST_KEY)).unwrap()),
+            vec![row("plain"), row("encrypted")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keys_from_file() -> TransactionResult<()> {
+        let dir = TempDir::new("keys-from-file");
+        let keys = TempDir::new("keys-from-file-keys");
+        fs::create_dir_all(keys.path()).unwrap();
+        let key_file = Path::new(keys.path()).join("keys").to_str().unwrap().to_owned();
+        fs::write(&key_file, format!("# active key\n{}\n", FIRST_KEY)).unwrap();
+        let location = Location {
+            path: dir.path().to_owned(),
+            encryption_keys: Some(KeySource::File(key_file)),
+            ..Location::default()
+        };
+        write(&Backends::default().open("persistent", location).unwrap(), SECRET)?;
+
+        assert_eq!(scan(&open(&dir, Some(FIRST_KEY)).unwrap()), vec![row(SECRET)]);
+        Ok(())
+    }
+
+    #[test]
+    fn rows_written_after_restart_do_not_replace_older_ones() -> TransactionResult<()> {
+        let dir = TempDir::new("keys-after-restart");
+        let database = open(&dir, Some(FIRST_KEY)).unwrap();
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            // enough rows for generated keys to differ in more than one byte
+            db.table(TABLE)
+                .write_batch((0..300).map(|index| row(&index.to_string())).collect())
+                .unwrap();
+            Ok(())
+        })?;
+        drop(database);
+
+        let database = open(&dir, Some(FIRST_KEY)).unwrap();
+        write(&database, "after restart")?;
+        let rows = scan(&database);
+        assert_eq!(rows.len(), 301);
+        assert_eq!(rows.last(), Some(&row("after restart")));
+        Ok(())
+    }
+
+    #[test]
+    fn backup_of_encrypted_database_is_written_in_plain_text_only_on_request() -> TransactionResult<()> {
+        let dir = TempDir::new("encrypted-backup");
+        let database = open(&dir, Some(FIRST_KEY)).unwrap();
+        write(&database, SECRET)?;
+
+        let mut backup = vec![];
+        assert!(matches!(
+            database.backup(&mut backup, false),
+            Err(StorageError::Encryption(_))
+        ));
+        assert!(backup.is_empty());
+
+        database.backup(&mut backup, true).unwrap();
+        let restored = Backends::default().restore("in_memory", "", backup.as_slice()).unwrap();
+        assert_eq!(scan(&restored), vec![row(SECRET)]);
+        Ok(())
+    }
+
+    #[test]
+    fn in_memory_storage_is_not_encrypted() {
+        let location = Location {
+            encryption_keys: Some(KeySource::Env("ISOMORPHICDB_IN_MEMORY_KEYS".to_owned())),
+            ..Location::default()
+        };
+        assert!(encryption_error(Backends::default().open("in_memory", location)));
+    }
+}
+
+#[cfg(test)]
+mod backup {
+    use super::*;
+
+    fn restore(backup: &[u8]) -> Result<Database, OpenError> {
+        Backends::default().restore("in_memory", "", backup)
+    }
+
+    #[test]
+    fn restored_database_has_the_same_rows() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "unique_index", &[0], true).unwrap();
+            let table = db.table(TABLE);
+            table.write(row("first")).unwrap();
+            table.write(row("second")).unwrap();
+            Ok(())
+        })?;
+        let mut backup = vec![];
+        // two rows, their index entries, the index definition, the public schema with its name index entry
+        // and definitions of indexes over names of databases, schemas, tables and sequences
+        assert_eq!(database.backup(&mut backup, false), Ok(11));
+
+        let restored = restore(&backup).unwrap();
+        assert_eq!(scan(&restored), vec![row("first"), row("second")]);
+        restored.transaction(|db| {
+            let table = db.table(TABLE);
+            assert_eq!(
+                table.write(row("first")),
+                Err(WriteError::UniqueViolation("unique_index".to_owned()))
+            );
+            table.write(row("third")).unwrap();
+            Ok(())
+        })?;
+        assert_eq!(scan(&restored), vec![row("first"), row("second"), row("third")]);
+        Ok(())
+    }
+
+    #[test]
+    fn restored_database_keeps_tree_stats() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.table(TABLE).write_batch(vec![row("first"), row("second")]).unwrap();
+            Ok(())
+        })?;
+        let mut backup = vec![];
+        database.backup(&mut backup, false).unwrap();
+
+        let restored = restore(&backup).unwrap();
+        assert_eq!(restored.transaction(|db| Ok(db.tree_stats(TABLE).rows)), Ok(2));
+        Ok(())
+    }
+
+    #[test]
+    fn restored_table_keeps_its_layout() -> TransactionResult<()> {
+        let database = Database::in_memory("");
+        database.transaction(|db| {
+            db.create_tree_with(TABLE, TreeLayout::Columns);
+            db.table(TABLE).write_batch(vec![row("first"), row("second")]).unwrap();
+            Ok(())
+        })?;
+        let mut backup = vec![];
+        database.backup(&mut backup, false).unwrap();
+
+        let restored = restore(&backup).unwrap();
+        assert_eq!(scan(&restored), vec![row("first"), row("second")]);
+        restored.transaction(|db| {
+            assert_eq!(db.table(TABLE).layout(), TreeLayout::Columns);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn backup_does_not_wait_for_running_transactions() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.table(TABLE).write(row("committed")).unwrap();
+            Ok(())
+        })?;
+
+        let mut backup = vec![];
+        database.transaction(|db| {
+            db.table(TABLE).write(row("uncommitted")).unwrap();
+            database.backup(&mut backup, false).unwrap();
+            Ok(())
+        })?;
+
+        assert_eq!(scan(&restore(&backup).unwrap()), vec![row("committed")]);
+        Ok(())
+    }
+
+    #[test]
+    fn backup_is_not_restored_over_existing_data() {
+        let mut backup = vec![];
+        database().backup(&mut backup, false).unwrap();
+
+        let storage = InMemoryDatabase::create();
+        storage.create_tree("table").unwrap();
+        assert_eq!(
+            Database::restore(Box::new(storage), backup.as_slice()).err(),
+            Some(OpenError::NotEmpty)
+        );
+    }
+
+    #[test]
+    fn incomplete_backup_is_not_restored() {
+        let mut backup = vec![];
+        database().backup(&mut backup, false).unwrap();
+        backup.truncate(backup.len() - 1);
+
+        assert_eq!(
+            restore(&backup).err(),
+            Some(OpenError::Storage(StorageError::Corrupted(
+                "backup is incomplete".to_owned()
+            )))
+        );
+    }
+}
+
+#[cfg(test)]
+mod databases {
+    use super::*;
+
+    const DATABASE: &str = "database_name";
+
+    fn with_database() -> TransactionResult<Database> {
+        let database = database();
+        database.transaction(|db| {
+            db.create_database(DATABASE);
+            Ok(())
+        })?;
+        Ok(database)
+    }
+
+    #[test]
+    fn default_database() -> TransactionResult<()> {
+        database().transaction(|db| {
+            assert_eq!(db.name(), DEFAULT_DATABASE);
+            assert_eq!(db.databases(), vec![DEFAULT_DATABASE.to_owned()]);
+            assert!(db.database(DATABASE).is_none());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn created_database() -> TransactionResult<()> {
+        with_database()?.transaction(|db| {
+            assert_eq!(db.databases(), vec![DEFAULT_DATABASE.to_owned(), DATABASE.to_owned()]);
+            let other = db.database(DATABASE).unwrap();
+            assert_eq!(other.name(), DATABASE);
+            assert_eq!(
+                other
+                    .table("DEFINITION_SCHEMA.SCHEMATA")
+                    .scan()
+                    .map(|(_key, value)| value.unpack())
+                    .collect::<Vec<_>>(),
+                vec![vec![
+                    Datum::from_string(DATABASE.to_owned()),
+                    Datum::from_string("public".to_owned())
+                ]]
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn trees_are_isolated() -> TransactionResult<()> {
+        let database = with_database()?;
+        database.transaction(|db| {
+            let other = db.database(DATABASE).unwrap();
+            other.create_tree(TABLE);
+            other.table(TABLE).write(row("other")).unwrap();
+            db.table(TABLE).write(row("default")).unwrap();
+            Ok(())
+        })?;
+
+        assert_eq!(scan(&database), vec![row("default")]);
+        database.transaction(|db| {
+            assert_eq!(
+                db.database(DATABASE)
+                    .unwrap()
+                    .table(TABLE)
+                    .scan()
+                    .map(|(_key, value)| value)
+                    .collect::<Vec<_>>(),
+                vec![row("other")]
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn dropped_database() -> TransactionResult<()> {
+        let database = with_database()?;
+        database.transaction(|db| {
+            let other = db.database(DATABASE).unwrap();
+            other.create_tree(TABLE);
+            other.create_index(TABLE, "index_name", &[0], false).unwrap();
+            other.table(TABLE).write(row("other")).unwrap();
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.drop_database(DATABASE);
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            assert_eq!(db.databases(), vec![DEFAULT_DATABASE.to_owned()]);
+            assert!(db.database(DATABASE).is_none());
+            Ok(())
+        })?;
+        assert!(database
+            .transaction(|db| Ok(db.inner.tree_names()))?
+            .iter()
+            .all(|tree| !tree.starts_with(DATABASE)));
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "contains separator of tree names")]
+    fn name_with_separator() {
+        with_database()
+            .unwrap()
+            .transaction(|db| {
+                db.create_database(&format!("{}/nested", DATABASE));
+                Ok(())
+            })
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod compression {
+    use super::*;
+
+    const COMPRESSION: Compression = Compression { threshold: 64 };
+
+    fn long(value: &str) -> String {
+        value.repeat(100)
+    }
+
+    fn unpacked(database: &Database) -> Vec<Vec<Datum>> {
+        scan(database).iter().map(Binary::unpack).collect()
+    }
+
+    fn compressed_database() -> Database {
+        let database = database();
+        database
+            .transaction(|db| {
+                db.compress_tree(TABLE, COMPRESSION);
+                Ok(())
+            })
+            .unwrap();
+        database
+    }
+
+    #[test]
+    fn rows_are_read_as_written() -> TransactionResult<()> {
+        let database = compressed_database();
+        let key = database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(row(&long("first"))).unwrap();
+            Ok(table.write(row("short")).unwrap())
+        })?;
+        database.transaction(|db| {
+            db.table(TABLE)
+                .write_key(key.clone(), Some(row(&long("updated"))))
+                .unwrap();
+            Ok(())
+        })?;
+
+        assert_eq!(
+            unpacked(&database),
+            vec![
+                vec![Datum::from_string(long("first"))],
+                vec![Datum::from_string(long("updated"))]
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compressed_values_are_counted() -> TransactionResult<()> {
+        let database = compressed_database();
+        database.transaction(|db| {
+            let table = db.table(TABLE);
+            table.write(row(&long("first"))).unwrap();
+            table.write(row("short")).unwrap();
+            Ok(())
+        })?;
+
+        let metrics = database.transaction(|db| Ok(db.tree_metrics(TABLE)))?;
+        assert_eq!(metrics.values_compressed, 1);
+        assert!(metrics.bytes_saved > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn not_compressed_by_default() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.table(TABLE).write(row(&long("first"))).unwrap();
+            Ok(())
+        })?;
+
+        assert_eq!(scan(&database), vec![row(&long("first"))]);
+        let metrics = database.transaction(|db| Ok(db.tree_metrics(TABLE)))?;
+        assert_eq!((metrics.values_compressed, metrics.bytes_saved), (0, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn compressed_values_are_indexed() -> TransactionResult<()> {
+        let database = compressed_database();
+        database.transaction(|db| {
+            db.create_index(TABLE, "index_name", &[0], false).unwrap();
+            db.table(TABLE).write(row(&long("first"))).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            let found = db
+                .table(TABLE)
+                .scan_index("index_name", &[Datum::from_string(long("first"))])
+                .unwrap()
+                .count();
+            assert_eq!(found, 1);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn setting_is_dropped_with_tree() -> TransactionResult<()> {
+        let database = compressed_database();
+        database.transaction(|db| {
+            db.drop_tree(TABLE);
+            Ok(())
+        })?;
+        database.transaction(|db| {
+            db.create_tree(TABLE);
+            db.table(TABLE).write(row(&long("first"))).unwrap();
+            Ok(())
+        })?;
+
+        assert_eq!(scan(&database), vec![row(&long("first"))]);
+        Ok(())
+    }
+
+    #[test]
+    fn setting_is_rolled_back() -> TransactionResult<()> {
+        let database = database();
+        assert_eq!(
+            database.transaction(|db| {
+                db.compress_tree(TABLE, COMPRESSION);
+                Err::<(), _>(ConflictableTransactionError::Abort)
+            }),
+            Err(TransactionError::Abort)
+        );
+        database.transaction(|db| {
+            db.table(TABLE).write(row(&long("first"))).unwrap();
+            Ok(())
+        })?;
+
+        assert_eq!(scan(&database), vec![row(&long("first"))]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod catalog_upgrade {
+    use super::{recovery::TempDir, *};
+
+    const TABLES: &str = "DEFINITION_SCHEMA.TABLES";
+    const INDEXES: &str = "DEFINITION_SCHEMA.INDEXES";
+    const STATISTICS: &str = "DEFINITION_SCHEMA.STATISTICS";
+    const SEQUENCES: &str = "DEFINITION_SCHEMA.SEQUENCES";
+
+    fn string(value: &str) -> Datum {
+        Datum::from_string(value.to_owned())
+    }
+
+    fn records(database: &Database, tree: &str) -> Vec<(Vec<Datum>, Vec<Datum>)> {
+        database
+            .transaction(|db| {
+                Ok(db
+                    .table(tree)
+                    .scan()
+                    .map(|(key, value)| (key.unpack(), value.unpack()))
+                    .collect())
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn index_records_are_moved_out_of_tables() -> TransactionResult<()> {
+        let dir = TempDir::new("catalog-upgrade");
+        let table_id = {
+            let database = Database::in_memory(dir.path());
+            database.transaction(|db| {
+                // catalog as older versions left it
+                db.drop_tree(INDEXES);
+                db.drop_index(TABLES, NAMES_INDEX);
+                db.create_tree(TABLE);
+                db.create_index(TABLE, "index_name", &[0], true).unwrap();
+                let tables = db.table(TABLES);
+                let table_id = tables
+                    .write(Binary::pack(&[
+                        string(DEFAULT_DATABASE),
+                        string("schema_name"),
+                        string("table_name"),
+                    ]))
+                    .unwrap();
+                tables
+                    .write(Binary::pack(&[
+                        string(DEFAULT_DATABASE),
+                        string("schema_name"),
+                        string("table_name"),
+                        string("index_name"),
+                        string("col_1, col_2"),
+                    ]))
+                    .unwrap();
+                Ok(table_id)
+            })?
+        };
+
+        let database = Database::in_memory(dir.path());
+        assert_eq!(
+            records(&database, TABLES),
+            vec![(
+                table_id.unpack(),
+                vec![string(DEFAULT_DATABASE), string("schema_name"), string("table_name")]
+            )]
+        );
+        let mut index_key = table_id.unpack();
+        index_key.push(string("index_name"));
+        assert_eq!(
+            records(&database, INDEXES),
+            vec![(
+                index_key,
+                vec![
+                    string(DEFAULT_DATABASE),
+                    string("schema_name"),
+                    string("table_name"),
+                    string("index_name"),
+                    Datum::from_bool(true),
+                    string("btree"),
+                    string("col_1"),
+                    string("col_2"),
+                ]
+            )]
+        );
+        database.transaction(|db| {
+            assert_eq!(
+                db.table(TABLES).indexes(),
+                vec![IndexInfo {
+                    name: NAMES_INDEX.to_owned(),
+                    columns: vec![0, 1, 2],
+                    unique: true
+                }]
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn missing_catalog_trees_are_created() -> TransactionResult<()> {
+        let dir = TempDir::new("catalog-trees");
+        {
+            let database = Database::in_memory(dir.path());
+            database.transaction(|db| {
+                // catalog as versions without statistics and sequences left it
+                db.drop_tree(STATISTICS);
+                db.drop_tree(SEQUENCES);
+                Ok(())
+            })?;
+        }
+
+        let database = Database::in_memory(dir.path());
+        let tree_names = database.transaction(|db| Ok(db.inner.tree_names()))?;
+        assert!(tree_names.contains(&STATISTICS.to_owned()));
+        assert!(tree_names.contains(&SEQUENCES.to_owned()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod rename {
+    use super::*;
+
+    const NEW_TABLE: &str = "schema_name.new_table_name";
+
+    #[test]
+    fn rows_are_moved() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.table(TABLE).write(row("first")).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            db.table(TABLE).write(row("second")).unwrap();
+            db.rename_tree(TABLE, NEW_TABLE).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            assert_eq!(
+                db.table(NEW_TABLE)
+                    .scan()
+                    .map(|(_key, value)| value)
+                    .collect::<Vec<_>>(),
+                vec![row("first"), row("second")]
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn indexes_are_moved() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.table(TABLE).write(row("first")).unwrap();
+            db.create_index(TABLE, "index_name", &[0], true).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            db.rename_tree(TABLE, NEW_TABLE).unwrap();
+            Ok(())
+        })?;
+
+        database.transaction(|db| {
+            let table = db.table(NEW_TABLE);
+            assert_eq!(
+                table.indexes(),
+                vec![IndexInfo {
+                    name: "index_name".to_owned(),
+                    columns: vec![0],
+                    unique: true,
+                }]
+            );
+            assert_eq!(
+                table
+                    .scan_index("index_name", &[Datum::from_string("first".to_owned())])
+                    .unwrap()
+                    .map(|(_key, value)| value)
+                    .collect::<Vec<_>>(),
+                vec![row("first")]
+            );
+            assert_eq!(
+                table.write(row("first")),
+                Err(WriteError::UniqueViolation("index_name".to_owned()))
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn compression_is_moved() -> TransactionResult<()> {
+        let database = database();
+        database.transaction(|db| {
+            db.compress_tree(TABLE, Compression { threshold: 64 });
+            db.rename_tree(TABLE, NEW_TABLE).unwrap();
+            db.table(NEW_TABLE).write(row(&"value".repeat(100))).unwrap();
+            Ok(())
+        })?;
+
+        let metrics = database.transaction(|db| Ok(db.tree_metrics(NEW_TABLE)))?;
+        assert_eq!(metrics.values_compressed, 1);
+        Ok(())
+    }
+}
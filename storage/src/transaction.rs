@@ -0,0 +1,1133 @@
+// Copyright 2020 - 2021 Alex Dukhno
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    backup::{BackupWriter, Entry},
+    metrics::{DatabaseMetrics, Metrics, TreeCounters, TreeMetrics},
+    IndexInfo, TreeStats, DEFAULT_DATABASE,
+};
+use binary::{repr::Datum, Binary, Compression};
+use std::{
+    cell::RefCell,
+    cmp::Ordering as KeyOrdering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{self, Debug, Formatter},
+    io::Write,
+    iter::Peekable,
+    mem,
+    ops::Bound,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    vec,
+};
+use storage_api::{
+    version::{project, Timestamp, VersionChain},
+    ConflictableTransactionError, ConflictableTransactionResult, Cursor, IndexKeys, Key, KeyRange, Storage,
+    StorageError, StorageResult, Tree, TreeLayout, Value, WriteBatch, WriteError,
+};
+
+const SYSTEM_TREE: &str = "__SYSTEM";
+const CLOCK: &str = "CLOCK";
+/// key of index entries of a fixed value. Index trees are filled again when it does not match
+/// the one of the storage, e.g. after a database was encrypted or restored from a backup
+const INDEX_KEYS: &str = "INDEX_KEYS";
+/// prefix of keys in system tree that hold sizes of trees
+const STATS: &str = "STATS";
+/// definitions of secondary indexes of all tables
+const INDEXES_TREE: &str = "__INDEXES";
+/// thresholds of tables which long string values are compressed
+const COMPRESSION_TREE: &str = "__COMPRESSION";
+
+/// number of restored rows that are written into a tree at once
+const RESTORE_BATCH_SIZE: usize = 1024;
+
+fn clock_key() -> Key {
+    Binary::pack(&[Datum::from_string(CLOCK.to_owned())])
+}
+
+fn index_keys_key() -> Key {
+    Binary::pack(&[Datum::from_string(INDEX_KEYS.to_owned())])
+}
+
+fn stats_key(tree: &str) -> Key {
+    Binary::pack(&[
+        Datum::from_string(STATS.to_owned()),
+        Datum::from_string(tree.to_owned()),
+    ])
+}
+
+fn compression_key(table: &str) -> Key {
+    Binary::pack_key(&[Datum::from_string(table.to_owned())])
+}
+
+fn index_key(table: &str, index: &str) -> Key {
+    Binary::pack_key(&[
+        Datum::from_string(table.to_owned()),
+        Datum::from_string(index.to_owned()),
+    ])
+}
+
+/// Multi-version concurrency control on top of a storage backend.
+///
+/// Every transaction reads from a snapshot of the database taken at its start and buffers
+/// its writes until commit. When two concurrent transactions change the same row the first
+/// one to commit wins and the other one has to be retried.
+pub(crate) struct Engine {
+    storage: Box<dyn Storage>,
+    /// timestamp of the last committed transaction
+    clock: AtomicU64,
+    commit: Mutex<()>,
+    /// snapshots of running transactions and how many transactions use each of them
+    snapshots: Mutex<BTreeMap<Timestamp, usize>>,
+    /// storage failed in the middle of applying a commit and its content can't be trusted anymore
+    broken: AtomicBool,
+    /// trees created by running transactions and how many of them created each tree.
+    /// A tree is dropped on rollback only if no other transaction created it and committed
+    creating: Mutex<HashMap<String, usize>>,
+    index_keys: Arc<dyn IndexKeys>,
+    pub(crate) metrics: Metrics,
+}
+
+impl Engine {
+    pub(crate) fn new(storage: Box<dyn Storage>) -> StorageResult<Engine> {
+        storage.create_tree(SYSTEM_TREE)?;
+        storage.create_tree(INDEXES_TREE)?;
+        storage.create_tree(COMPRESSION_TREE)?;
+        let clock = match storage.lookup_tree(SYSTEM_TREE)?.get(&clock_key())? {
+            Some(value) => value.try_unpack()?[0].as_u64(),
+            None => 0,
+        };
+        let index_keys = storage.index_keys();
+        Engine::reindex(storage.as_ref(), index_keys.as_ref(), clock)?;
+        Ok(Engine {
+            storage,
+            clock: AtomicU64::new(clock),
+            commit: Mutex::default(),
+            snapshots: Mutex::default(),
+            broken: AtomicBool::new(false),
+            creating: Mutex::default(),
+            index_keys,
+            metrics: Metrics::default(),
+        })
+    }
+
+    /// fills index trees again if their entries were keyed differently than the storage keys them now.
+    /// Nothing runs yet, so entries are written as committed by the last transaction
+    fn reindex(storage: &dyn Storage, index_keys: &dyn IndexKeys, clock: Timestamp) -> StorageResult<()> {
+        let system = storage.lookup_tree(SYSTEM_TREE)?;
+        let probe = index_keys.key(&[Datum::from_string(INDEX_KEYS.to_owned())]);
+        if system.get(&index_keys_key())?.as_ref() == Some(&probe) {
+            return Ok(());
+        }
+        for row in storage.lookup_tree(INDEXES_TREE)?.select() {
+            let (key, chain) = row?;
+            let definition = match VersionChain::decode(&chain)?.visible_at(clock) {
+                Some(definition) => definition.try_unpack()?,
+                None => continue,
+            };
+            let key = key.unpack_key();
+            let table = key[0].as_string();
+            let name = format!("{}.{}", table, key[1].as_string());
+            log::info!("{:?} INDEX ENTRIES ARE WRITTEN AGAIN", name);
+            let unique = definition[0] == Datum::from_bool(true);
+            let columns = definition[1..]
+                .iter()
+                .map(|column| column.as_u64() as usize)
+                .collect::<Vec<_>>();
+            let index = storage.lookup_tree(&name)?;
+            let mut batch = index
+                .select()
+                .map(|row| row.map(|(entry, _chain)| (entry, None)))
+                .collect::<StorageResult<WriteBatch>>()?;
+            let mut stats = TreeStats::default();
+            for row in storage.lookup_tree(&table)?.select() {
+                let (key, chain) = row?;
+                if let Some(row) = VersionChain::decode(&chain)?.visible_at(clock) {
+                    let entry = index_entry(index_keys, &columns, unique, &key, row);
+                    let mut chain = VersionChain::default();
+                    chain.push(clock, Some(key));
+                    let chain = chain.encode();
+                    stats.change(1, TreeStats::size(&entry, chain.as_ref()) as i64);
+                    batch.push((entry, chain));
+                }
+            }
+            index.apply_batch(batch)?;
+            system.insert_key(stats_key(&name), stats.encode())?;
+        }
+        // written last, interrupted reindex starts over
+        system.insert_key(index_keys_key(), probe)?;
+        storage.sync()
+    }
+
+    pub(crate) fn is_encrypted(&self) -> bool {
+        self.storage.is_encrypted()
+    }
+
+    /// nothing has been ever committed into the database
+    pub(crate) fn is_empty(&self) -> bool {
+        self.clock.load(Ordering::SeqCst) == 0
+    }
+
+    pub(crate) fn begin(&self) -> Transaction<'_> {
+        let mut snapshots = self.snapshots.lock().unwrap();
+        let snapshot = self.clock.load(Ordering::SeqCst);
+        *snapshots.entry(snapshot).or_default() += 1;
+        Transaction {
+            engine: self,
+            snapshot,
+            writes: Rc::default(),
+            failure: Rc::default(),
+            created: RefCell::default(),
+            dropped: RefCell::default(),
+            database: RefCell::new(DEFAULT_DATABASE.to_owned()),
+        }
+    }
+
+    /// writes rows of all trees as they are seen by a new transaction. Other transactions keep
+    /// running and committing, versions that the backup reads are not pruned until it is done
+    pub(crate) fn backup<W: Write>(&self, backup: &mut BackupWriter<W>) -> StorageResult<()> {
+        let transaction = self.begin();
+        let mut names = self.storage.tree_names()?;
+        names.sort();
+        for name in names.into_iter().filter(|name| name != SYSTEM_TREE) {
+            backup.write(&Entry::Tree(name.clone(), self.storage.tree_layout(&name)?))?;
+            for (key, value) in transaction.tree(name).scan() {
+                backup.write(&Entry::Row(key, value))?;
+            }
+            if let Some(error) = transaction.failure.take() {
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// loads backup into a storage without trees as if it was committed by a single transaction
+    pub(crate) fn restore(
+        storage: &dyn Storage,
+        backup: impl Iterator<Item = StorageResult<Entry>>,
+    ) -> StorageResult<()> {
+        const RESTORED: Timestamp = 1;
+
+        fn load(tree: &dyn Tree, batch: WriteBatch) -> StorageResult<()> {
+            tree.apply_batch(batch)?;
+            tree.reset_next_key()
+        }
+
+        let mut current: Option<(Box<dyn Tree>, WriteBatch)> = None;
+        let mut stats: Vec<(String, TreeStats)> = vec![];
+        for entry in backup {
+            match entry? {
+                Entry::Tree(name, layout) => {
+                    if let Some((tree, batch)) = current.take() {
+                        load(tree.as_ref(), batch)?;
+                    }
+                    storage.create_tree_with(name.as_str(), layout)?;
+                    current = Some((storage.lookup_tree(name.as_str())?, vec![]));
+                    stats.push((name, TreeStats::default()));
+                }
+                Entry::Row(key, value) => match (current.as_mut(), stats.last_mut()) {
+                    (Some((tree, batch)), Some((_name, stats))) => {
+                        let mut chain = VersionChain::default();
+                        chain.push(RESTORED, Some(value));
+                        let chain = chain.encode();
+                        stats.change(1, TreeStats::size(&key, chain.as_ref()) as i64);
+                        batch.push((key, chain));
+                        if batch.len() == RESTORE_BATCH_SIZE {
+                            tree.apply_batch(mem::take(batch))?;
+                        }
+                    }
+                    _ => return Err(StorageError::Corrupted("backup has a row outside of a tree".to_owned())),
+                },
+            }
+        }
+        if let Some((tree, batch)) = current {
+            load(tree.as_ref(), batch)?;
+        }
+        storage.create_tree(SYSTEM_TREE)?;
+        let system = storage.lookup_tree(SYSTEM_TREE)?;
+        for (name, stats) in stats {
+            system.insert_key(stats_key(&name), stats.encode())?;
+        }
+        // the clock is written last, an interrupted restore is not mistaken for a database
+        system.insert_key(clock_key(), Binary::pack(&[Datum::from_u64(RESTORED)]))?;
+        storage.commit(RESTORED)?;
+        storage.sync()
+    }
+
+    /// snapshot of the oldest running transaction
+    fn horizon(&self) -> Timestamp {
+        let snapshots = self.snapshots.lock().unwrap();
+        snapshots
+            .keys()
+            .next()
+            .copied()
+            .unwrap_or_else(|| self.clock.load(Ordering::SeqCst))
+    }
+
+    /// the transaction shares the tree with other transactions that created it until one of them commits
+    fn create_tree(&self, name: &str, layout: TreeLayout) -> StorageResult<bool> {
+        let mut creating = self.creating.lock().unwrap();
+        if self.storage.create_tree_with(name, layout)? {
+            creating.insert(name.to_owned(), 1);
+            Ok(true)
+        } else if let Some(count) = creating.get_mut(name) {
+            *count += 1;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// trees stay in the storage after one of the transactions that created them committed
+    fn keep_trees(&self, names: Vec<String>) {
+        let mut creating = self.creating.lock().unwrap();
+        for name in names {
+            creating.remove(&name);
+        }
+    }
+
+    /// trees that were created only by rolled back transactions are dropped
+    fn discard_trees(&self, names: Vec<String>) {
+        let mut creating = self.creating.lock().unwrap();
+        for name in names {
+            match creating.get_mut(&name) {
+                Some(count) if *count > 1 => *count -= 1,
+                Some(_) => {
+                    creating.remove(&name);
+                    if let Err(error) = self.storage.drop_tree(name.as_str()) {
+                        log::error!("{:?} tree of rolled back transaction is not dropped: {}", name, error);
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn release(&self, snapshot: Timestamp) {
+        let mut snapshots = self.snapshots.lock().unwrap();
+        if let Some(count) = snapshots.get_mut(&snapshot) {
+            *count -= 1;
+            if *count == 0 {
+                snapshots.remove(&snapshot);
+            }
+        }
+    }
+}
+
+/// new versions of rows in one tree, together with their current version chains
+/// and how many bytes the chains take in the tree
+type TreeChanges = Vec<(Key, VersionChain, u64, Option<Value>)>;
+
+pub(crate) struct Transaction<'t> {
+    engine: &'t Engine,
+    snapshot: Timestamp,
+    writes: Rc<WriteSet>,
+    failure: Rc<Failure>,
+    created: RefCell<Vec<String>>,
+    dropped: RefCell<Vec<String>>,
+    /// database which commits and rollbacks of the transaction are counted for
+    database: RefCell<String>,
+}
+
+impl<'t> Transaction<'t> {
+    pub(crate) fn table<T: Into<String>>(&self, full_table_name: T) -> TransactionalTree {
+        let name = full_table_name.into();
+        let indexes = self.indexes(&name);
+        let compression = self.compression(&name);
+        TransactionalTree {
+            indexes,
+            compression,
+            ..self.tree(name)
+        }
+    }
+
+    fn compression(&self, table: &str) -> Option<Compression> {
+        self.tree(COMPRESSION_TREE.to_owned())
+            .get(&compression_key(table))
+            .map(|threshold| Compression {
+                threshold: threshold.unpack()[0].as_u64() as usize,
+            })
+    }
+
+    /// rows that are written into the table from now on have their long string values compressed
+    pub(crate) fn compress_tree(&self, full_table_name: &str, compression: Compression) {
+        self.tree(COMPRESSION_TREE.to_owned()).put(
+            compression_key(full_table_name),
+            Some(Binary::pack(&[Datum::from_u64(compression.threshold as u64)])),
+        );
+    }
+
+    /// tree without secondary indexes
+    fn tree(&self, name: String) -> TransactionalTree {
+        let tree = match self.engine.storage.lookup_tree(name.as_str()) {
+            Ok(tree) => Some(Rc::from(tree)),
+            Err(error) => {
+                self.failure.record(error);
+                None
+            }
+        };
+        let layout = match self.engine.storage.tree_layout(name.as_str()) {
+            Ok(layout) => layout,
+            Err(error) => {
+                self.failure.record(error);
+                TreeLayout::Rows
+            }
+        };
+        TransactionalTree {
+            tree,
+            layout,
+            counters: self.engine.metrics.tree(&name),
+            name,
+            snapshot: self.snapshot,
+            writes: self.writes.clone(),
+            failure: self.failure.clone(),
+            indexes: vec![],
+            compression: None,
+        }
+    }
+
+    fn indexes(&self, table: &str) -> Vec<Index> {
+        let prefix = Binary::pack_key(&[Datum::from_string(table.to_owned())]);
+        self.tree(INDEXES_TREE.to_owned())
+            .scan_range(KeyRange::prefix(&prefix), false)
+            .map(|(key, definition)| {
+                let name = key.unpack_key()[1].as_string();
+                let definition = definition.unpack();
+                Index {
+                    tree: self.tree(format!("{}.{}", table, name)),
+                    keys: self.engine.index_keys.clone(),
+                    name,
+                    unique: definition[0] == Datum::from_bool(true),
+                    columns: definition[1..].iter().map(|column| column.as_u64() as usize).collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// creates index over `columns` of the table and fills it with rows visible to the transaction
+    pub(crate) fn create_index(
+        &self,
+        full_table_name: &str,
+        index_name: &str,
+        columns: &[usize],
+        unique: bool,
+    ) -> Result<(), WriteError> {
+        let mut entries = BTreeMap::new();
+        for (key, row) in self.tree(full_table_name.to_owned()).scan() {
+            let entry = index_entry(self.engine.index_keys.as_ref(), columns, unique, &key, &row);
+            if entries.insert(entry, key).is_some() {
+                return Err(WriteError::UniqueViolation(index_name.to_owned()));
+            }
+        }
+
+        let name = format!("{}.{}", full_table_name, index_name);
+        self.create_tree(name.as_str());
+        let index = self.tree(name);
+        for (entry, key) in entries {
+            index.put(entry, Some(key));
+        }
+        let mut definition = vec![Datum::from_bool(unique)];
+        definition.extend(columns.iter().map(|column| Datum::from_u64(*column as u64)));
+        self.tree(INDEXES_TREE.to_owned())
+            .put(index_key(full_table_name, index_name), Some(Binary::pack(&definition)));
+        Ok(())
+    }
+
+    pub(crate) fn drop_index(&self, full_table_name: &str, index_name: &str) {
+        self.tree(INDEXES_TREE.to_owned())
+            .put(index_key(full_table_name, index_name), None);
+        self.drop_tree(format!("{}.{}", full_table_name, index_name));
+    }
+
+    pub(crate) fn tree_metrics(&self, name: &str) -> TreeMetrics {
+        self.engine.metrics.tree_metrics(name)
+    }
+
+    pub(crate) fn database_metrics(&self, database: &str) -> DatabaseMetrics {
+        self.engine.metrics.database_metrics(database)
+    }
+
+    pub(crate) fn work_in(&self, database: &str) {
+        *self.database.borrow_mut() = database.to_owned();
+    }
+
+    pub(crate) fn database(&self) -> String {
+        self.database.borrow().clone()
+    }
+
+    pub(crate) fn failure(&self) -> Rc<Failure> {
+        self.failure.clone()
+    }
+
+    /// committed number of rows and size of the tree, changes of running transactions are not counted
+    pub(crate) fn tree_stats(&self, name: &str) -> TreeStats {
+        let stats = self
+            .engine
+            .storage
+            .lookup_tree(SYSTEM_TREE)
+            .and_then(|system| tree_stats(system.as_ref(), name));
+        match stats {
+            Ok(stats) => stats,
+            Err(error) => {
+                self.failure.record(error);
+                TreeStats::default()
+            }
+        }
+    }
+
+    pub(crate) fn create_tree<T: Into<String>>(&self, full_table_name: T) {
+        self.create_tree_with(full_table_name, TreeLayout::Rows)
+    }
+
+    pub(crate) fn create_tree_with<T: Into<String>>(&self, full_table_name: T, layout: TreeLayout) {
+        let name = full_table_name.into();
+        let was_dropped = {
+            let mut dropped = self.dropped.borrow_mut();
+            let position = dropped.iter().position(|table| *table == name);
+            position.map(|position| dropped.remove(position)).is_some()
+        };
+        if was_dropped {
+            // tree dropped by the same transaction is reused, its rows are deleted instead
+            let tree = self.tree(name);
+            for (key, _row) in tree.scan() {
+                tree.put(key, None);
+            }
+            return;
+        }
+        // empty tree is not visible to anyone until catalog records about it are committed
+        match self.engine.create_tree(name.as_str(), layout) {
+            Ok(true) => self.created.borrow_mut().push(name),
+            Ok(false) => {}
+            Err(error) => self.failure.record(error),
+        }
+    }
+
+    pub(crate) fn drop_tree<T: Into<String>>(&self, full_table_name: T) {
+        let name = full_table_name.into();
+        if self.dropped.borrow().contains(&name) {
+            return;
+        }
+        for index in self.indexes(&name) {
+            self.drop_index(&name, &index.name);
+        }
+        if self.compression(&name).is_some() {
+            self.tree(COMPRESSION_TREE.to_owned()).put(compression_key(&name), None);
+        }
+        // concurrent transactions can still read from the tree
+        self.dropped.borrow_mut().push(name);
+    }
+
+    /// moves rows of the tree together with its indexes and compression under the new name
+    pub(crate) fn rename_tree(&self, from: &str, to: &str) -> Result<(), WriteError> {
+        let source = self.tree(from.to_owned());
+        self.create_tree_with(to, source.layout);
+        if let Some(compression) = self.compression(from) {
+            self.compress_tree(to, compression);
+        }
+        // rows get keys of the new tree, so the ones that are written later do not collide with them
+        self.table(to)
+            .write_batch(source.scan().map(|(_key, row)| row).collect())?;
+        for index in self.indexes(from) {
+            self.create_index(to, &index.name, &index.columns, index.unique)?;
+        }
+        self.drop_tree(from);
+        Ok(())
+    }
+
+    /// trees of the storage including the ones that are created by running transactions
+    pub(crate) fn tree_names(&self) -> Vec<String> {
+        match self.engine.storage.tree_names() {
+            Ok(names) => names,
+            Err(error) => {
+                self.failure.record(error);
+                vec![]
+            }
+        }
+    }
+
+    pub(crate) fn commit(&self) -> ConflictableTransactionResult<()> {
+        if let Some(error) = self.failure.take() {
+            return Err(ConflictableTransactionError::Storage(error));
+        }
+        let writes = self.writes.take();
+        let dropped = self.dropped.take();
+        if writes.is_empty() && dropped.is_empty() {
+            self.engine.keep_trees(self.created.take());
+            return Ok(());
+        }
+
+        let guard = self.engine.commit.lock().unwrap();
+        if self.engine.broken.load(Ordering::SeqCst) {
+            return Err(ConflictableTransactionError::Storage(StorageError::Io(
+                "previous commit was not applied completely, database has to be restarted".to_owned(),
+            )));
+        }
+        // everything is read and checked before the first change is applied
+        let mut changes = vec![];
+        for (table, rows) in writes {
+            if dropped.contains(&table) {
+                continue;
+            }
+            let tree = self.engine.storage.lookup_tree(table.as_str())?;
+            let mut tree_changes = vec![];
+            for (key, value) in rows {
+                let stored = tree.get(&key)?;
+                let chain = match &stored {
+                    None => VersionChain::default(),
+                    Some(chain) => VersionChain::decode(chain).map_err(|error| {
+                        log::error!("{:?} key in {:?} table is corrupted: {}", key, table, error);
+                        error
+                    })?,
+                };
+                if matches!(chain.latest(), Some(latest) if latest > self.snapshot) {
+                    log::debug!("CONFLICT on {:?} key in {:?} table", key, table);
+                    return Err(ConflictableTransactionError::Conflict);
+                }
+                let size = TreeStats::size(&key, stored.as_ref());
+                tree_changes.push((key, chain, size, value));
+            }
+            changes.push((table, tree, tree_changes));
+        }
+
+        let timestamp = self.engine.clock.load(Ordering::SeqCst) + 1;
+        let horizon = self.engine.horizon();
+        // created trees are part of the commit from now on even if it fails
+        self.engine.keep_trees(self.created.take());
+        if let Err(error) = self.apply(changes, dropped, timestamp, horizon) {
+            log::error!("commit failed after its changes were partially applied: {}", error);
+            self.engine.broken.store(true, Ordering::SeqCst);
+            return Err(ConflictableTransactionError::Storage(error));
+        }
+        self.engine.clock.store(timestamp, Ordering::SeqCst);
+        log::debug!("TRANSACTION COMMITTED AT {}", timestamp);
+        drop(guard);
+        // other transactions can commit while this one is waiting and be synced together
+        self.engine.storage.sync()?;
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        changes: Vec<(String, Box<dyn Tree>, TreeChanges)>,
+        dropped: Vec<String>,
+        timestamp: Timestamp,
+        horizon: Timestamp,
+    ) -> StorageResult<()> {
+        let system = self.engine.storage.lookup_tree(SYSTEM_TREE)?;
+        for (table, tree, tree_changes) in changes {
+            let mut stats = tree_stats(system.as_ref(), &table)?;
+            let mut metrics = TreeMetrics::default();
+            let batch = tree_changes
+                .into_iter()
+                .map(|(key, mut chain, size, value)| {
+                    let existed = chain.visible_at(timestamp).is_some();
+                    if let Some(Ok(compressed)) = value.as_ref().map(Binary::compression_stats) {
+                        metrics.values_compressed += compressed.values;
+                        metrics.bytes_saved += compressed.bytes_saved;
+                    }
+                    chain.push(timestamp, value);
+                    chain.prune(horizon);
+                    let exists = chain.visible_at(timestamp).is_some();
+                    let chain = chain.encode();
+                    match (existed, exists) {
+                        (false, true) => metrics.rows_inserted += 1,
+                        (true, true) => metrics.rows_updated += 1,
+                        (true, false) => metrics.rows_deleted += 1,
+                        (false, false) => {}
+                    }
+                    metrics.bytes_written += chain
+                        .as_ref()
+                        .map(|chain| chain.as_ref().len() as u64)
+                        .unwrap_or_default();
+                    stats.change(
+                        exists as i64 - existed as i64,
+                        TreeStats::size(&key, chain.as_ref()) as i64 - size as i64,
+                    );
+                    (key, chain)
+                })
+                .collect();
+            tree.apply_batch(batch)?;
+            system.insert_key(stats_key(&table), stats.encode())?;
+            self.engine.metrics.tree(&table).committed(&metrics);
+        }
+        for table in dropped {
+            self.engine.storage.drop_tree(&table)?;
+            system.remove(&stats_key(&table))?;
+            self.engine.metrics.drop_tree(&table);
+        }
+        system.insert_key(clock_key(), Binary::pack(&[Datum::from_u64(timestamp)]))?;
+        self.engine.storage.commit(timestamp)
+    }
+
+    pub(crate) fn rollback(&self) {
+        self.writes.take();
+        self.dropped.take();
+        self.failure.take();
+        self.engine.discard_trees(self.created.take());
+    }
+}
+
+impl<'t> Drop for Transaction<'t> {
+    fn drop(&mut self) {
+        self.engine.release(self.snapshot);
+    }
+}
+
+/// trees that were created before their sizes were tracked are considered empty
+fn tree_stats(system: &dyn Tree, name: &str) -> StorageResult<TreeStats> {
+    match system.get(&stats_key(name))? {
+        None => Ok(TreeStats::default()),
+        Some(stats) => TreeStats::decode(&stats),
+    }
+}
+
+/// The first storage error that a transaction ran into. Cursors can't return errors,
+/// so the error is kept and fails the transaction on commit
+#[derive(Debug, Default)]
+pub(crate) struct Failure(RefCell<Option<StorageError>>);
+
+impl Failure {
+    pub(crate) fn record(&self, error: StorageError) {
+        log::error!("storage error: {}", error);
+        self.0.borrow_mut().get_or_insert(error);
+    }
+
+    fn take(&self) -> Option<StorageError> {
+        self.0.take()
+    }
+
+    pub(crate) fn is_recorded(&self) -> bool {
+        self.0.borrow().is_some()
+    }
+}
+
+/// rows a transaction has written to a single table, `None` marks a deleted row
+type Writes = BTreeMap<Key, Option<Value>>;
+
+/// Changes made by a transaction that are not visible to others until commit.
+/// Rows of a table are shared with open cursors and copied only when the table is changed
+/// while a cursor reads them
+#[derive(Debug, Default)]
+pub(crate) struct WriteSet(RefCell<BTreeMap<String, Rc<Writes>>>);
+
+impl WriteSet {
+    fn put(&self, table: &str, key: Key, value: Option<Value>) {
+        Rc::make_mut(self.0.borrow_mut().entry(table.to_owned()).or_default()).insert(key, value);
+    }
+
+    fn get(&self, table: &str, key: &Key) -> Option<Option<Value>> {
+        self.0.borrow().get(table).and_then(|rows| rows.get(key).cloned())
+    }
+
+    fn rows(&self, table: &str) -> Rc<Writes> {
+        self.0.borrow().get(table).cloned().unwrap_or_default()
+    }
+
+    fn take(&self) -> BTreeMap<String, Writes> {
+        self.0
+            .take()
+            .into_iter()
+            .map(|(table, rows)| (table, Rc::try_unwrap(rows).unwrap_or_else(|rows| (*rows).clone())))
+            .collect()
+    }
+}
+
+/// Rows of the write set that are in the range, one at a time in the order of keys
+struct OwnRows {
+    rows: Rc<Writes>,
+    range: KeyRange,
+    reverse: bool,
+}
+
+impl Iterator for OwnRows {
+    type Item = (Key, Option<Value>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.range.is_empty() {
+            return None;
+        }
+        let mut rows = self.rows.range((self.range.start.as_ref(), self.range.end.as_ref()));
+        let (key, value) = if self.reverse { rows.next_back()? } else { rows.next()? };
+        let (key, value) = (key.clone(), value.clone());
+        if self.reverse {
+            self.range.end = Bound::Excluded(key.clone());
+        } else {
+            self.range.start = Bound::Excluded(key.clone());
+        }
+        Some((key, value))
+    }
+}
+
+pub(crate) struct TransactionalTree {
+    name: String,
+    /// `None` if the tree could not be opened, the transaction fails on commit then
+    tree: Option<Rc<dyn Tree>>,
+    layout: TreeLayout,
+    counters: Arc<TreeCounters>,
+    snapshot: Timestamp,
+    writes: Rc<WriteSet>,
+    failure: Rc<Failure>,
+    indexes: Vec<Index>,
+    compression: Option<Compression>,
+}
+
+impl Debug for TransactionalTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "TransactionalTree({:?})", self.name)
+    }
+}
+
+impl TransactionalTree {
+    pub(crate) fn write(&self, row: Value) -> Result<Key, WriteError> {
+        let key = self.tree.as_ref().map(|tree| tree.next_key()).unwrap_or_default();
+        self.change(key.clone(), None, Some(row))?;
+        Ok(key)
+    }
+
+    /// writes all rows or none of them if any of unique indexes is violated
+    pub(crate) fn write_batch(&self, rows: Vec<Value>) -> Result<Vec<Key>, WriteError> {
+        let keys = match &self.tree {
+            Some(tree) => tree.next_keys(rows.len()),
+            None => vec![Key::default(); rows.len()],
+        };
+        let mut entries = vec![];
+        for index in self.indexes.iter() {
+            let index_entries = keys
+                .iter()
+                .zip(rows.iter())
+                .map(|(key, row)| index.entry(key, row))
+                .collect::<Vec<_>>();
+            if index.unique {
+                let mut batch_entries = BTreeSet::new();
+                for entry in index_entries.iter() {
+                    if !batch_entries.insert(entry) || index.tree.get(entry).is_some() {
+                        return Err(WriteError::UniqueViolation(index.name.clone()));
+                    }
+                }
+            }
+            entries.push((index, index_entries));
+        }
+        for (index, index_entries) in entries {
+            for (entry, key) in index_entries.into_iter().zip(keys.iter()) {
+                index.tree.put(entry, Some(key.clone()));
+            }
+        }
+        for (key, row) in keys.iter().zip(rows) {
+            self.put(key.clone(), Some(row));
+        }
+        Ok(keys)
+    }
+
+    /// replaces rows of the keys all at once, so rows can swap values of unique columns.
+    /// Nothing is changed if any of unique indexes is violated
+    pub(crate) fn update_batch(&self, rows: Vec<(Key, Value)>) -> Result<(), WriteError> {
+        let old_rows = rows.iter().map(|(key, _row)| self.get(key)).collect::<Vec<_>>();
+        let mut entries = vec![];
+        for index in self.indexes.iter() {
+            let changed = rows
+                .iter()
+                .zip(old_rows.iter())
+                .map(|((key, row), old)| {
+                    (
+                        old.as_ref().map(|old| index.entry(key, old)),
+                        index.entry(key, row),
+                        key,
+                    )
+                })
+                .filter(|(old_entry, new_entry, _key)| old_entry.as_ref() != Some(new_entry))
+                .collect::<Vec<_>>();
+            if index.unique {
+                let freed = changed
+                    .iter()
+                    .filter_map(|(old_entry, _new_entry, _key)| old_entry.as_ref())
+                    .collect::<BTreeSet<_>>();
+                let mut batch_entries = BTreeSet::new();
+                for (_old_entry, new_entry, key) in changed.iter() {
+                    let taken = matches!(index.tree.get(new_entry), Some(other) if other != **key && !freed.contains(new_entry));
+                    if !batch_entries.insert(new_entry) || taken {
+                        return Err(WriteError::UniqueViolation(index.name.clone()));
+                    }
+                }
+            }
+            entries.push((index, changed));
+        }
+        for (index, changed) in entries {
+            for (old_entry, _new_entry, _key) in changed.iter() {
+                if let Some(old_entry) = old_entry {
+                    index.tree.put(old_entry.clone(), None);
+                }
+            }
+            for (_old_entry, new_entry, key) in changed {
+                index.tree.put(new_entry, Some(key.clone()));
+            }
+        }
+        for (key, row) in rows {
+            self.put(key, Some(row));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_key(&self, key: Key, row: Option<Value>) -> Result<(), WriteError> {
+        let old = if self.indexes.is_empty() { None } else { self.get(&key) };
+        self.change(key, old, row)
+    }
+
+    /// the row as it is seen by the transaction
+    pub(crate) fn get(&self, key: &Key) -> Option<Value> {
+        self.reader().get(key)
+    }
+
+    pub(crate) fn is_changed(&self, key: &Key) -> bool {
+        self.writes.get(&self.name, key).is_some()
+    }
+
+    fn reader(&self) -> Reader {
+        Reader {
+            name: self.name.clone(),
+            tree: self.tree.clone(),
+            snapshot: self.snapshot,
+            writes: self.writes.clone(),
+            failure: self.failure.clone(),
+        }
+    }
+
+    /// replaces `old` row with `row` and moves index entries accordingly.
+    /// Nothing is changed if any of unique indexes is violated
+    fn change(&self, key: Key, old: Option<Value>, row: Option<Value>) -> Result<(), WriteError> {
+        let mut entries = vec![];
+        for index in self.indexes.iter() {
+            let old_entry = old.as_ref().map(|old| index.entry(&key, old));
+            let new_entry = row.as_ref().map(|row| index.entry(&key, row));
+            if old_entry == new_entry {
+                continue;
+            }
+            if let Some(new_entry) = &new_entry {
+                if index.unique && matches!(index.tree.get(new_entry), Some(other) if other != key) {
+                    return Err(WriteError::UniqueViolation(index.name.clone()));
+                }
+            }
+            entries.push((index, old_entry, new_entry));
+        }
+        for (index, old_entry, new_entry) in entries {
+            if let Some(old_entry) = old_entry {
+                index.tree.put(old_entry, None);
+            }
+            if let Some(new_entry) = new_entry {
+                index.tree.put(new_entry, Some(key.clone()));
+            }
+        }
+        self.put(key, row);
+        Ok(())
+    }
+
+    fn put(&self, key: Key, row: Option<Value>) {
+        let row = match (row, self.compression) {
+            (Some(row), Some(compression)) => Some(row.compress(compression)),
+            (row, _) => row,
+        };
+        self.writes.put(&self.name, key, row);
+    }
+
+    pub(crate) fn indexes(&self) -> Vec<IndexInfo> {
+        self.indexes
+            .iter()
+            .map(|index| IndexInfo {
+                name: index.name.clone(),
+                columns: index.columns.clone(),
+                unique: index.unique,
+            })
+            .collect()
+    }
+
+    /// rows which indexed columns are equal to `values`.
+    /// Returns `None` if the table does not have such index
+    pub(crate) fn scan_index(&self, index_name: &str, values: &[Datum]) -> Option<Cursor> {
+        let index = self.indexes.iter().find(|index| index.name == index_name)?;
+        let reader = self.reader();
+        let counters = self.counters.clone();
+        counters.index_scan();
+        Some(Cursor::new(
+            index
+                .tree
+                .scan_range(KeyRange::prefix(&index.keys.key(values)), false)
+                .filter_map(move |(_entry, key)| reader.get(&key).map(|row| (key, row)))
+                .inspect(move |_row| counters.index_row_read()),
+        ))
+    }
+
+    pub(crate) fn scan(&self) -> Cursor {
+        self.scan_range(KeyRange::all(), false)
+    }
+
+    pub(crate) fn scan_range(&self, range: KeyRange, reverse: bool) -> Cursor {
+        self.read(range, reverse, None)
+    }
+
+    pub(crate) fn scan_columns(&self, columns: &[usize]) -> Cursor {
+        self.read(KeyRange::all(), false, Some(columns))
+    }
+
+    pub(crate) fn layout(&self) -> TreeLayout {
+        self.layout
+    }
+
+    fn read(&self, range: KeyRange, reverse: bool, columns: Option<&[usize]>) -> Cursor {
+        let snapshot = self.snapshot;
+        let failure = self.failure.clone();
+        let counters = self.counters.clone();
+        counters.scan();
+        let committed = match (&self.tree, columns) {
+            (None, _) => Box::new(std::iter::empty()),
+            (Some(tree), Some(columns)) => tree.select_columns(range.clone(), columns),
+            (Some(tree), None) if reverse => tree.select_range_rev(range.clone()),
+            (Some(tree), None) => tree.select_range(range.clone()),
+        }
+        // the scan stops at the first error
+        .map_while(
+            move |row| match row.and_then(|(key, chain)| Ok((key, VersionChain::decode(&chain)?))) {
+                Ok(row) => Some(row),
+                Err(error) => {
+                    failure.record(error);
+                    None
+                }
+            },
+        )
+        .filter_map(move |(key, chain)| chain.visible_at(snapshot).map(|value| (key, value.clone())))
+        .inspect(move |_row| counters.row_read());
+        // the cursor reads own changes as they were when it was opened, the transaction can keep writing
+        let failure = self.failure.clone();
+        let columns = columns.map(<[usize]>::to_vec);
+        let own = OwnRows {
+            rows: self.writes.rows(&self.name),
+            range,
+            reverse,
+        }
+        .map(move |(key, value)| match (value, &columns) {
+            (Some(value), Some(columns)) => match project(&value, columns) {
+                Ok(value) => (key, Some(value)),
+                Err(error) => {
+                    failure.record(error.into());
+                    (key, None)
+                }
+            },
+            (value, _) => (key, value),
+        });
+        Cursor::new(Merge {
+            committed: committed.peekable(),
+            own: own.peekable(),
+            reverse,
+        })
+    }
+}
+
+/// Reads single rows as they are seen by the transaction, cursors keep it to look up rows
+/// found through indexes
+struct Reader {
+    name: String,
+    tree: Option<Rc<dyn Tree>>,
+    snapshot: Timestamp,
+    writes: Rc<WriteSet>,
+    failure: Rc<Failure>,
+}
+
+impl Reader {
+    fn get(&self, key: &Key) -> Option<Value> {
+        match self.writes.get(&self.name, key) {
+            Some(own) => own,
+            None => {
+                let chain = self.tree.as_ref()?.get(key).and_then(|chain| match chain {
+                    None => Ok(None),
+                    Some(chain) => Ok(Some(VersionChain::decode(&chain)?)),
+                });
+                match chain {
+                    Ok(chain) => chain?.visible_at(self.snapshot).cloned(),
+                    Err(error) => {
+                        self.failure.record(error);
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Secondary index of a table. Entries are written in the same transaction as the rows
+#[derive(Debug)]
+struct Index {
+    name: String,
+    columns: Vec<usize>,
+    unique: bool,
+    tree: TransactionalTree,
+    keys: Arc<dyn IndexKeys>,
+}
+
+impl Index {
+    fn entry(&self, key: &Key, row: &Value) -> Key {
+        index_entry(self.keys.as_ref(), &self.columns, self.unique, key, row)
+    }
+}
+
+/// Index entries are keyed by values of indexed columns and point to a row key.
+/// Entries of non unique indexes end with the row key to tell apart rows with the same values
+fn index_entry(keys: &dyn IndexKeys, columns: &[usize], unique: bool, key: &Key, row: &Value) -> Key {
+    let row = row.unpack();
+    let values = columns
+        .iter()
+        .map(|column| row.get(*column).cloned().unwrap_or_else(Datum::from_null))
+        .collect::<Vec<_>>();
+    let entry = keys.key(&values);
+    // NULL is not equal to any other value, including NULL
+    if unique && !values.contains(&Datum::from_null()) {
+        entry
+    } else {
+        Binary::with_data([entry.as_ref(), key.as_ref()].concat())
+    }
+}
+
+/// Overlays transaction's own changes on top of committed rows. Both sources are ordered by key
+/// in the same direction
+struct Merge<C: Iterator<Item = (Key, Value)>, O: Iterator<Item = (Key, Option<Value>)>> {
+    committed: Peekable<C>,
+    own: Peekable<O>,
+    reverse: bool,
+}
+
+impl<C: Iterator<Item = (Key, Value)>, O: Iterator<Item = (Key, Option<Value>)>> Iterator for Merge<C, O> {
+    type Item = (Key, Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let order = match (self.committed.peek(), self.own.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => KeyOrdering::Less,
+                (None, Some(_)) => KeyOrdering::Greater,
+                (Some((committed, _)), Some((own, _))) if self.reverse => own.cmp(committed),
+                (Some((committed, _)), Some((own, _))) => committed.cmp(own),
+            };
+            match order {
+                KeyOrdering::Less => return self.committed.next(),
+                KeyOrdering::Equal => {
+                    self.committed.next();
+                }
+                KeyOrdering::Greater => {}
+            }
+            if let Some((key, Some(value))) = self.own.next() {
+                return Some((key, value));
+            }
+        }
+    }
+}
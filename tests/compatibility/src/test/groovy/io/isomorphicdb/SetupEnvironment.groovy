@@ -61,7 +61,7 @@ class SetupEnvironment extends Specification {
   static Map<String, String> dbConf() {
     [
             //TODO: sslmode as parameter to test both encrypted and not traffic on CI
-        url: "jdbc:postgresql://localhost:5432/test?gssEncMode=disable&sslmode=disable&preferQueryMode=extendedForPrepared",
+        url: "jdbc:postgresql://localhost:5432/postgres?gssEncMode=disable&sslmode=disable&preferQueryMode=extendedForPrepared",
         user: USER,
         password: PASSWORD,
         driver: DRIVER_CLASS,
@@ -71,7 +71,7 @@ class SetupEnvironment extends Specification {
   static HikariDataSource dbPool() {
     HikariDataSource ds = new HikariDataSource();
     //TODO: sslmode as parameter to test both encrypted and not traffic on CI
-    ds.setJdbcUrl("jdbc:postgresql://localhost:5432/test?gssEncMode=disable&sslmode=disable&preferQueryMode=extendedForPrepared");
+    ds.setJdbcUrl("jdbc:postgresql://localhost:5432/postgres?gssEncMode=disable&sslmode=disable&preferQueryMode=extendedForPrepared");
     ds.setUsername(USER);
     ds.setPassword(PASSWORD);
     ds.setDriverClassName(DRIVER_CLASS);